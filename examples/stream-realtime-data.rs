@@ -91,7 +91,7 @@ async fn main() {
 
   let subscribe = subscription.subscribe(&data).boxed();
   // Actually subscribe with the websocket server.
-  let () = drive(subscribe, &mut stream)
+  let _ = drive(subscribe, &mut stream)
     .await
     .unwrap()
     .unwrap()
@@ -6,7 +6,15 @@ pub mod v2;
 
 /// The API base URL used for paper trading.
 pub(crate) const API_BASE_URL: &str = "https://paper-api.alpaca.markets";
+/// The API base URL used for live trading.
+pub(crate) const LIVE_API_BASE_URL: &str = "https://api.alpaca.markets";
 /// The HTTP header representing the key ID.
 pub(crate) const HDR_KEY_ID: &str = "APCA-API-KEY-ID";
 /// The HTTP header representing the secret key.
 pub(crate) const HDR_SECRET: &str = "APCA-API-SECRET-KEY";
+/// The HTTP header representing the account to scope a request to.
+pub(crate) const HDR_ACCOUNT_ID: &str = "APCA-ACCOUNT-ID";
+/// The HTTP header carrying the client generated ID of a request.
+pub(crate) const HDR_CLIENT_REQUEST_ID: &str = "X-Client-Request-ID";
+/// The user agent used by default.
+pub(crate) const DEFAULT_USER_AGENT: &str = concat!("apca/", env!("CARGO_PKG_VERSION"));
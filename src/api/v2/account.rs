@@ -10,9 +10,16 @@ use num_decimal::Num;
 
 use serde::Deserialize;
 use serde::Serialize;
+use serde_urlencoded::to_string as to_query;
 
 use uuid::Uuid;
 
+#[cfg(feature = "trading")]
+use crate::api::v2::position::Position;
+use crate::util::strict_variant;
+use crate::util::UnknownVariant;
+#[cfg(feature = "money")]
+use crate::Money;
 use crate::Str;
 
 /// A type representing an account ID.
@@ -61,6 +68,13 @@ pub enum Status {
   Unknown,
 }
 
+impl UnknownVariant for Status {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 /// An object as returned by the /v2/account endpoint.
 // TODO: The `sma` field is not yet hooked up.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -78,7 +92,7 @@ pub struct Account {
   #[serde(rename = "account_number")]
   pub account_number: String,
   /// The account's status.
-  #[serde(rename = "status")]
+  #[serde(rename = "status", deserialize_with = "strict_variant")]
   pub status: Status,
   /// The account's crypto status.
   #[serde(rename = "crypto_status")]
@@ -132,10 +146,10 @@ pub struct Account {
   /// classification. Valid values are:
   /// - 1: the standard limited margin account with 1x buying power
   /// - 2: regular margin account with 2x intra day and overnight buying
-  ///      power (the default for all non-pattern-day-trader accounts
-  ///      with USD 2000 or more equity),
+  ///   power (the default for all non-pattern-day-trader accounts
+  ///   with USD 2000 or more equity),
   /// - 4: pattern day trader account with 4x intra day buying power and
-  ///      2x regular overnight buying power
+  ///   2x regular overnight buying power
   #[serde(rename = "multiplier")]
   pub multiplier: Num,
   /// The currently available buying power. Calculated based on the
@@ -209,6 +223,321 @@ pub struct Account {
   pub _non_exhaustive: (),
 }
 
+impl Account {
+  /// Estimate the remaining day trading buying power.
+  ///
+  /// If `intraday` is `None`, the day trading buying power as last
+  /// reported by the server (`daytrading_buying_power`) is returned.
+  /// Otherwise `intraday` is expected to contain all positions that
+  /// were opened today and the remaining buying power is estimated as
+  /// the beginning of day day trading buying power (`bod_dtbp`) minus
+  /// the absolute cost basis of these positions, but never less than
+  /// zero.
+  ///
+  /// # Notes
+  /// - the estimate is only an approximation: it does not account for
+  ///   positions that were opened and closed again during the day, nor
+  ///   for buying power tied up in open orders
+  /// - day trading buying power is only meaningful for accounts
+  ///   flagged as pattern day traders; for other accounts it is
+  ///   typically zero
+  #[cfg(feature = "trading")]
+  pub fn day_trade_buying_power_remaining(&self, intraday: Option<&[Position]>) -> Num {
+    match intraday {
+      None => self.daytrading_buying_power.clone(),
+      Some(positions) => {
+        let used = positions.iter().fold(Num::from(0), |used, position| {
+          if position.cost_basis.is_negative() {
+            used - &position.cost_basis
+          } else {
+            used + &position.cost_basis
+          }
+        });
+        let remaining = &self.bod_dtbp - used;
+        if remaining.is_negative() {
+          Num::from(0)
+        } else {
+          remaining
+        }
+      },
+    }
+  }
+
+  /// Calculate the maximum number of whole shares of a marginable
+  /// security that can be bought at `price` with the available buying
+  /// power.
+  ///
+  /// Zero is reported for a price that is zero or negative.
+  #[inline]
+  pub fn max_shares_affordable(&self, price: &Num) -> Num {
+    max_shares(&self.buying_power, price)
+  }
+
+  /// Calculate the maximum number of whole shares of a non-marginable
+  /// security that can be bought at `price`, based on the non-marginable
+  /// buying power.
+  ///
+  /// Zero is reported for a price that is zero or negative.
+  #[inline]
+  pub fn max_shares_affordable_non_marginable(&self, price: &Num) -> Num {
+    max_shares(&self.non_marginable_buying_power, price)
+  }
+
+  /// Retrieve the maximum notional value of an order for a marginable
+  /// security, i.e., the available buying power, but never less than
+  /// zero.
+  #[inline]
+  pub fn max_notional(&self) -> Num {
+    non_negative(&self.buying_power)
+  }
+
+  /// Retrieve the maximum notional value of an order for a
+  /// non-marginable security, i.e., the available non-marginable
+  /// buying power, but never less than zero.
+  #[inline]
+  pub fn max_notional_non_marginable(&self) -> Num {
+    non_negative(&self.non_marginable_buying_power)
+  }
+
+  /// Check whether the available buying power covers the provided
+  /// cost, e.g., the notional value of an order.
+  #[inline]
+  pub fn can_afford(&self, cost: &Num) -> bool {
+    cost <= &self.buying_power
+  }
+
+  /// Retrieve the cash balance, tagged with the account's currency.
+  #[cfg(feature = "money")]
+  #[inline]
+  pub fn cash_money(&self) -> Money {
+    Money::new(self.cash.clone(), self.currency.clone())
+  }
+
+  /// Retrieve the equity, tagged with the account's currency.
+  #[cfg(feature = "money")]
+  #[inline]
+  pub fn equity_money(&self) -> Money {
+    Money::new(self.equity.clone(), self.currency.clone())
+  }
+
+  /// Retrieve the buying power, tagged with the account's currency.
+  #[cfg(feature = "money")]
+  #[inline]
+  pub fn buying_power_money(&self) -> Money {
+    Money::new(self.buying_power.clone(), self.currency.clone())
+  }
+
+  /// Capture the account's buying power figures as of the provided
+  /// time, which typically is the time the account was retrieved.
+  pub fn buying_power_snapshot(&self, time: DateTime<Utc>) -> BuyingPowerSnapshot {
+    BuyingPowerSnapshot {
+      time,
+      buying_power: self.buying_power.clone(),
+      regt_buying_power: self.regt_buying_power.clone(),
+      daytrading_buying_power: self.daytrading_buying_power.clone(),
+      options_buying_power: self.options_buying_power.clone(),
+      effective_buying_power: self.effective_buying_power.clone(),
+      non_marginable_buying_power: self.non_marginable_buying_power.clone(),
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Determine the fields that differ between this account and
+  /// `other`, with the values of this account being reported as the
+  /// old ones.
+  ///
+  /// Only numeric, boolean, and status fields are compared. Changes are
+  /// reported in the order in which fields are declared.
+  pub fn diff(&self, other: &Account) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+
+    macro_rules! diff {
+      ($variant:ident, $($field:ident),* $(,)?) => {
+        $(
+          if self.$field != other.$field {
+            changes.push(FieldChange {
+              field: stringify!($field),
+              old: FieldValue::$variant(self.$field.clone()),
+              new: FieldValue::$variant(other.$field.clone()),
+              _non_exhaustive: (),
+            });
+          }
+        )*
+      };
+    }
+
+    diff!(Status, status);
+    diff!(Num, cash, portfolio_value);
+    diff!(
+      Bool,
+      day_trader,
+      trading_suspended,
+      trading_blocked,
+      transfers_blocked,
+      account_blocked,
+      shorting_enabled,
+    );
+    diff!(
+      Num,
+      market_value_long,
+      market_value_short,
+      equity,
+      last_equity,
+      multiplier,
+      buying_power,
+      regt_buying_power,
+      daytrading_buying_power,
+      options_buying_power,
+      effective_buying_power,
+      non_marginable_buying_power,
+      bod_dtbp,
+      accrued_fees,
+      pending_transfer_in,
+      position_market_value,
+      initial_margin,
+      maintenance_margin,
+      last_maintenance_margin,
+      sma,
+    );
+    diff!(Count, daytrade_count, crypto_tier, options_trading_level);
+    diff!(Num, intraday_adjustments, pending_reg_taf_fees);
+
+    changes
+  }
+}
+
+
+/// Clamp a value to zero from below.
+fn non_negative(value: &Num) -> Num {
+  if value.is_negative() {
+    Num::from(0)
+  } else {
+    value.clone()
+  }
+}
+
+
+/// Calculate the number of whole shares affordable at `price` with the
+/// provided buying power.
+fn max_shares(buying_power: &Num, price: &Num) -> Num {
+  if !price.is_positive() {
+    return Num::from(0)
+  }
+  (non_negative(buying_power) / price).trunc()
+}
+
+
+/// The buying power related figures of an [`Account`] at a certain
+/// point in time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BuyingPowerSnapshot {
+  /// The time the snapshot was taken.
+  pub time: DateTime<Utc>,
+  /// See `Account::buying_power`.
+  pub buying_power: Num,
+  /// See `Account::regt_buying_power`.
+  pub regt_buying_power: Num,
+  /// See `Account::daytrading_buying_power`.
+  pub daytrading_buying_power: Num,
+  /// See `Account::options_buying_power`.
+  pub options_buying_power: Num,
+  /// See `Account::effective_buying_power`.
+  pub effective_buying_power: Num,
+  /// See `Account::non_marginable_buying_power`.
+  pub non_marginable_buying_power: Num,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl BuyingPowerSnapshot {
+  /// Calculate the change in buying power from this snapshot to
+  /// `other`, i.e., `other` minus `self` for each of the figures.
+  pub fn diff(&self, other: &Self) -> BuyingPowerDelta {
+    BuyingPowerDelta {
+      buying_power: &other.buying_power - &self.buying_power,
+      regt_buying_power: &other.regt_buying_power - &self.regt_buying_power,
+      daytrading_buying_power: &other.daytrading_buying_power - &self.daytrading_buying_power,
+      options_buying_power: &other.options_buying_power - &self.options_buying_power,
+      effective_buying_power: &other.effective_buying_power - &self.effective_buying_power,
+      non_marginable_buying_power: &other.non_marginable_buying_power
+        - &self.non_marginable_buying_power,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// The change in buying power between two [`BuyingPowerSnapshot`]
+/// objects, as calculated by [`BuyingPowerSnapshot::diff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BuyingPowerDelta {
+  /// The change in `buying_power`.
+  pub buying_power: Num,
+  /// The change in `regt_buying_power`.
+  pub regt_buying_power: Num,
+  /// The change in `daytrading_buying_power`.
+  pub daytrading_buying_power: Num,
+  /// The change in `options_buying_power`.
+  pub options_buying_power: Num,
+  /// The change in `effective_buying_power`.
+  pub effective_buying_power: Num,
+  /// The change in `non_marginable_buying_power`.
+  pub non_marginable_buying_power: Num,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// The value of an [`Account`] field, as reported by
+/// [`Account::diff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FieldValue {
+  /// The value of a numeric field.
+  Num(Num),
+  /// The value of a boolean field.
+  Bool(bool),
+  /// The value of a counter field.
+  Count(u64),
+  /// The value of the status field.
+  Status(Status),
+}
+
+
+/// A change to an [`Account`] field, as reported by
+/// [`Account::diff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldChange {
+  /// The name of the changed field, e.g., "cash".
+  pub field: &'static str,
+  /// The old value.
+  pub old: FieldValue,
+  /// The new value.
+  pub new: FieldValue,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A GET request to be made to the /v2/account endpoint, as issued
+/// through [`GetInCurrency`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct GetReq {
+  /// The currency in which to report monetary values, e.g., "EUR".
+  ///
+  /// If not set, values are reported in USD.
+  #[serde(rename = "currency", skip_serializing_if = "Option::is_none")]
+  pub currency: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
 Endpoint! {
   /// The representation of a GET request to the /v2/account endpoint.
   pub Get(()),
@@ -224,10 +553,40 @@ Endpoint! {
   }
 }
 
+
+Endpoint! {
+  /// The representation of a GET request to the /v2/account endpoint,
+  /// optionally reporting monetary values in a currency other than
+  /// USD.
+  pub GetInCurrency(GetReq),
+  Ok => Account, [
+    /// The account information was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetInCurrencyError, []
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/account".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let query = to_query(input)?;
+    Ok((!query.is_empty()).then(|| query.into()))
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  use chrono::Duration;
+
+  use http_endpoint::Endpoint as _;
+
+  use proptest::collection::vec;
+  use proptest::proptest;
+
   use serde_json::from_str as from_json;
   use serde_json::to_string as to_json;
 
@@ -235,11 +594,58 @@ mod tests {
 
   use uuid::Uuid;
 
+  use crate::api::v2::num_util::check_round_trip;
+  use crate::api::v2::num_util::signed_decimal;
   use crate::api::API_BASE_URL;
   use crate::api_info::ApiInfo;
+  use crate::test_util::serve_once;
   use crate::Client;
   use crate::RequestError;
 
+
+  /// The names of the money fields of an [`Account`].
+  const MONEY_FIELDS: [&str; 23] = [
+    "cash",
+    "portfolio_value",
+    "long_market_value",
+    "short_market_value",
+    "equity",
+    "last_equity",
+    "multiplier",
+    "buying_power",
+    "regt_buying_power",
+    "daytrading_buying_power",
+    "options_buying_power",
+    "effective_buying_power",
+    "non_marginable_buying_power",
+    "bod_dtbp",
+    "accrued_fees",
+    "pending_transfer_in",
+    "position_market_value",
+    "initial_margin",
+    "maintenance_margin",
+    "last_maintenance_margin",
+    "sma",
+    "intraday_adjustments",
+    "pending_reg_taf_fees",
+  ];
+
+  /// Check that the currency to report values in is only included in
+  /// the query string if set.
+  #[test]
+  fn serialize_currency_query() {
+    assert_eq!(GetInCurrency::query(&GetReq::default()).unwrap(), None);
+
+    let request = GetReq {
+      currency: Some("EUR".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(
+      GetInCurrency::query(&request).unwrap().unwrap(),
+      "currency=EUR"
+    );
+  }
+
   /// Make sure that we can deserialize and serialize the reference
   /// account object.
   #[test]
@@ -328,6 +734,298 @@ mod tests {
     assert_eq!(acc.pending_reg_taf_fees, Num::from(0));
   }
 
+  /// Create a `Position` for the given symbol with the given cost
+  /// basis.
+  #[cfg(feature = "trading")]
+  fn position(symbol: &str, side: &str, cost_basis: &str) -> Position {
+    let json = format!(
+      r#"{{
+    "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "symbol": "{symbol}",
+    "exchange": "NASDAQ",
+    "asset_class": "us_equity",
+    "avg_entry_price": "100.0",
+    "qty": "5",
+    "qty_available": "5",
+    "side": "{side}",
+    "market_value": null,
+    "cost_basis": "{cost_basis}",
+    "unrealized_pl": null,
+    "unrealized_plpc": null,
+    "unrealized_intraday_pl": null,
+    "unrealized_intraday_plpc": null,
+    "current_price": null,
+    "lastday_price": null,
+    "change_today": null
+}}"#
+    );
+    from_json::<Position>(&json).unwrap()
+  }
+
+  /// Create an `Account` flagged as pattern day trader, with the given
+  /// beginning of day and current day trading buying power.
+  fn pattern_day_trader(bod_dtbp: i64, daytrading_buying_power: i64) -> Account {
+    let json = format!(
+      r#"{{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "admin_configurations": {{}},
+  "user_configurations": null,
+  "account_number": "PALPACA_123",
+  "status": "ACTIVE",
+  "crypto_status": "ACTIVE",
+  "currency": "USD",
+  "buying_power": "100000.0",
+  "regt_buying_power": "50000.0",
+  "daytrading_buying_power": "{daytrading_buying_power}",
+  "options_buying_power": "0.0",
+  "effective_buying_power": "100000.0",
+  "non_marginable_buying_power": "25000.0",
+  "bod_dtbp": "{bod_dtbp}",
+  "cash": "25000.00",
+  "accrued_fees": "0.0",
+  "portfolio_value": "30000.00",
+  "pattern_day_trader": true,
+  "trade_suspended_by_user": false,
+  "trading_blocked": false,
+  "transfers_blocked": false,
+  "account_blocked": false,
+  "created_at": "2018-10-01T13:35:25Z",
+  "shorting_enabled": true,
+  "multiplier": "4",
+  "long_market_value": "5000.00",
+  "short_market_value": "0.00",
+  "position_market_value": "5000.00",
+  "equity": "30000.00",
+  "last_equity": "30000.00",
+  "initial_margin": "2500.00",
+  "maintenance_margin": "1500.00",
+  "last_maintenance_margin": "1500.00",
+  "sma": "0.0",
+  "daytrade_count": 4,
+  "balance_asof": "2018-10-01",
+  "crypto_tier": 1,
+  "options_trading_level": 2,
+  "intraday_adjustments": "0.0",
+  "pending_reg_taf_fees": "0.0"
+}}"#
+    );
+    let account = from_json::<Account>(&json).unwrap();
+    assert!(account.day_trader);
+    account
+  }
+
+  proptest! {
+    /// Check that arbitrary values of money fields of an [`Account`]
+    /// survive a round trip through JSON.
+    #[test]
+    fn round_trip_money_fields(decimals in vec(signed_decimal(), MONEY_FIELDS.len())) {
+      let () = check_round_trip(&pattern_day_trader(0, 0), &MONEY_FIELDS, &decimals)?;
+    }
+  }
+
+  /// Check that money fields are serialized with at most eight
+  /// fractional digits, meaning that more precise values do not
+  /// survive a round trip unaltered.
+  #[test]
+  fn serialize_excess_precision() {
+    let mut account = pattern_day_trader(0, 0);
+    account.cash = Num::new(123456789, 1000000000);
+
+    let json = to_json(&account).unwrap();
+    assert!(json.contains(r#""cash":"0.12345679""#), "{json}");
+    assert_eq!(
+      from_json::<Account>(&json).unwrap().cash,
+      Num::new(12345679, 100000000)
+    );
+  }
+
+  /// Check that an unknown account status is rejected in strict mode
+  /// but mapped to [`Status::Unknown`] otherwise.
+  #[test(tokio::test)]
+  async fn strict_unknown_status() {
+    let json = to_json(&pattern_day_trader(0, 0))
+      .unwrap()
+      .replace(r#""status":"ACTIVE""#, r#""status":"FROZEN""#);
+    assert!(json.contains("FROZEN"));
+
+    let url = serve_once(json.clone()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let account = client.issue::<Get>(&()).await.unwrap();
+    assert_eq!(account.status, Status::Unknown);
+
+    let url = serve_once(json).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder().strict(true).build(api_info);
+    let err = client.issue::<Get>(&()).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(GetError::Conversion(err)) => {
+        assert!(err.to_string().contains("JSON"), "{err}")
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that we report the server provided day trading buying power
+  /// as remaining if no intraday positions are supplied.
+  #[cfg(feature = "trading")]
+  #[test]
+  fn day_trade_buying_power_remaining_without_positions() {
+    let account = pattern_day_trader(100000, 90000);
+    assert_eq!(
+      account.day_trade_buying_power_remaining(None),
+      Num::from(90000)
+    );
+    assert_eq!(
+      account.day_trade_buying_power_remaining(Some(&[])),
+      Num::from(100000)
+    );
+  }
+
+  /// Check that we correctly estimate the remaining day trading buying
+  /// power in the presence of intraday positions.
+  #[cfg(feature = "trading")]
+  #[test]
+  fn day_trade_buying_power_remaining_with_positions() {
+    let account = pattern_day_trader(100000, 90000);
+    let positions = [
+      position("AAPL", "long", "30000.50"),
+      position("SPY", "short", "-20000.25"),
+    ];
+    assert_eq!(
+      account.day_trade_buying_power_remaining(Some(&positions)),
+      Num::new(4999925, 100)
+    );
+
+    let positions = [
+      position("AAPL", "long", "80000"),
+      position("SPY", "long", "40000"),
+    ];
+    assert_eq!(
+      account.day_trade_buying_power_remaining(Some(&positions)),
+      Num::from(0)
+    );
+  }
+
+  /// Check that we calculate the number of affordable shares correctly
+  /// at various prices.
+  #[test]
+  fn max_shares_affordable() {
+    let account = pattern_day_trader(100000, 90000);
+    let shares = |price| account.max_shares_affordable(&price);
+    assert_eq!(shares(Num::from(100)), Num::from(1000));
+    assert_eq!(shares(Num::new(3333, 100)), Num::from(3000));
+    assert_eq!(shares(Num::from(99999)), Num::from(1));
+    assert_eq!(shares(Num::from(100001)), Num::from(0));
+    assert_eq!(shares(Num::from(0)), Num::from(0));
+    assert_eq!(shares(Num::from(-5)), Num::from(0));
+
+    let shares = |price| account.max_shares_affordable_non_marginable(&price);
+    assert_eq!(shares(Num::from(100)), Num::from(250));
+    assert_eq!(shares(Num::from(30000)), Num::from(0));
+
+    assert_eq!(account.max_notional(), Num::from(100000));
+    assert_eq!(account.max_notional_non_marginable(), Num::from(25000));
+  }
+
+  /// Check that `Account::can_afford` compares costs against the
+  /// available buying power.
+  #[test]
+  fn can_afford() {
+    let account = pattern_day_trader(100000, 90000);
+    assert!(account.can_afford(&Num::from(0)));
+    assert!(account.can_afford(&Num::new(9999999, 100)));
+    assert!(account.can_afford(&Num::from(100000)));
+    assert!(!account.can_afford(&Num::new(10000001, 100)));
+    assert!(!account.can_afford(&Num::from(1000000)));
+  }
+
+  /// Make sure that negative buying power does not result in a
+  /// negative number of affordable shares.
+  #[test]
+  fn max_shares_affordable_negative_buying_power() {
+    let mut account = pattern_day_trader(100000, 90000);
+    account.buying_power = Num::from(-500);
+    assert_eq!(account.max_shares_affordable(&Num::from(10)), Num::from(0));
+    assert_eq!(account.max_notional(), Num::from(0));
+  }
+
+  /// Check that we can diff two buying power snapshots.
+  #[test]
+  fn diff_buying_power_snapshots() {
+    let time = DateTime::parse_from_rfc3339("2024-03-01T14:30:00Z")
+      .unwrap()
+      .with_timezone(&Utc);
+    let before = pattern_day_trader(100000, 90000).buying_power_snapshot(time);
+
+    let mut account = pattern_day_trader(100000, 75000);
+    account.buying_power = Num::new(8250050, 100);
+    account.effective_buying_power = Num::from(60000);
+    let after = account.buying_power_snapshot(time + Duration::try_hours(1).unwrap());
+
+    let delta = before.diff(&after);
+    assert_eq!(delta.buying_power, Num::new(-1749950, 100));
+    assert_eq!(delta.regt_buying_power, Num::from(0));
+    assert_eq!(delta.daytrading_buying_power, Num::from(-15000));
+    assert_eq!(delta.options_buying_power, Num::from(0));
+    assert_eq!(delta.effective_buying_power, Num::from(-40000));
+    assert_eq!(delta.non_marginable_buying_power, Num::from(0));
+
+    let delta = after.diff(&before);
+    assert_eq!(delta.daytrading_buying_power, Num::from(15000));
+    assert_eq!(before.diff(&before).buying_power, Num::from(0));
+  }
+
+  /// Check that diffing two accounts reports the changed fields along
+  /// with their old and new values.
+  #[test]
+  fn diff_accounts() {
+    let old = pattern_day_trader(100000, 90000);
+    assert_eq!(old.diff(&old), Vec::new());
+
+    let mut new = old.clone();
+    new.cash = Num::new(2450025, 100);
+    new.status = Status::Rejected;
+
+    let changes = old.diff(&new);
+    let change = |field, old, new| FieldChange {
+      field,
+      old,
+      new,
+      _non_exhaustive: (),
+    };
+    let expected = vec![
+      change(
+        "status",
+        FieldValue::Status(Status::Active),
+        FieldValue::Status(Status::Rejected),
+      ),
+      change(
+        "cash",
+        FieldValue::Num(Num::from(25000)),
+        FieldValue::Num(Num::new(2450025, 100)),
+      ),
+    ];
+    assert_eq!(changes, expected);
+  }
+
+  /// Check that the `Money` helpers tag amounts with the account's
+  /// currency.
+  #[cfg(feature = "money")]
+  #[test]
+  fn money_helpers() {
+    let account = pattern_day_trader(100000, 90000);
+    let cash = account.cash_money();
+    assert_eq!(cash, Money::new(Num::from(25000), "USD"));
+    assert_eq!(account.equity_money().currency(), "USD");
+
+    let total = cash.checked_add(&account.buying_power_money()).unwrap();
+    assert_eq!(total.amount(), &Num::from(125000));
+
+    let eur = Money::new(Num::from(1), "EUR");
+    assert!(account.equity_money().checked_sub(&eur).is_err());
+  }
+
   /// Test that we can retrieve information about the account.
   #[test(tokio::test)]
   async fn request_account() {
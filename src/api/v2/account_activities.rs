@@ -12,6 +12,7 @@ use serde::Deserialize;
 use serde::Deserializer;
 use serde::Serialize;
 use serde_urlencoded::to_string as to_query;
+use serde_variant::to_variant_name;
 
 use crate::api::v2::de::ContentDeserializer;
 use crate::api::v2::de::TaggedContentVisitor;
@@ -394,6 +395,11 @@ pub struct ActivityReq {
   /// The direction in which to report account activities.
   #[serde(rename = "direction")]
   pub direction: Direction,
+  /// The response will contain only activities dated on this date.
+  ///
+  /// This filter cannot be combined with `until` or `after`.
+  #[serde(rename = "date")]
+  pub date: Option<NaiveDate>,
   /// The response will contain only activities until this time.
   #[serde(rename = "until")]
   pub until: Option<DateTime<Utc>>,
@@ -436,12 +442,99 @@ Endpoint! {
 }
 
 
+/// A GET request to be made to the
+/// /v2/account/activities/{activity_type} endpoint.
+///
+/// The request is equivalent to an [`ActivityReq`], except that the
+/// activity type is conveyed by the endpoint used.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct TypedActivityReq {
+  /// See `ActivityReq::direction`.
+  #[serde(rename = "direction")]
+  pub direction: Direction,
+  /// See `ActivityReq::date`.
+  #[serde(rename = "date")]
+  pub date: Option<NaiveDate>,
+  /// See `ActivityReq::until`.
+  #[serde(rename = "until")]
+  pub until: Option<DateTime<Utc>>,
+  /// See `ActivityReq::after`.
+  #[serde(rename = "after")]
+  pub after: Option<DateTime<Utc>>,
+  /// See `ActivityReq::page_size`.
+  #[serde(rename = "page_size")]
+  pub page_size: Option<usize>,
+  /// See `ActivityReq::page_token`.
+  #[serde(rename = "page_token")]
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/account/activities/FILL endpoint, retrieving trade activities
+  /// only.
+  pub GetTrades(TypedActivityReq),
+  Ok => Vec<TradeActivity>, [
+    /// The trade activities were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetTradesError, []
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/account/activities/FILL".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/account/activities/{activity_type} endpoint, retrieving
+  /// non-trade activities of a single type only.
+  ///
+  /// Use [`GetTrades`] for retrieving activities of type
+  /// [`ActivityType::Fill`].
+  pub GetNonTrades((ActivityType, TypedActivityReq)),
+  Ok => Vec<NonTradeActivity>, [
+    /// The non-trade activities were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetNonTradesError, []
+
+  fn path(input: &Self::Input) -> Str {
+    let (type_, _) = input;
+    // SANITY: `ActivityType` is a unit-only enum and so serialization
+    //         of its variants' names cannot fail.
+    let type_ = to_variant_name(type_).unwrap();
+    path!("/v2/account/activities/{}", type_).into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let (_, request) = input;
+    Ok(Some(to_query(request)?.into()))
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
   use chrono::Duration;
 
+  use http::StatusCode;
+
+  use http_endpoint::Endpoint as _;
+
   use serde_json::from_str as from_json;
 
   use test_log::test;
@@ -538,6 +631,126 @@ mod tests {
     assert_eq!(non_trade.per_share_amount, Some(Num::new(108783, 1000000)));
   }
 
+  /// Check that a list of mixed activities is deserialized into the
+  /// appropriate `Activity` variants.
+  #[test]
+  fn parse_mixed_activities() {
+    let response = r#"[{
+  "activity_type": "FILL",
+  "cum_qty": "1",
+  "id": "20190524113406977::8efc7b9a-8b2b-4000-9955-d36e7db0df74",
+  "leaves_qty": "0",
+  "price": "1.63",
+  "qty": "1",
+  "side": "sell",
+  "symbol": "LPCN",
+  "transaction_time": "2019-05-24T15:34:06.977Z",
+  "order_id": "904837e3-3b76-47ec-b432-046db621571b",
+  "type": "fill"
+}, {
+  "activity_type": "DIV",
+  "id": "20190801011955195::5f596936-6f23-4cef-bdf1-3806aae57dbf",
+  "date": "2019-08-01",
+  "net_amount": "1.02",
+  "symbol": "T",
+  "qty": "2",
+  "per_share_amount": "0.51"
+}]"#;
+
+    let activities = from_json::<Vec<Activity>>(response).unwrap();
+    assert_eq!(activities.len(), 2);
+
+    match &activities[0] {
+      Activity::Trade(trade) => {
+        assert_eq!(trade.symbol, "LPCN");
+        assert_eq!(trade.side, Side::Sell);
+      },
+      activity => panic!("received unexpected activity: {activity:?}"),
+    }
+
+    match &activities[1] {
+      Activity::NonTrade(non_trade) => {
+        assert_eq!(non_trade.type_, ActivityType::Dividend);
+        assert_eq!(non_trade.net_amount, Num::new(102, 100));
+        assert_eq!(non_trade.quantity, Some(Num::from(2)));
+      },
+      activity => panic!("received unexpected activity: {activity:?}"),
+    }
+  }
+
+  /// Check that an `ActivityReq` is serialized into the expected query
+  /// string.
+  #[test]
+  fn serialize_activity_request() {
+    let request = ActivityReq {
+      types: vec![ActivityType::Fill, ActivityType::Dividend],
+      direction: Direction::Ascending,
+      date: Some(NaiveDate::from_ymd_opt(2019, 8, 1).unwrap()),
+      page_size: Some(50),
+      page_token: Some("20190801011955195::5f596936".to_string()),
+      ..Default::default()
+    };
+
+    let query = to_query(&request).unwrap();
+    let expected = "activity_types=FILL%2CDIV&direction=asc&date=2019-08-01&page_size=50&page_token=20190801011955195%3A%3A5f596936";
+    assert_eq!(query, expected);
+  }
+
+  /// Check that trade activities can be retrieved with a single type
+  /// endpoint, with the type being conveyed by the path.
+  #[test]
+  fn get_trade_activities() {
+    let request = TypedActivityReq {
+      page_size: Some(10),
+      ..Default::default()
+    };
+    assert_eq!(GetTrades::path(&request), "/v2/account/activities/FILL");
+    assert_eq!(
+      GetTrades::query(&request).unwrap().unwrap(),
+      "direction=desc&page_size=10"
+    );
+
+    let response = br#"[{
+  "activity_type": "FILL",
+  "cum_qty": "1",
+  "id": "20190524113406977::8efc7b9a-8b2b-4000-9955-d36e7db0df74",
+  "leaves_qty": "0",
+  "price": "1.63",
+  "qty": "1",
+  "side": "sell",
+  "symbol": "LPCN",
+  "transaction_time": "2019-05-24T15:34:06.977Z",
+  "order_id": "904837e3-3b76-47ec-b432-046db621571b",
+  "type": "fill"
+}]"#;
+    let trades: Vec<TradeActivity> = GetTrades::evaluate(StatusCode::OK, response).unwrap();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].symbol, "LPCN");
+    assert_eq!(trades[0].side, Side::Sell);
+    assert_eq!(trades[0].price, Num::new(163, 100));
+  }
+
+  /// Check that non-trade activities can be retrieved with a single
+  /// type endpoint.
+  #[test]
+  fn get_non_trade_activities() {
+    let input = (ActivityType::Dividend, TypedActivityReq::default());
+    assert_eq!(GetNonTrades::path(&input), "/v2/account/activities/DIV");
+
+    let response = br#"[{
+  "activity_type": "DIV",
+  "id": "20190801011955195::5f596936-6f23-4cef-bdf1-3806aae57dbf",
+  "date": "2019-08-01",
+  "net_amount": "1.02",
+  "symbol": "T",
+  "per_share_amount": "0.51"
+}]"#;
+    let dividends = GetNonTrades::evaluate(StatusCode::OK, response).unwrap();
+    assert_eq!(dividends.len(), 1);
+    assert_eq!(dividends[0].type_, ActivityType::Dividend);
+    assert_eq!(dividends[0].net_amount, Num::new(102, 100));
+  }
+
   #[test(tokio::test)]
   async fn retrieve_some_activities() {
     let api_info = ApiInfo::from_env().unwrap();
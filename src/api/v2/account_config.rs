@@ -1,6 +1,11 @@
 // Copyright (C) 2020-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::time::Duration;
+
+use futures::stream::unfold;
+use futures::Stream;
+
 use http::Method;
 use http_endpoint::Bytes;
 
@@ -8,6 +13,10 @@ use serde::Deserialize;
 use serde::Serialize;
 use serde_json::to_vec as to_json;
 
+use tokio::time::sleep;
+
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
@@ -92,16 +101,138 @@ Endpoint! {
 }
 
 
+/// A change of the account configuration, as reported by
+/// [`Client::account_configurations`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConfigurationChange {
+  /// The configuration before the change.
+  ///
+  /// This member is `None` for the initial state.
+  pub old: Option<Configuration>,
+  /// The configuration after the change.
+  pub new: Configuration,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// The state of the stream created by
+/// [`Client::account_configurations`].
+struct WatchState {
+  /// The most recently retrieved configuration, if any.
+  config: Option<Configuration>,
+  /// Whether the configuration has been polled before.
+  polled: bool,
+  /// Whether polling has stopped.
+  done: bool,
+}
+
+
+impl Client {
+  /// Create a stream reporting changes to the account configuration.
+  ///
+  /// The configuration is polled every `interval` and a change is
+  /// reported whenever it differs from the previously retrieved one.
+  /// If `initial` is `true`, the configuration as first retrieved is
+  /// reported as well. Errors are reported as `Err` items. Transient
+  /// errors leave the stream intact, with the next poll happening
+  /// after `interval`, while all other errors terminate it.
+  pub fn account_configurations(
+    &self,
+    interval: Duration,
+    initial: bool,
+  ) -> impl Stream<Item = Result<ConfigurationChange, RequestError<GetError>>> + '_ {
+    let state = WatchState {
+      config: None,
+      polled: false,
+      done: false,
+    };
+
+    unfold(state, move |mut state| async move {
+      loop {
+        if state.done {
+          break None
+        }
+
+        if state.polled {
+          let () = sleep(interval).await;
+        }
+        state.polled = true;
+
+        match self.issue::<Get>(&()).await {
+          Ok(new) if state.config == Some(new) => (),
+          Ok(new) => {
+            let old = state.config.replace(new);
+            if old.is_some() || initial {
+              let change = ConfigurationChange {
+                old,
+                new,
+                _non_exhaustive: (),
+              };
+              break Some((Ok(change), state))
+            }
+          },
+          Err(err) => {
+            state.done = !err.is_transient();
+            break Some((Err(err), state))
+          },
+        }
+      }
+    })
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  use std::sync::Mutex;
+
+  use futures::StreamExt as _;
+
   use serde_json::from_str as from_json;
 
   use test_log::test;
 
+  use tokio::time::timeout;
+
   use crate::api_info::ApiInfo;
-  use crate::Client;
+  use crate::test_util::serve_with;
+  use crate::test_util::Reply;
+
+
+  /// A configuration with trading enabled.
+  const ENABLED: &str = r#"{
+  "dtbp_check": "entry",
+  "no_shorting": false,
+  "suspend_trade": false,
+  "trade_confirm_email": "all"
+}"#;
+
+  /// A configuration with trading suspended.
+  const SUSPENDED: &str = r#"{
+  "dtbp_check": "entry",
+  "no_shorting": false,
+  "suspend_trade": true,
+  "trade_confirm_email": "all"
+}"#;
+
+
+  /// Serve the provided configurations, one per connection, on a local
+  /// socket, repeating the last one indefinitely. Return the URL to
+  /// connect to.
+  async fn serve_configs(configs: Vec<&'static str>) -> String {
+    let last = *configs.last().unwrap();
+    let configs = Mutex::new(configs.into_iter());
+    let (url, _requests) = serve_with(move |_request| {
+      let config = configs.lock().unwrap().next().unwrap_or(last);
+      Reply::respond("200 OK", config)
+    })
+    .await;
+    url
+  }
 
 
   #[test]
@@ -119,6 +250,51 @@ mod tests {
     assert!(!config.no_shorting);
   }
 
+  /// Check that we report a single change when the configuration
+  /// changes between polls.
+  #[test(tokio::test)]
+  async fn watch_configuration_change() {
+    let url = serve_configs(vec![ENABLED, SUSPENDED]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let interval = Duration::from_millis(10);
+    let mut stream = Box::pin(client.account_configurations(interval, false));
+
+    let change = stream.next().await.unwrap().unwrap();
+    let old = change.old.unwrap();
+    assert!(!old.trading_suspended);
+    assert!(change.new.trading_suspended);
+    assert_eq!(
+      Configuration {
+        trading_suspended: true,
+        ..old
+      },
+      change.new
+    );
+
+    // The configuration stays the same from here on, so no further
+    // change should be reported.
+    let result = timeout(Duration::from_millis(100), stream.next()).await;
+    assert!(result.is_err(), "{result:?}");
+  }
+
+  /// Verify that the initial configuration is reported if requested.
+  #[test(tokio::test)]
+  async fn watch_configuration_initial() {
+    let url = serve_configs(vec![ENABLED]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let interval = Duration::from_millis(10);
+    let mut stream = Box::pin(client.account_configurations(interval, true));
+
+    let change = stream.next().await.unwrap().unwrap();
+    assert_eq!(change.old, None);
+    assert!(!change.new.trading_suspended);
+
+    let result = timeout(Duration::from_millis(100), stream.next()).await;
+    assert!(result.is_err(), "{result:?}");
+  }
+
   #[test(tokio::test)]
   async fn retrieve_and_update_configuration() {
     let api_info = ApiInfo::from_env().unwrap();
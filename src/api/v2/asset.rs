@@ -8,6 +8,8 @@ use std::fmt::Result as FmtResult;
 use std::ops::Deref;
 use std::str::FromStr;
 
+use num_decimal::Num;
+
 use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
@@ -15,11 +17,16 @@ use serde::Serializer;
 use uuid::Error as UuidError;
 use uuid::Uuid;
 
+use crate::endpoint::encode_segment;
+use crate::endpoint::PathSegment;
+use crate::util::strict_variant;
+use crate::util::vec_from_str;
+use crate::util::UnknownVariant;
 use crate::Str;
 
 
 /// An ID uniquely identifying an asset.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct Id(pub Uuid);
 
 impl Deref for Id {
@@ -42,6 +49,9 @@ pub enum Class {
   /// Crypto currencies.
   #[serde(rename = "crypto")]
   Crypto,
+  /// US options.
+  #[serde(rename = "us_option")]
+  UsOption,
   /// Any other asset class that we have not accounted for.
   ///
   /// Note that having any such unknown asset class should be considered
@@ -51,12 +61,20 @@ pub enum Class {
   Unknown,
 }
 
+impl UnknownVariant for Class {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 impl AsRef<str> for Class {
   #[inline]
   fn as_ref(&self) -> &'static str {
     match *self {
       Class::UsEquity => "us_equity",
       Class::Crypto => "crypto",
+      Class::UsOption => "us_option",
       Class::Unknown => "unknown",
     }
   }
@@ -78,6 +96,8 @@ impl FromStr for Class {
       Ok(Class::UsEquity)
     } else if s == Class::Crypto.as_ref() {
       Ok(Class::Crypto)
+    } else if s == Class::UsOption.as_ref() {
+      Ok(Class::UsOption)
     } else {
       // Note that we do not support creating the `Unknown` variant
       // here. This variant is really only meant to cover
@@ -107,6 +127,13 @@ pub enum Status {
   Unknown,
 }
 
+impl UnknownVariant for Status {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 impl AsRef<str> for Status {
   #[inline]
   fn as_ref(&self) -> &'static str {
@@ -240,6 +267,12 @@ impl Display for Symbol {
   }
 }
 
+impl PathSegment for Symbol {
+  fn to_segment(&self) -> String {
+    encode_segment(&self.to_string())
+  }
+}
+
 impl Serialize for Symbol {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
@@ -285,6 +318,13 @@ pub enum Exchange {
   Unknown,
 }
 
+impl UnknownVariant for Exchange {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 impl AsRef<str> for Exchange {
   fn as_ref(&self) -> &'static str {
     match *self {
@@ -316,6 +356,8 @@ impl FromStr for Exchange {
       Ok(Exchange::Nyse)
     } else if s == Exchange::Nysearca.as_ref() {
       Ok(Exchange::Nysearca)
+    } else if s == Exchange::Otc.as_ref() {
+      Ok(Exchange::Otc)
     } else {
       // Note that we do not support creating the `Unknown` variant
       // here. This variant is really only meant to cover
@@ -333,16 +375,16 @@ pub struct Asset {
   #[serde(rename = "id")]
   pub id: Id,
   /// The asset's class.
-  #[serde(rename = "class")]
+  #[serde(rename = "class", deserialize_with = "strict_variant")]
   pub class: Class,
   /// The exchange the asset is traded at.
-  #[serde(rename = "exchange")]
+  #[serde(rename = "exchange", deserialize_with = "strict_variant")]
   pub exchange: Exchange,
   /// The asset's symbol.
   #[serde(rename = "symbol")]
   pub symbol: String,
   /// The asset's status.
-  #[serde(rename = "status")]
+  #[serde(rename = "status", deserialize_with = "strict_variant")]
   pub status: Status,
   /// Whether the asset is tradable on Alpaca or not.
   #[serde(rename = "tradable")]
@@ -361,11 +403,78 @@ pub struct Asset {
   /// Whether the asset is fractionable or not.
   #[serde(rename = "fractionable")]
   pub fractionable: bool,
+  /// Additional attributes of the asset, e.g., `fractional_eh_enabled`
+  /// or `options_enabled`.
+  #[serde(rename = "attributes", default, deserialize_with = "vec_from_str")]
+  pub attributes: Vec<String>,
+  /// The minimum quantity an order for the asset has to be for.
+  ///
+  /// This value is only reported for crypto currencies.
+  #[serde(
+    rename = "min_order_size",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub min_order_size: Option<Num>,
+  /// The increment in which the asset's quantity can be traded.
+  ///
+  /// This value is only reported for crypto currencies.
+  #[serde(
+    rename = "min_trade_increment",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub min_trade_increment: Option<Num>,
+  /// The increment in which the asset's price is quoted.
+  ///
+  /// This value is only reported for crypto currencies.
+  #[serde(
+    rename = "price_increment",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub price_increment: Option<Num>,
   #[doc(hidden)]
   #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
+impl Asset {
+  /// Check whether the asset carries the given attribute.
+  #[inline]
+  fn has_attribute(&self, attribute: &str) -> bool {
+    self.attributes.iter().any(|other| other == attribute)
+  }
+
+  /// Check whether the asset can be traded in fractional quantities.
+  ///
+  /// Besides the `fractionable` flag, the `fractional_eh_enabled`
+  /// attribute, indicating fractional trading during extended hours, is
+  /// taken into account.
+  #[inline]
+  pub fn is_fractionable(&self) -> bool {
+    self.fractionable || self.has_attribute("fractional_eh_enabled")
+  }
+
+  /// Check whether options can be traded on the asset.
+  #[inline]
+  pub fn supports_options(&self) -> bool {
+    self.has_attribute("options_enabled")
+  }
+
+  /// Round the provided quantity down (towards zero) to the asset's
+  /// minimum trade increment.
+  ///
+  /// The quantity is reported unchanged if the asset does not have a
+  /// trade increment.
+  pub fn round_qty_to_increment(&self, qty: &Num) -> Num {
+    match &self.min_trade_increment {
+      Some(increment) if !increment.is_zero() => (qty / increment).trunc() * increment,
+      _ => qty.clone(),
+    }
+  }
+}
+
 
 Endpoint! {
   /// The representation of a GET request to the /v2/assets/{symbol} endpoint.
@@ -381,7 +490,7 @@ Endpoint! {
 
   #[inline]
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/assets/{input}").into()
+    path!("/v2/assets/{}", input).into()
   }
 }
 
@@ -440,6 +549,36 @@ mod tests {
     );
   }
 
+  /// Check that the textual representations of asset classes and
+  /// exchanges can be parsed back.
+  #[test]
+  fn class_exchange_from_str() {
+    for class in [Class::UsEquity, Class::Crypto, Class::UsOption] {
+      assert_eq!(Class::from_str(class.as_ref()), Ok(class));
+    }
+    assert_eq!(Class::from_str("unknown"), Err(()));
+
+    for exchange in [
+      Exchange::Amex,
+      Exchange::Arca,
+      Exchange::Bats,
+      Exchange::Nasdaq,
+      Exchange::Nyse,
+      Exchange::Nysearca,
+      Exchange::Otc,
+    ] {
+      assert_eq!(Exchange::from_str(exchange.as_ref()), Ok(exchange));
+    }
+    assert_eq!(Exchange::from_str("unknown"), Err(()));
+  }
+
+  /// Verify that we can deserialize an options asset class.
+  #[test]
+  fn parse_option_class() {
+    let class = from_json::<Class>(r#""us_option""#).unwrap();
+    assert_eq!(class, Class::UsOption);
+  }
+
   /// Make sure that we can serialize and deserialize a symbol.
   #[test]
   fn serialize_deserialize_symbol() {
@@ -492,6 +631,52 @@ mod tests {
     assert!(asset.marginable);
     assert!(asset.shortable);
     assert!(asset.easy_to_borrow);
+    assert_eq!(asset.min_order_size, None);
+    assert_eq!(asset.min_trade_increment, None);
+    assert_eq!(asset.price_increment, None);
+  }
+
+  /// Check that we can parse the order size and increment metadata of
+  /// a crypto currency asset and round quantities to its increment.
+  #[test]
+  fn parse_crypto_asset() {
+    let response = r#"{
+  "id": "276e2673-764b-4ab6-a611-caf665ca6340",
+  "class": "crypto",
+  "exchange": "CRYPTO",
+  "symbol": "BTC/USD",
+  "name": "Bitcoin  / US Dollar",
+  "status": "active",
+  "tradable": true,
+  "marginable": false,
+  "maintenance_margin_requirement": 100,
+  "shortable": false,
+  "easy_to_borrow": false,
+  "fractionable": true,
+  "attributes": [],
+  "min_order_size": "0.000026873",
+  "min_trade_increment": "0.000000001",
+  "price_increment": "1"
+}"#;
+
+    let asset = from_json::<Asset>(response).unwrap();
+    assert_eq!(asset.class, Class::Crypto);
+    assert_eq!(asset.min_order_size, Some(Num::new(26873, 1_000_000_000)));
+    assert_eq!(asset.min_trade_increment, Some(Num::new(1, 1_000_000_000)));
+    assert_eq!(asset.price_increment, Some(Num::from(1)));
+
+    let qty = Num::new(12_345_678_912_345_i64, 1_000_000_000_000_i64);
+    assert_eq!(
+      asset.round_qty_to_increment(&qty),
+      Num::new(12_345_678_912_i64, 1_000_000_000)
+    );
+    assert_eq!(asset.round_qty_to_increment(&Num::from(2)), Num::from(2));
+
+    // Equities do not report any of the metadata and quantities are
+    // left untouched.
+    let mut asset = asset;
+    asset.min_trade_increment = None;
+    assert_eq!(asset.round_qty_to_increment(&qty), qty);
   }
 
   /// Verify that we can parse an asset object with an unknown exchange.
@@ -514,6 +699,43 @@ mod tests {
     assert_eq!(asset.exchange, Exchange::Unknown);
   }
 
+  /// Check that we can parse an asset object with a populated
+  /// attributes array.
+  #[test]
+  fn parse_with_attributes() {
+    let response = r#"{
+  "id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+  "class": "us_equity",
+  "exchange": "NASDAQ",
+  "symbol": "AAPL",
+  "status": "active",
+  "tradable": true,
+  "marginable": true,
+  "shortable": true,
+  "easy_to_borrow": true,
+  "fractionable": false,
+  "attributes": ["fractional_eh_enabled", "options_enabled"]
+}"#;
+
+    let asset = from_json::<Asset>(response).unwrap();
+    assert_eq!(
+      asset.attributes,
+      vec!["fractional_eh_enabled", "options_enabled"]
+    );
+    assert!(asset.is_fractionable());
+    assert!(asset.supports_options());
+
+    let response = response.replace(r#""fractional_eh_enabled", "options_enabled""#, "");
+    let asset = from_json::<Asset>(&response).unwrap();
+    assert!(asset.attributes.is_empty());
+    assert!(!asset.is_fractionable());
+    assert!(!asset.supports_options());
+
+    let response = response.replace("[]", "null");
+    let asset = from_json::<Asset>(&response).unwrap();
+    assert!(asset.attributes.is_empty());
+  }
+
   /// Check that we can serialize and deserialize an `Asset` object.
   #[test(tokio::test)]
   async fn serialize_deserialize_asset() {
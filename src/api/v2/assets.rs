@@ -1,16 +1,118 @@
 // Copyright (C) 2019-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use http_endpoint::Endpoint as _;
+
+use serde::de::Error as _;
 use serde::Deserialize;
+use serde::Deserializer;
 use serde::Serialize;
+use serde::Serializer;
 use serde_urlencoded::to_string as to_query;
 
 use crate::api::v2::asset::Asset;
 use crate::api::v2::asset::Class;
+use crate::api::v2::asset::Exchange;
 use crate::api::v2::asset::Status;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
+/// The set of asset attributes used for filtering listed assets.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Attributes {
+  /// The `fractional_eh_enabled` attribute, marking assets that can
+  /// be traded in fractional quantities during extended hours.
+  pub fractional_eh_enabled: bool,
+  /// The `has_options` attribute, marking assets with listed options.
+  pub has_options: bool,
+  /// The `ipo` attribute, marking assets that are part of an initial
+  /// public offering.
+  pub ipo: bool,
+  /// The `options_enabled` attribute, marking assets on which options
+  /// can be traded.
+  pub options_enabled: bool,
+  /// The `ptp_no_exception` attribute, marking publicly traded
+  /// partnerships without a qualified notice exception.
+  pub ptp_no_exception: bool,
+  /// The `ptp_with_exception` attribute, marking publicly traded
+  /// partnerships with a qualified notice exception.
+  pub ptp_with_exception: bool,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl Attributes {
+  /// Retrieve the flag corresponding to each attribute along with
+  /// the attribute's name.
+  fn flags(&self) -> [(bool, &'static str); 6] {
+    [
+      (self.fractional_eh_enabled, "fractional_eh_enabled"),
+      (self.has_options, "has_options"),
+      (self.ipo, "ipo"),
+      (self.options_enabled, "options_enabled"),
+      (self.ptp_no_exception, "ptp_no_exception"),
+      (self.ptp_with_exception, "ptp_with_exception"),
+    ]
+  }
+
+  /// Check whether no attribute is set.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.flags().iter().all(|(set, _name)| !set)
+  }
+}
+
+impl Serialize for Attributes {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    let names = self
+      .flags()
+      .iter()
+      .filter(|(set, _name)| *set)
+      .map(|(_set, name)| *name)
+      .collect::<Vec<_>>();
+
+    if !names.is_empty() {
+      serializer.serialize_str(&names.join(","))
+    } else {
+      serializer.serialize_none()
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for Attributes {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let mut attributes = Self::default();
+    let string = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+
+    for name in string.split(',').filter(|name| !name.is_empty()) {
+      let flag = match name {
+        "fractional_eh_enabled" => &mut attributes.fractional_eh_enabled,
+        "has_options" => &mut attributes.has_options,
+        "ipo" => &mut attributes.ipo,
+        "options_enabled" => &mut attributes.options_enabled,
+        "ptp_no_exception" => &mut attributes.ptp_no_exception,
+        "ptp_with_exception" => &mut attributes.ptp_with_exception,
+        _ => return Err(D::Error::custom(format!("unknown asset attribute: {name}"))),
+      };
+      *flag = true;
+    }
+    Ok(attributes)
+  }
+}
+
+
 /// A GET request to be made to the /v2/assets endpoint.
 #[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ListReq {
@@ -20,6 +122,21 @@ pub struct ListReq {
   /// The asset class of which to include assets in the response.
   #[serde(rename = "asset_class")]
   pub class: Class,
+  /// The exchange of which to include assets in the response.
+  ///
+  /// If not set, assets from all exchanges are included.
+  #[serde(rename = "exchange")]
+  pub exchange: Option<Exchange>,
+  /// The attributes of which assets have to have at least one to be
+  /// included in the response.
+  ///
+  /// If empty, assets are not filtered by attributes.
+  #[serde(
+    rename = "attributes",
+    default,
+    skip_serializing_if = "Attributes::is_empty"
+  )]
+  pub attributes: Attributes,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
@@ -47,6 +164,79 @@ Endpoint! {
 }
 
 
+/// A cache of asset lists, as used by [`Client::list_assets_cached`].
+///
+/// Lists are cached along with their entity tag, separately for each
+/// distinct request. A cache is held by each [`Client`] and can be
+/// accessed via [`Client::assets_cache`].
+#[derive(Debug, Default)]
+pub struct AssetsCache {
+  /// The cached lists along with their entity tags, keyed by the query
+  /// of the request that retrieved them.
+  entries: Mutex<HashMap<String, (String, Vec<Asset>)>>,
+}
+
+impl AssetsCache {
+  /// Remove all cached asset lists.
+  pub fn clear(&self) {
+    let () = self.entries.lock().unwrap().clear();
+  }
+}
+
+
+impl Client {
+  /// Retrieve a list of assets, reusing a previously retrieved one if
+  /// it has not changed since.
+  ///
+  /// The entity tag reported along with a list is stored in the
+  /// client's [`AssetsCache`] and sent along with subsequent requests
+  /// for the same list. If the server reports that the list was not
+  /// modified, the cached list is reported without transferring or
+  /// parsing it again.
+  pub async fn list_assets_cached(
+    &self,
+    request: &ListReq,
+  ) -> Result<Vec<Asset>, RequestError<ListError>> {
+    let key = List::query(request)
+      .map_err(|err| RequestError::Endpoint(ListError::from(err)))?
+      .unwrap_or_default()
+      .into_owned();
+    let etag = self
+      .assets_cache()
+      .entries
+      .lock()
+      .unwrap()
+      .get(&key)
+      .map(|(etag, _assets)| etag.clone());
+
+    let (assets, meta) = self
+      .issue_conditional::<List>(request, etag.as_deref())
+      .await?;
+    let cached = {
+      let mut entries = self.assets_cache().entries.lock().unwrap();
+      match assets {
+        Some(assets) => {
+          let _prev = match meta.etag {
+            Some(etag) => entries.insert(key, (etag, assets.clone())),
+            None => entries.remove(&key),
+          };
+          Some(assets)
+        },
+        None => entries.get(&key).map(|(_etag, assets)| assets.clone()),
+      }
+    };
+
+    match cached {
+      Some(assets) => Ok(assets),
+      // The server reported the list as not modified, but we do not
+      // have it cached (anymore), e.g., because the cache got cleared
+      // concurrently. Retrieve it unconditionally instead.
+      None => self.issue::<List>(request).await,
+    }
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -56,9 +246,31 @@ mod tests {
 
   use test_log::test;
 
-  use crate::api::v2::asset::Exchange;
   use crate::api_info::ApiInfo;
-  use crate::Client;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::Reply;
+  use crate::test_util::Response;
+
+
+  /// A reference list of assets.
+  const ASSETS: &str = r#"[{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "class": "us_equity",
+  "exchange": "NASDAQ",
+  "symbol": "AAPL",
+  "status": "active",
+  "tradable": true,
+  "marginable": true,
+  "shortable": true,
+  "easy_to_borrow": true,
+  "fractionable": true
+}]"#;
+
+
+  /// Create a reply with the given status line, entity tag, and body.
+  fn response(status: &'static str, etag: &str, body: &'static str) -> Reply {
+    Response::new(status, body).header("etag", etag).into()
+  }
 
 
   /// Check that we can serialize and deserialize a [`ListReq`].
@@ -72,8 +284,103 @@ mod tests {
 
     let json = to_json(&request).unwrap();
     assert_eq!(from_json::<ListReq>(&json).unwrap(), request);
+
+    let request = ListReq {
+      attributes: Attributes {
+        ipo: true,
+        has_options: true,
+        ..Default::default()
+      },
+      ..request
+    };
+    let json = to_json(&request).unwrap();
+    assert_eq!(from_json::<ListReq>(&json).unwrap(), request);
   }
 
+  /// Make sure that unknown asset attributes are rejected when
+  /// deserializing a [`ListReq`].
+  #[test]
+  fn deserialize_unknown_attribute() {
+    let json = br#"{"status": "active", "asset_class": "us_equity", "attributes": "foo"}"#;
+    let err = from_json::<ListReq>(json).unwrap_err();
+    assert!(err.to_string().contains("foo"), "{err}");
+  }
+
+  /// Check that a [`ListReq`] is serialized into the expected query
+  /// string.
+  #[test]
+  fn serialize_list_request_query() {
+    let request = ListReq::default();
+    let query = List::query(&request).unwrap().unwrap();
+    assert_eq!(query, "status=active&asset_class=us_equity");
+
+    let request = ListReq {
+      status: Status::Active,
+      class: Class::UsEquity,
+      exchange: Some(Exchange::Nyse),
+      ..Default::default()
+    };
+    let query = List::query(&request).unwrap().unwrap();
+    assert_eq!(query, "status=active&asset_class=us_equity&exchange=NYSE");
+
+    let request = ListReq {
+      status: Status::Inactive,
+      attributes: Attributes {
+        options_enabled: true,
+        fractional_eh_enabled: true,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let query = List::query(&request).unwrap().unwrap();
+    assert_eq!(
+      query,
+      "status=inactive&asset_class=us_equity&attributes=fractional_eh_enabled%2Coptions_enabled"
+    );
+  }
+
+
+  /// Check that asset lists are cached based on their entity tag and
+  /// reported without being transferred again if not modified.
+  #[test(tokio::test)]
+  async fn list_cached_assets() {
+    let (url, requests) = serve_sequence(vec![
+      response("200 OK", r#""v1""#, ASSETS),
+      response("304 Not Modified", r#""v1""#, ""),
+      response("200 OK", r#""v1""#, ASSETS),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let request = ListReq::default();
+
+    let assets = client.list_assets_cached(&request).await.unwrap();
+    assert_eq!(assets.len(), 1);
+    assert_eq!(assets[0].symbol, "AAPL");
+
+    // The second response has an empty body, which would fail to parse
+    // if it were evaluated.
+    let cached = client.list_assets_cached(&request).await.unwrap();
+    assert_eq!(cached, assets);
+
+    let () = client.assets_cache().clear();
+    let refreshed = client.list_assets_cached(&request).await.unwrap();
+    assert_eq!(refreshed, assets);
+
+    let requests = requests
+      .all()
+      .into_iter()
+      .map(|request| request.to_lowercase())
+      .collect::<Vec<_>>();
+    assert_eq!(requests.len(), 3);
+    assert!(!requests[0].contains("if-none-match"), "{}", requests[0]);
+    assert!(
+      requests[1].contains("if-none-match: \"v1\"\r\n"),
+      "{}",
+      requests[1]
+    );
+    assert!(!requests[2].contains("if-none-match"), "{}", requests[2]);
+  }
 
   /// Make sure that we can list available US stock assets.
   #[test(tokio::test)]
@@ -3,8 +3,11 @@
 
 use std::ops::Range;
 
+use chrono::Datelike as _;
+use chrono::FixedOffset;
 use chrono::NaiveDate;
 use chrono::NaiveTime;
+use chrono::Weekday;
 
 use serde::de::Error;
 use serde::de::Unexpected;
@@ -39,6 +42,54 @@ where
   serializer.serialize_str(&time.format("%H:%M").to_string())
 }
 
+/// Deserialize a `NaiveTime` from a string without separator.
+fn deserialize_compact_naive_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let string = String::deserialize(deserializer)?;
+  NaiveTime::parse_from_str(&string, "%H%M").map_err(|_| {
+    Error::invalid_value(
+      Unexpected::Str(&string),
+      &"a time stamp string in format %H%M",
+    )
+  })
+}
+
+/// Serialize a `NaiveTime` into a string without separator.
+fn serialize_compact_naive_time<S>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+  S: Serializer,
+{
+  serializer.serialize_str(&time.format("%H%M").to_string())
+}
+
+
+/// Retrieve the UTC offset of the time zone the market operates in
+/// (US Eastern time) on the given date.
+///
+/// All dates and times reported by the calendar are to be interpreted
+/// in this time zone.
+pub(crate) fn market_offset(date: NaiveDate) -> FixedOffset {
+  // Daylight saving time starts on the second Sunday in March and ends
+  // on the first Sunday in November. The switch happens at 2am local
+  // time, which is outside of any trading session, and so we don't
+  // bother being more precise than day granularity.
+  let year = date.year();
+  // SANITY: The second Sunday in March and the first Sunday in
+  //         November always exist.
+  let dst_start = NaiveDate::from_weekday_of_month_opt(year, 3, Weekday::Sun, 2).unwrap();
+  let dst_end = NaiveDate::from_weekday_of_month_opt(year, 11, Weekday::Sun, 1).unwrap();
+
+  let hours = if date >= dst_start && date < dst_end {
+    -4
+  } else {
+    -5
+  };
+  // SANITY: The offset is always in bounds.
+  FixedOffset::east_opt(hours * 60 * 60).unwrap()
+}
+
 
 /// The market open and close times for a specific date.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -60,6 +111,20 @@ pub struct OpenClose {
     serialize_with = "serialize_naive_time"
   )]
   pub close: NaiveTime,
+  /// The time the extended hours trading session starts at.
+  #[serde(
+    rename = "session_open",
+    deserialize_with = "deserialize_compact_naive_time",
+    serialize_with = "serialize_compact_naive_time"
+  )]
+  pub session_open: NaiveTime,
+  /// The time the extended hours trading session ends at.
+  #[serde(
+    rename = "session_close",
+    deserialize_with = "deserialize_compact_naive_time",
+    serialize_with = "serialize_compact_naive_time"
+  )]
+  pub session_close: NaiveTime,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
@@ -67,6 +132,88 @@ pub struct OpenClose {
 }
 
 
+/// Check whether the given date is a trading day as per the provided
+/// calendar.
+///
+/// The calendar only lists days on which the market is open. Note that
+/// `false` is also reported for dates outside of the range that the
+/// calendar covers.
+pub fn is_trading_day(calendar: &[OpenClose], date: NaiveDate) -> bool {
+  calendar.iter().any(|day| day.date == date)
+}
+
+
+/// Count the number of trading days in the given date range as per the
+/// provided calendar.
+pub fn trading_days(calendar: &[OpenClose], range: Range<NaiveDate>) -> usize {
+  calendar
+    .iter()
+    .filter(|day| range.contains(&day.date))
+    .count()
+}
+
+
+/// Check whether the given date is within the range of dates that the
+/// provided calendar covers.
+fn covers(calendar: &[OpenClose], date: NaiveDate) -> bool {
+  match (calendar.first(), calendar.last()) {
+    (Some(first), Some(last)) => first.date <= date && date <= last.date,
+    _ => false,
+  }
+}
+
+
+/// Find the trading day `n` trading days away from the given date as
+/// per the provided calendar.
+///
+/// A positive `n` moves forward in time, a negative one backward. The
+/// date itself does not have to be a trading day, in which case one
+/// trading day away is the next or previous trading day, respectively.
+/// For an `n` of zero the date is reported as is.
+///
+/// The calendar is expected to be sorted by date, as reported by the
+/// API. `None` is reported if the date or the resulting trading day is
+/// outside of the range that the calendar covers.
+pub fn add_trading_days(calendar: &[OpenClose], date: NaiveDate, n: i64) -> Option<NaiveDate> {
+  if !covers(calendar, date) {
+    return None
+  }
+
+  let count = usize::try_from(n.unsigned_abs()).ok()?;
+  let index = if n > 0 {
+    let after = calendar.partition_point(|day| day.date <= date);
+    after.checked_add(count - 1)?
+  } else if n < 0 {
+    let before = calendar.partition_point(|day| day.date < date);
+    before.checked_sub(count)?
+  } else {
+    return Some(date)
+  };
+
+  calendar.get(index).map(|day| day.date)
+}
+
+
+/// Find the last trading day before the given date as per the provided
+/// calendar.
+///
+/// Please refer to [`add_trading_days`] for details.
+#[inline]
+pub fn previous_trading_day(calendar: &[OpenClose], date: NaiveDate) -> Option<NaiveDate> {
+  add_trading_days(calendar, date, -1)
+}
+
+
+/// Find the first trading day after the given date as per the provided
+/// calendar.
+///
+/// Please refer to [`add_trading_days`] for details.
+#[inline]
+pub fn next_trading_day(calendar: &[OpenClose], date: NaiveDate) -> Option<NaiveDate> {
+  add_trading_days(calendar, date, 1)
+}
+
+
 /// A GET request to be made to the /v2/calendar endpoint.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ListReq {
@@ -149,10 +296,27 @@ mod tests {
 
   use serde_json::from_slice as from_json;
   use serde_json::to_vec as to_json;
+  use serde_urlencoded::to_string as to_query;
 
   use test_log::test;
 
 
+  /// Check that we infer the market's UTC offset correctly.
+  #[test]
+  fn market_time_offset() {
+    let offset = |y, m, d| market_offset(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+    let est = FixedOffset::west_opt(5 * 60 * 60).unwrap();
+    let edt = FixedOffset::west_opt(4 * 60 * 60).unwrap();
+
+    assert_eq!(offset(2024, 1, 2), est);
+    assert_eq!(offset(2024, 3, 8), est);
+    assert_eq!(offset(2024, 3, 11), edt);
+    assert_eq!(offset(2024, 7, 3), edt);
+    assert_eq!(offset(2024, 11, 1), edt);
+    assert_eq!(offset(2024, 11, 4), est);
+    assert_eq!(offset(2024, 12, 24), est);
+  }
+
   /// Check that we can serialize and deserialize an `OpenClose` object.
   #[test]
   fn serialize_deserialize_open_close() {
@@ -160,6 +324,8 @@ mod tests {
       date: NaiveDate::from_ymd_opt(2020, 4, 9).unwrap(),
       open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
       close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+      session_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      session_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
       _non_exhaustive: (),
     };
 
@@ -167,6 +333,99 @@ mod tests {
     assert_eq!(from_json::<OpenClose>(&json).unwrap(), open_close);
   }
 
+  /// Check that we can parse the times of a reference calendar entry.
+  #[test]
+  fn parse_reference_open_close() {
+    let serialized = br#"{
+  "date": "2024-07-03",
+  "open": "09:30",
+  "close": "13:00",
+  "session_open": "0400",
+  "session_close": "1700",
+  "settlement_date": "2024-07-05"
+}"#;
+    let open_close = from_json::<OpenClose>(serialized).unwrap();
+    assert_eq!(
+      open_close.date,
+      NaiveDate::from_ymd_opt(2024, 7, 3).unwrap()
+    );
+    assert_eq!(open_close.open, NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    assert_eq!(open_close.close, NaiveTime::from_hms_opt(13, 0, 0).unwrap());
+    assert_eq!(
+      open_close.session_open,
+      NaiveTime::from_hms_opt(4, 0, 0).unwrap()
+    );
+    assert_eq!(
+      open_close.session_close,
+      NaiveTime::from_hms_opt(17, 0, 0).unwrap()
+    );
+  }
+
+  /// Check that we can check for and count trading days.
+  #[test]
+  fn count_trading_days() {
+    let day = |day| OpenClose {
+      date: NaiveDate::from_ymd_opt(2024, 7, day).unwrap(),
+      open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+      session_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      session_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+      _non_exhaustive: (),
+    };
+    let date = |day| NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+    // July 4th is a holiday.
+    let calendar = [day(1), day(2), day(3), day(5), day(8)];
+
+    assert!(is_trading_day(&calendar, date(3)));
+    assert!(!is_trading_day(&calendar, date(4)));
+    assert!(!is_trading_day(&calendar, date(6)));
+    assert!(!is_trading_day(&calendar, date(9)));
+
+    assert_eq!(trading_days(&calendar, date(1)..date(8)), 4);
+    assert_eq!(trading_days(&calendar, date(3)..date(6)), 2);
+    assert_eq!(trading_days(&calendar, date(6)..date(8)), 0);
+    assert_eq!(trading_days(&calendar, date(1)..date(31)), 5);
+  }
+
+  /// Verify that we can step over trading days, skipping weekends and
+  /// holidays.
+  #[test]
+  fn trading_day_arithmetic() {
+    let day = |day| OpenClose {
+      date: NaiveDate::from_ymd_opt(2024, 7, day).unwrap(),
+      open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+      session_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      session_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+      _non_exhaustive: (),
+    };
+    let date = |day| NaiveDate::from_ymd_opt(2024, 7, day).unwrap();
+    // July 4th is a holiday and the 6th and 7th are a weekend.
+    let calendar = [day(1), day(2), day(3), day(5), day(8), day(9)];
+
+    assert_eq!(next_trading_day(&calendar, date(3)), Some(date(5)));
+    assert_eq!(next_trading_day(&calendar, date(4)), Some(date(5)));
+    assert_eq!(next_trading_day(&calendar, date(5)), Some(date(8)));
+    assert_eq!(next_trading_day(&calendar, date(6)), Some(date(8)));
+    assert_eq!(previous_trading_day(&calendar, date(5)), Some(date(3)));
+    assert_eq!(previous_trading_day(&calendar, date(7)), Some(date(5)));
+    assert_eq!(previous_trading_day(&calendar, date(8)), Some(date(5)));
+
+    assert_eq!(add_trading_days(&calendar, date(2), 3), Some(date(8)));
+    assert_eq!(add_trading_days(&calendar, date(9), -4), Some(date(2)));
+    assert_eq!(add_trading_days(&calendar, date(4), 0), Some(date(4)));
+    assert_eq!(add_trading_days(&calendar, date(1), 5), Some(date(9)));
+
+    // Results outside of the calendar's range are not guessed.
+    assert_eq!(previous_trading_day(&calendar, date(1)), None);
+    assert_eq!(next_trading_day(&calendar, date(9)), None);
+    assert_eq!(add_trading_days(&calendar, date(1), 6), None);
+    assert_eq!(add_trading_days(&calendar, date(3), -3), None);
+    assert_eq!(next_trading_day(&calendar, date(10)), None);
+    assert_eq!(previous_trading_day(&calendar, date(12)), None);
+    assert_eq!(next_trading_day(&[], date(1)), None);
+  }
+
   /// Check that we error out as expected when failing to parse an
   /// `OpenClose` object because the time format is unexpected.
   #[test]
@@ -189,6 +448,18 @@ mod tests {
     assert_eq!(from_json::<ListReq>(&json).unwrap(), request);
   }
 
+  /// Check that a [`ListReq`] for a bounded date range is serialized
+  /// into the expected query string.
+  #[test]
+  fn serialize_calendar_request_query() {
+    let start = NaiveDate::from_ymd_opt(2020, 4, 6).unwrap();
+    let end = NaiveDate::from_ymd_opt(2020, 4, 10).unwrap();
+    let request = ListReq::from(start..end);
+
+    let query = to_query(request).unwrap();
+    assert_eq!(query, "start=2020-04-06&end=2020-04-10");
+  }
+
   /// Check that we can retrieve the market calendar for a specific time
   /// frame.
   #[test(tokio::test)]
@@ -208,6 +479,8 @@ mod tests {
         date: NaiveDate::from_ymd_opt(2020, 4, day).unwrap(),
         open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
         close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+        session_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+        session_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
         _non_exhaustive: (),
       })
       .collect::<Vec<_>>();
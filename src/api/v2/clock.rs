@@ -1,12 +1,28 @@
 // Copyright (C) 2019-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::sync::Mutex;
+use std::time::Duration;
+
 use chrono::DateTime;
+use chrono::NaiveTime;
 use chrono::Utc;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+use thiserror::Error as ThisError;
+
+use tokio::time::sleep;
+use tokio::time::Instant;
+
+use tracing::warn;
+
+use crate::api::v2::calendar;
+use crate::api::v2::calendar::market_offset;
+use crate::api::v2::calendar::OpenClose;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
@@ -16,7 +32,10 @@ pub struct Clock {
   /// An indication whether the market is currently open or not.
   #[serde(rename = "is_open")]
   pub open: bool,
-  /// The current time.
+  /// The current time, as reported by the server.
+  ///
+  /// Comparing this time stamp to the local time allows for detecting
+  /// clock skew.
   #[serde(rename = "timestamp")]
   pub current: DateTime<Utc>,
   /// The next market opening time stamp.
@@ -31,6 +50,293 @@ pub struct Clock {
   pub _non_exhaustive: (),
 }
 
+impl Clock {
+  /// Check whether the clock's current time falls into the extended
+  /// hours trading session of the provided day.
+  ///
+  /// Extended hours comprise the pre-market session, from the session
+  /// open (typically 4:00am) up to the market open, and the
+  /// after-hours session, from the market close up to the session
+  /// close (typically 8:00pm). `day` should be the calendar entry for
+  /// the current day; if it is not, `false` is returned.
+  pub fn is_extended_hours(&self, day: &OpenClose) -> bool {
+    self.is_pre_market(day) || self.is_after_hours(day)
+  }
+
+  /// Check whether the clock's current time falls into the pre-market
+  /// session of the provided day.
+  ///
+  /// See [`Clock::is_extended_hours`] for details.
+  pub fn is_pre_market(&self, day: &OpenClose) -> bool {
+    self
+      .market_time(day)
+      .map(|time| day.session_open <= time && time < day.open)
+      .unwrap_or(false)
+  }
+
+  /// Check whether the clock's current time falls into the after-hours
+  /// session of the provided day.
+  ///
+  /// See [`Clock::is_extended_hours`] for details.
+  pub fn is_after_hours(&self, day: &OpenClose) -> bool {
+    self
+      .market_time(day)
+      .map(|time| day.close <= time && time < day.session_close)
+      .unwrap_or(false)
+  }
+
+  /// Calculate the offset of the provided local time from the
+  /// server's current time.
+  ///
+  /// A positive value indicates that the local clock is ahead of the
+  /// server's, a negative one that it is behind.
+  #[inline]
+  pub fn skew(&self, now: DateTime<Utc>) -> chrono::Duration {
+    now - self.current
+  }
+
+  /// Retrieve the clock's current time in the market's time zone,
+  /// provided it falls onto the given day.
+  fn market_time(&self, day: &OpenClose) -> Option<NaiveTime> {
+    let current = self
+      .current
+      .with_timezone(&market_offset(day.date))
+      .naive_local();
+    (current.date() == day.date).then(|| current.time())
+  }
+}
+
+
+/// How long before the projected market open
+/// [`Client::sleep_until_open`] queries the market clock again.
+const REQUERY_MARGIN: Duration = Duration::from_secs(60);
+
+/// The minimum time [`Client::sleep_until_open`] waits before querying
+/// the market clock again.
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+
+/// The default time for which a [`ClockCache`] holds on to a market
+/// clock.
+pub(crate) const DEFAULT_CLOCK_TTL: Duration = Duration::from_secs(5);
+
+
+/// A cache of the most recently retrieved market clock, as used by
+/// [`Client::cached_clock`].
+///
+/// A cache is held by each [`Client`] and can be accessed via
+/// [`Client::clock_cache`]. The time for which a clock is cached can
+/// be configured when building the client (see [`Client::builder`]).
+#[derive(Debug)]
+pub struct ClockCache {
+  /// The time for which a clock is considered valid.
+  ttl: Duration,
+  /// The cached clock along with the instant it got retrieved at.
+  entry: Mutex<Option<(Instant, Clock)>>,
+}
+
+impl ClockCache {
+  /// Create a cache holding on to clocks for `ttl`.
+  pub(crate) fn new(ttl: Duration) -> Self {
+    Self {
+      ttl,
+      entry: Mutex::new(None),
+    }
+  }
+
+  /// Retrieve the cached clock, with its current time advanced by the
+  /// time passed since its retrieval.
+  ///
+  /// `None` is returned if no clock is cached, if it has expired, or
+  /// if the market opened or closed since its retrieval.
+  fn get(&self) -> Option<Clock> {
+    let (retrieved, clock) = (*self.entry.lock().unwrap())?;
+    let elapsed = retrieved.elapsed();
+    if elapsed >= self.ttl {
+      return None
+    }
+
+    let current = clock.current + chrono::Duration::from_std(elapsed).ok()?;
+    let transition = if clock.open {
+      clock.next_close
+    } else {
+      clock.next_open
+    };
+    (current < transition).then_some(Clock { current, ..clock })
+  }
+
+  /// Store a freshly retrieved clock in the cache.
+  fn set(&self, clock: Clock) {
+    *self.entry.lock().unwrap() = Some((Instant::now(), clock));
+  }
+
+  /// Remove the cached clock, if any.
+  pub fn clear(&self) {
+    *self.entry.lock().unwrap() = None;
+  }
+}
+
+
+/// An error as reported by [`is_extended_hours`].
+#[derive(Debug, ThisError)]
+pub enum ExtendedHoursError {
+  /// Retrieval of the market clock failed.
+  #[error("failed to retrieve market clock")]
+  Clock(#[source] RequestError<GetError>),
+  /// Retrieval of the market calendar failed.
+  #[error("failed to retrieve market calendar")]
+  Calendar(#[source] RequestError<calendar::ListError>),
+}
+
+
+/// Check whether the market is currently in an extended hours trading
+/// session.
+///
+/// This function retrieves the current market clock as well as the
+/// calendar entry for the current day and evaluates them by means of
+/// [`Clock::is_extended_hours`]. On days the market is closed, `false`
+/// is reported.
+pub async fn is_extended_hours(client: &Client) -> Result<bool, ExtendedHoursError> {
+  let clock = client
+    .issue::<Get>(&())
+    .await
+    .map_err(ExtendedHoursError::Clock)?;
+
+  is_extended_hours_at(client, &clock)
+    .await
+    .map_err(ExtendedHoursError::Calendar)
+}
+
+/// Check whether the provided clock's current time falls into an
+/// extended hours trading session, retrieving the calendar entry for
+/// its day.
+pub(crate) async fn is_extended_hours_at(
+  client: &Client,
+  clock: &Clock,
+) -> Result<bool, RequestError<calendar::ListError>> {
+  let date = clock
+    .current
+    .with_timezone(&market_offset(clock.current.naive_utc().date()))
+    .naive_local()
+    .date();
+  // SANITY: We will never operate at the end of the supported date
+  //         range.
+  let request = calendar::ListReq::from(date..date.succ_opt().unwrap());
+  let days = client.issue::<calendar::List>(&request).await?;
+
+  let extended = days
+    .iter()
+    .find(|day| day.date == date)
+    .map(|day| clock.is_extended_hours(day))
+    .unwrap_or(false);
+  Ok(extended)
+}
+
+
+impl Client {
+  /// Retrieve the time stamp of the next market open.
+  ///
+  /// If the market is currently open, the open of the next trading
+  /// session is reported.
+  pub async fn next_market_open(&self) -> Result<DateTime<Utc>, RequestError<GetError>> {
+    let clock = self.issue::<Get>(&()).await?;
+    Ok(clock.next_open)
+  }
+
+  /// Retrieve the market clock, reusing a recently retrieved one if
+  /// possible.
+  ///
+  /// Clocks are held on to by the client's [`ClockCache`] for a short
+  /// time and their current time is advanced accordingly when reused.
+  /// A cached clock is not reused once the market opened or closed
+  /// according to it.
+  pub async fn cached_clock(&self) -> Result<Clock, RequestError<GetError>> {
+    if let Some(clock) = self.clock_cache().get() {
+      return Ok(clock)
+    }
+
+    let clock = self.issue::<Get>(&()).await?;
+    let () = self.clock_cache().set(clock);
+    Ok(clock)
+  }
+
+  /// Determine the skew of the local clock relative to the server's.
+  ///
+  /// The market clock is retrieved and its current time compared to
+  /// the local time once the response arrived, as per
+  /// [`Clock::skew`]. Note that the result includes the latency of
+  /// the request.
+  pub async fn clock_skew(&self) -> Result<chrono::Duration, RequestError<GetError>> {
+    let clock = self.issue::<Get>(&()).await?;
+    Ok(clock.skew(Utc::now()))
+  }
+
+  /// Determine the skew of the local clock relative to the server's,
+  /// emitting a warning if it exceeds `threshold` in either direction.
+  ///
+  /// The skew is reported as per [`Client::clock_skew`].
+  pub async fn warn_on_skew(
+    &self,
+    threshold: chrono::Duration,
+  ) -> Result<chrono::Duration, RequestError<GetError>> {
+    let skew = self.clock_skew().await?;
+    if skew > threshold || skew < -threshold {
+      warn!(
+        skew = display(skew),
+        threshold = display(threshold),
+        "local clock is skewed relative to server time"
+      );
+    }
+    Ok(skew)
+  }
+
+  /// Wait until the market is open.
+  ///
+  /// If the market is open already, this method returns right away.
+  /// Otherwise it sleeps until shortly before the next open, as
+  /// reported by the market clock, and then queries the clock again,
+  /// until the market is reported as open. That way trading halts or
+  /// changes to the calendar are accounted for.
+  ///
+  /// If `max_wait` is provided, at most that long is waited for the
+  /// market to open. The result indicates whether the market is open
+  /// (`true`) or the maximum wait time elapsed before that (`false`).
+  pub async fn sleep_until_open(
+    &self,
+    max_wait: Option<Duration>,
+  ) -> Result<bool, RequestError<GetError>> {
+    let deadline = max_wait.map(|max_wait| Instant::now() + max_wait);
+
+    loop {
+      let clock = self.issue::<Get>(&()).await?;
+      if clock.open {
+        break Ok(true)
+      }
+
+      // We use the server's notion of the current time here, so that we
+      // are not susceptible to a skewed local clock.
+      let until_open = (clock.next_open - clock.current)
+        .to_std()
+        .unwrap_or_default();
+      let wait = if until_open > REQUERY_MARGIN {
+        until_open - REQUERY_MARGIN
+      } else {
+        until_open.max(MIN_POLL_INTERVAL)
+      };
+
+      if let Some(deadline) = deadline {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if wait >= remaining {
+          let () = sleep(remaining).await;
+          break Ok(false)
+        }
+      }
+
+      let () = sleep(wait).await;
+    }
+  }
+}
+
 
 Endpoint! {
   /// The representation of a GET request to the /v2/clock endpoint.
@@ -55,6 +361,7 @@ mod tests {
   use std::str::FromStr as _;
 
   use chrono::Duration;
+  use chrono::NaiveDate;
 
   use serde_json::from_str as from_json;
   use serde_json::to_string as to_json;
@@ -63,6 +370,9 @@ mod tests {
 
   use crate::api::API_BASE_URL;
   use crate::api_info::ApiInfo;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::Reply;
+  use crate::test_util::Requests;
   use crate::Client;
   use crate::RequestError;
 
@@ -86,6 +396,257 @@ mod tests {
     );
   }
 
+  /// Create a `Clock` object with the given current time.
+  fn clock(current: &str) -> Clock {
+    Clock {
+      open: false,
+      current: DateTime::<Utc>::from_str(current).unwrap(),
+      next_open: DateTime::<Utc>::from_str("2024-07-05T09:30:00-04:00").unwrap(),
+      next_close: DateTime::<Utc>::from_str("2024-07-05T16:00:00-04:00").unwrap(),
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Create an `OpenClose` object for the given date, with the given
+  /// open and close hours.
+  fn open_close(date: NaiveDate, open: (u32, u32), close: (u32, u32)) -> OpenClose {
+    let (hour, minute) = open;
+    let open = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+    let (hour, minute) = close;
+    let close = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+
+    OpenClose {
+      date,
+      open,
+      close,
+      session_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      session_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Check that we correctly detect when a clock is inside extended
+  /// trading hours.
+  #[test]
+  fn inside_extended_hours() {
+    // A summer day, during which the market operates at UTC-4.
+    let day = open_close(
+      NaiveDate::from_ymd_opt(2024, 7, 2).unwrap(),
+      (9, 30),
+      (16, 0),
+    );
+    assert!(clock("2024-07-02T08:00:00Z").is_extended_hours(&day));
+    assert!(clock("2024-07-02T13:29:59Z").is_extended_hours(&day));
+    assert!(clock("2024-07-02T20:00:00Z").is_extended_hours(&day));
+    assert!(clock("2024-07-02T23:59:59Z").is_extended_hours(&day));
+
+    // A winter day, during which the market operates at UTC-5.
+    let day = open_close(
+      NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+      (9, 30),
+      (16, 0),
+    );
+    assert!(clock("2024-01-02T09:00:00Z").is_extended_hours(&day));
+    assert!(clock("2024-01-02T21:00:00Z").is_extended_hours(&day));
+
+    // A day with an early close.
+    let day = open_close(
+      NaiveDate::from_ymd_opt(2024, 7, 3).unwrap(),
+      (9, 30),
+      (13, 0),
+    );
+    assert!(clock("2024-07-03T17:00:00Z").is_extended_hours(&day));
+  }
+
+  /// Check that we distinguish between the pre-market and after-hours
+  /// sessions.
+  #[test]
+  fn pre_market_after_hours() {
+    let day = open_close(
+      NaiveDate::from_ymd_opt(2024, 7, 2).unwrap(),
+      (9, 30),
+      (16, 0),
+    );
+    assert!(clock("2024-07-02T08:00:00Z").is_pre_market(&day));
+    assert!(!clock("2024-07-02T08:00:00Z").is_after_hours(&day));
+    assert!(!clock("2024-07-02T20:00:00Z").is_pre_market(&day));
+    assert!(clock("2024-07-02T20:00:00Z").is_after_hours(&day));
+    assert!(!clock("2024-07-02T15:00:00Z").is_pre_market(&day));
+    assert!(!clock("2024-07-02T15:00:00Z").is_after_hours(&day));
+  }
+
+  /// Check that we correctly detect when a clock is outside extended
+  /// trading hours.
+  #[test]
+  fn outside_extended_hours() {
+    let day = open_close(
+      NaiveDate::from_ymd_opt(2024, 7, 2).unwrap(),
+      (9, 30),
+      (16, 0),
+    );
+    // Before the pre-market session.
+    assert!(!clock("2024-07-02T07:59:59Z").is_extended_hours(&day));
+    // During regular trading hours.
+    assert!(!clock("2024-07-02T13:30:00Z").is_extended_hours(&day));
+    assert!(!clock("2024-07-02T19:59:59Z").is_extended_hours(&day));
+    // After the after-hours session.
+    assert!(!clock("2024-07-03T00:00:00Z").is_extended_hours(&day));
+    // On a different day.
+    assert!(!clock("2024-07-03T08:00:00Z").is_extended_hours(&day));
+
+    let day = open_close(
+      NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+      (9, 30),
+      (16, 0),
+    );
+    assert!(!clock("2024-01-02T08:59:59Z").is_extended_hours(&day));
+    assert!(!clock("2024-01-02T14:30:00Z").is_extended_hours(&day));
+    assert!(!clock("2024-01-03T01:00:00Z").is_extended_hours(&day));
+  }
+
+  /// Serve the provided clocks, one per connection, on a local socket.
+  /// Return the URL to connect to along with the requests received.
+  async fn serve(clocks: Vec<Clock>) -> (String, Requests) {
+    let replies = clocks
+      .iter()
+      .map(|clock| Reply::respond("200 OK", to_json(clock).unwrap()))
+      .collect();
+    serve_sequence(replies).await
+  }
+
+  /// Create a `Clock` object reporting the market as `open` and opening
+  /// next after `until_open`.
+  fn clock_opening_in(open: bool, until_open: Duration) -> Clock {
+    let current = Utc::now();
+    Clock {
+      open,
+      current,
+      next_open: current + until_open,
+      next_close: current + until_open + Duration::try_hours(6).unwrap(),
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Check that we can retrieve the next market open from the market
+  /// clock.
+  #[test(tokio::test)]
+  async fn next_market_open() {
+    let clock = clock_opening_in(false, Duration::try_hours(2).unwrap());
+    let (url, _requests) = serve(vec![clock]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let next_open = client.next_market_open().await.unwrap();
+    assert_eq!(next_open, clock.next_open);
+  }
+
+  /// Make sure that waiting for the market to open returns promptly
+  /// once it is open, re-querying the clock close to the projected
+  /// open.
+  #[test(tokio::test)]
+  async fn sleep_until_open() {
+    let clocks = vec![
+      clock_opening_in(true, Duration::try_hours(20).unwrap()),
+      clock_opening_in(false, Duration::try_milliseconds(100).unwrap()),
+      clock_opening_in(true, Duration::try_hours(20).unwrap()),
+    ];
+    let (url, requests) = serve(clocks).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    // The market is open already.
+    let start = Instant::now();
+    assert!(client.sleep_until_open(None).await.unwrap());
+    assert!(start.elapsed() < MIN_POLL_INTERVAL);
+    assert_eq!(requests.all().len(), 1);
+
+    // The market opens shortly.
+    let max_wait = Some(std::time::Duration::from_secs(10));
+    assert!(client.sleep_until_open(max_wait).await.unwrap());
+    assert_eq!(requests.all().len(), 3);
+  }
+
+  /// Check that waiting for the market to open gives up once the
+  /// maximum wait time elapsed.
+  #[test(tokio::test)]
+  async fn sleep_until_open_max_wait() {
+    let clock = clock_opening_in(false, Duration::try_days(2).unwrap());
+    let (url, _requests) = serve(vec![clock]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let max_wait = Some(std::time::Duration::from_millis(50));
+    let start = Instant::now();
+    assert!(!client.sleep_until_open(max_wait).await.unwrap());
+    assert!(start.elapsed() < MIN_POLL_INTERVAL);
+  }
+
+  /// Make sure that a cached clock is reused until it expires or the
+  /// market opens according to it.
+  #[test(tokio::test)]
+  async fn cache_clock() {
+    let clocks = vec![
+      clock_opening_in(false, Duration::try_hours(2).unwrap()),
+      clock_opening_in(false, Duration::try_milliseconds(50).unwrap()),
+      clock_opening_in(true, Duration::try_hours(20).unwrap()),
+    ];
+    let (url, requests) = serve(clocks.clone()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let clock = client.cached_clock().await.unwrap();
+    assert_eq!(clock, clocks[0]);
+    let clock = client.cached_clock().await.unwrap();
+    assert!(!clock.open);
+    assert!(clock.current >= clocks[0].current);
+    assert_eq!(requests.all().len(), 1);
+
+    let () = client.clock_cache().clear();
+    let clock = client.cached_clock().await.unwrap();
+    assert_eq!(clock, clocks[1]);
+    assert_eq!(requests.all().len(), 2);
+
+    // Once the market opened according to the cached clock, it is no
+    // longer used.
+    let () = sleep(std::time::Duration::from_millis(100)).await;
+    let clock = client.cached_clock().await.unwrap();
+    assert!(clock.open);
+    assert_eq!(requests.all().len(), 3);
+  }
+
+  /// Check that we correctly calculate the skew of a frozen local time
+  /// relative to the server's time.
+  #[test]
+  fn calculate_skew() {
+    let clock = clock("2024-07-02T08:00:00Z");
+    let now = DateTime::<Utc>::from_str("2024-07-02T08:00:02.5Z").unwrap();
+    assert_eq!(clock.skew(now), Duration::try_milliseconds(2500).unwrap());
+
+    let now = DateTime::<Utc>::from_str("2024-07-02T07:59:00Z").unwrap();
+    assert_eq!(clock.skew(now), -Duration::try_minutes(1).unwrap());
+    assert_eq!(clock.skew(clock.current), Duration::zero());
+  }
+
+  /// Make sure that we can determine the skew of the local clock
+  /// relative to the one reported by the server.
+  #[test(tokio::test)]
+  async fn retrieve_clock_skew() {
+    let offset = Duration::try_hours(1).unwrap();
+    let mut clock = clock_opening_in(false, Duration::try_hours(2).unwrap());
+    clock.current -= offset;
+
+    let (url, _requests) = serve(vec![clock, clock]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let tolerance = Duration::try_seconds(10).unwrap();
+    let skew = client.clock_skew().await.unwrap();
+    assert!(skew >= offset && skew < offset + tolerance, "{skew}");
+
+    let skew = client.warn_on_skew(tolerance).await.unwrap();
+    assert!(skew >= offset && skew < offset + tolerance, "{skew}");
+  }
+
   /// Verify that we can retrieve the current market clock.
   #[test(tokio::test)]
   async fn current_market_clock() {
@@ -112,6 +673,20 @@ mod tests {
     }
   }
 
+  /// Check that we can inquire whether the market is currently in an
+  /// extended hours trading session.
+  #[test(tokio::test)]
+  async fn current_extended_hours() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let extended = is_extended_hours(&client).await.unwrap();
+
+    let clock = client.issue::<Get>(&()).await.unwrap();
+    if clock.open {
+      assert!(!extended);
+    }
+  }
+
   /// Check that we get back the expected error when requesting the
   /// market clock with invalid credentials.
   #[test(tokio::test)]
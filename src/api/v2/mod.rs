@@ -4,6 +4,7 @@
 /// Definitions pertaining the user's account.
 pub mod account;
 /// Definitions pertaining account activities.
+#[cfg(feature = "trading")]
 pub mod account_activities;
 /// Definitions pertaining the user's account configuration.
 pub mod account_config;
@@ -18,21 +19,34 @@ pub mod calendar;
 /// for the current trading day.
 pub mod clock;
 /// Definitions surrounding orders.
+#[cfg(feature = "trading")]
 pub mod order;
 /// Functionality for listing orders.
+#[cfg(feature = "trading")]
 pub mod orders;
+/// Functionality for retrieving a snapshot of the portfolio.
+#[cfg(feature = "trading")]
+pub mod portfolio;
+/// Functionality for retrieving the account's portfolio history.
+pub mod portfolio_history;
 /// Definitions surrounding open positions.
+#[cfg(feature = "trading")]
 pub mod position;
 /// Functionality for listing open positions.
+#[cfg(feature = "trading")]
 pub mod positions;
 /// Definitions for trade related updates.
+#[cfg(all(feature = "streaming", feature = "trading"))]
 pub mod updates;
 /// Definitions surrounding watchlists.
 pub mod watchlist;
 /// Functionality for listing watchlists.
 pub mod watchlists;
 
+#[cfg(feature = "trading")]
 mod de;
 
 #[cfg(test)]
+mod num_util;
+#[cfg(all(test, feature = "trading"))]
 mod order_util;
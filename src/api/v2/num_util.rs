@@ -0,0 +1,100 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Debug;
+
+use proptest::arbitrary::any;
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prop_assert_eq;
+use proptest::strategy::Strategy;
+use proptest::test_runner::TestCaseError;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::from_slice as from_json;
+use serde_json::to_value;
+use serde_json::to_vec as to_json;
+use serde_json::Value;
+
+
+/// Create a strategy generating non-negative decimal strings with up
+/// to eight fractional digits, potentially including trailing zeros
+/// (e.g., "0.0" or "12.50").
+///
+/// Eight digits is the maximum precision `Num` serializes with.
+pub(crate) fn decimal() -> impl Strategy<Value = String> {
+  let fraction = option::of(vec(0u8..10, 1..=8));
+  (0..1_000_000_000_000u64, fraction).prop_map(|(integer, fraction)| match fraction {
+    Some(digits) => {
+      let digits = digits.iter().map(u8::to_string).collect::<String>();
+      format!("{integer}.{digits}")
+    },
+    None => integer.to_string(),
+  })
+}
+
+/// Create a strategy generating decimal strings as [`decimal`] does,
+/// but potentially negative.
+pub(crate) fn signed_decimal() -> impl Strategy<Value = String> {
+  (any::<bool>(), decimal()).prop_map(|(negative, decimal)| {
+    if negative {
+      format!("-{decimal}")
+    } else {
+      decimal
+    }
+  })
+}
+
+/// Normalize a decimal string, as generated by [`signed_decimal`], into
+/// the form `Num` serializes it in.
+///
+/// Trailing zeros of the fractional part, as well as a then empty
+/// fractional part, are dropped and zero is never negative. That is,
+/// "0.0" and "-0" both become "0" and "12.50" becomes "12.5".
+pub(crate) fn canonical(decimal: &str) -> String {
+  let (negative, digits) = match decimal.strip_prefix('-') {
+    Some(digits) => (true, digits),
+    None => (false, decimal),
+  };
+  let digits = if digits.contains('.') {
+    digits.trim_end_matches('0').trim_end_matches('.')
+  } else {
+    digits
+  };
+
+  if negative && digits != "0" {
+    format!("-{digits}")
+  } else {
+    digits.to_string()
+  }
+}
+
+/// Check that an object created from `base` with the given `fields`
+/// set to the provided decimal strings survives a round trip through
+/// JSON and that each field is serialized in its canonical form.
+pub(crate) fn check_round_trip<T>(
+  base: &T,
+  fields: &[&str],
+  decimals: &[String],
+) -> Result<(), TestCaseError>
+where
+  T: Debug + DeserializeOwned + PartialEq + Serialize,
+{
+  let mut value = to_value(base).unwrap();
+  for (field, decimal) in fields.iter().zip(decimals) {
+    value[*field] = Value::String(decimal.clone());
+  }
+
+  // Note that we deliberately go through a byte representation here,
+  // as not all types can be deserialized from a `Value` directly.
+  let object = from_json::<T>(&to_json(&value).unwrap()).unwrap();
+  let json = to_json(&object).unwrap();
+  prop_assert_eq!(from_json::<T>(&json).unwrap(), object);
+
+  let value = from_json::<Value>(&json).unwrap();
+  for (field, decimal) in fields.iter().zip(decimals) {
+    prop_assert_eq!(&value[*field], &Value::String(canonical(decimal)));
+  }
+  Ok(())
+}
@@ -1,12 +1,19 @@
 // Copyright (C) 2019-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::ops::Not;
+use std::time::Duration;
 
 use chrono::DateTime;
 use chrono::Utc;
 
+use futures::stream::iter;
+use futures::StreamExt as _;
+
 use http::Method;
 use http_endpoint::Bytes;
 
@@ -20,15 +27,38 @@ use serde_json::from_slice as from_json;
 use serde_json::to_vec as to_json;
 use serde_urlencoded::to_string as to_query;
 
+use thiserror::Error as ThisError;
+
+use tokio::time::sleep;
+
+use tracing::debug;
+
 use uuid::Uuid;
 
+use crate::api::v2::account;
+use crate::api::v2::account::Account;
 use crate::api::v2::asset;
+use crate::api::v2::asset::Asset;
+use crate::api::v2::calendar;
+use crate::api::v2::calendar::OpenClose;
+use crate::api::v2::clock;
+use crate::api::v2::clock::is_extended_hours_at;
+use crate::api::v2::clock::Clock;
+use crate::api::v2::position;
+#[cfg(feature = "market-data")]
+use crate::data::v2::last_quotes;
+use crate::endpoint::ConversionError;
+use crate::util::strict_variant;
 use crate::util::vec_from_str;
+use crate::util::UnknownVariant;
+use crate::ApiError;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
 /// An ID uniquely identifying an order.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct Id(pub Uuid);
 
 impl Deref for Id {
@@ -125,6 +155,13 @@ pub enum Status {
   Unknown,
 }
 
+impl UnknownVariant for Status {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 impl Status {
   /// Check whether the status is terminal, i.e., no more changes will
   /// occur to the associated order.
@@ -140,6 +177,7 @@ impl Status {
 
 /// The side an order is on.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
 pub enum Side {
   /// Buy an asset.
   #[serde(rename = "buy")]
@@ -147,6 +185,26 @@ pub enum Side {
   /// Sell an asset.
   #[serde(rename = "sell")]
   Sell,
+  /// Any other side that we have not accounted for.
+  ///
+  /// Note that having any such side should be considered a bug.
+  #[doc(hidden)]
+  #[serde(other, rename(serialize = "unknown"))]
+  Unknown,
+}
+
+impl Side {
+  /// Retrieve the side of an order flattening a position on the
+  /// provided side, i.e., selling a long position and buying back a
+  /// short one.
+  #[inline]
+  pub fn closing_for(position_side: position::Side) -> Self {
+    match position_side {
+      position::Side::Long => Self::Sell,
+      position::Side::Short => Self::Buy,
+      position::Side::Unknown => Self::Unknown,
+    }
+  }
 }
 
 impl Not for Side {
@@ -157,10 +215,18 @@ impl Not for Side {
     match self {
       Self::Buy => Self::Sell,
       Self::Sell => Self::Buy,
+      Self::Unknown => Self::Unknown,
     }
   }
 }
 
+impl UnknownVariant for Side {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 
 /// The class an order belongs to.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -186,6 +252,17 @@ pub enum Class {
   /// entry order.
   #[serde(rename = "oto")]
   OneTriggersOther,
+  /// A multi-leg options order, comprising multiple legs that are
+  /// executed together (see [`CreateMultiLegReq`]).
+  #[serde(rename = "mleg")]
+  MultiLeg,
+  /// Any other order class that we have not accounted for.
+  ///
+  /// Note that having any such unknown order class should be considered
+  /// a bug.
+  #[doc(hidden)]
+  #[serde(other, rename(serialize = "unknown"))]
+  Unknown,
 }
 
 impl Default for Class {
@@ -195,6 +272,13 @@ impl Default for Class {
   }
 }
 
+impl UnknownVariant for Class {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 
 /// The type of an order.
 // Note that we currently do not support `stop_limit` orders.
@@ -216,6 +300,13 @@ pub enum Type {
   /// A trailing stop order.
   #[serde(rename = "trailing_stop")]
   TrailingStop,
+  /// Any other order type that we have not accounted for.
+  ///
+  /// Note that having any such unknown order type should be considered
+  /// a bug.
+  #[doc(hidden)]
+  #[serde(other, rename(serialize = "unknown"))]
+  Unknown,
 }
 
 impl Default for Type {
@@ -225,6 +316,13 @@ impl Default for Type {
   }
 }
 
+impl UnknownVariant for Type {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 
 /// A description of the time for which an order is valid.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -262,6 +360,53 @@ impl Default for TimeInForce {
   }
 }
 
+impl TimeInForce {
+  /// Check whether the time in force may be used for an order of the
+  /// given type for an asset of the given class.
+  ///
+  /// Opening and closing auction orders have to be market or limit
+  /// orders of US equities. Trailing stop orders have to be day or good
+  /// until canceled orders. Crypto currency orders have to be good until
+  /// canceled or immediate-or-cancel, while option orders have to be
+  /// day orders.
+  pub fn validate(self, type_: Type, class: asset::Class) -> Result<(), TimeInForceError> {
+    let supported = match class {
+      asset::Class::Crypto => matches!(self, Self::UntilCanceled | Self::ImmediateOrCancel),
+      asset::Class::UsOption => self == Self::Day,
+      _ => true,
+    };
+    if !supported {
+      return Err(TimeInForceError::UnsupportedAssetClass(self, class))
+    }
+
+    let supported = match self {
+      Self::UntilMarketOpen | Self::UntilMarketClose => {
+        matches!(type_, Type::Market | Type::Limit)
+      },
+      Self::FillOrKill | Self::ImmediateOrCancel => type_ != Type::TrailingStop,
+      Self::Day | Self::UntilCanceled => true,
+    };
+    if !supported {
+      return Err(TimeInForceError::UnsupportedType(self, type_))
+    }
+    Ok(())
+  }
+}
+
+
+/// An error as reported by [`TimeInForce::validate`], indicating that a
+/// time in force may not be used for an order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum TimeInForceError {
+  /// The time in force is not supported for orders of the given type.
+  #[error("time in force {0:?} is not supported for {1:?} orders")]
+  UnsupportedType(TimeInForce, Type),
+  /// The time in force is not supported for assets of the given class.
+  #[error("time in force {0:?} is not supported for {1:?} assets")]
+  UnsupportedAssetClass(TimeInForce, asset::Class),
+}
+
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename = "take_profit")]
@@ -383,6 +528,39 @@ impl Amount {
 }
 
 
+/// Round a price to the nearest tick accepted by Alpaca for an asset
+/// trading at `asset_price`.
+///
+/// The tick is a cent for assets trading at one dollar or more and a
+/// hundredth of a cent below. A non-zero price is never rounded to
+/// zero, but to the smallest tick instead.
+pub fn round_to_tick(price: &Num, asset_price: &Num) -> Num {
+  let (precision, tick) = if asset_price >= &Num::from(1) || asset_price <= &Num::from(-1) {
+    (2, Num::new(1, 100))
+  } else {
+    (4, Num::new(1, 10000))
+  };
+
+  let rounded = price.round_with(precision);
+  if rounded.is_zero() && !price.is_zero() {
+    if price.is_negative() {
+      -tick
+    } else {
+      tick
+    }
+  } else {
+    rounded
+  }
+}
+
+
+/// Round a price to the nearest tick for an asset trading at that very
+/// price.
+fn round_price(price: Num) -> Num {
+  round_to_tick(&price, &price)
+}
+
+
 /// A helper for initializing `CreateReq` objects.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct CreateReqInit {
@@ -408,6 +586,11 @@ pub struct CreateReqInit {
   pub extended_hours: bool,
   /// See `CreateReq::client_order_id`.
   pub client_order_id: Option<String>,
+  /// See `CreateReq::position_intent`.
+  pub position_intent: Option<PositionIntent>,
+  /// Whether to round the limit and stop prices of the order and its
+  /// legs to the nearest tick (see [`round_to_tick`]).
+  pub round_prices: bool,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   pub _non_exhaustive: (),
@@ -424,6 +607,29 @@ impl CreateReqInit {
   where
     S: Into<String>,
   {
+    let (limit_price, stop_price, take_profit, stop_loss) = if self.round_prices {
+      (
+        self.limit_price.map(round_price),
+        self.stop_price.map(round_price),
+        self
+          .take_profit
+          .map(|TakeProfit::Limit(limit)| TakeProfit::Limit(round_price(limit))),
+        self.stop_loss.map(|stop_loss| match stop_loss {
+          StopLoss::Stop(stop) => StopLoss::Stop(round_price(stop)),
+          StopLoss::StopLimit(stop, limit) => {
+            StopLoss::StopLimit(round_price(stop), round_price(limit))
+          },
+        }),
+      )
+    } else {
+      (
+        self.limit_price,
+        self.stop_price,
+        self.take_profit,
+        self.stop_loss,
+      )
+    };
+
     CreateReq {
       symbol: asset::Symbol::Sym(symbol.into()),
       amount,
@@ -431,17 +637,107 @@ impl CreateReqInit {
       class: self.class,
       type_: self.type_,
       time_in_force: self.time_in_force,
-      limit_price: self.limit_price,
-      stop_price: self.stop_price,
-      take_profit: self.take_profit,
-      stop_loss: self.stop_loss,
+      limit_price,
+      stop_price,
+      take_profit,
+      stop_loss,
       extended_hours: self.extended_hours,
       client_order_id: self.client_order_id,
       trail_price: self.trail_price,
       trail_percent: self.trail_percent,
+      position_intent: self.position_intent,
       _non_exhaustive: (),
     }
   }
+
+  /// Create a `CreateReq` from a `CreateReqInit`, just like
+  /// [`CreateReqInit::init`], but check that extended hours trading is
+  /// only requested for an order supporting it (see
+  /// [`CreateReq::validate_extended_hours`]).
+  pub fn try_init<S>(
+    self,
+    symbol: S,
+    side: Side,
+    amount: Amount,
+  ) -> Result<CreateReq, ExtendedHoursError>
+  where
+    S: Into<String>,
+  {
+    let request = self.init(symbol, side, amount);
+    let () = request.validate_extended_hours()?;
+    Ok(request)
+  }
+
+  /// Create a limit order `CreateReq` priced at the midpoint of the
+  /// provided quote's national best bid and offer.
+  ///
+  /// The midpoint is rounded to the nearest tick, which is a cent for
+  /// prices of at least one dollar and a hundredth of a cent below.
+  /// The `type_` and `limit_price` members of the `CreateReqInit` are
+  /// overwritten. Crossed or locked quotes (i.e., those with a bid
+  /// price at or above the ask price) are rejected.
+  #[cfg(feature = "market-data")]
+  #[allow(clippy::result_large_err)]
+  pub fn init_limit_at_midpoint<S>(
+    self,
+    symbol: S,
+    side: Side,
+    amount: Amount,
+    quote: &last_quotes::Quote,
+  ) -> Result<CreateReq, CrossedQuote>
+  where
+    S: Into<String>,
+  {
+    if quote.bid_price >= quote.ask_price {
+      return Err(CrossedQuote {
+        bid_price: quote.bid_price.clone(),
+        ask_price: quote.ask_price.clone(),
+      })
+    }
+
+    let midpoint = (&quote.bid_price + &quote.ask_price) / 2;
+
+    let init = Self {
+      type_: Type::Limit,
+      limit_price: Some(round_price(midpoint)),
+      ..self
+    };
+    Ok(init.init(symbol, side, amount))
+  }
+}
+
+
+/// An error indicating that extended hours trading was requested for
+/// an order not supporting it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum ExtendedHoursError {
+  /// Extended hours trading is only supported for limit orders.
+  #[error("extended hours trading is not supported for {0:?} orders")]
+  UnsupportedType(Type),
+  /// Extended hours trading is only supported for day orders.
+  #[error("extended hours trading is not supported with time in force {0:?}")]
+  UnsupportedTimeInForce(TimeInForce),
+}
+
+
+/// An error indicating that a quote is crossed or locked, i.e., that
+/// its bid price is not below its ask price.
+#[cfg(feature = "market-data")]
+#[derive(Clone, Debug, Eq, ThisError, PartialEq)]
+#[error("quote is crossed or locked (bid: {bid_price}, ask: {ask_price})")]
+pub struct CrossedQuote {
+  /// The quote's bid price.
+  pub bid_price: Num,
+  /// The quote's ask price.
+  pub ask_price: Num,
+}
+
+
+/// Check whether a flag is unset, i.e., `false`.
+#[inline]
+fn is_false(value: &bool) -> bool {
+  !*value
 }
 
 
@@ -487,8 +783,10 @@ pub struct CreateReq {
   /// Whether or not the order is eligible to execute during
   /// pre-market/after hours. Note that a value of `true` can only be
   /// combined with limit orders that are good for the day (i.e.,
-  /// `TimeInForce::Day`).
-  #[serde(rename = "extended_hours")]
+  /// `TimeInForce::Day`), which is checked by
+  /// [`CreateReq::validate_extended_hours`]. The flag is only sent to
+  /// the server if set.
+  #[serde(rename = "extended_hours", default, skip_serializing_if = "is_false")]
   pub extended_hours: bool,
   /// Client unique order ID (free form string).
   ///
@@ -499,6 +797,18 @@ pub struct CreateReq {
   /// The documented maximum length is 48 characters.
   #[serde(rename = "client_order_id")]
   pub client_order_id: Option<String>,
+  /// The intent of the order with respect to the position in the
+  /// asset, i.e., whether it opens or closes a long or short position.
+  ///
+  /// The intent has to be consistent with the order's side, e.g., an
+  /// order selling to open a short position has to be a sell order.
+  /// It is only sent to the server if set.
+  #[serde(
+    rename = "position_intent",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub position_intent: Option<PositionIntent>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
@@ -506,6 +816,441 @@ pub struct CreateReq {
 }
 
 
+/// A reason for an order being deemed infeasible.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Infeasibility {
+  /// Trading is blocked for the account.
+  TradingBlocked,
+  /// The asset is not tradable.
+  NotTradable,
+  /// The order is for a fractional amount of an asset that is not
+  /// fractionable.
+  NotFractionable,
+  /// The order is of a type requiring a limit price, but none was
+  /// provided.
+  MissingLimitPrice,
+  /// The order is of a type requiring a stop price, but none was
+  /// provided.
+  MissingStopPrice,
+  /// The account's buying power does not cover the order's estimated
+  /// cost.
+  InsufficientBuyingPower {
+    /// The estimated cost of the order.
+    required: Num,
+    /// The buying power available.
+    available: Num,
+  },
+}
+
+
+/// A report on whether an order is expected to be accepted, as
+/// produced by [`CreateReq::feasibility`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OrderFeasibility {
+  /// The reasons for the order being infeasible.
+  pub reasons: Vec<Infeasibility>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl OrderFeasibility {
+  /// Check whether the order is expected to be feasible, i.e., whether
+  /// no reason for it being infeasible was found.
+  #[inline]
+  pub fn is_feasible(&self) -> bool {
+    self.reasons.is_empty()
+  }
+}
+
+
+/// A problem with an order request, as detected by
+/// [`CreateReq::validate`].
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum OrderProblem {
+  /// The order's quantity or notional amount is not positive.
+  #[error("order amount is not positive")]
+  NonPositiveAmount,
+  /// The order is for a notional amount but not a day order.
+  #[error("time in force {0:?} is not supported for notional orders")]
+  NotionalTimeInForce(TimeInForce),
+  /// The order is of a type requiring a limit price, but none was
+  /// provided.
+  #[error("{0:?} order requires a limit price")]
+  MissingLimitPrice(Type),
+  /// The order is of a type requiring a stop price, but none was
+  /// provided.
+  #[error("{0:?} order requires a stop price")]
+  MissingStopPrice(Type),
+  /// The order is a trailing stop order, but neither a trail price nor
+  /// a trail percent was provided.
+  #[error("trailing stop order requires a trail price or percent")]
+  MissingTrail,
+  /// Both a trail price and a trail percent were provided.
+  #[error("trail price and trail percent are mutually exclusive")]
+  ConflictingTrail,
+  /// A trail price or percent was provided for an order that is not a
+  /// trailing stop order.
+  #[error("{0:?} order does not support a trail price or percent")]
+  UnexpectedTrail(Type),
+  /// The take-profit and stop-loss legs of the order do not match its
+  /// class.
+  #[error("take-profit and stop-loss legs are inconsistent with order class {0:?}")]
+  InconsistentLegs(Class),
+  /// The order's position intent is inconsistent with its side.
+  #[error("position intent {0:?} is inconsistent with order side {1:?}")]
+  InconsistentPositionIntent(PositionIntent, Side),
+  /// Extended hours trading was requested for an order not supporting
+  /// it.
+  #[error(transparent)]
+  ExtendedHours(ExtendedHoursError),
+}
+
+
+/// An error as reported by [`CreateReq::validate`], listing all the
+/// problems found with an order request.
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+#[error("invalid order request: {}", .problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct OrderValidationError {
+  /// The problems found, in the order they were checked for.
+  pub problems: Vec<OrderProblem>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// An error as reported by [`CreateReq::preflight`], indicating that
+/// an order is incompatible with the asset it is for.
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum OrderPreflightError {
+  /// The asset is not tradable.
+  #[error("asset is not tradable")]
+  NotTradable,
+  /// The order is for a fractional quantity or a notional amount of an
+  /// asset that is not fractionable.
+  #[error("asset is not fractionable")]
+  NotFractionable,
+  /// The order's time in force is not supported for the asset or for
+  /// the order's amount.
+  #[error("time in force {0:?} is not supported for the order")]
+  UnsupportedTimeInForce(TimeInForce),
+  /// The order's quantity is below the asset's minimum order size.
+  #[error("order quantity is below the minimum order size of {0}")]
+  BelowMinOrderSize(Num),
+}
+
+
+impl CreateReq {
+  /// Retrieve the request's client order ID, generating a random one
+  /// first if none is set.
+  ///
+  /// Reusing the same request (and, hence, client order ID) when
+  /// retrying a submission allows for detecting whether the order was
+  /// already created.
+  pub fn ensure_client_order_id(&mut self) -> &str {
+    self
+      .client_order_id
+      .get_or_insert_with(|| Uuid::new_v4().as_simple().to_string())
+  }
+
+  /// Estimate whether the order would be accepted, without submitting
+  /// it.
+  ///
+  /// Checks comprise the presence of prices required by the order
+  /// type, the tradability and fractionability of `asset`, and, for buy
+  /// orders, whether the buying power of `account` covers the order's
+  /// estimated cost. The cost of a quantity based order is estimated
+  /// using its limit price (or, for stop orders, its stop price), if
+  /// any, and `price` (typically the latest trade price) otherwise.
+  ///
+  /// # Notes
+  /// - this check is only an approximation of the validation performed
+  ///   by Alpaca and an order deemed feasible may still get rejected
+  /// - buying power tied up in other open orders is not accounted for
+  pub fn feasibility(&self, account: &Account, asset: &Asset, price: &Num) -> OrderFeasibility {
+    let mut reasons = Vec::new();
+
+    let needs_limit = matches!(self.type_, Type::Limit | Type::StopLimit);
+    if needs_limit && self.limit_price.is_none() {
+      reasons.push(Infeasibility::MissingLimitPrice);
+    }
+    let needs_stop = matches!(self.type_, Type::Stop | Type::StopLimit);
+    if needs_stop && self.stop_price.is_none() {
+      reasons.push(Infeasibility::MissingStopPrice);
+    }
+
+    if account.trading_blocked {
+      reasons.push(Infeasibility::TradingBlocked);
+    }
+    if !asset.tradable {
+      reasons.push(Infeasibility::NotTradable);
+    }
+
+    if self.is_fractional() && !asset.fractionable {
+      reasons.push(Infeasibility::NotFractionable);
+    }
+
+    if self.side == Side::Buy {
+      let required = match &self.amount {
+        Amount::Quantity { quantity } => {
+          let price = match self.type_ {
+            Type::Limit | Type::StopLimit => self.limit_price.as_ref(),
+            Type::Stop => self.stop_price.as_ref(),
+            Type::Market | Type::TrailingStop | Type::Unknown => None,
+          }
+          .unwrap_or(price);
+          quantity * price
+        },
+        Amount::Notional { notional } => notional.clone(),
+      };
+
+      if required > account.buying_power {
+        reasons.push(Infeasibility::InsufficientBuyingPower {
+          required,
+          available: account.buying_power.clone(),
+        });
+      }
+    }
+
+    OrderFeasibility {
+      reasons,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Check that the order request satisfies the invariants enforceable
+  /// without contacting the server, reporting all problems found.
+  ///
+  /// Checked are that the amount is positive, that notional orders are
+  /// day orders (a restriction only applying to equities; see below),
+  /// that prices required by the order type are present,
+  /// that exactly one of a trail price and percent is provided for
+  /// trailing stop orders and neither for others, that take-profit and
+  /// stop-loss legs match the order class, that the position intent, if
+  /// any, matches the order side, and that extended hours trading is
+  /// only requested where supported (see
+  /// [`CreateReq::validate_extended_hours`]). Quantity and notional
+  /// amount are mutually exclusive by virtue of [`Amount`].
+  ///
+  /// The same checks are performed when issuing a [`Create`] request,
+  /// in which case a failure is reported as a conversion error (see
+  /// [`CreateError::validation_error`]). The exception is the time in
+  /// force of notional orders, because the request does not convey
+  /// the asset class and crypto orders cannot be day orders. Alpaca
+  /// does not offer validating an order without placing it, so passing
+  /// validation does not guarantee that the order is accepted (see also
+  /// [`CreateReq::feasibility`]).
+  pub fn validate(&self) -> Result<(), OrderValidationError> {
+    self.check(true)
+  }
+
+  /// Check the order request, optionally including the equity specific
+  /// restriction of notional orders to day orders.
+  fn check(&self, equity: bool) -> Result<(), OrderValidationError> {
+    let mut problems = Vec::new();
+    let () = self.check_amount(equity, &mut problems);
+    let () = self.check_prices(&mut problems);
+    let () = self.check_trail(&mut problems);
+    let () = self.check_legs(&mut problems);
+    let () = self.check_position_intent(&mut problems);
+    if let Err(error) = self.validate_extended_hours() {
+      problems.push(OrderProblem::ExtendedHours(error));
+    }
+
+    if problems.is_empty() {
+      Ok(())
+    } else {
+      Err(OrderValidationError {
+        problems,
+        _non_exhaustive: (),
+      })
+    }
+  }
+
+  /// Check that the order's amount is positive and, if `equity` is
+  /// set, that notional orders are day orders.
+  fn check_amount(&self, equity: bool, problems: &mut Vec<OrderProblem>) {
+    let (amount, notional) = match &self.amount {
+      Amount::Quantity { quantity } => (quantity, false),
+      Amount::Notional { notional } => (notional, true),
+    };
+    if !amount.is_positive() {
+      problems.push(OrderProblem::NonPositiveAmount);
+    }
+    if equity && notional && self.time_in_force != TimeInForce::Day {
+      problems.push(OrderProblem::NotionalTimeInForce(self.time_in_force));
+    }
+  }
+
+  /// Check that the prices required by the order's type are present.
+  fn check_prices(&self, problems: &mut Vec<OrderProblem>) {
+    let needs_limit = matches!(self.type_, Type::Limit | Type::StopLimit);
+    if needs_limit && self.limit_price.is_none() {
+      problems.push(OrderProblem::MissingLimitPrice(self.type_));
+    }
+    let needs_stop = matches!(self.type_, Type::Stop | Type::StopLimit);
+    if needs_stop && self.stop_price.is_none() {
+      problems.push(OrderProblem::MissingStopPrice(self.type_));
+    }
+  }
+
+  /// Check that exactly one of a trail price and percent is provided
+  /// for trailing stop orders and neither for any other order.
+  fn check_trail(&self, problems: &mut Vec<OrderProblem>) {
+    let trail = (self.trail_price.is_some(), self.trail_percent.is_some());
+    if self.type_ == Type::TrailingStop {
+      match trail {
+        (false, false) => problems.push(OrderProblem::MissingTrail),
+        (true, true) => problems.push(OrderProblem::ConflictingTrail),
+        _ => (),
+      }
+    } else if trail != (false, false) {
+      problems.push(OrderProblem::UnexpectedTrail(self.type_));
+    }
+  }
+
+  /// Check whether the order is for a fractional quantity or a
+  /// notional amount.
+  fn is_fractional(&self) -> bool {
+    match &self.amount {
+      Amount::Quantity { quantity } => !quantity.fract().is_zero(),
+      Amount::Notional { .. } => true,
+    }
+  }
+
+  /// Check that the order is compatible with `asset`, without
+  /// submitting it.
+  ///
+  /// Checked are the tradability of `asset`, whether it supports the
+  /// order's fractional quantity or notional amount, and whether the
+  /// order's time in force is supported (see
+  /// [`TimeInForce::validate`]); in addition, fractional and notional
+  /// equity orders have to be day orders and quantity based orders have
+  /// to satisfy the asset's minimum order size, if any. The order's
+  /// side is accepted for all assets, because whether a sell order
+  /// constitutes a short sale depends on the account's positions.
+  pub fn preflight(&self, asset: &Asset) -> Result<(), OrderPreflightError> {
+    if !asset.tradable {
+      return Err(OrderPreflightError::NotTradable)
+    }
+
+    let fractional = self.is_fractional();
+    if fractional && !asset.is_fractionable() {
+      return Err(OrderPreflightError::NotFractionable)
+    }
+
+    let supported = match asset.class {
+      asset::Class::UsEquity => !fractional || self.time_in_force == TimeInForce::Day,
+      _ => true,
+    } && self.time_in_force.validate(self.type_, asset.class).is_ok();
+    if !supported {
+      return Err(OrderPreflightError::UnsupportedTimeInForce(
+        self.time_in_force,
+      ))
+    }
+
+    if let (Amount::Quantity { quantity }, Some(min_order_size)) =
+      (&self.amount, &asset.min_order_size)
+    {
+      if quantity < min_order_size {
+        return Err(OrderPreflightError::BelowMinOrderSize(
+          min_order_size.clone(),
+        ))
+      }
+    }
+    Ok(())
+  }
+
+  /// Check that the request's legs are consistent with its order
+  /// class.
+  ///
+  /// Simple orders have no legs attached, while bracket and
+  /// one-cancels-other orders have both a take-profit and a stop-loss
+  /// leg. A one-triggers-other order has exactly one leg attached.
+  fn check_legs(&self, problems: &mut Vec<OrderProblem>) {
+    let legs = (self.take_profit.is_some(), self.stop_loss.is_some());
+    let consistent = match self.class {
+      Class::Simple => legs == (false, false),
+      Class::Bracket | Class::OneCancelsOther => legs == (true, true),
+      Class::OneTriggersOther => legs.0 != legs.1,
+      Class::MultiLeg | Class::Unknown => true,
+    };
+    if !consistent {
+      problems.push(OrderProblem::InconsistentLegs(self.class));
+    }
+  }
+
+  /// Check that the request's position intent, if any, is consistent
+  /// with its side.
+  fn check_position_intent(&self, problems: &mut Vec<OrderProblem>) {
+    let intent = match self.position_intent {
+      Some(intent) => intent,
+      None => return,
+    };
+    let consistent = match intent {
+      PositionIntent::BuyToOpen | PositionIntent::BuyToClose => self.side == Side::Buy,
+      PositionIntent::SellToOpen | PositionIntent::SellToClose => self.side == Side::Sell,
+      PositionIntent::Unknown => false,
+    };
+
+    if !consistent {
+      problems.push(OrderProblem::InconsistentPositionIntent(intent, self.side));
+    }
+  }
+
+  /// Check that extended hours trading, if requested, is combined only
+  /// with a limit order that is good for the day.
+  pub fn validate_extended_hours(&self) -> Result<(), ExtendedHoursError> {
+    if !self.extended_hours {
+      return Ok(())
+    }
+
+    if self.type_ != Type::Limit {
+      return Err(ExtendedHoursError::UnsupportedType(self.type_))
+    }
+    if self.time_in_force != TimeInForce::Day {
+      return Err(ExtendedHoursError::UnsupportedTimeInForce(
+        self.time_in_force,
+      ))
+    }
+    Ok(())
+  }
+
+  /// Enable extended hours trading for a day limit order that is
+  /// submitted during the after-hours session.
+  ///
+  /// Without extended hours trading enabled, such an order would be
+  /// queued for the next trading day, which may come as a surprise.
+  /// This method is meant to be invoked explicitly before submission,
+  /// with `clock` reflecting the current time (it may be a cached
+  /// object with an updated [`current`][Clock::current] time) and
+  /// `day` being the calendar entry for the current day. It returns
+  /// `true` if extended hours trading got enabled and `false` if the
+  /// request was left untouched.
+  pub fn enable_extended_hours_after_close(&mut self, clock: &Clock, day: &OpenClose) -> bool {
+    let eligible = !self.extended_hours
+      && self.class == Class::Simple
+      && self.type_ == Type::Limit
+      && self.time_in_force == TimeInForce::Day;
+
+    if eligible && clock.is_after_hours(day) {
+      debug!(
+        symbol = display(&self.symbol),
+        "enabling extended hours trading for order"
+      );
+      self.extended_hours = true;
+      true
+    } else {
+      false
+    }
+  }
+}
+
+
 /// A PATCH request to be made to the /v2/orders/{order-id} endpoint.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ChangeReq {
@@ -546,65 +1291,296 @@ where
   if class.is_empty() {
     Ok(Class::default())
   } else {
-    Class::deserialize(class.into_deserializer())
+    strict_variant(class.into_deserializer())
   }
 }
 
 
-/// A single order as returned by the /v2/orders endpoint on a GET
-/// request.
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
-pub struct Order {
-  /// The order's ID.
-  #[serde(rename = "id")]
-  pub id: Id,
-  /// Client unique order ID.
-  #[serde(rename = "client_order_id")]
-  pub client_order_id: String,
-  /// The status of the order.
-  #[serde(rename = "status")]
-  pub status: Status,
-  /// Timestamp this order was created at.
-  #[serde(rename = "created_at")]
-  pub created_at: DateTime<Utc>,
-  /// Timestamp this order was updated at last.
-  #[serde(rename = "updated_at")]
-  pub updated_at: Option<DateTime<Utc>>,
-  /// Timestamp this order was submitted at.
-  #[serde(rename = "submitted_at")]
-  pub submitted_at: Option<DateTime<Utc>>,
-  /// Timestamp this order was filled at.
-  #[serde(rename = "filled_at")]
-  pub filled_at: Option<DateTime<Utc>>,
-  /// Timestamp this order expired at.
-  #[serde(rename = "expired_at")]
-  pub expired_at: Option<DateTime<Utc>>,
-  /// Timestamp this order expired at.
-  #[serde(rename = "canceled_at")]
-  pub canceled_at: Option<DateTime<Utc>>,
-  /// The order's asset class.
-  #[serde(rename = "asset_class")]
-  pub asset_class: asset::Class,
-  /// The ID of the asset represented by the order.
-  #[serde(rename = "asset_id")]
-  pub asset_id: asset::Id,
-  /// The symbol of the asset being traded.
-  #[serde(rename = "symbol")]
-  pub symbol: String,
-  /// The amount being requested.
-  #[serde(flatten)]
-  pub amount: Amount,
-  /// The quantity that was filled.
+/// Deserialize an asset ID, mapping an empty string to the nil ID.
+fn empty_to_nil<'de, D>(deserializer: D) -> Result<asset::Id, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let id = String::deserialize(deserializer)?;
+  if id.is_empty() {
+    Ok(asset::Id(Uuid::nil()))
+  } else {
+    Uuid::deserialize(id.as_str().into_deserializer()).map(asset::Id)
+  }
+}
+
+
+/// The intent of an order leg with respect to the position held in
+/// its asset.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum PositionIntent {
+  /// Buy to open a long position.
+  #[serde(rename = "buy_to_open")]
+  BuyToOpen,
+  /// Buy to close a short position.
+  #[serde(rename = "buy_to_close")]
+  BuyToClose,
+  /// Sell to open a short position.
+  #[serde(rename = "sell_to_open")]
+  SellToOpen,
+  /// Sell to close a long position.
+  #[serde(rename = "sell_to_close")]
+  SellToClose,
+  /// Any other position intent that we have not accounted for.
+  ///
+  /// Note that having any such unknown position intent should be
+  /// considered a bug.
+  #[doc(hidden)]
+  #[serde(other, rename(serialize = "unknown"))]
+  Unknown,
+}
+
+impl UnknownVariant for PositionIntent {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
+
+/// A single leg of a multi-leg order.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Leg {
+  /// The symbol of the option contract to trade.
+  #[serde(rename = "symbol")]
+  pub symbol: String,
+  /// The quantity of the leg relative to the order's quantity.
+  #[serde(rename = "ratio_qty")]
+  pub ratio_quantity: Num,
+  /// The side the leg is on.
+  #[serde(rename = "side")]
+  pub side: Side,
+  /// The intent of the leg with respect to the position in its asset.
+  #[serde(rename = "position_intent", skip_serializing_if = "Option::is_none")]
+  pub position_intent: Option<PositionIntent>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+impl Leg {
+  /// Create a `Leg` for the provided symbol, without a position
+  /// intent.
+  pub fn new<S>(symbol: S, ratio_quantity: impl Into<Num>, side: Side) -> Self
+  where
+    S: Into<String>,
+  {
+    Self {
+      symbol: symbol.into(),
+      ratio_quantity: ratio_quantity.into(),
+      side,
+      position_intent: None,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// A helper for initializing [`CreateMultiLegReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CreateMultiLegReqInit {
+  /// See `CreateMultiLegReq::type_`.
+  pub type_: Type,
+  /// See `CreateMultiLegReq::time_in_force`.
+  pub time_in_force: TimeInForce,
+  /// See `CreateMultiLegReq::limit_price`.
+  pub limit_price: Option<Num>,
+  /// See `CreateMultiLegReq::client_order_id`.
+  pub client_order_id: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl CreateMultiLegReqInit {
+  /// Create a `CreateMultiLegReq` from a `CreateMultiLegReqInit`.
+  pub fn init(self, quantity: impl Into<Num>, legs: Vec<Leg>) -> CreateMultiLegReq {
+    CreateMultiLegReq {
+      quantity: quantity.into(),
+      type_: self.type_,
+      time_in_force: self.time_in_force,
+      limit_price: self.limit_price,
+      legs,
+      client_order_id: self.client_order_id,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// A POST request to be made to the /v2/orders endpoint for submitting
+/// a multi-leg options order, e.g., a spread.
+///
+/// The request is sent with an order class of
+/// [`MultiLeg`][Class::MultiLeg].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "order_class", rename = "mleg")]
+pub struct CreateMultiLegReq {
+  /// The number of units of the strategy to trade, i.e., the quantity
+  /// that all leg ratios are relative to.
+  #[serde(rename = "qty")]
+  pub quantity: Num,
+  /// The type of the order.
+  #[serde(rename = "type")]
+  pub type_: Type,
+  /// How long the order will be valid.
+  #[serde(rename = "time_in_force")]
+  pub time_in_force: TimeInForce,
+  /// The limit price of the strategy as a whole.
+  #[serde(rename = "limit_price")]
+  pub limit_price: Option<Num>,
+  /// The legs of the order.
+  #[serde(rename = "legs")]
+  pub legs: Vec<Leg>,
+  /// Client unique order ID (free form string).
+  ///
+  /// See [`CreateReq::client_order_id`].
+  #[serde(rename = "client_order_id")]
+  pub client_order_id: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+impl CreateMultiLegReq {
+  /// Check that the request's legs are consistent.
+  ///
+  /// A multi-leg order has two to four legs, each with a positive ratio
+  /// quantity, and the ratio quantities of the buy legs have to add up
+  /// to those of the sell legs.
+  fn check_legs(&self) -> Result<(), ConversionError> {
+    if !(2..=4).contains(&self.legs.len()) {
+      return Err(ConversionError::Validation(format!(
+        "multi-leg order requires two to four legs, but {} were provided",
+        self.legs.len()
+      )))
+    }
+
+    let mut balance = Num::from(0);
+    for leg in &self.legs {
+      if !leg.ratio_quantity.is_positive() {
+        return Err(ConversionError::Validation(format!(
+          "leg {} has non-positive ratio quantity {}",
+          leg.symbol, leg.ratio_quantity
+        )))
+      }
+
+      match leg.side {
+        Side::Buy => balance += &leg.ratio_quantity,
+        Side::Sell => balance -= &leg.ratio_quantity,
+        Side::Unknown => {
+          return Err(ConversionError::Validation(format!(
+            "leg {} has unknown side",
+            leg.symbol
+          )))
+        },
+      }
+    }
+
+    if !balance.is_zero() {
+      return Err(ConversionError::Validation(format!(
+        "ratio quantities of buy and sell legs are unbalanced by {balance}"
+      )))
+    }
+    Ok(())
+  }
+}
+
+
+/// The overall status of a bracket order, taking into account its
+/// legs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BracketStatus {
+  /// The entry order has not been filled yet.
+  Working,
+  /// The entry order has been filled and the exit legs are active.
+  EntryFilled,
+  /// The bracket has run its course, either because the entry order
+  /// did not fill or because the position has been exited.
+  Closed,
+}
+
+
+/// A single order as returned by the /v2/orders endpoint on a GET
+/// request.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Order {
+  /// The order's ID.
+  #[serde(rename = "id")]
+  pub id: Id,
+  /// Client unique order ID.
+  #[serde(rename = "client_order_id")]
+  pub client_order_id: String,
+  /// The status of the order.
+  #[serde(rename = "status", deserialize_with = "strict_variant")]
+  pub status: Status,
+  /// Timestamp this order was created at.
+  #[serde(rename = "created_at")]
+  pub created_at: DateTime<Utc>,
+  /// Timestamp this order was updated at last.
+  #[serde(rename = "updated_at")]
+  pub updated_at: Option<DateTime<Utc>>,
+  /// Timestamp this order was submitted at.
+  #[serde(rename = "submitted_at")]
+  pub submitted_at: Option<DateTime<Utc>>,
+  /// Timestamp this order was filled at.
+  #[serde(rename = "filled_at")]
+  pub filled_at: Option<DateTime<Utc>>,
+  /// Timestamp this order expired at.
+  #[serde(rename = "expired_at")]
+  pub expired_at: Option<DateTime<Utc>>,
+  /// Timestamp this order was canceled at.
+  #[serde(rename = "canceled_at")]
+  pub canceled_at: Option<DateTime<Utc>>,
+  /// Timestamp this order failed at.
+  #[serde(rename = "failed_at", default)]
+  pub failed_at: Option<DateTime<Utc>>,
+  /// Timestamp this order was replaced at.
+  #[serde(rename = "replaced_at", default)]
+  pub replaced_at: Option<DateTime<Utc>>,
+  /// The ID of the order that replaced this one, if any.
+  #[serde(rename = "replaced_by", default)]
+  pub replaced_by: Option<Id>,
+  /// The ID of the order this one replaced, if any.
+  #[serde(rename = "replaces", default)]
+  pub replaces: Option<Id>,
+  /// The order's asset class.
+  #[serde(rename = "asset_class", deserialize_with = "strict_variant")]
+  pub asset_class: asset::Class,
+  /// The ID of the asset represented by the order.
+  ///
+  /// The parent order of a multi-leg order does not represent a single
+  /// asset and has a nil ID.
+  #[serde(rename = "asset_id", deserialize_with = "empty_to_nil")]
+  pub asset_id: asset::Id,
+  /// The symbol of the asset being traded.
+  ///
+  /// The parent order of a multi-leg order has an empty symbol.
+  #[serde(rename = "symbol")]
+  pub symbol: String,
+  /// The amount being requested.
+  #[serde(flatten)]
+  pub amount: Amount,
+  /// The quantity that was filled.
   #[serde(rename = "filled_qty")]
   pub filled_quantity: Num,
   /// The type of order.
-  #[serde(rename = "type")]
+  #[serde(rename = "type", deserialize_with = "strict_variant")]
   pub type_: Type,
   /// The order class.
   #[serde(rename = "order_class", deserialize_with = "empty_to_default")]
   pub class: Class,
   /// The side the order is on.
-  #[serde(rename = "side")]
+  #[serde(rename = "side", deserialize_with = "strict_variant")]
   pub side: Side,
   /// A representation of how long the order will be valid.
   #[serde(rename = "time_in_force")]
@@ -631,15 +1607,239 @@ pub struct Order {
   /// Additional legs of the order.
   ///
   /// Such an additional leg could be, for example, the order for the
-  /// take profit part of a bracket-style order.
+  /// take profit part of a bracket-style order. Note that when listing
+  /// orders, legs are only reported if nested orders were requested
+  /// (see [`orders::ListReq::nested`][crate::api::v2::orders::ListReq::nested]);
+  /// otherwise this list is empty.
   #[serde(rename = "legs", deserialize_with = "vec_from_str")]
   pub legs: Vec<Order>,
+  /// The quantity of the order relative to its parent, if it is a leg
+  /// of a multi-leg order.
+  #[serde(rename = "ratio_qty", default, skip_serializing_if = "Option::is_none")]
+  pub ratio_quantity: Option<Num>,
+  /// The intent of the order with respect to the position in its
+  /// asset, if reported.
+  #[serde(
+    rename = "position_intent",
+    default,
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub position_intent: Option<PositionIntent>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
+impl Order {
+  /// Retrieve the order's quantity, signed according to its side.
+  ///
+  /// The quantity is negative for sell orders and positive for buy
+  /// orders. For notional orders, for which the quantity is not known
+  /// up front, the filled quantity is reported.
+  pub fn signed_qty(&self) -> Num {
+    let quantity = match &self.amount {
+      Amount::Quantity { quantity } => quantity.clone(),
+      Amount::Notional { .. } => self.filled_quantity.clone(),
+    };
+
+    match self.side {
+      Side::Buy | Side::Unknown => quantity,
+      Side::Sell => -quantity,
+    }
+  }
+
+  /// Retrieve a key ordering orders chronologically by the time they
+  /// were submitted at.
+  ///
+  /// Orders that have not been submitted yet are ordered by their
+  /// creation time instead. The order ID breaks ties, so that distinct
+  /// orders never share a key.
+  #[inline]
+  pub fn submission_key(&self) -> (DateTime<Utc>, Id) {
+    (self.submitted_at.unwrap_or(self.created_at), self.id)
+  }
+
+  /// Compare two orders by the time they were submitted at, as per
+  /// [`Order::submission_key`].
+  ///
+  /// This function can be used with, e.g., [`slice::sort_by`].
+  #[inline]
+  pub fn cmp_by_submission(&self, other: &Self) -> Ordering {
+    self.submission_key().cmp(&other.submission_key())
+  }
+
+  /// Retrieve the order's quantity, if known.
+  ///
+  /// Notional orders do not have a quantity and `None` is reported for
+  /// them.
+  #[inline]
+  pub fn qty(&self) -> Option<&Num> {
+    match &self.amount {
+      Amount::Quantity { quantity } => Some(quantity),
+      Amount::Notional { .. } => None,
+    }
+  }
+
+  /// Retrieve the quantity that has yet to be filled.
+  ///
+  /// `None` is reported for notional orders, as the quantity they will
+  /// end up with is not known up front.
+  pub fn remaining_qty(&self) -> Option<Num> {
+    self.qty().map(|qty| qty - &self.filled_quantity)
+  }
+
+  /// Check whether the order has been filled completely.
+  ///
+  /// For notional orders, which lack a quantity to compare the filled
+  /// quantity against, the order's status is consulted instead.
+  pub fn is_fully_filled(&self) -> bool {
+    match self.qty() {
+      Some(qty) => &self.filled_quantity >= qty,
+      None => self.status == Status::Filled,
+    }
+  }
+
+  /// Find the bracket leg with a type matching the provided predicate.
+  fn bracket_leg(&self, predicate: fn(Type) -> bool) -> Option<&Order> {
+    if self.class != Class::Bracket {
+      return None
+    }
+
+    self.legs.iter().find(|leg| predicate(leg.type_))
+  }
+
+  /// Retrieve the take-profit leg of a bracket order.
+  ///
+  /// `None` is reported for orders that are not bracket orders, as well
+  /// as when legs were not reported (see [`Order::legs`]).
+  #[inline]
+  pub fn take_profit_leg(&self) -> Option<&Order> {
+    self.bracket_leg(|type_| type_ == Type::Limit)
+  }
+
+  /// Retrieve the stop-loss leg of a bracket order.
+  ///
+  /// `None` is reported for orders that are not bracket orders, as well
+  /// as when legs were not reported (see [`Order::legs`]).
+  #[inline]
+  pub fn stop_loss_leg(&self) -> Option<&Order> {
+    self.bracket_leg(|type_| matches!(type_, Type::Stop | Type::StopLimit))
+  }
+
+  /// Determine the overall status of a bracket order.
+  ///
+  /// Orders that are not bracket orders are always reported as
+  /// [`BracketStatus::Working`]. A filled bracket order is considered
+  /// closed only once all of its legs reached a terminal state, which
+  /// requires legs to be reported (see [`Order::legs`]).
+  pub fn bracket_status(&self) -> BracketStatus {
+    if self.class != Class::Bracket {
+      return BracketStatus::Working
+    }
+
+    match self.status {
+      Status::Filled => {
+        let closed = !self.legs.is_empty() && self.legs.iter().all(|leg| leg.status.is_terminal());
+        if closed {
+          BracketStatus::Closed
+        } else {
+          BracketStatus::EntryFilled
+        }
+      },
+      status if status.is_terminal() => BracketStatus::Closed,
+      _ => BracketStatus::Working,
+    }
+  }
+
+  /// Check whether the order is in a terminal state, i.e., no more
+  /// changes will occur to it.
+  #[inline]
+  pub fn is_terminal(&self) -> bool {
+    self.status.is_terminal()
+  }
+
+  /// Cancel the order, by means of the [`Delete`] endpoint.
+  #[inline]
+  pub async fn cancel(&self, client: &Client) -> Result<(), RequestError<DeleteError>> {
+    client.issue::<Delete>(&self.id).await
+  }
+}
+
+
+/// An error as reported by [`replace_chain`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum ReplaceChainError {
+  /// No orders were provided.
+  #[error("no orders provided")]
+  Empty,
+  /// The `replaced_by` links form a cycle, running through the order
+  /// with the given ID.
+  #[error("replace chain contains a cycle through order {}", .0.as_hyphenated())]
+  Cycle(Id),
+  /// An order was replaced by one that is not part of the provided
+  /// set.
+  #[error("replacement order {} is missing", .0.as_hyphenated())]
+  MissingLink(Id),
+  /// An order is not part of the chain of replacements.
+  #[error("order {} is not part of the replace chain", .0.as_hyphenated())]
+  Unlinked(Id),
+}
+
+
+/// Arrange a set of related orders into the chain formed by their
+/// replacements.
+///
+/// Starting with the order that did not replace any of the others,
+/// [`Order::replaced_by`] links are followed until reaching the order
+/// that has not been replaced (yet). The chain is reported in that
+/// sequence, meaning that its last entry represents the order
+/// currently in effect. All provided orders have to be part of the
+/// chain.
+pub fn replace_chain(orders: &[Order]) -> Result<Vec<&Order>, ReplaceChainError> {
+  let by_id = orders
+    .iter()
+    .map(|order| (order.id, order))
+    .collect::<HashMap<_, _>>();
+
+  let first = orders.first().ok_or(ReplaceChainError::Empty)?;
+  // If every order replaced another one of the set, all of them are
+  // part of a cycle.
+  let mut order = orders
+    .iter()
+    .find(|order| {
+      order
+        .replaces
+        .map(|replaces| !by_id.contains_key(&replaces))
+        .unwrap_or(true)
+    })
+    .ok_or(ReplaceChainError::Cycle(first.id))?;
+
+  let mut visited = HashSet::new();
+  let mut chain = Vec::new();
+  loop {
+    if !visited.insert(order.id) {
+      return Err(ReplaceChainError::Cycle(order.id))
+    }
+    let () = chain.push(order);
+
+    match order.replaced_by {
+      Some(replaced_by) => {
+        order = by_id
+          .get(&replaced_by)
+          .ok_or(ReplaceChainError::MissingLink(replaced_by))?;
+      },
+      None => break,
+    }
+  }
+
+  if let Some(order) = orders.iter().find(|order| !visited.contains(&order.id)) {
+    return Err(ReplaceChainError::Unlinked(order.id))
+  }
+  Ok(chain)
+}
+
 
 Endpoint! {
   /// The representation of a GET request to the /v2/orders/{order-id}
@@ -655,7 +1855,7 @@ Endpoint! {
   ]
 
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/orders/{}", input.as_simple()).into()
+    path!("/v2/orders/{}", input).into()
   }
 }
 
@@ -719,6 +1919,50 @@ Endpoint! {
   }
 
   fn body(input: &Self::Input) -> Result<Option<Bytes>, Self::ConversionError> {
+    let () = input.check(false).map_err(ConversionError::InvalidOrder)?;
+    let json = to_json(input)?;
+    let bytes = Bytes::from(json);
+    Ok(Some(bytes))
+  }
+}
+
+impl CreateError {
+  /// Retrieve the problems found by client-side validation, if the
+  /// request was rejected before being submitted.
+  pub fn validation_error(&self) -> Option<&OrderValidationError> {
+    match self {
+      Self::Conversion(ConversionError::InvalidOrder(error)) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a POST request to the /v2/orders endpoint
+  /// for submitting a multi-leg order.
+  pub CreateMultiLeg(CreateMultiLegReq),
+  Ok => Order, [
+    /// The order was submitted successfully.
+    /* 200 */ OK,
+  ],
+  Err => CreateMultiLegError, [
+    /// Some data in the request was invalid.
+    /* 422 */ UNPROCESSABLE_ENTITY => InvalidInput,
+  ]
+
+  #[inline]
+  fn method() -> Method {
+    Method::POST
+  }
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/orders".into()
+  }
+
+  fn body(input: &Self::Input) -> Result<Option<Bytes>, Self::ConversionError> {
+    let () = input.check_legs()?;
     let json = to_json(input)?;
     let bytes = Bytes::from(json);
     Ok(Some(bytes))
@@ -748,7 +1992,7 @@ Endpoint! {
 
   fn path(input: &Self::Input) -> Str {
     let (id, _) = input;
-    format!("/v2/orders/{}", id.as_simple()).into()
+    path!("/v2/orders/{}", id).into()
   }
 
   fn body(input: &Self::Input) -> Result<Option<Bytes>, Self::ConversionError> {
@@ -781,7 +2025,7 @@ EndpointNoParse! {
   }
 
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/orders/{}", input.as_simple()).into()
+    path!("/v2/orders/{}", input).into()
   }
 
   #[inline]
@@ -796,27 +2040,480 @@ EndpointNoParse! {
 }
 
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+/// The code Alpaca reports for order submissions rejected because of
+/// invalid input, including a client order ID that is in use already.
+const CODE_INVALID_ORDER: u64 = 40010001;
 
-  use std::str::FromStr as _;
 
-  use futures::TryFutureExt;
+/// Check whether an error reported by the [`Create`] endpoint may
+/// indicate that an order with the same client order ID exists
+/// already.
+///
+/// Alpaca does not use a dedicated code for duplicate client order IDs,
+/// so callers have to confirm that such an order exists.
+fn is_duplicate_client_order_id(error: &CreateError) -> bool {
+  matches!(
+    error,
+    CreateError::InvalidInput(Ok(ApiError {
+      code: Some(CODE_INVALID_ORDER),
+      ..
+    }))
+  )
+}
 
-  use serde_json::from_slice as from_json;
 
-  use test_log::test;
+/// An error as reported by [`Client::submit_idempotent`].
+#[derive(Debug, ThisError)]
+pub enum SubmitIdempotentError {
+  /// Submission of the order failed.
+  #[error("failed to submit order")]
+  Create(#[source] RequestError<CreateError>),
+  /// Retrieval of the already existing order failed.
+  #[error("failed to retrieve existing order")]
+  Get(#[source] RequestError<GetByClientIdError>),
+}
 
-  use uuid::Uuid;
+
+/// The maximum number of times [`Client::submit_order_idempotent`]
+/// attempts submission of an order.
+const MAX_SUBMIT_ATTEMPTS: usize = 3;
+
+
+/// An error as reported by [`Client::submit_order`].
+#[derive(Debug, ThisError)]
+pub enum SubmitOrderError {
+  /// The order failed the pre-flight check against its asset.
+  #[error("order failed pre-flight check")]
+  Preflight(#[source] OrderPreflightError),
+  /// Submission of the order failed.
+  #[error("failed to submit order")]
+  Create(#[source] RequestError<CreateError>),
+}
+
+
+/// An error as reported by [`Client::submit_order_market_guarded`].
+#[derive(Debug, ThisError)]
+pub enum SubmitGuardedError {
+  /// The market is closed and the order was not submitted.
+  #[error("market is closed until {next_open}; order was not submitted")]
+  MarketClosed {
+    /// The time stamp of the next market open.
+    next_open: DateTime<Utc>,
+  },
+  /// Retrieval of the market clock failed.
+  #[error("failed to retrieve market clock")]
+  Clock(#[source] RequestError<clock::GetError>),
+  /// Retrieval of the market calendar, for checking whether the market
+  /// is in an extended hours session, failed.
+  #[error("failed to retrieve market calendar")]
+  Calendar(#[source] RequestError<calendar::ListError>),
+  /// Submission of the order failed.
+  #[error("failed to submit order")]
+  Create(#[source] RequestError<CreateError>),
+}
+
+
+/// An error as reported by [`Client::limit_at_midpoint`].
+#[cfg(feature = "market-data")]
+#[derive(Debug, ThisError)]
+pub enum LimitAtMidpointError {
+  /// Retrieval of the latest quote failed.
+  #[error("failed to retrieve latest quote")]
+  Quote(#[source] RequestError<last_quotes::GetError>),
+  /// No quote was reported for the symbol.
+  #[error("no quote available for {0}")]
+  NoQuote(String),
+  /// The latest quote is crossed or locked.
+  #[error("failed to determine midpoint")]
+  Crossed(#[source] CrossedQuote),
+  /// Submission of the order failed.
+  #[error("failed to submit order")]
+  Create(#[source] RequestError<CreateError>),
+}
+
+
+/// An error as reported by [`Client::estimate_order_impact`].
+#[derive(Debug, ThisError)]
+pub enum EstimateOrderImpactError {
+  /// Retrieval of the account failed.
+  #[error("failed to retrieve account")]
+  Account(#[source] RequestError<account::GetError>),
+  /// Retrieval of the asset failed.
+  #[error("failed to retrieve asset")]
+  Asset(#[source] RequestError<asset::GetError>),
+}
+
+
+/// The interval at which [`Client::cancel_and_replace`] checks whether
+/// the order to replace got canceled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The maximum number of times [`Client::cancel_and_replace`] looks up
+/// the order to replace before giving up on confirming its
+/// cancellation.
+const MAX_CANCEL_CHECKS: usize = 20;
+
+
+/// An error as reported by [`Client::cancel_and_replace`].
+#[derive(Debug, ThisError)]
+pub enum CancelAndReplaceError {
+  /// The request to cancel the existing order failed.
+  #[error("failed to cancel order")]
+  Cancel(#[source] RequestError<DeleteError>),
+  /// Looking up the existing order for confirming its cancellation
+  /// failed.
+  #[error("failed to confirm order cancellation")]
+  Get(#[source] RequestError<GetError>),
+  /// The existing order reached a terminal state other than canceled,
+  /// e.g., because it got filled before the cancellation took effect.
+  /// No replacement was submitted.
+  #[error("order {} is {:?} instead of canceled; no replacement was submitted", .0.id.as_hyphenated(), .0.status)]
+  NotCanceled(Box<Order>),
+  /// The cancellation of the existing order could not be confirmed in
+  /// time. No replacement was submitted.
+  #[error("cancellation of order {} was not confirmed; no replacement was submitted", .0.id.as_hyphenated())]
+  Unconfirmed(Box<Order>),
+  /// The existing order got canceled, but submitting the replacement
+  /// failed. The old order stays canceled.
+  #[error("order {} got canceled but its replacement could not be submitted", .canceled.id.as_hyphenated())]
+  Replace {
+    /// The canceled order.
+    canceled: Box<Order>,
+    /// The error reported when submitting the replacement.
+    #[source]
+    error: RequestError<CreateError>,
+  },
+}
+
+
+impl Client {
+  /// Retrieve the order with the provided client order ID, by means of
+  /// the [`GetByClientId`] endpoint.
+  ///
+  /// The ID is URL-encoded as necessary. An order that does not exist
+  /// is reported as [`GetByClientIdError::NotFound`].
+  #[inline]
+  pub async fn get_order_by_client_id(
+    &self,
+    client_order_id: &str,
+  ) -> Result<Order, RequestError<GetByClientIdError>> {
+    self
+      .issue::<GetByClientId>(&client_order_id.to_string())
+      .await
+  }
+
+  /// Submit an order, optionally checking it against its asset first.
+  ///
+  /// If `asset` is provided (typically a cached object, as no request
+  /// is made to retrieve it), the order is validated as described for
+  /// [`CreateReq::preflight`] and only submitted if it passes. If it is
+  /// `None`, this method behaves just like issuing a [`Create`]
+  /// request.
+  pub async fn submit_order(
+    &self,
+    request: &CreateReq,
+    asset: Option<&Asset>,
+  ) -> Result<Order, SubmitOrderError> {
+    if let Some(asset) = asset {
+      let () = request
+        .preflight(asset)
+        .map_err(SubmitOrderError::Preflight)?;
+    }
+
+    self
+      .issue::<Create>(request)
+      .await
+      .map_err(SubmitOrderError::Create)
+  }
+
+  /// Submit an order, unless it is a market or day order and the
+  /// market is closed.
+  ///
+  /// The market clock is checked by means of [`Client::cached_clock`],
+  /// meaning that it is not necessarily retrieved for each order. If
+  /// the market is closed, market orders as well as orders with time
+  /// in force [`Day`][TimeInForce::Day] are rejected with
+  /// [`SubmitGuardedError::MarketClosed`] without contacting the
+  /// server, unless the request has
+  /// [`extended_hours`][CreateReq::extended_hours] set and the market
+  /// is in an extended hours session. Other orders are submitted just
+  /// like with a [`Create`] request.
+  pub async fn submit_order_market_guarded(
+    &self,
+    request: &CreateReq,
+  ) -> Result<Order, SubmitGuardedError> {
+    if request.type_ == Type::Market || request.time_in_force == TimeInForce::Day {
+      let clock = self
+        .cached_clock()
+        .await
+        .map_err(SubmitGuardedError::Clock)?;
+
+      if !clock.open {
+        let extended = request.extended_hours
+          && is_extended_hours_at(self, &clock)
+            .await
+            .map_err(SubmitGuardedError::Calendar)?;
+        if !extended {
+          return Err(SubmitGuardedError::MarketClosed {
+            next_open: clock.next_open,
+          })
+        }
+      }
+    }
+
+    self
+      .issue::<Create>(request)
+      .await
+      .map_err(SubmitGuardedError::Create)
+  }
+
+  /// Submit an order, or retrieve the already existing one with the
+  /// same client order ID.
+  ///
+  /// If an order with the request's
+  /// [`client_order_id`][CreateReq::client_order_id] exists already
+  /// (which Alpaca reports as an error), this order is retrieved and
+  /// returned instead. That allows for safely retrying submissions of
+  /// orders. Note that the existing order is returned as-is, even if it
+  /// differs from the provided request in some way.
+  ///
+  /// If the request does not contain a client order ID or no order
+  /// with this ID exists, this method behaves just like issuing a
+  /// [`Create`] request.
+  pub async fn submit_idempotent(
+    &self,
+    request: &CreateReq,
+  ) -> Result<Order, SubmitIdempotentError> {
+    let error = match self.issue::<Create>(request).await {
+      Ok(order) => return Ok(order),
+      Err(error) => error,
+    };
+
+    match (&error, &request.client_order_id) {
+      (RequestError::Endpoint(create), Some(client_order_id))
+        if is_duplicate_client_order_id(create) =>
+      {
+        match self.issue::<GetByClientId>(client_order_id).await {
+          Ok(order) => Ok(order),
+          // The submission was rejected for another reason.
+          Err(RequestError::Endpoint(GetByClientIdError::NotFound(..))) => {
+            Err(SubmitIdempotentError::Create(error))
+          },
+          Err(err) => Err(SubmitIdempotentError::Get(err)),
+        }
+      },
+      _ => Err(SubmitIdempotentError::Create(error)),
+    }
+  }
+
+  /// Submit an order, retrying submission on transient failures without
+  /// creating duplicates.
+  ///
+  /// If the request does not contain a client order ID, a random one
+  /// is generated. When submission fails with a transient error (e.g.,
+  /// because the connection timed out), the order may or may not have
+  /// been created. In that case the order is looked up by its client
+  /// order ID and only resubmitted if it does not exist. Submission is
+  /// attempted at most three times.
+  ///
+  /// Note that requests only time out if the client was built with a
+  /// request timeout (see [`Client::builder`]).
+  pub async fn submit_order_idempotent(
+    &self,
+    request: &CreateReq,
+  ) -> Result<Order, SubmitIdempotentError> {
+    let mut request = request.clone();
+    let client_order_id = request.ensure_client_order_id().to_string();
+    let mut attempt = 1;
+
+    loop {
+      match self.submit_idempotent(&request).await {
+        Err(SubmitIdempotentError::Create(error))
+          if error.is_transient() && attempt < MAX_SUBMIT_ATTEMPTS =>
+        {
+          debug!(
+            client_order_id = display(&client_order_id),
+            "order submission failed; checking for existing order"
+          );
+          match self.issue::<GetByClientId>(&client_order_id).await {
+            Ok(order) => break Ok(order),
+            Err(RequestError::Endpoint(GetByClientIdError::NotFound(..))) => (),
+            Err(error) => break Err(SubmitIdempotentError::Get(error)),
+          }
+          attempt += 1;
+        },
+        result => break result,
+      }
+    }
+  }
+
+  /// Submit multiple orders, with at most `max_concurrency` of them in
+  /// flight at any time.
+  ///
+  /// Each request is issued just like a [`Create`] request, meaning
+  /// that a failure to submit one order does not affect the others.
+  /// The results are reported in the order of the provided requests,
+  /// irrespective of the order in which submissions complete. A
+  /// `max_concurrency` of zero is treated as one.
+  pub async fn submit_orders(
+    &self,
+    requests: Vec<CreateReq>,
+    max_concurrency: usize,
+  ) -> Vec<Result<Order, RequestError<CreateError>>> {
+    iter(requests.iter().map(|request| self.issue::<Create>(request)))
+      .buffered(max_concurrency.max(1))
+      .collect()
+      .await
+  }
+
+  /// Submit a day limit order priced at the midpoint of the latest
+  /// quote for `symbol`.
+  ///
+  /// Please refer to [`CreateReqInit::init_limit_at_midpoint`] for
+  /// details on how the limit price is determined.
+  #[cfg(feature = "market-data")]
+  pub async fn limit_at_midpoint(
+    &self,
+    symbol: &str,
+    side: Side,
+    qty: Num,
+  ) -> Result<Order, LimitAtMidpointError> {
+    let request = last_quotes::GetReqInit::default().init([symbol]);
+    let quotes = self
+      .issue::<last_quotes::Get>(&request)
+      .await
+      .map_err(LimitAtMidpointError::Quote)?;
+    let (_, quote) = quotes
+      .into_iter()
+      .find(|(quoted, _)| quoted == symbol)
+      .ok_or_else(|| LimitAtMidpointError::NoQuote(symbol.to_string()))?;
+
+    let request = CreateReqInit::default()
+      .init_limit_at_midpoint(symbol, side, Amount::quantity(qty), &quote)
+      .map_err(LimitAtMidpointError::Crossed)?;
+    self
+      .issue::<Create>(&request)
+      .await
+      .map_err(LimitAtMidpointError::Create)
+  }
+
+  /// Estimate whether an order would be accepted, without submitting
+  /// it.
+  ///
+  /// The account and the order's asset are retrieved and checked as
+  /// described for [`CreateReq::feasibility`]. Callers already having
+  /// the account and asset at hand can use that method directly
+  /// instead, avoiding any network access.
+  pub async fn estimate_order_impact(
+    &self,
+    request: &CreateReq,
+    price: &Num,
+  ) -> Result<OrderFeasibility, EstimateOrderImpactError> {
+    let account = self
+      .issue::<account::Get>(&())
+      .await
+      .map_err(EstimateOrderImpactError::Account)?;
+    let asset = self
+      .issue::<asset::Get>(&request.symbol)
+      .await
+      .map_err(EstimateOrderImpactError::Asset)?;
+
+    Ok(request.feasibility(&account, &asset, price))
+  }
+
+  /// Cancel the order with the provided ID and submit `request` as its
+  /// replacement once the cancellation is confirmed.
+  ///
+  /// Unlike the [`Change`] endpoint, this works for any change to the
+  /// order, at the cost of not being atomic. The order is looked up
+  /// until it is reported as canceled and the replacement is only
+  /// submitted afterwards. If the order reaches another terminal state
+  /// instead (e.g., because it got filled), no replacement is
+  /// submitted. Both the canceled and the new order are reported, in
+  /// this order.
+  ///
+  /// There is no way to roll back the cancellation. If submission of
+  /// the replacement fails,
+  /// [`CancelAndReplaceError::Replace`] is reported, containing the
+  /// canceled order.
+  pub async fn cancel_and_replace(
+    &self,
+    id: Id,
+    request: &CreateReq,
+  ) -> Result<(Order, Order), CancelAndReplaceError> {
+    let () = self
+      .issue::<Delete>(&id)
+      .await
+      .map_err(CancelAndReplaceError::Cancel)?;
+
+    let mut checks = 0;
+    let canceled = loop {
+      let order = self
+        .issue::<Get>(&id)
+        .await
+        .map_err(CancelAndReplaceError::Get)?;
+      checks += 1;
+
+      match order.status {
+        Status::Canceled => break order,
+        _ if order.is_terminal() => return Err(CancelAndReplaceError::NotCanceled(Box::new(order))),
+        _ if checks >= MAX_CANCEL_CHECKS => {
+          return Err(CancelAndReplaceError::Unconfirmed(Box::new(order)))
+        },
+        _ => {
+          let () = sleep(CANCEL_POLL_INTERVAL).await;
+        },
+      }
+    };
+
+    match self.issue::<Create>(request).await {
+      Ok(replacement) => Ok((canceled, replacement)),
+      Err(error) => Err(CancelAndReplaceError::Replace {
+        canceled: Box::new(canceled),
+        error,
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::collections::BTreeSet;
+  use std::error::Error as _;
+  use std::str::FromStr as _;
+  use std::time::Duration;
+
+  use chrono::NaiveDate;
+  use chrono::NaiveTime;
+
+  use futures::TryFutureExt;
+
+  use http_endpoint::Endpoint as _;
+
+  use proptest::collection::vec;
+  use proptest::proptest;
+
+  use serde_json::from_slice as from_json;
+
+  use test_log::test;
+
+  use uuid::Uuid;
 
   use crate::api::v2::asset;
   use crate::api::v2::asset::Exchange;
   use crate::api::v2::asset::Symbol;
+  use crate::api::v2::num_util::check_round_trip;
+  use crate::api::v2::num_util::decimal;
   use crate::api::v2::order_util::order_aapl;
   use crate::api_info::ApiInfo;
-  use crate::Client;
-  use crate::RequestError;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::serve_with;
+  use crate::test_util::Reply;
+  use crate::test_util::Response;
+  use crate::util::with_strict;
+  use crate::ApiError;
 
 
   /// Check that we can serialize a [`Side`] object.
@@ -833,6 +2530,25 @@ mod tests {
     assert_eq!(!Side::Sell, Side::Buy);
   }
 
+  /// Check that we can deserialize a [`Side`] object, including sides
+  /// we do not know about.
+  #[test]
+  fn parse_side() {
+    assert_eq!(from_json::<Side>(br#""buy""#).unwrap(), Side::Buy);
+    assert_eq!(from_json::<Side>(br#""sell""#).unwrap(), Side::Sell);
+    assert_eq!(
+      from_json::<Side>(br#""sell_short""#).unwrap(),
+      Side::Unknown
+    );
+  }
+
+  /// Verify that we pick the correct order side for closing a position.
+  #[test]
+  fn closing_side() {
+    assert_eq!(Side::closing_for(position::Side::Long), Side::Sell);
+    assert_eq!(Side::closing_for(position::Side::Short), Side::Buy);
+  }
+
   /// Check that we can serialize a [`Type`] object.
   #[test]
   fn emit_type() {
@@ -841,6 +2557,83 @@ mod tests {
     assert_eq!(to_json(&Type::Stop).unwrap(), br#""stop""#);
   }
 
+  /// Check that we can serialize a [`TimeInForce`] object.
+  #[test]
+  fn emit_time_in_force() {
+    let expected = [
+      (TimeInForce::Day, &br#""day""#[..]),
+      (TimeInForce::UntilCanceled, br#""gtc""#),
+      (TimeInForce::UntilMarketOpen, br#""opg""#),
+      (TimeInForce::UntilMarketClose, br#""cls""#),
+      (TimeInForce::ImmediateOrCancel, br#""ioc""#),
+      (TimeInForce::FillOrKill, br#""fok""#),
+    ];
+
+    for (time_in_force, json) in expected {
+      assert_eq!(to_json(&time_in_force).unwrap(), json);
+      assert_eq!(from_json::<TimeInForce>(json).unwrap(), time_in_force);
+    }
+  }
+
+  /// Check that we reject invalid combinations of time in force, order
+  /// type, and asset class.
+  #[test]
+  fn validate_time_in_force() {
+    let equity = asset::Class::UsEquity;
+    assert_eq!(
+      TimeInForce::UntilMarketOpen.validate(Type::Stop, equity),
+      Err(TimeInForceError::UnsupportedType(
+        TimeInForce::UntilMarketOpen,
+        Type::Stop
+      ))
+    );
+    assert_eq!(
+      TimeInForce::UntilMarketClose.validate(Type::StopLimit, equity),
+      Err(TimeInForceError::UnsupportedType(
+        TimeInForce::UntilMarketClose,
+        Type::StopLimit
+      ))
+    );
+    assert_eq!(
+      TimeInForce::FillOrKill.validate(Type::TrailingStop, equity),
+      Err(TimeInForceError::UnsupportedType(
+        TimeInForce::FillOrKill,
+        Type::TrailingStop
+      ))
+    );
+    assert_eq!(
+      TimeInForce::UntilMarketOpen.validate(Type::Market, asset::Class::Crypto),
+      Err(TimeInForceError::UnsupportedAssetClass(
+        TimeInForce::UntilMarketOpen,
+        asset::Class::Crypto
+      ))
+    );
+    assert_eq!(
+      TimeInForce::UntilCanceled.validate(Type::Limit, asset::Class::UsOption),
+      Err(TimeInForceError::UnsupportedAssetClass(
+        TimeInForce::UntilCanceled,
+        asset::Class::UsOption
+      ))
+    );
+
+    assert_eq!(
+      TimeInForce::UntilCanceled.validate(Type::Limit, equity),
+      Ok(())
+    );
+    assert_eq!(
+      TimeInForce::UntilMarketClose.validate(Type::Market, equity),
+      Ok(())
+    );
+    assert_eq!(
+      TimeInForce::UntilCanceled.validate(Type::Limit, asset::Class::Crypto),
+      Ok(())
+    );
+    assert_eq!(
+      TimeInForce::Day.validate(Type::TrailingStop, equity),
+      Ok(())
+    );
+  }
+
   /// Make sure that we can serialize and deserialize order legs.
   #[test]
   fn serialize_deserialize_legs() {
@@ -861,6 +2654,479 @@ mod tests {
     assert_eq!(from_json::<StopLoss>(&json).unwrap(), stop_loss);
   }
 
+  /// Check that we serialize a one-triggers-other order with a
+  /// take-profit leg correctly.
+  #[test]
+  fn serialize_one_triggers_other_take_profit() {
+    let request = CreateReqInit {
+      class: Class::OneTriggersOther,
+      type_: Type::Limit,
+      limit_price: Some(Num::from(2)),
+      take_profit: Some(TakeProfit::Limit(Num::from(3))),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+
+    let body = Create::body(&request).unwrap().unwrap();
+    let json = from_json::<serde_json::Value>(&body).unwrap();
+    assert_eq!(json["order_class"], "oto");
+    assert_eq!(json["take_profit"]["limit_price"], "3");
+    assert!(json["stop_loss"].is_null());
+  }
+
+  /// Check that we serialize a one-triggers-other order with a
+  /// stop-loss leg correctly.
+  #[test]
+  fn serialize_one_triggers_other_stop_loss() {
+    let request = CreateReqInit {
+      class: Class::OneTriggersOther,
+      type_: Type::Limit,
+      limit_price: Some(Num::from(2)),
+      stop_loss: Some(StopLoss::StopLimit(Num::from(1), Num::new(9, 10))),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+
+    let body = Create::body(&request).unwrap().unwrap();
+    let json = from_json::<serde_json::Value>(&body).unwrap();
+    assert_eq!(json["order_class"], "oto");
+    assert_eq!(json["stop_loss"]["stop_price"], "1");
+    assert_eq!(json["stop_loss"]["limit_price"], "0.9");
+    assert!(json["take_profit"].is_null());
+  }
+
+  /// Create a two-leg vertical call spread.
+  fn vertical_spread() -> CreateMultiLegReq {
+    let legs = vec![
+      Leg {
+        position_intent: Some(PositionIntent::BuyToOpen),
+        ..Leg::new("AAPL250620C00200000", 1, Side::Buy)
+      },
+      Leg {
+        position_intent: Some(PositionIntent::SellToOpen),
+        ..Leg::new("AAPL250620C00210000", 1, Side::Sell)
+      },
+    ];
+
+    CreateMultiLegReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::new(35, 10)),
+      ..Default::default()
+    }
+    .init(2, legs)
+  }
+
+  /// Check that we serialize a multi-leg order correctly.
+  #[test]
+  fn serialize_multi_leg_order() {
+    let request = vertical_spread();
+    let body = CreateMultiLeg::body(&request).unwrap().unwrap();
+    let json = from_json::<serde_json::Value>(&body).unwrap();
+    let expected = serde_json::json!({
+      "order_class": "mleg",
+      "qty": "2",
+      "type": "limit",
+      "time_in_force": "day",
+      "limit_price": "3.5",
+      "client_order_id": null,
+      "legs": [{
+        "symbol": "AAPL250620C00200000",
+        "ratio_qty": "1",
+        "side": "buy",
+        "position_intent": "buy_to_open",
+      }, {
+        "symbol": "AAPL250620C00210000",
+        "ratio_qty": "1",
+        "side": "sell",
+        "position_intent": "sell_to_open",
+      }],
+    });
+    assert_eq!(json, expected);
+    assert_eq!(from_json::<CreateMultiLegReq>(&body).unwrap(), request);
+  }
+
+  /// Check that the position intent of an order is serialized only if
+  /// set and that it has to be consistent with the order's side.
+  #[test]
+  fn serialize_position_intent() {
+    let request = CreateReqInit::default().init("AAPL", Side::Sell, Amount::quantity(1));
+    let body = Create::body(&request).unwrap().unwrap();
+    let json = from_json::<serde_json::Value>(&body).unwrap();
+    assert_eq!(json.get("position_intent"), None);
+
+    let request = CreateReqInit {
+      position_intent: Some(PositionIntent::SellToOpen),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Sell, Amount::quantity(1));
+    let body = Create::body(&request).unwrap().unwrap();
+    let json = from_json::<serde_json::Value>(&body).unwrap();
+    assert_eq!(json["position_intent"], "sell_to_open");
+    assert_eq!(from_json::<CreateReq>(&body).unwrap(), request);
+
+    let request = CreateReqInit {
+      position_intent: Some(PositionIntent::SellToOpen),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    let err = Create::body(&request).unwrap_err();
+    assert!(matches!(err, ConversionError::InvalidOrder(..)), "{err}");
+
+    let request = CreateReqInit {
+      position_intent: Some(PositionIntent::BuyToClose),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Sell, Amount::quantity(1));
+    let err = Create::body(&request).unwrap_err();
+    assert!(matches!(err, ConversionError::InvalidOrder(..)), "{err}");
+  }
+
+  /// Make sure that multi-leg orders with unbalanced ratio quantities
+  /// or an invalid number of legs are rejected.
+  #[test]
+  fn reject_invalid_multi_leg_order() {
+    let mut request = vertical_spread();
+    request.legs[1].ratio_quantity = Num::from(2);
+    let err = CreateMultiLeg::body(&request).unwrap_err();
+    assert!(matches!(err, ConversionError::Validation(..)), "{err}");
+    assert!(err.to_string().contains("unbalanced"), "{err}");
+
+    let mut request = vertical_spread();
+    let _leg = request.legs.pop();
+    let err = CreateMultiLeg::body(&request).unwrap_err();
+    assert!(matches!(err, ConversionError::Validation(..)), "{err}");
+  }
+
+  /// Check that we can deserialize the parent order of a multi-leg
+  /// order along with its legs.
+  #[test]
+  fn deserialize_multi_leg_order() {
+    let leg = |symbol, side, intent| {
+      format!(
+        r#"{{
+      "id": "a5c1c5ab-4fb4-4ecb-8d3c-3a2ee8837cf1",
+      "client_order_id": "leg",
+      "created_at": "2025-01-02T15:00:00Z",
+      "updated_at": "2025-01-02T15:00:00Z",
+      "submitted_at": "2025-01-02T15:00:00Z",
+      "filled_at": null,
+      "expired_at": null,
+      "canceled_at": null,
+      "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+      "symbol": "{symbol}",
+      "asset_class": "us_option",
+      "qty": "2",
+      "filled_qty": "0",
+      "type": "limit",
+      "order_class": "mleg",
+      "side": "{side}",
+      "position_intent": "{intent}",
+      "ratio_qty": "1",
+      "time_in_force": "day",
+      "limit_price": null,
+      "stop_price": null,
+      "filled_avg_price": null,
+      "status": "pending_new",
+      "extended_hours": false,
+      "legs": null
+    }}"#
+      )
+    };
+    let json = format!(
+      r#"{{
+    "id": "904837e3-3b76-47ec-b432-046db621571b",
+    "client_order_id": "spread",
+    "created_at": "2025-01-02T15:00:00Z",
+    "updated_at": "2025-01-02T15:00:00Z",
+    "submitted_at": "2025-01-02T15:00:00Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "asset_id": "",
+    "symbol": "",
+    "asset_class": "",
+    "qty": "2",
+    "filled_qty": "0",
+    "type": "limit",
+    "order_class": "mleg",
+    "side": "",
+    "position_intent": "",
+    "time_in_force": "day",
+    "limit_price": "3.5",
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "pending_new",
+    "extended_hours": false,
+    "legs": [{}, {}]
+}}"#,
+      leg("AAPL250620C00200000", "buy", "buy_to_open"),
+      leg("AAPL250620C00210000", "sell", "sell_to_open"),
+    );
+
+    let order = from_json::<Order>(json.as_bytes()).unwrap();
+    assert_eq!(order.class, Class::MultiLeg);
+    assert_eq!(order.asset_id, asset::Id(Uuid::nil()));
+    assert_eq!(order.symbol, "");
+    assert_eq!(order.ratio_quantity, None);
+    assert_eq!(order.legs.len(), 2);
+    assert_eq!(order.legs[0].symbol, "AAPL250620C00200000");
+    assert_eq!(order.legs[0].side, Side::Buy);
+    assert_eq!(
+      order.legs[0].position_intent,
+      Some(PositionIntent::BuyToOpen)
+    );
+    assert_eq!(order.legs[1].side, Side::Sell);
+    assert_eq!(order.legs[1].ratio_quantity, Some(Num::from(1)));
+  }
+
+  /// Make sure that a one-triggers-other order with both or neither of
+  /// the take-profit and stop-loss legs is rejected.
+  #[test]
+  fn reject_invalid_one_triggers_other() {
+    let request = CreateReqInit {
+      class: Class::OneTriggersOther,
+      type_: Type::Limit,
+      limit_price: Some(Num::from(2)),
+      take_profit: Some(TakeProfit::Limit(Num::from(3))),
+      stop_loss: Some(StopLoss::Stop(Num::from(1))),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+
+    let err = Create::body(&request).unwrap_err();
+    assert!(matches!(err, ConversionError::InvalidOrder(..)), "{err}");
+
+    let request = CreateReqInit {
+      class: Class::OneTriggersOther,
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+    assert!(Create::body(&request).is_err());
+
+    // Bracket orders carry both legs.
+    let request = CreateReqInit {
+      class: Class::Bracket,
+      take_profit: Some(TakeProfit::Limit(Num::from(3))),
+      stop_loss: Some(StopLoss::Stop(Num::from(1))),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+    assert!(Create::body(&request).is_ok());
+  }
+
+  /// Check that a well-formed order request passes validation.
+  #[test]
+  fn validate_valid_order() {
+    let request = CreateReqInit {
+      type_: Type::StopLimit,
+      limit_price: Some(Num::from(2)),
+      stop_price: Some(Num::from(1)),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+    assert_eq!(request.validate(), Ok(()));
+
+    let request = CreateReqInit {
+      type_: Type::TrailingStop,
+      trail_percent: Some(Num::from(5)),
+      ..Default::default()
+    }
+    .init("SPY", Side::Sell, Amount::quantity(1));
+    assert_eq!(request.validate(), Ok(()));
+  }
+
+  /// Verify that `CreateReq::validate` reports the problems of invalid
+  /// order requests and that `Create` rejects the same requests.
+  #[test]
+  fn validate_invalid_orders() {
+    let problems = |init: CreateReqInit, amount| {
+      let request = init.init("SPY", Side::Buy, amount);
+      let result = request.validate().map_err(|err| err.problems);
+      let body = match Create::body(&request) {
+        Ok(..) => Vec::new(),
+        Err(ConversionError::InvalidOrder(err)) => err.problems,
+        Err(err) => panic!("unexpected error: {err}"),
+      };
+      let expected = result
+        .clone()
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|problem| !matches!(problem, OrderProblem::NotionalTimeInForce(..)))
+        .collect::<Vec<_>>();
+      assert_eq!(body, expected);
+      result
+    };
+
+    assert_eq!(
+      problems(CreateReqInit::default(), Amount::quantity(0)),
+      Err(vec![OrderProblem::NonPositiveAmount])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          time_in_force: TimeInForce::UntilCanceled,
+          ..Default::default()
+        },
+        Amount::notional(10)
+      ),
+      Err(vec![OrderProblem::NotionalTimeInForce(
+        TimeInForce::UntilCanceled
+      )])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          type_: Type::StopLimit,
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![
+        OrderProblem::MissingLimitPrice(Type::StopLimit),
+        OrderProblem::MissingStopPrice(Type::StopLimit),
+      ])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          type_: Type::TrailingStop,
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::MissingTrail])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          type_: Type::TrailingStop,
+          trail_price: Some(Num::from(1)),
+          trail_percent: Some(Num::from(5)),
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::ConflictingTrail])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          trail_price: Some(Num::from(1)),
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::UnexpectedTrail(Type::Market)])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          take_profit: Some(TakeProfit::Limit(Num::from(3))),
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::InconsistentLegs(Class::Simple)])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          class: Class::OneCancelsOther,
+          stop_loss: Some(StopLoss::Stop(Num::from(1))),
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::InconsistentLegs(Class::OneCancelsOther)])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          position_intent: Some(PositionIntent::SellToClose),
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::InconsistentPositionIntent(
+        PositionIntent::SellToClose,
+        Side::Buy
+      )])
+    );
+    assert_eq!(
+      problems(
+        CreateReqInit {
+          extended_hours: true,
+          ..Default::default()
+        },
+        Amount::quantity(1)
+      ),
+      Err(vec![OrderProblem::ExtendedHours(
+        ExtendedHoursError::UnsupportedType(Type::Market)
+      )])
+    );
+
+    // All problems are reported at once.
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      time_in_force: TimeInForce::UntilCanceled,
+      trail_percent: Some(Num::from(5)),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::notional(0));
+    let err = request.validate().unwrap_err();
+    assert_eq!(
+      err.problems,
+      vec![
+        OrderProblem::NonPositiveAmount,
+        OrderProblem::NotionalTimeInForce(TimeInForce::UntilCanceled),
+        OrderProblem::MissingLimitPrice(Type::Limit),
+        OrderProblem::UnexpectedTrail(Type::Limit),
+      ]
+    );
+    assert_eq!(
+      err.to_string(),
+      "invalid order request: order amount is not positive; \
+       time in force UntilCanceled is not supported for notional orders; \
+       Limit order requires a limit price; \
+       Limit order does not support a trail price or percent"
+    );
+  }
+
+  /// Make sure that notional orders that are not day orders, as
+  /// required for crypto currencies, can still be submitted.
+  #[test]
+  fn create_notional_crypto_order() {
+    let request = CreateReqInit {
+      time_in_force: TimeInForce::UntilCanceled,
+      ..Default::default()
+    }
+    .init("BTC/USD", Side::Buy, Amount::notional(10));
+    assert!(Create::body(&request).is_ok());
+    assert_eq!(
+      request.validate().unwrap_err().problems,
+      vec![OrderProblem::NotionalTimeInForce(
+        TimeInForce::UntilCanceled
+      )]
+    );
+  }
+
+  /// Check that validation problems detected when creating an order
+  /// are reported in typed form.
+  #[test]
+  fn create_validation_error() {
+    let request = CreateReqInit::default().init("SPY", Side::Buy, Amount::quantity(0));
+    let err = CreateError::from(Create::body(&request).unwrap_err());
+    let expected = vec![OrderProblem::NonPositiveAmount];
+    assert_eq!(err.validation_error().unwrap().problems, expected);
+
+    let source = err
+      .source()
+      .and_then(|source| source.downcast_ref::<OrderValidationError>())
+      .unwrap();
+    assert_eq!(source.problems, expected);
+  }
+
   /// Check that we can parse the `Amount::quantity` variant properly.
   #[test]
   fn parse_quantity_amount() {
@@ -881,6 +3147,98 @@ mod tests {
     assert_eq!(amount, Amount::notional(Num::from_str("15.12").unwrap()));
   }
 
+  /// Check that we can deserialize a canceled order.
+  #[test]
+  fn deserialize_canceled_order() {
+    let json = br#"{
+    "id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+    "client_order_id": "eb9e2aaa-f71a-4f51-b5b4-52a6c565dad4",
+    "created_at": "2021-03-16T18:38:01.942282Z",
+    "updated_at": "2021-03-16T18:39:01.942282Z",
+    "submitted_at": "2021-03-16T18:38:01.937734Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": "2021-03-16T18:39:01.940912Z",
+    "failed_at": null,
+    "replaced_at": null,
+    "replaced_by": null,
+    "replaces": null,
+    "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "1",
+    "filled_qty": "0",
+    "type": "limit",
+    "order_class": "simple",
+    "side": "buy",
+    "time_in_force": "day",
+    "limit_price": "100",
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "canceled",
+    "extended_hours": false,
+    "legs": null
+}"#;
+
+    let order = from_json::<Order>(json).unwrap();
+    assert_eq!(order.status, Status::Canceled);
+    assert_eq!(
+      order.canceled_at.unwrap(),
+      DateTime::parse_from_rfc3339("2021-03-16T18:39:01.940912Z").unwrap()
+    );
+    assert_eq!(order.filled_at, None);
+    assert_eq!(order.replaced_by, None);
+    assert!(order.is_terminal());
+  }
+
+  /// Check that we can deserialize a replaced order referencing its
+  /// replacement.
+  #[test]
+  fn deserialize_replaced_order() {
+    let json = br#"{
+    "id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+    "client_order_id": "eb9e2aaa-f71a-4f51-b5b4-52a6c565dad4",
+    "created_at": "2021-03-16T18:38:01.942282Z",
+    "updated_at": "2021-03-16T18:40:01.942282Z",
+    "submitted_at": "2021-03-16T18:38:01.937734Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "failed_at": null,
+    "replaced_at": "2021-03-16T18:40:01.940912Z",
+    "replaced_by": "a2e4ac6a-b0e5-4b8f-8f2b-34fb0d8235d2",
+    "replaces": "0e5d6b29-4c3d-45d7-bae6-cf1e0acd8ab5",
+    "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "1",
+    "filled_qty": "0",
+    "type": "limit",
+    "order_class": "simple",
+    "side": "buy",
+    "time_in_force": "day",
+    "limit_price": "100",
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "replaced",
+    "extended_hours": false,
+    "legs": null
+}"#;
+
+    let order = from_json::<Order>(json).unwrap();
+    let replaced_by = Id(Uuid::parse_str("a2e4ac6a-b0e5-4b8f-8f2b-34fb0d8235d2").unwrap());
+    let replaces = Id(Uuid::parse_str("0e5d6b29-4c3d-45d7-bae6-cf1e0acd8ab5").unwrap());
+    assert_eq!(order.status, Status::Replaced);
+    assert_eq!(order.replaced_by, Some(replaced_by));
+    assert_eq!(order.replaces, Some(replaces));
+    assert_eq!(
+      order.replaced_at.unwrap(),
+      DateTime::parse_from_rfc3339("2021-03-16T18:40:01.940912Z").unwrap()
+    );
+    assert_eq!(order.canceled_at, None);
+    assert!(order.is_terminal());
+  }
+
   /// Verify that we can deserialize and serialize a reference order.
   #[test]
   fn deserialize_serialize_reference_order() {
@@ -928,6 +3286,575 @@ mod tests {
     assert_eq!(order.average_fill_price, Some(Num::new(10625, 100)));
   }
 
+  /// Create an order on the given side for the given amount.
+  fn order(side: &str, amount: &str) -> Order {
+    let json = format!(
+      r#"{{
+    "id": "904837e3-3b76-47ec-b432-046db621571b",
+    "client_order_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "created_at": "2018-10-05T05:48:59Z",
+    "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    {amount},
+    "filled_qty": "2",
+    "type": "market",
+    "order_class": "simple",
+    "side": "{side}",
+    "time_in_force": "day",
+    "status": "accepted",
+    "extended_hours": false,
+    "legs": null
+}}"#
+    );
+    from_json::<Order>(json.as_bytes()).unwrap()
+  }
+
+  /// Create a notional order on the given side.
+  fn order_notional(side: &str) -> Order {
+    let order = order(side, r#""notional": "250""#);
+    assert_eq!(order.amount, Amount::notional(250));
+    order
+  }
+
+  /// Check that we report the signed quantity of a buy order correctly.
+  #[test]
+  fn signed_qty_buy() {
+    let order = order("buy", r#""qty": "15""#);
+    assert_eq!(order.signed_qty(), Num::from(15));
+
+    let order = order_notional("buy");
+    assert_eq!(order.signed_qty(), Num::from(2));
+  }
+
+  /// Check that we report the signed quantity of a sell order
+  /// correctly.
+  #[test]
+  fn signed_qty_sell() {
+    let order = order("sell", r#""qty": "15""#);
+    assert_eq!(order.signed_qty(), Num::from(-15));
+
+    let order = order_notional("sell");
+    assert_eq!(order.signed_qty(), Num::from(-2));
+  }
+
+  /// Check that orders can be ordered chronologically by their
+  /// submission time.
+  #[test]
+  fn order_by_submission() {
+    let submitted = |id: u128, time: Option<&str>| {
+      let mut order = order("buy", r#""qty": "1""#);
+      order.id = Id(Uuid::from_u128(id));
+      order.submitted_at = time.map(|time| DateTime::from_str(time).unwrap());
+      order
+    };
+
+    let orders = [
+      submitted(1, Some("2024-01-02T15:00:00Z")),
+      submitted(2, Some("2024-01-02T14:00:00Z")),
+      // Not yet submitted; ordered by its creation time in 2018.
+      submitted(3, None),
+      submitted(4, Some("2024-01-02T15:00:00Z")),
+    ];
+
+    let set = orders
+      .iter()
+      .map(Order::submission_key)
+      .collect::<BTreeSet<_>>();
+    let ids = set.iter().map(|(_, id)| id.0.as_u128()).collect::<Vec<_>>();
+    assert_eq!(ids, vec![3, 2, 1, 4]);
+
+    let mut sorted = orders.to_vec();
+    let () = sorted.sort_by(Order::cmp_by_submission);
+    let ids = sorted
+      .iter()
+      .map(|order| order.id.0.as_u128())
+      .collect::<Vec<_>>();
+    assert_eq!(ids, vec![3, 2, 1, 4]);
+    assert_eq!(orders[0].cmp_by_submission(&orders[0]), Ordering::Equal);
+  }
+
+  /// Check that we can arrange orders into the chain formed by their
+  /// replacements.
+  #[test]
+  fn arrange_replace_chain() {
+    let link = |id: u128, replaces: Option<u128>, replaced_by: Option<u128>| {
+      let mut order = order("buy", r#""qty": "1""#);
+      order.id = Id(Uuid::from_u128(id));
+      order.replaces = replaces.map(|id| Id(Uuid::from_u128(id)));
+      order.replaced_by = replaced_by.map(|id| Id(Uuid::from_u128(id)));
+      order
+    };
+    let ids = |chain: Vec<&Order>| {
+      chain
+        .iter()
+        .map(|order| order.id.0.as_u128())
+        .collect::<Vec<_>>()
+    };
+
+    let orders = [
+      link(3, Some(2), None),
+      link(1, None, Some(2)),
+      link(2, Some(1), Some(3)),
+    ];
+    let chain = replace_chain(&orders).unwrap();
+    assert_eq!(chain.last().unwrap().id, orders[0].id);
+    assert_eq!(ids(chain), vec![1, 2, 3]);
+
+    // The first order in the set may have replaced one not included.
+    let orders = [link(2, Some(1), Some(3)), link(3, Some(2), None)];
+    assert_eq!(ids(replace_chain(&orders).unwrap()), vec![2, 3]);
+
+    let orders = [link(1, None, None)];
+    assert_eq!(ids(replace_chain(&orders).unwrap()), vec![1]);
+
+    assert_eq!(replace_chain(&[]), Err(ReplaceChainError::Empty));
+
+    let orders = [link(1, Some(2), Some(2)), link(2, Some(1), Some(1))];
+    assert_eq!(
+      replace_chain(&orders),
+      Err(ReplaceChainError::Cycle(orders[0].id))
+    );
+
+    let orders = [
+      link(1, None, Some(2)),
+      link(2, Some(1), Some(3)),
+      link(3, Some(2), Some(2)),
+    ];
+    assert_eq!(
+      replace_chain(&orders),
+      Err(ReplaceChainError::Cycle(orders[1].id))
+    );
+
+    let orders = [link(1, None, Some(2)), link(2, Some(1), Some(3))];
+    assert_eq!(
+      replace_chain(&orders),
+      Err(ReplaceChainError::MissingLink(Id(Uuid::from_u128(3))))
+    );
+
+    let orders = [
+      link(1, None, Some(2)),
+      link(2, Some(1), None),
+      link(5, None, None),
+    ];
+    assert_eq!(
+      replace_chain(&orders),
+      Err(ReplaceChainError::Unlinked(orders[2].id))
+    );
+  }
+
+  /// Check that we report the remaining quantity of a partially filled
+  /// order.
+  #[test]
+  fn partially_filled_order() {
+    let amount = r#""qty": "15", "notional": null, "filled_avg_price": "179.08""#;
+    let mut order = order("buy", amount);
+    assert_eq!(order.qty(), Some(&Num::from(15)));
+    assert_eq!(order.filled_quantity, Num::from(2));
+    assert_eq!(order.average_fill_price, Some(Num::new(17908, 100)));
+    assert_eq!(order.remaining_qty(), Some(Num::from(13)));
+    assert!(!order.is_fully_filled());
+
+    order.filled_quantity = Num::from(15);
+    assert_eq!(order.remaining_qty(), Some(Num::from(0)));
+    assert!(order.is_fully_filled());
+  }
+
+  /// Check that no remaining quantity is reported for notional orders.
+  #[test]
+  fn notional_order_remaining_qty() {
+    let mut order = order("buy", r#""qty": null, "notional": "250""#);
+    assert_eq!(order.amount, Amount::notional(250));
+    assert_eq!(order.qty(), None);
+    assert_eq!(order.remaining_qty(), None);
+    assert!(!order.is_fully_filled());
+
+    order.status = Status::Filled;
+    assert!(order.is_fully_filled());
+  }
+
+  /// A nested bracket order, along with its stop-loss and take-profit
+  /// legs, as reported by the server.
+  const BRACKET: &str = r#"{
+    "id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+    "client_order_id": "eb9e2aaa-f71a-4f51-b5b4-52a6c565dad4",
+    "created_at": "2024-04-01T14:30:00Z",
+    "updated_at": "2024-04-01T14:30:00Z",
+    "submitted_at": "2024-04-01T14:30:00Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "10",
+    "filled_qty": "0",
+    "type": "limit",
+    "order_class": "bracket",
+    "side": "buy",
+    "time_in_force": "gtc",
+    "limit_price": "170",
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "new",
+    "extended_hours": false,
+    "legs": [{
+      "id": "1d1a2e43-57f6-4e0f-9d0c-28cf7c67f6b4",
+      "client_order_id": "5d4b3d0c-95b6-4a3b-9c07-b9c1b2bd8ff1",
+      "created_at": "2024-04-01T14:30:00Z",
+      "updated_at": "2024-04-01T14:30:00Z",
+      "submitted_at": "2024-04-01T14:30:00Z",
+      "filled_at": null,
+      "expired_at": null,
+      "canceled_at": null,
+      "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+      "symbol": "AAPL",
+      "asset_class": "us_equity",
+      "qty": "10",
+      "filled_qty": "0",
+      "type": "stop",
+      "order_class": "bracket",
+      "side": "sell",
+      "time_in_force": "gtc",
+      "limit_price": null,
+      "stop_price": "160",
+      "filled_avg_price": null,
+      "status": "held",
+      "extended_hours": false,
+      "legs": null
+    }, {
+      "id": "9ad3e6cf-1d7b-47e7-bd5b-2e5ea6e4c293",
+      "client_order_id": "0f6d6a7b-1bb0-4b05-ab2c-8b1b2e3a1c6f",
+      "created_at": "2024-04-01T14:30:00Z",
+      "updated_at": "2024-04-01T14:30:00Z",
+      "submitted_at": "2024-04-01T14:30:00Z",
+      "filled_at": null,
+      "expired_at": null,
+      "canceled_at": null,
+      "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+      "symbol": "AAPL",
+      "asset_class": "us_equity",
+      "qty": "10",
+      "filled_qty": "0",
+      "type": "limit",
+      "order_class": "bracket",
+      "side": "sell",
+      "time_in_force": "gtc",
+      "limit_price": "185",
+      "stop_price": null,
+      "filled_avg_price": null,
+      "status": "held",
+      "extended_hours": false,
+      "legs": null
+    }]
+  }"#;
+
+  /// Check that we pick the correct legs of a bracket order.
+  #[test]
+  fn bracket_legs() {
+    let order = from_json::<Order>(BRACKET.as_bytes()).unwrap();
+
+    let take_profit = order.take_profit_leg().unwrap();
+    assert_eq!(take_profit.type_, Type::Limit);
+    assert_eq!(take_profit.limit_price, Some(Num::from(185)));
+
+    let stop_loss = order.stop_loss_leg().unwrap();
+    assert_eq!(stop_loss.type_, Type::Stop);
+    assert_eq!(stop_loss.stop_price, Some(Num::from(160)));
+
+    // Legs are not reported for non-nested orders.
+    let mut unnested = order.clone();
+    unnested.legs.clear();
+    assert_eq!(unnested.take_profit_leg(), None);
+    assert_eq!(unnested.stop_loss_leg(), None);
+
+    let simple = from_json::<Order>(ORDER.as_bytes()).unwrap();
+    assert_eq!(simple.take_profit_leg(), None);
+    assert_eq!(simple.stop_loss_leg(), None);
+    assert_eq!(simple.bracket_status(), BracketStatus::Working);
+  }
+
+  /// Verify that we roll up the status of a bracket order correctly
+  /// throughout its lifecycle.
+  #[test]
+  fn bracket_status() {
+    let mut order = from_json::<Order>(BRACKET.as_bytes()).unwrap();
+    assert_eq!(order.bracket_status(), BracketStatus::Working);
+
+    order.status = Status::PartiallyFilled;
+    assert_eq!(order.bracket_status(), BracketStatus::Working);
+
+    // Once the entry order filled, the legs become active.
+    order.status = Status::Filled;
+    order.legs[0].status = Status::New;
+    order.legs[1].status = Status::New;
+    assert_eq!(order.bracket_status(), BracketStatus::EntryFilled);
+
+    // The take-profit leg filled and the stop-loss leg is canceled.
+    order.legs[1].status = Status::Filled;
+    assert_eq!(order.bracket_status(), BracketStatus::EntryFilled);
+    order.legs[0].status = Status::Canceled;
+    assert_eq!(order.bracket_status(), BracketStatus::Closed);
+    assert_eq!(order.take_profit_leg().unwrap().status, Status::Filled);
+    assert_eq!(order.stop_loss_leg().unwrap().status, Status::Canceled);
+
+    // An entry order that never filled closes the bracket as well.
+    let mut order = from_json::<Order>(BRACKET.as_bytes()).unwrap();
+    order.status = Status::Canceled;
+    assert_eq!(order.bracket_status(), BracketStatus::Closed);
+  }
+
+  /// Create an `Account` with the given buying power.
+  fn account(buying_power: &str) -> Account {
+    let json = format!(
+      r#"{{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "admin_configurations": {{}},
+  "user_configurations": null,
+  "account_number": "PALPACA_123",
+  "status": "ACTIVE",
+  "crypto_status": "ACTIVE",
+  "currency": "USD",
+  "buying_power": "{buying_power}",
+  "regt_buying_power": "{buying_power}",
+  "daytrading_buying_power": "0.0",
+  "options_buying_power": "0.0",
+  "effective_buying_power": "{buying_power}",
+  "non_marginable_buying_power": "{buying_power}",
+  "bod_dtbp": "0.0",
+  "cash": "{buying_power}",
+  "accrued_fees": "0.0",
+  "portfolio_value": "{buying_power}",
+  "pattern_day_trader": false,
+  "trade_suspended_by_user": false,
+  "trading_blocked": false,
+  "transfers_blocked": false,
+  "account_blocked": false,
+  "created_at": "2018-10-01T13:35:25Z",
+  "shorting_enabled": true,
+  "multiplier": "1",
+  "long_market_value": "0.0",
+  "short_market_value": "0.0",
+  "position_market_value": "0.0",
+  "equity": "{buying_power}",
+  "last_equity": "{buying_power}",
+  "initial_margin": "0.0",
+  "maintenance_margin": "0.0",
+  "last_maintenance_margin": "0.0",
+  "sma": "0.0",
+  "daytrade_count": 0,
+  "balance_asof": "2018-10-01",
+  "crypto_tier": 1,
+  "options_trading_level": 0,
+  "intraday_adjustments": "0.0",
+  "pending_reg_taf_fees": "0.0"
+}}"#
+    );
+    from_json::<Account>(json.as_bytes()).unwrap()
+  }
+
+  /// Create an `Asset` for AAPL that is tradable.
+  fn asset(fractionable: bool) -> Asset {
+    let json = format!(
+      r#"{{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "class": "us_equity",
+  "exchange": "NASDAQ",
+  "symbol": "AAPL",
+  "status": "active",
+  "tradable": true,
+  "marginable": true,
+  "shortable": true,
+  "easy_to_borrow": true,
+  "fractionable": {fractionable}
+}}"#
+    );
+    from_json::<Asset>(json.as_bytes()).unwrap()
+  }
+
+  /// Check that an order covered by the account's buying power is
+  /// deemed feasible.
+  #[test]
+  fn feasible_order() {
+    let account = account("1000");
+    let asset = asset(true);
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::from(100)),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(10));
+
+    let feasibility = request.feasibility(&account, &asset, &Num::from(200));
+    assert!(feasibility.is_feasible(), "{feasibility:?}");
+
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::notional(500));
+    let feasibility = request.feasibility(&account, &asset, &Num::from(200));
+    assert!(feasibility.is_feasible(), "{feasibility:?}");
+  }
+
+  /// Check that orders exceeding the account's buying power or
+  /// otherwise being invalid are deemed infeasible.
+  #[test]
+  fn infeasible_order() {
+    let account = account("1000");
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(10));
+
+    let feasibility = request.feasibility(&account, &asset(true), &Num::from(150));
+    assert!(!feasibility.is_feasible());
+    assert_eq!(
+      feasibility.reasons,
+      vec![Infeasibility::InsufficientBuyingPower {
+        required: Num::from(1500),
+        available: Num::from(1000),
+      }]
+    );
+
+    // Buying power is irrelevant for sell orders.
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      ..Default::default()
+    }
+    .init("AAPL", Side::Sell, Amount::quantity(Num::new(15, 10)));
+    let feasibility = request.feasibility(&account, &asset(false), &Num::from(150));
+    assert_eq!(
+      feasibility.reasons,
+      vec![
+        Infeasibility::MissingLimitPrice,
+        Infeasibility::NotFractionable
+      ]
+    );
+  }
+
+  /// Check that orders incompatible with their asset are rejected by
+  /// the pre-flight check.
+  #[test]
+  fn preflight_order() {
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::notional(500));
+    assert_eq!(request.preflight(&asset(true)), Ok(()));
+    assert_eq!(
+      request.preflight(&asset(false)),
+      Err(OrderPreflightError::NotFractionable)
+    );
+
+    let request = CreateReqInit {
+      time_in_force: TimeInForce::UntilCanceled,
+      ..Default::default()
+    }
+    .init("AAPL", Side::Sell, Amount::quantity(Num::new(15, 10)));
+    assert_eq!(
+      request.preflight(&asset(true)),
+      Err(OrderPreflightError::UnsupportedTimeInForce(
+        TimeInForce::UntilCanceled
+      ))
+    );
+
+    // Whole share orders may be good until canceled.
+    let request = CreateReqInit {
+      time_in_force: TimeInForce::UntilCanceled,
+      ..Default::default()
+    }
+    .init("AAPL", Side::Sell, Amount::quantity(2));
+    assert_eq!(request.preflight(&asset(false)), Ok(()));
+
+    let mut crypto = asset(true);
+    crypto.class = asset::Class::Crypto;
+    let request = CreateReqInit::default().init("BTC/USD", Side::Buy, Amount::notional(10));
+    assert_eq!(
+      request.preflight(&crypto),
+      Err(OrderPreflightError::UnsupportedTimeInForce(
+        TimeInForce::Day
+      ))
+    );
+
+    crypto.min_order_size = Some(Num::new(5, 10000));
+    let request = CreateReqInit {
+      time_in_force: TimeInForce::UntilCanceled,
+      ..Default::default()
+    }
+    .init("BTC/USD", Side::Buy, Amount::quantity(Num::new(1, 10000)));
+    assert_eq!(
+      request.preflight(&crypto),
+      Err(OrderPreflightError::BelowMinOrderSize(Num::new(5, 10000)))
+    );
+
+    let request = CreateReqInit {
+      time_in_force: TimeInForce::UntilCanceled,
+      ..Default::default()
+    }
+    .init("BTC/USD", Side::Buy, Amount::quantity(Num::new(5, 10000)));
+    assert_eq!(request.preflight(&crypto), Ok(()));
+
+    crypto.tradable = false;
+    assert_eq!(
+      request.preflight(&crypto),
+      Err(OrderPreflightError::NotTradable)
+    );
+  }
+
+  /// Check that extended hours trading gets enabled for a day limit
+  /// order submitted after the market close, and only then.
+  #[test]
+  fn enable_extended_hours_after_close() {
+    let clock = |current| Clock {
+      open: false,
+      current: DateTime::<Utc>::from_str(current).unwrap(),
+      next_open: DateTime::<Utc>::from_str("2024-07-03T09:30:00-04:00").unwrap(),
+      next_close: DateTime::<Utc>::from_str("2024-07-03T16:00:00-04:00").unwrap(),
+      _non_exhaustive: (),
+    };
+    let day = OpenClose {
+      date: NaiveDate::from_ymd_opt(2024, 7, 2).unwrap(),
+      open: NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      close: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+      session_open: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      session_close: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+      _non_exhaustive: (),
+    };
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::from(100)),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+
+    // 5pm market time, i.e., after hours.
+    let after_hours = clock("2024-07-02T21:00:00Z");
+    let mut req = request.clone();
+    assert!(req.enable_extended_hours_after_close(&after_hours, &day));
+    assert!(req.extended_hours);
+    // The request is now eligible for extended hours already.
+    assert!(!req.enable_extended_hours_after_close(&after_hours, &day));
+
+    // 6am market time, i.e., pre-market.
+    let pre_market = clock("2024-07-02T10:00:00Z");
+    let mut req = request.clone();
+    assert!(!req.enable_extended_hours_after_close(&pre_market, &day));
+    assert!(!req.extended_hours);
+
+    // 11am market time, i.e., regular hours.
+    let regular = clock("2024-07-02T15:00:00Z");
+    let mut req = request.clone();
+    assert!(!req.enable_extended_hours_after_close(&regular, &day));
+    assert!(!req.extended_hours);
+
+    // Market orders are not eligible for extended hours trading.
+    let mut req = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    assert!(!req.enable_extended_hours_after_close(&after_hours, &day));
+    assert!(!req.extended_hours);
+
+    // Neither are orders not good for the day only.
+    let mut req = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::from(100)),
+      time_in_force: TimeInForce::UntilCanceled,
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    assert!(!req.enable_extended_hours_after_close(&after_hours, &day));
+    assert!(!req.extended_hours);
+  }
+
   /// Verify that we can deserialize an order with an empty order class.
   ///
   /// Unfortunately, the Alpaca API may return such an empty class for
@@ -961,22 +3888,120 @@ mod tests {
     "legs": null
 }"#;
 
-    let order = from_json::<Order>(json).unwrap();
-    assert_eq!(order.class, Class::Simple);
+    let order = from_json::<Order>(json).unwrap();
+    assert_eq!(order.class, Class::Simple);
+  }
+
+  /// Check that an order with a type, class, and side we do not know
+  /// about is deserialized, without failing the list it is part of,
+  /// unless strict deserialization is enabled.
+  #[test]
+  fn deserialize_order_with_unknown_variants() {
+    let unknown = ORDER
+      .replace(r#""type": "market""#, r#""type": "future_type""#)
+      .replace(
+        r#""order_class": "simple""#,
+        r#""order_class": "future_class""#,
+      )
+      .replace(r#""side": "buy""#, r#""side": "future_side""#);
+    assert_ne!(unknown, ORDER);
+
+    let order = from_json::<Order>(unknown.as_bytes()).unwrap();
+    assert_eq!(order.type_, Type::Unknown);
+    assert_eq!(order.class, Class::Unknown);
+    assert_eq!(order.side, Side::Unknown);
+
+    let json = format!("[{ORDER},{unknown}]");
+    let orders = from_json::<Vec<Order>>(json.as_bytes()).unwrap();
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[0].type_, Type::Market);
+    assert_eq!(orders[1].type_, Type::Unknown);
+
+    let result = with_strict(true, || from_json::<Order>(unknown.as_bytes()));
+    assert!(result.is_err());
+  }
+
+  /// Check that we can serialize and deserialize a [`CreateReq`].
+  #[test]
+  fn serialize_deserialize_order_request() {
+    let request = CreateReqInit {
+      type_: Type::TrailingStop,
+      trail_price: Some(Num::from(50)),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+
+    let json = to_json(&request).unwrap();
+    assert_eq!(from_json::<CreateReq>(&json).unwrap(), request);
+  }
+
+  /// Check that an extended hours limit day order serializes with the
+  /// flag set, while the flag is omitted for regular orders.
+  #[test]
+  fn serialize_extended_hours_order_request() {
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::from(100)),
+      extended_hours: true,
+      ..Default::default()
+    }
+    .try_init("SPY", Side::Buy, Amount::quantity(1))
+    .unwrap();
+
+    let json = to_json(&request).unwrap();
+    let value = from_json::<serde_json::Value>(&json).unwrap();
+    assert_eq!(value["extended_hours"], true);
+    assert_eq!(value["type"], "limit");
+    assert_eq!(value["time_in_force"], "day");
+    assert_eq!(from_json::<CreateReq>(&json).unwrap(), request);
+
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::from(100)),
+      ..Default::default()
+    }
+    .try_init("SPY", Side::Buy, Amount::quantity(1))
+    .unwrap();
+
+    let json = to_json(&request).unwrap();
+    let value = from_json::<serde_json::Value>(&json).unwrap();
+    assert!(value.get("extended_hours").is_none());
+    assert_eq!(from_json::<CreateReq>(&json).unwrap(), request);
   }
 
-  /// Check that we can serialize and deserialize a [`CreateReq`].
+  /// Make sure that extended hours trading is rejected for orders not
+  /// supporting it.
   #[test]
-  fn serialize_deserialize_order_request() {
+  fn reject_invalid_extended_hours_order_request() {
+    let result = CreateReqInit {
+      type_: Type::Market,
+      extended_hours: true,
+      ..Default::default()
+    }
+    .try_init("SPY", Side::Buy, Amount::quantity(1));
+    assert_eq!(
+      result.unwrap_err(),
+      ExtendedHoursError::UnsupportedType(Type::Market)
+    );
+
     let request = CreateReqInit {
-      type_: Type::TrailingStop,
-      trail_price: Some(Num::from(50)),
+      type_: Type::Limit,
+      time_in_force: TimeInForce::UntilCanceled,
+      limit_price: Some(Num::from(100)),
+      extended_hours: true,
       ..Default::default()
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
-
-    let json = to_json(&request).unwrap();
-    assert_eq!(from_json::<CreateReq>(&json).unwrap(), request);
+    assert_eq!(
+      request.validate_extended_hours(),
+      Err(ExtendedHoursError::UnsupportedTimeInForce(
+        TimeInForce::UntilCanceled
+      ))
+    );
+    assert!(matches!(
+      Create::body(&request),
+      Err(ConversionError::InvalidOrder(..))
+    ));
   }
 
   /// Check that we can serialize and deserialize a [`ChangeReq`].
@@ -1320,16 +4345,26 @@ mod tests {
     }
     .init("SPY", Side::Buy, Amount::quantity(1));
 
-    let api_info = ApiInfo::from_env().unwrap();
+    let api_info =
+      ApiInfo::from_parts("https://paper-api.alpaca.markets/", "XXXX", "YYYY").unwrap();
     let client = Client::new(api_info);
 
     // We are submitting a market order with extended_hours, that is
-    // invalid as per the Alpaca documentation.
+    // invalid as per the Alpaca documentation and rejected before
+    // being issued.
     let result = client.issue::<Create>(&request).await;
     let err = result.unwrap_err();
 
     match err {
-      RequestError::Endpoint(CreateError::InvalidInput(..)) => (),
+      RequestError::Endpoint(ref error) => {
+        let error = error.validation_error().unwrap();
+        assert_eq!(
+          error.problems,
+          vec![OrderProblem::ExtendedHours(
+            ExtendedHoursError::UnsupportedType(Type::Market)
+          )]
+        );
+      },
       _ => panic!("Received unexpected error: {err:?}"),
     };
   }
@@ -1463,6 +4498,692 @@ mod tests {
     };
   }
 
+  /// The names of the money fields of an [`Order`].
+  const MONEY_FIELDS: [&str; 7] = [
+    "qty",
+    "filled_qty",
+    "limit_price",
+    "stop_price",
+    "trail_price",
+    "trail_percent",
+    "filled_avg_price",
+  ];
+
+  proptest! {
+    /// Check that arbitrary values of money fields of an [`Order`]
+    /// survive a round trip through JSON.
+    #[test]
+    fn round_trip_money_fields(decimals in vec(decimal(), MONEY_FIELDS.len())) {
+      let order = from_json::<Order>(ORDER.as_bytes()).unwrap();
+      let () = check_round_trip(&order, &MONEY_FIELDS, &decimals)?;
+    }
+  }
+
+  /// A reference order as returned by the server.
+  const ORDER: &str = r#"{
+    "id": "904837e3-3b76-47ec-b432-046db621571b",
+    "client_order_id": "my-order",
+    "created_at": "2018-10-05T05:48:59Z",
+    "updated_at": "2018-10-05T05:48:59Z",
+    "submitted_at": "2018-10-05T05:48:59Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "failed_at": null,
+    "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "1",
+    "filled_qty": "0",
+    "type": "market",
+    "order_class": "simple",
+    "side": "buy",
+    "time_in_force": "day",
+    "limit_price": null,
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "accepted",
+    "extended_hours": false,
+    "legs": null
+  }"#;
+
+  /// Serve order submissions concurrently on a local socket, replying
+  /// with an order for the submitted symbol. Orders for `BAD` are
+  /// rejected and orders for `SLOW` are only responded to after a
+  /// delay.
+  async fn serve_orders() -> String {
+    let (url, _requests) = serve_with(|request| {
+      let body = request.split("\r\n\r\n").nth(1).unwrap_or_default();
+      let create = serde_json::from_str::<serde_json::Value>(body).unwrap();
+      match create["symbol"].as_str().unwrap() {
+        "BAD" => Reply::respond(
+          "422 Unprocessable Entity",
+          r#"{"code":42210000,"message":"invalid order"}"#,
+        ),
+        symbol => {
+          let order = ORDER.replace(r#""symbol": "AAPL""#, &format!(r#""symbol": "{symbol}""#));
+          let response = Response::new("200 OK", order);
+          if symbol == "SLOW" {
+            response.delay(Duration::from_millis(100)).into()
+          } else {
+            response.into()
+          }
+        },
+      }
+    })
+    .await;
+    url
+  }
+
+  /// A market clock reporting the market as closed.
+  const CLOSED_CLOCK: &str = r#"{
+    "timestamp": "2024-01-06T12:00:00-05:00",
+    "is_open": false,
+    "next_open": "2124-01-08T09:30:00-05:00",
+    "next_close": "2124-01-08T16:00:00-05:00"
+  }"#;
+
+  /// A market clock reporting the market as open.
+  const OPEN_CLOCK: &str = r#"{
+    "timestamp": "2024-01-08T12:00:00-05:00",
+    "is_open": true,
+    "next_open": "2124-01-09T09:30:00-05:00",
+    "next_close": "2124-01-08T16:00:00-05:00"
+  }"#;
+
+  /// Check that market orders are rejected without contacting the
+  /// server while the market is closed.
+  #[test(tokio::test)]
+  async fn submit_guarded_market_closed() {
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", CLOSED_CLOCK)]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    for _ in 0..2 {
+      let err = client
+        .submit_order_market_guarded(&request)
+        .await
+        .unwrap_err();
+      match err {
+        SubmitGuardedError::MarketClosed { next_open } => {
+          assert_eq!(
+            next_open,
+            DateTime::parse_from_rfc3339("2124-01-08T09:30:00-05:00").unwrap()
+          )
+        },
+        err => panic!("Received unexpected error: {err:?}"),
+      }
+    }
+
+    // The clock was only retrieved once and no order was submitted.
+    assert_eq!(requests.lines(), vec!["GET /v2/clock".to_string()]);
+  }
+
+  /// Check that orders are submitted as usual while the market is
+  /// open.
+  #[test(tokio::test)]
+  async fn submit_guarded_market_open() {
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond("200 OK", OPEN_CLOCK),
+      Reply::respond("200 OK", ORDER),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    let order = client.submit_order_market_guarded(&request).await.unwrap();
+    assert_eq!(order.symbol, "AAPL");
+    assert_eq!(
+      requests.lines(),
+      vec!["GET /v2/clock".to_string(), "POST /v2/orders".to_string()]
+    );
+  }
+
+  /// Check that batch submission reports per-order results in the
+  /// order of the provided requests.
+  #[test(tokio::test)]
+  async fn submit_orders_in_order() {
+    let url = serve_orders().await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let symbols = ["SLOW", "AAPL", "BAD", "MSFT", "SPY"];
+    let requests = symbols
+      .iter()
+      .map(|symbol| CreateReqInit::default().init(*symbol, Side::Buy, Amount::quantity(1)))
+      .collect();
+    let results = client.submit_orders(requests, 3).await;
+    assert_eq!(results.len(), symbols.len());
+
+    for (symbol, result) in symbols.iter().zip(results) {
+      match result {
+        Ok(order) => {
+          assert_ne!(*symbol, "BAD");
+          assert_eq!(&order.symbol, symbol)
+        },
+        Err(RequestError::Endpoint(CreateError::InvalidInput(..))) => {
+          assert_eq!(*symbol, "BAD")
+        },
+        Err(err) => panic!("Received unexpected error: {err:?}"),
+      }
+    }
+  }
+
+  /// Create a quote with the given bid and ask prices.
+  #[cfg(feature = "market-data")]
+  fn quote(bid_price: Num, ask_price: Num) -> last_quotes::Quote {
+    last_quotes::Quote {
+      time: DateTime::parse_from_rfc3339("2024-03-01T14:30:00Z")
+        .unwrap()
+        .with_timezone(&Utc),
+      ask_price,
+      ask_size: 100,
+      bid_price,
+      bid_size: 200,
+      conditions: Vec::new(),
+      tape: None,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Check that we create limit orders at the rounded quote midpoint.
+  #[cfg(feature = "market-data")]
+  #[test]
+  fn limit_at_midpoint() {
+    let nbbo = quote(Num::new(10010, 100), Num::new(10017, 100));
+    let request = CreateReqInit::default()
+      .init_limit_at_midpoint("AAPL", Side::Buy, Amount::quantity(5), &nbbo)
+      .unwrap();
+    let expected = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::new(10014, 100)),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(5));
+    assert_eq!(request, expected);
+
+    // Sub-dollar prices use a finer tick size.
+    let nbbo = quote(Num::new(5012, 10000), Num::new(5017, 10000));
+    let request = CreateReqInit::default()
+      .init_limit_at_midpoint("XYZ", Side::Sell, Amount::quantity(10), &nbbo)
+      .unwrap();
+    assert_eq!(request.limit_price, Some(Num::new(5014, 10000)));
+  }
+
+  /// Check that prices are rounded to the nearest tick.
+  #[test]
+  fn round_prices_to_tick() {
+    let price = Num::from_str("50.12345").unwrap();
+    assert_eq!(round_to_tick(&price, &price), Num::new(5012, 100));
+
+    let price = Num::from_str("0.123456").unwrap();
+    assert_eq!(round_to_tick(&price, &price), Num::new(1235, 10000));
+
+    // Exactly one dollar already uses penny increments.
+    let price = Num::from_str("1.004999").unwrap();
+    assert_eq!(round_to_tick(&price, &Num::from(1)), Num::from(1));
+    let price = Num::from_str("0.999949").unwrap();
+    assert_eq!(round_to_tick(&price, &price), Num::new(9999, 10000));
+
+    // A non-zero price is never rounded to zero.
+    let price = Num::from_str("0.00001").unwrap();
+    assert_eq!(round_to_tick(&price, &price), Num::new(1, 10000));
+    assert_eq!(round_to_tick(&Num::from(0), &Num::from(0)), Num::from(0));
+
+    let request = CreateReqInit {
+      type_: Type::StopLimit,
+      limit_price: Some(Num::from_str("50.12345").unwrap()),
+      stop_price: Some(Num::from_str("0.123456").unwrap()),
+      round_prices: true,
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    assert_eq!(request.limit_price, Some(Num::new(5012, 100)));
+    assert_eq!(request.stop_price, Some(Num::new(1235, 10000)));
+
+    let request = CreateReqInit {
+      limit_price: Some(Num::from_str("50.12345").unwrap()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    assert_eq!(request.limit_price, Num::from_str("50.12345").ok());
+  }
+
+  /// Make sure that we reject crossed and locked quotes when creating
+  /// limit orders at the midpoint.
+  #[cfg(feature = "market-data")]
+  #[test]
+  fn limit_at_midpoint_crossed_quote() {
+    let quotes = [
+      quote(Num::from(101), Num::from(100)),
+      quote(Num::from(100), Num::from(100)),
+    ];
+    for quote in quotes {
+      let err = CreateReqInit::default()
+        .init_limit_at_midpoint("AAPL", Side::Buy, Amount::quantity(1), &quote)
+        .unwrap_err();
+      assert_eq!(err.bid_price, quote.bid_price);
+      assert_eq!(err.ask_price, quote.ask_price);
+    }
+  }
+
+  /// Check that we generate a client order ID only if none is set.
+  #[test]
+  fn ensure_client_order_id() {
+    let mut request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    let client_order_id = request.ensure_client_order_id().to_string();
+    assert!(!client_order_id.is_empty());
+    assert_eq!(request.client_order_id.as_ref(), Some(&client_order_id));
+    assert_eq!(request.ensure_client_order_id(), client_order_id);
+
+    let mut request = CreateReqInit {
+      client_order_id: Some("my-order".to_string()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    assert_eq!(request.ensure_client_order_id(), "my-order");
+  }
+
+  /// Check that we construct the URL for looking up an order by its
+  /// client order ID correctly.
+  #[test]
+  fn get_by_client_id_url() {
+    let client_order_id = "my order&id".to_string();
+    assert_eq!(
+      GetByClientId::path(&client_order_id),
+      "/v2/orders:by_client_order_id"
+    );
+    assert_eq!(
+      GetByClientId::query(&client_order_id).unwrap().unwrap(),
+      "client_order_id=my+order%26id"
+    );
+
+    let client_order_id = "my order/id".to_string();
+    assert_eq!(
+      GetByClientId::query(&client_order_id).unwrap().unwrap(),
+      "client_order_id=my+order%2Fid"
+    );
+  }
+
+  /// Check that an order can be retrieved by its client order ID and
+  /// that a missing one is reported as such.
+  #[test(tokio::test)]
+  async fn get_order_by_client_id() {
+    let not_found = r#"{"code":40410000,"message":"order not found"}"#;
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond("200 OK", ORDER),
+      Reply::respond("404 Not Found", not_found),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let order = client.get_order_by_client_id("my-order").await.unwrap();
+    assert_eq!(order.client_order_id, "my-order");
+
+    let err = client
+      .get_order_by_client_id("my order/id")
+      .await
+      .unwrap_err();
+    match err {
+      RequestError::Endpoint(GetByClientIdError::NotFound(..)) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+
+    let requests = requests.lines();
+    let expected = [
+      "GET /v2/orders:by_client_order_id?client_order_id=my-order",
+      "GET /v2/orders:by_client_order_id?client_order_id=my+order%2Fid",
+    ];
+    assert_eq!(requests, expected);
+  }
+
+  /// Make sure that a notional order for a non-fractionable asset is
+  /// rejected pre-flight, without being submitted, while a valid one
+  /// is submitted.
+  #[test(tokio::test)]
+  async fn submit_order_preflight() {
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", ORDER)]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::notional(100));
+    let err = client
+      .submit_order(&request, Some(&asset(false)))
+      .await
+      .unwrap_err();
+    match err {
+      SubmitOrderError::Preflight(OrderPreflightError::NotFractionable) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+    assert!(requests.lines().is_empty());
+
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    let order = client
+      .submit_order(&request, Some(&asset(false)))
+      .await
+      .unwrap();
+    assert_eq!(order.symbol, "AAPL");
+
+    let requests = requests.lines();
+    assert_eq!(requests, ["POST /v2/orders"]);
+  }
+
+  /// Check that `Order::cancel` issues a DELETE request for the order.
+  #[test(tokio::test)]
+  async fn cancel_order() {
+    let (url, requests) = serve_sequence(vec![Reply::respond("204 No Content", "")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let order = from_json::<Order>(ORDER.as_bytes()).unwrap();
+    let () = order.cancel(&client).await.unwrap();
+
+    let requests = requests.lines();
+    assert_eq!(
+      requests,
+      ["DELETE /v2/orders/904837e33b7647ecb432046db621571b"]
+    );
+  }
+
+  /// Create a reference order JSON object with the given status.
+  fn order_with_status(status: &str) -> String {
+    ORDER.replace(
+      r#""status": "accepted""#,
+      &format!(r#""status": "{status}""#),
+    )
+  }
+
+  /// Check that `Client::cancel_and_replace` waits for the existing
+  /// order to be canceled before submitting the replacement.
+  #[test(tokio::test)]
+  async fn cancel_and_replace() {
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond("204 No Content", ""),
+      Reply::respond("200 OK", order_with_status("pending_cancel")),
+      Reply::respond("200 OK", order_with_status("canceled")),
+      Reply::respond("200 OK", ORDER),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let order = from_json::<Order>(ORDER.as_bytes()).unwrap();
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    let (canceled, replacement) = client.cancel_and_replace(order.id, &request).await.unwrap();
+    assert_eq!(canceled.status, Status::Canceled);
+    assert_eq!(replacement.status, Status::Accepted);
+
+    let requests = requests.lines();
+    let expected = [
+      "DELETE /v2/orders/904837e33b7647ecb432046db621571b",
+      "GET /v2/orders/904837e33b7647ecb432046db621571b",
+      "GET /v2/orders/904837e33b7647ecb432046db621571b",
+      "POST /v2/orders",
+    ];
+    assert_eq!(requests, expected);
+  }
+
+  /// Make sure that `Client::cancel_and_replace` reports the canceled
+  /// order if submission of the replacement fails, and that no
+  /// replacement is submitted for an order that got filled instead.
+  #[test(tokio::test)]
+  async fn cancel_and_replace_failure() {
+    let invalid = r#"{"code":42210000,"message":"qty must be > 0"}"#;
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond("204 No Content", ""),
+      Reply::respond("200 OK", order_with_status("canceled")),
+      Reply::respond("422 Unprocessable Entity", invalid),
+      Reply::respond("204 No Content", ""),
+      Reply::respond("200 OK", order_with_status("filled")),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let order = from_json::<Order>(ORDER.as_bytes()).unwrap();
+    let request = CreateReqInit::default().init("AAPL", Side::Buy, Amount::quantity(1));
+    let err = client
+      .cancel_and_replace(order.id, &request)
+      .await
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "order 904837e3-3b76-47ec-b432-046db621571b got canceled but its replacement could not be submitted"
+    );
+    match err {
+      CancelAndReplaceError::Replace {
+        canceled,
+        error: RequestError::Endpoint(CreateError::InvalidInput(..)),
+      } => assert_eq!(canceled.status, Status::Canceled),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+
+    let err = client
+      .cancel_and_replace(order.id, &request)
+      .await
+      .unwrap_err();
+    match err {
+      CancelAndReplaceError::NotCanceled(order) => assert_eq!(order.status, Status::Filled),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+
+    let requests = requests.lines();
+    assert_eq!(requests.len(), 5);
+    assert_eq!(requests[2], "POST /v2/orders");
+    assert!(!requests[3..].contains(&"POST /v2/orders".to_string()));
+  }
+
+  /// Check that an idempotent submission failing transiently finds the
+  /// already created order instead of resubmitting it.
+  #[test(tokio::test)]
+  async fn submit_order_idempotent_finds_existing() {
+    let (url, requests) = serve_sequence(vec![Reply::Close, Reply::respond("200 OK", ORDER)]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = CreateReqInit {
+      client_order_id: Some("my-order".to_string()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    let order = client.submit_order_idempotent(&request).await.unwrap();
+    assert_eq!(order.client_order_id, "my-order");
+
+    let requests = requests.lines();
+    let expected = [
+      "POST /v2/orders",
+      "GET /v2/orders:by_client_order_id?client_order_id=my-order",
+    ];
+    assert_eq!(requests, expected);
+  }
+
+  /// Check that an order submission timing out after the order was
+  /// created does not result in a duplicate order.
+  #[test(tokio::test)]
+  async fn submit_order_idempotent_timeout() {
+    // The server never responds to the submission, but the order is
+    // created nevertheless.
+    let (url, requests) = serve_sequence(vec![Reply::Hang, Reply::respond("200 OK", ORDER)]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder()
+      .request_timeout(Some(Duration::from_millis(100)))
+      .build(api_info);
+
+    let request = CreateReqInit {
+      client_order_id: Some("my-order".to_string()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    let order = client.submit_order_idempotent(&request).await.unwrap();
+    assert_eq!(order.client_order_id, "my-order");
+
+    let requests = requests.lines();
+    let expected = [
+      "POST /v2/orders",
+      "GET /v2/orders:by_client_order_id?client_order_id=my-order",
+    ];
+    assert_eq!(requests, expected);
+  }
+
+  /// Check that an idempotent submission failing transiently
+  /// resubmits the order if it was not created.
+  #[test(tokio::test)]
+  async fn submit_order_idempotent_resubmits() {
+    let not_found = r#"{"code":40410000,"message":"order not found"}"#;
+    let (url, requests) = serve_sequence(vec![
+      Reply::Close,
+      Reply::respond("404 Not Found", not_found),
+      Reply::respond("200 OK", ORDER),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = CreateReqInit {
+      client_order_id: Some("my-order".to_string()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+    let order = client.submit_order_idempotent(&request).await.unwrap();
+    assert_eq!(order.symbol, "AAPL");
+
+    let requests = requests.lines();
+    let expected = [
+      "POST /v2/orders",
+      "GET /v2/orders:by_client_order_id?client_order_id=my-order",
+      "POST /v2/orders",
+    ];
+    assert_eq!(requests, expected);
+  }
+
+  /// Check that we can identify errors possibly indicating duplicate
+  /// client order IDs correctly.
+  #[test]
+  fn duplicate_client_order_id_detection() {
+    let error = CreateError::InvalidInput(Ok(ApiError {
+      code: Some(40010001),
+      message: "client_order_id must be unique".to_string(),
+    }));
+    assert!(is_duplicate_client_order_id(&error));
+
+    let error = CreateError::InvalidInput(Ok(ApiError {
+      code: Some(42210000),
+      message: "client_order_id must be unique".to_string(),
+    }));
+    assert!(!is_duplicate_client_order_id(&error));
+
+    let error = CreateError::InvalidInput(Err(b"client_order_id must be unique".to_vec()));
+    assert!(!is_duplicate_client_order_id(&error));
+  }
+
+  /// Check that `Client::submit_idempotent` retrieves the existing
+  /// order when resubmitting an order with the same client order ID.
+  #[test(tokio::test)]
+  async fn submit_idempotent_duplicate() {
+    let duplicate = r#"{"code":40010001,"message":"client_order_id must be unique"}"#;
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond("200 OK", ORDER),
+      Reply::respond("422 Unprocessable Entity", duplicate),
+      Reply::respond("200 OK", ORDER),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let request = CreateReqInit {
+      client_order_id: Some("my-order".to_string()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+
+    let submitted = client.submit_idempotent(&request).await.unwrap();
+    let resubmitted = client.submit_idempotent(&request).await.unwrap();
+    assert_eq!(resubmitted.id, submitted.id);
+    assert_eq!(resubmitted.client_order_id, "my-order");
+
+    let expected = [
+      "POST /v2/orders",
+      "POST /v2/orders",
+      "GET /v2/orders:by_client_order_id?client_order_id=my-order",
+    ];
+    assert_eq!(requests.lines(), expected);
+  }
+
+  /// Make sure that `Client::submit_idempotent` reports the original
+  /// submission error if no order with the client order ID exists or
+  /// none was provided.
+  #[test(tokio::test)]
+  async fn submit_idempotent_invalid() {
+    let invalid = r#"{"code":40010001,"message":"qty must be > 0"}"#;
+    let not_found = r#"{"code":40410000,"message":"order not found"}"#;
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond("422 Unprocessable Entity", invalid),
+      Reply::respond("404 Not Found", not_found),
+      Reply::respond("422 Unprocessable Entity", invalid),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let mut request = CreateReqInit {
+      client_order_id: Some("my-order".to_string()),
+      ..Default::default()
+    }
+    .init("AAPL", Side::Buy, Amount::quantity(1));
+
+    let err = client.submit_idempotent(&request).await.unwrap_err();
+    match err {
+      SubmitIdempotentError::Create(RequestError::Endpoint(CreateError::InvalidInput(Ok(err)))) => {
+        assert_eq!(err.message, "qty must be > 0")
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    }
+
+    request.client_order_id = None;
+    let err = client.submit_idempotent(&request).await.unwrap_err();
+    assert!(
+      matches!(
+        err,
+        SubmitIdempotentError::Create(RequestError::Endpoint(CreateError::InvalidInput(..)))
+      ),
+      "{err:?}"
+    );
+
+    let expected = [
+      "POST /v2/orders",
+      "GET /v2/orders:by_client_order_id?client_order_id=my-order",
+      "POST /v2/orders",
+    ];
+    assert_eq!(requests.lines(), expected);
+  }
+
+  /// Check that resubmitting an order with the same client order ID
+  /// via [`Client::submit_idempotent`] returns the existing order.
+  #[test(tokio::test)]
+  async fn submit_idempotent_with_client_order_id() {
+    let client_order_id = Uuid::new_v4().as_simple().to_string();
+    let request = CreateReqInit {
+      type_: Type::Limit,
+      limit_price: Some(Num::from(1)),
+      client_order_id: Some(client_order_id.clone()),
+      ..Default::default()
+    }
+    .init("SPY", Side::Buy, Amount::quantity(1));
+
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let submitted = client.submit_idempotent(&request).await.unwrap();
+    let resubmitted = client.submit_idempotent(&request).await;
+    client.issue::<Delete>(&submitted.id).await.unwrap();
+
+    let resubmitted = resubmitted.unwrap();
+    assert_eq!(submitted.client_order_id, client_order_id);
+    assert_eq!(resubmitted.client_order_id, client_order_id);
+    assert_eq!(resubmitted.id, submitted.id);
+  }
+
   /// Test that we can change the client order ID of an order.
   #[test(tokio::test)]
   async fn change_client_order_id() {
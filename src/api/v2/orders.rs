@@ -1,6 +1,11 @@
 // Copyright (C) 2019-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::collections::HashSet;
+
+use chrono::DateTime;
+use chrono::Utc;
+
 use serde::Deserialize;
 use serde::Serialize;
 use serde_urlencoded::to_string as to_query;
@@ -8,6 +13,8 @@ use serde_urlencoded::to_string as to_query;
 use crate::api::v2::order::Order;
 use crate::util::string_slice_to_str;
 use crate::util::vec_from_comma_separated_str;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 /// The status of orders to list.
@@ -25,6 +32,20 @@ pub enum Status {
 }
 
 
+/// The direction in which orders are reported.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Direction {
+  /// Report orders in descending order, i.e., from more recently
+  /// submitted orders to older ones.
+  #[serde(rename = "desc")]
+  Descending,
+  /// Report orders in ascending order, i.e., from older orders to more
+  /// recently submitted ones.
+  #[serde(rename = "asc")]
+  Ascending,
+}
+
+
 /// A GET request to be made to the /v2/orders endpoint.
 // Note that we do not expose or supply all parameters that the Alpaca
 // API supports.
@@ -49,6 +70,16 @@ pub struct ListReq {
   /// legs field of the primary order.
   #[serde(rename = "nested")]
   pub nested: bool,
+  /// Only include orders submitted after this time.
+  #[serde(rename = "after")]
+  pub after: Option<DateTime<Utc>>,
+  /// Only include orders submitted until this time.
+  #[serde(rename = "until")]
+  pub until: Option<DateTime<Utc>>,
+  /// The direction in which to report orders. Defaults to
+  /// descending.
+  #[serde(rename = "direction")]
+  pub direction: Option<Direction>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
@@ -65,6 +96,9 @@ impl Default for ListReq {
       // returned. As such, having them included is very non-intrusive
       // and should be a reasonable default.
       nested: true,
+      after: None,
+      until: None,
+      direction: None,
       _non_exhaustive: (),
     }
   }
@@ -91,13 +125,61 @@ Endpoint! {
 }
 
 
+/// The maximum number of orders the server reports in response to a
+/// single request.
+const MAX_LIMIT: usize = 500;
+
+
+impl Client {
+  /// Retrieve the orders that were updated after the provided time.
+  ///
+  /// The server only supports filtering by submission time. Hence, all
+  /// orders submitted after `since` as well as all currently open
+  /// orders are retrieved and only those updated after `since` are
+  /// reported. Note that this means that orders submitted before
+  /// `since` that reached a terminal state since then are not
+  /// reported. At most 500 orders submitted after `since` are taken
+  /// into account.
+  pub async fn orders_changed_since(
+    &self,
+    since: DateTime<Utc>,
+  ) -> Result<Vec<Order>, RequestError<ListError>> {
+    let recent = ListReq {
+      status: Status::All,
+      limit: Some(MAX_LIMIT),
+      after: Some(since),
+      ..Default::default()
+    };
+    let open = ListReq {
+      status: Status::Open,
+      limit: Some(MAX_LIMIT),
+      ..Default::default()
+    };
+
+    let mut orders = self.issue::<List>(&recent).await?;
+    let () = orders.extend(self.issue::<List>(&open).await?);
+
+    let mut seen = HashSet::new();
+    let () = orders.retain(|order| {
+      let updated = order.updated_at.unwrap_or(order.created_at);
+      updated > since && seen.insert(order.id)
+    });
+    Ok(orders)
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[cfg(feature = "streaming")]
   use futures::future::ok;
+  #[cfg(feature = "streaming")]
   use futures::pin_mut;
+  #[cfg(feature = "streaming")]
   use futures::StreamExt;
+  #[cfg(feature = "streaming")]
   use futures::TryStreamExt;
 
   use num_decimal::Num;
@@ -110,8 +192,11 @@ mod tests {
   use test_log::test;
 
   use crate::api::v2::order;
+  #[cfg(feature = "streaming")]
   use crate::api::v2::order_util::order_aapl;
+  #[cfg(feature = "streaming")]
   use crate::api::v2::order_util::order_stock;
+  #[cfg(feature = "streaming")]
   use crate::api::v2::updates;
   use crate::api_info::ApiInfo;
   use crate::Client;
@@ -152,12 +237,138 @@ mod tests {
     assert_eq!(from_query::<ListReq>(&query).unwrap(), request);
 
     request.symbols.clear();
+    request.direction = Some(Direction::Ascending);
     let query = to_query(&request).unwrap();
     assert_eq!(from_query::<ListReq>(&query).unwrap(), request);
   }
 
+  /// Check that the `symbols` and `nested` members are serialized
+  /// into the expected query string.
+  #[test]
+  fn serialize_symbols_nested_query() {
+    let request = ListReq {
+      symbols: vec!["AAPL".into(), "MSFT".into()],
+      nested: true,
+      ..Default::default()
+    };
+
+    let query = to_query(&request).unwrap();
+    assert_eq!(query, "symbols=AAPL%2CMSFT&status=open&nested=true");
+
+    let request = ListReq {
+      nested: false,
+      ..request
+    };
+    let query = to_query(&request).unwrap();
+    assert_eq!(query, "symbols=AAPL%2CMSFT&status=open&nested=false");
+  }
+
+  /// Verify that we can deserialize a list containing a bracket order
+  /// with nested legs.
+  #[test]
+  fn deserialize_nested_bracket_order() {
+    let json = br#"[{
+    "id": "61e69015-8549-4bfd-b9c3-01e75843f47d",
+    "client_order_id": "eb9e2aaa-f71a-4f51-b5b4-52a6c565dad4",
+    "created_at": "2021-03-16T18:38:01.942282Z",
+    "updated_at": "2021-03-16T18:38:01.942282Z",
+    "submitted_at": "2021-03-16T18:38:01.937734Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "failed_at": null,
+    "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "1",
+    "filled_qty": "0",
+    "type": "limit",
+    "order_class": "bracket",
+    "side": "buy",
+    "time_in_force": "day",
+    "limit_price": "100",
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "accepted",
+    "extended_hours": false,
+    "legs": [{
+      "id": "9c9a8d4b-6e25-4b1e-a4f4-2f2e1d2b2c11",
+      "client_order_id": "ba1cf1e2-9a0a-4c8a-bd74-5c35a8f5f3a1",
+      "created_at": "2021-03-16T18:38:01.942282Z",
+      "updated_at": "2021-03-16T18:38:01.942282Z",
+      "submitted_at": "2021-03-16T18:38:01.937734Z",
+      "filled_at": null,
+      "expired_at": null,
+      "canceled_at": null,
+      "failed_at": null,
+      "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+      "symbol": "AAPL",
+      "asset_class": "us_equity",
+      "qty": "1",
+      "filled_qty": "0",
+      "type": "limit",
+      "order_class": "bracket",
+      "side": "sell",
+      "time_in_force": "day",
+      "limit_price": "110",
+      "stop_price": null,
+      "filled_avg_price": null,
+      "status": "held",
+      "extended_hours": false,
+      "legs": null
+    }, {
+      "id": "4b5c1e4f-0f4e-4b8e-9b3e-7a6d5c4b3a21",
+      "client_order_id": "d1f7c7e2-3c5b-4f0a-8a9e-1b2c3d4e5f60",
+      "created_at": "2021-03-16T18:38:01.942282Z",
+      "updated_at": "2021-03-16T18:38:01.942282Z",
+      "submitted_at": "2021-03-16T18:38:01.937734Z",
+      "filled_at": null,
+      "expired_at": null,
+      "canceled_at": null,
+      "failed_at": null,
+      "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+      "symbol": "AAPL",
+      "asset_class": "us_equity",
+      "qty": "1",
+      "filled_qty": "0",
+      "type": "stop",
+      "order_class": "bracket",
+      "side": "sell",
+      "time_in_force": "day",
+      "limit_price": null,
+      "stop_price": "95",
+      "filled_avg_price": null,
+      "status": "held",
+      "extended_hours": false,
+      "legs": null
+    }]
+}]"#;
+
+    let orders = from_json::<Vec<Order>>(json).unwrap();
+    assert_eq!(orders.len(), 1);
+
+    let order = &orders[0];
+    assert_eq!(order.class, order::Class::Bracket);
+    assert_eq!(order.legs.len(), 2);
+
+    let take_profit = &order.legs[0];
+    assert_eq!(take_profit.type_, order::Type::Limit);
+    assert_eq!(take_profit.side, order::Side::Sell);
+    assert_eq!(take_profit.status, order::Status::Held);
+    assert_eq!(take_profit.limit_price, Some(Num::from(110)));
+    assert!(take_profit.legs.is_empty());
+
+    let stop_loss = &order.legs[1];
+    assert_eq!(stop_loss.type_, order::Type::Stop);
+    assert_eq!(stop_loss.side, order::Side::Sell);
+    assert_eq!(stop_loss.status, order::Status::Held);
+    assert_eq!(stop_loss.stop_price, Some(Num::from(95)));
+    assert!(stop_loss.legs.is_empty());
+  }
+
   /// Cancel an order and wait for the corresponding cancellation event
   /// to arrive.
+  #[cfg(feature = "streaming")]
   async fn cancel_order(client: &Client, id: order::Id) {
     let (stream, _subscription) = client.subscribe::<updates::OrderUpdates>().await.unwrap();
     pin_mut!(stream);
@@ -174,7 +385,7 @@ mod tests {
       // only interested in ones belonging to the order canceled
       // earlier.
       .try_skip_while(|update| {
-        ok(update.order.id != id || !matches!(update.event, updates::OrderStatus::Canceled))
+        ok(update.order.id != id || !matches!(update.event, updates::OrderEvent::Canceled))
       })
       .next()
       .await
@@ -183,6 +394,7 @@ mod tests {
   }
 
   /// Check that we can list existing orders.
+  #[cfg(feature = "streaming")]
   #[test(tokio::test)]
   #[ignore]
   async fn list_orders() {
@@ -256,6 +468,7 @@ mod tests {
   }
 
   /// Test that orders can be correctly filtered by a list of symbols.
+  #[cfg(feature = "streaming")]
   #[test(tokio::test)]
   #[ignore]
   async fn symbol_filter_orders() {
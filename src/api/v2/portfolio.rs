@@ -0,0 +1,210 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use futures::future::join3;
+
+use crate::api::v2::account;
+use crate::api::v2::account::Account;
+use crate::api::v2::order::Order;
+use crate::api::v2::orders;
+use crate::api::v2::position::Position;
+use crate::api::v2::positions;
+use crate::Client;
+use crate::RequestError;
+
+
+/// A snapshot of the portfolio, as retrieved by
+/// [`Client::portfolio_snapshot`].
+#[derive(Debug)]
+pub struct PortfolioSnapshot {
+  /// The account.
+  pub account: Account,
+  /// The open positions, or the error encountered retrieving them.
+  pub positions: Result<Vec<Position>, RequestError<positions::ListError>>,
+  /// The open orders, or the error encountered retrieving them.
+  pub open_orders: Result<Vec<Order>, RequestError<orders::ListError>>,
+  /// The time at which the snapshot was taken, i.e., at which all
+  /// requests completed.
+  pub fetched_at: DateTime<Utc>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+impl Client {
+  /// Retrieve a snapshot of the portfolio, comprising the account, all
+  /// open positions, and up to 500 open orders.
+  ///
+  /// All three requests are issued concurrently. Only a failure to
+  /// retrieve the account fails the snapshot as a whole; errors
+  /// retrieving positions or orders are reported as part of it
+  /// instead.
+  pub async fn portfolio_snapshot(
+    &self,
+  ) -> Result<PortfolioSnapshot, RequestError<account::GetError>> {
+    let orders = orders::ListReq {
+      limit: Some(500),
+      ..Default::default()
+    };
+
+    let (account, positions, open_orders) = join3(
+      self.issue::<account::Get>(&()),
+      self.issue::<positions::List>(&()),
+      self.issue::<orders::List>(&orders),
+    )
+    .await;
+
+    Ok(PortfolioSnapshot {
+      account: account?,
+      positions,
+      open_orders,
+      fetched_at: Utc::now(),
+      _non_exhaustive: (),
+    })
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+  use crate::test_util::request_line;
+  use crate::test_util::serve_with;
+  use crate::test_util::Reply;
+
+
+  /// A reference account.
+  const ACCOUNT: &str = r#"{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "admin_configurations": {},
+  "user_configurations": null,
+  "account_number": "PALPACA_123",
+  "status": "ACTIVE",
+  "crypto_status": "ACTIVE",
+  "currency": "USD",
+  "buying_power": "5000.00",
+  "regt_buying_power": "0.0",
+  "daytrading_buying_power": "0.0",
+  "options_buying_power": "0.0",
+  "effective_buying_power": "0.0",
+  "non_marginable_buying_power": "0.0",
+  "bod_dtbp": "0.0",
+  "cash": "5000.00",
+  "accrued_fees": "0.0",
+  "pending_transfer_in": "0.0",
+  "portfolio_value": "5000.00",
+  "pattern_day_trader": false,
+  "trade_suspended_by_user": false,
+  "trading_blocked": false,
+  "transfers_blocked": false,
+  "account_blocked": false,
+  "created_at": "2018-10-01T13:35:25Z",
+  "shorting_enabled": true,
+  "multiplier": "2",
+  "long_market_value": "7000.00",
+  "short_market_value": "-3000.00",
+  "position_market_value": "4000.00",
+  "equity": "5000.00",
+  "last_equity": "5000.00",
+  "initial_margin": "5000.00",
+  "maintenance_margin": "3000.00",
+  "last_maintenance_margin": "3000.00",
+  "sma": "0.0",
+  "daytrade_count": 0,
+  "balance_asof": "2018-10-01",
+  "crypto_tier": 1,
+  "options_trading_level": 2,
+  "intraday_adjustments": "0.0",
+  "pending_reg_taf_fees": "0.0"
+}"#;
+
+  /// A reference position.
+  const POSITION: &str = r#"{
+  "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+  "symbol": "AAPL",
+  "exchange": "NASDAQ",
+  "asset_class": "us_equity",
+  "avg_entry_price": "100.0",
+  "qty": "5",
+  "qty_available": "5",
+  "side": "long",
+  "market_value": "600.0",
+  "cost_basis": "500.0",
+  "unrealized_pl": "100.0",
+  "unrealized_plpc": "0.20",
+  "unrealized_intraday_pl": "10.0",
+  "unrealized_intraday_plpc": "0.0084",
+  "current_price": "120.0",
+  "lastday_price": "119.0",
+  "change_today": "0.0084"
+}"#;
+
+
+  /// Serve the endpoints making up a portfolio snapshot on a local
+  /// socket, optionally failing the positions one. Return the URL to
+  /// connect to.
+  async fn serve_portfolio(fail_positions: bool) -> String {
+    let (url, _requests) = serve_with(move |request| {
+      let line = request_line(request);
+      if line == "GET /v2/account" {
+        Reply::respond("200 OK", ACCOUNT)
+      } else if line == "GET /v2/positions" {
+        if fail_positions {
+          Reply::respond(
+            "500 Internal Server Error",
+            r#"{"code":50010000,"message":"internal server error"}"#,
+          )
+        } else {
+          Reply::respond("200 OK", format!("[{POSITION}]"))
+        }
+      } else if line.starts_with("GET /v2/orders?") {
+        Reply::respond("200 OK", "[]")
+      } else {
+        Reply::respond("404 Not Found", "")
+      }
+    })
+    .await;
+    url
+  }
+
+
+  /// Check that a portfolio snapshot is populated from all three
+  /// endpoints.
+  #[test(tokio::test)]
+  async fn retrieve_portfolio_snapshot() {
+    let url = serve_portfolio(false).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let before = Utc::now();
+    let snapshot = client.portfolio_snapshot().await.unwrap();
+    assert_eq!(snapshot.account.account_number, "PALPACA_123");
+    let positions = snapshot.positions.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].symbol, "AAPL");
+    assert_eq!(snapshot.open_orders.unwrap(), Vec::new());
+    assert!(snapshot.fetched_at >= before);
+  }
+
+  /// Make sure that a failure to retrieve positions is reported as part
+  /// of the snapshot, without failing it.
+  #[test(tokio::test)]
+  async fn portfolio_snapshot_without_positions() {
+    let url = serve_portfolio(true).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let snapshot = client.portfolio_snapshot().await.unwrap();
+    assert_eq!(snapshot.account.account_number, "PALPACA_123");
+    assert!(snapshot.positions.is_err());
+    assert!(snapshot.open_orders.is_ok());
+  }
+}
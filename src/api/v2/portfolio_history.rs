@@ -0,0 +1,326 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::TimeZone as _;
+use chrono::Utc;
+
+use num_decimal::Num;
+
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use serde_urlencoded::to_string as to_query;
+
+use crate::Str;
+
+
+/// Deserialize a `Vec` of timestamps from a list of Unix epoch seconds.
+fn timestamps_from_epoch<'de, D>(deserializer: D) -> Result<Vec<DateTime<Utc>>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  Vec::<i64>::deserialize(deserializer)?
+    .into_iter()
+    .map(|secs| {
+      Utc
+        .timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| D::Error::custom(format!("timestamp {secs} is out of range")))
+    })
+    .collect()
+}
+
+/// Serialize a slice of timestamps into a list of Unix epoch seconds.
+fn timestamps_to_epoch<S>(timestamps: &[DateTime<Utc>], serializer: S) -> Result<S::Ok, S::Error>
+where
+  S: Serializer,
+{
+  serializer.collect_seq(timestamps.iter().map(DateTime::timestamp))
+}
+
+
+/// The duration of the period covered by a portfolio history.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Period {
+  /// A period of the given number of days.
+  Days(u32),
+  /// A period of the given number of weeks.
+  Weeks(u32),
+  /// A period of the given number of months.
+  Months(u32),
+  /// A period of the given number of years.
+  Years(u32),
+}
+
+impl Serialize for Period {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    let (count, unit) = match self {
+      Self::Days(count) => (count, 'D'),
+      Self::Weeks(count) => (count, 'W'),
+      Self::Months(count) => (count, 'M'),
+      Self::Years(count) => (count, 'A'),
+    };
+    serializer.collect_str(&format_args!("{count}{unit}"))
+  }
+}
+
+
+/// The resolution of the data points in a portfolio history.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum TimeFrame {
+  /// A time frame of one minute.
+  #[serde(rename = "1Min")]
+  OneMinute,
+  /// A time frame of five minutes.
+  #[serde(rename = "5Min")]
+  FiveMinutes,
+  /// A time frame of 15 minutes.
+  #[serde(rename = "15Min")]
+  FifteenMinutes,
+  /// A time frame of one hour.
+  #[serde(rename = "1H")]
+  OneHour,
+  /// A time frame of one day.
+  #[serde(rename = "1D")]
+  OneDay,
+}
+
+
+/// The market sessions included in an intraday portfolio history.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum IntradayReporting {
+  /// Only include data points during regular market hours.
+  #[serde(rename = "market_hours")]
+  MarketHours,
+  /// Include data points during extended hours sessions.
+  #[serde(rename = "extended_hours")]
+  ExtendedHours,
+  /// Include data points for the entire day.
+  #[serde(rename = "continuous")]
+  Continuous,
+}
+
+
+/// How profit/loss figures are accumulated in a portfolio history.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum PnlReset {
+  /// Reset profit/loss at the end of each trading day.
+  #[serde(rename = "per_day")]
+  PerDay,
+  /// Accumulate profit/loss over the entire period.
+  #[serde(rename = "no_reset")]
+  NoReset,
+}
+
+
+/// A GET request to be made to the /v2/account/portfolio/history
+/// endpoint.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct PortfolioHistoryReq {
+  /// The duration of the data.
+  ///
+  /// Defaults to one month.
+  #[serde(rename = "period")]
+  pub period: Option<Period>,
+  /// The resolution of the time window.
+  ///
+  /// Defaults to [`OneDay`][TimeFrame::OneDay] for periods longer than
+  /// 30 days and to [`FifteenMinutes`][TimeFrame::FifteenMinutes]
+  /// otherwise.
+  #[serde(rename = "timeframe")]
+  pub timeframe: Option<TimeFrame>,
+  /// The date the data is returned up to.
+  ///
+  /// Defaults to the current market date.
+  #[serde(rename = "date_end")]
+  pub date_end: Option<NaiveDate>,
+  /// Whether to include extended hours in the result.
+  ///
+  /// This setting is only relevant for time frames of less than one
+  /// day.
+  #[serde(rename = "extended_hours")]
+  pub extended_hours: Option<bool>,
+  /// The market sessions to include for intraday time frames.
+  #[serde(rename = "intraday_reporting")]
+  pub intraday_reporting: Option<IntradayReporting>,
+  /// How profit/loss figures are accumulated over the period.
+  #[serde(rename = "pnl_reset")]
+  pub pnl_reset: Option<PnlReset>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A portfolio history as returned by the /v2/account/portfolio/history
+/// endpoint.
+///
+/// The vectors contained are parallel to each other, i.e., the n-th
+/// element of each refers to the same point in time.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PortfolioHistory {
+  /// The time of each data point.
+  #[serde(
+    rename = "timestamp",
+    deserialize_with = "timestamps_from_epoch",
+    serialize_with = "timestamps_to_epoch"
+  )]
+  pub timestamp: Vec<DateTime<Utc>>,
+  /// The equity value of the account at each point in time.
+  #[serde(rename = "equity")]
+  pub equity: Vec<Num>,
+  /// The profit/loss in dollar from the base value.
+  #[serde(rename = "profit_loss")]
+  pub profit_loss: Vec<Num>,
+  /// The profit/loss as a factor of 1 from the base value.
+  #[serde(rename = "profit_loss_pct")]
+  pub profit_loss_pct: Vec<Num>,
+  /// The basis in dollar of the profit/loss calculation.
+  #[serde(rename = "base_value")]
+  pub base_value: Num,
+  /// The time frame of each data point.
+  #[serde(rename = "timeframe")]
+  pub timeframe: String,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/account/portfolio/history endpoint.
+  pub Get(PortfolioHistoryReq),
+  Ok => PortfolioHistory, [
+    /// The portfolio history was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetError, [
+    /// A query parameter was invalid.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/account/portfolio/history".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+
+  use http_endpoint::Endpoint;
+
+  use serde_json::from_str as from_json;
+  use serde_json::to_string as to_json;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+  use crate::Client;
+
+
+  /// Check that we can deserialize a reference portfolio history and
+  /// that the contained series line up.
+  #[test]
+  fn parse_reference_portfolio_history() {
+    let response = r#"{
+  "timestamp": [1580826600, 1580827500, 1580828400],
+  "equity": [27423.73, 27408.19, 27515.97],
+  "profit_loss": [11.8, -3.74, 104.04],
+  "profit_loss_pct": [0.0004305, -0.0001364, 0.0037954],
+  "base_value": 27411.93,
+  "timeframe": "15Min"
+}"#;
+
+    let history = from_json::<PortfolioHistory>(response).unwrap();
+    assert_eq!(history.timestamp.len(), 3);
+    assert_eq!(history.equity.len(), history.timestamp.len());
+    assert_eq!(history.profit_loss.len(), history.timestamp.len());
+    assert_eq!(history.profit_loss_pct.len(), history.timestamp.len());
+    assert_eq!(history.base_value, Num::new(2741193, 100));
+    assert_eq!(history.timeframe, "15Min");
+
+    let expected = DateTime::<Utc>::from_str("2020-02-04T14:30:00Z").unwrap();
+    assert_eq!(history.timestamp[0], expected);
+    assert_eq!(history.equity[0], Num::new(2742373, 100));
+    assert_eq!(history.profit_loss[0], Num::new(118, 10));
+
+    let expected = DateTime::<Utc>::from_str("2020-02-04T15:00:00Z").unwrap();
+    assert_eq!(history.timestamp[2], expected);
+    assert_eq!(history.equity[2], Num::new(2751597, 100));
+    assert_eq!(history.profit_loss[2], Num::new(10404, 100));
+
+    // Each data point's equity should be the base value plus the
+    // profit/loss at that time.
+    for (equity, profit_loss) in history.equity.iter().zip(&history.profit_loss) {
+      assert_eq!(equity, &(&history.base_value + profit_loss));
+    }
+
+    let serialized = to_json(&history).unwrap();
+    assert_eq!(from_json::<PortfolioHistory>(&serialized).unwrap(), history);
+  }
+
+  /// Verify that a `PortfolioHistoryReq` is serialized into the expected
+  /// query string.
+  #[test]
+  fn serialize_portfolio_history_request() {
+    let request = PortfolioHistoryReq {
+      period: Some(Period::Years(1)),
+      timeframe: Some(TimeFrame::OneDay),
+      date_end: Some(NaiveDate::from_ymd_opt(2020, 2, 4).unwrap()),
+      extended_hours: Some(true),
+      intraday_reporting: Some(IntradayReporting::Continuous),
+      pnl_reset: Some(PnlReset::NoReset),
+      ..Default::default()
+    };
+
+    let query = Get::query(&request).unwrap().unwrap();
+    let expected = "period=1A&timeframe=1D&date_end=2020-02-04&extended_hours=true&intraday_reporting=continuous&pnl_reset=no_reset";
+    assert_eq!(query, expected);
+
+    let query = Get::query(&PortfolioHistoryReq::default())
+      .unwrap()
+      .unwrap();
+    assert_eq!(query, "");
+  }
+
+  /// Check that we can retrieve the portfolio history of the account.
+  #[test(tokio::test)]
+  async fn retrieve_portfolio_history() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let request = PortfolioHistoryReq {
+      period: Some(Period::Weeks(1)),
+      timeframe: Some(TimeFrame::OneDay),
+      ..Default::default()
+    };
+
+    let history = client.issue::<Get>(&request).await.unwrap();
+    assert_eq!(history.timeframe, "1D");
+    assert_eq!(history.equity.len(), history.timestamp.len());
+    assert_eq!(history.profit_loss.len(), history.timestamp.len());
+    assert_eq!(history.profit_loss_pct.len(), history.timestamp.len());
+  }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::ops::Not;
+use std::time::Duration;
 
 use http::Method;
 
@@ -9,15 +10,26 @@ use num_decimal::Num;
 
 use serde::Deserialize;
 use serde::Serialize;
+use serde_urlencoded::to_string as to_query;
 
+use thiserror::Error as ThisError;
+
+use tokio::time::sleep;
+
+use crate::api::v2::account::Account;
 use crate::api::v2::asset;
 use crate::api::v2::order;
 use crate::util::abs_num_from_str;
+use crate::util::strict_variant;
+use crate::util::UnknownVariant;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
 /// The side of a position.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
 pub enum Side {
   /// A long position of an asset.
   #[serde(rename = "long")]
@@ -25,6 +37,12 @@ pub enum Side {
   /// A short position of an asset.
   #[serde(rename = "short")]
   Short,
+  /// Any other side that we have not accounted for.
+  ///
+  /// Note that having any such side should be considered a bug.
+  #[doc(hidden)]
+  #[serde(other, rename(serialize = "unknown"))]
+  Unknown,
 }
 
 impl Not for Side {
@@ -35,10 +53,18 @@ impl Not for Side {
     match self {
       Self::Long => Self::Short,
       Self::Short => Self::Long,
+      Self::Unknown => Self::Unknown,
     }
   }
 }
 
+impl UnknownVariant for Side {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
 
 /// A single position as returned by the /v2/positions endpoint on a GET
 /// request.
@@ -51,10 +77,10 @@ pub struct Position {
   #[serde(rename = "symbol")]
   pub symbol: String,
   /// The exchange the position is held at.
-  #[serde(rename = "exchange")]
+  #[serde(rename = "exchange", deserialize_with = "strict_variant")]
   pub exchange: asset::Exchange,
   /// The position's asset class.
-  #[serde(rename = "asset_class")]
+  #[serde(rename = "asset_class", deserialize_with = "strict_variant")]
   pub asset_class: asset::Class,
   /// The average entry price of the position.
   #[serde(rename = "avg_entry_price")]
@@ -64,10 +90,14 @@ pub struct Position {
   pub quantity: Num,
   /// Total number of shares available minus those covered by open
   /// orders.
+  ///
+  /// Note that, unlike `quantity`, this value is reported signed, i.e.,
+  /// it is negative for short positions. See also
+  /// [`Position::locked_qty`].
   #[serde(rename = "qty_available")]
   pub quantity_available: Num,
   /// The side the position is on.
-  #[serde(rename = "side")]
+  #[serde(rename = "side", deserialize_with = "strict_variant")]
   pub side: Side,
   /// The total dollar amount of the position.
   #[serde(rename = "market_value")]
@@ -103,9 +133,309 @@ pub struct Position {
 }
 
 
-Endpoint! {
-  /// The representation of a GET request to the /v2/positions/{symbol}
+impl Position {
+  /// Retrieve the key identifying the position, i.e., its symbol.
+  ///
+  /// An account holds at most one position per symbol, making the key
+  /// suitable for use in maps.
+  #[inline]
+  pub fn key(&self) -> &str {
+    &self.symbol
+  }
+
+  /// Retrieve the position's quantity, signed according to its side.
+  ///
+  /// The quantity is negative for short positions and positive for
+  /// long ones.
+  pub fn signed_qty(&self) -> Num {
+    match self.side {
+      Side::Long | Side::Unknown => self.quantity.clone(),
+      Side::Short => -self.quantity.clone(),
+    }
+  }
+
+  /// Retrieve the number of shares tied up in open orders, e.g., in a
+  /// pending closing order.
+  ///
+  /// Only the remaining shares can be covered by a new order without
+  /// it being rejected for an insufficient quantity. Like `quantity`,
+  /// the result is never negative.
+  pub fn locked_qty(&self) -> Num {
+    let available = if self.quantity_available.is_negative() {
+      -self.quantity_available.clone()
+    } else {
+      self.quantity_available.clone()
+    };
+    &self.quantity - available
+  }
+
+  /// Retrieve the position's market value.
+  ///
+  /// The value reported by Alpaca is used if present, otherwise it is
+  /// calculated from the current price. Short positions have a negative
+  /// market value.
+  pub fn market_value(&self) -> Option<Num> {
+    self.market_value.clone().or_else(|| {
+      self
+        .current_price
+        .as_ref()
+        .map(|price| price * self.signed_qty())
+    })
+  }
+
+  /// Retrieve the position's notional value, i.e., the absolute value
+  /// of its market value.
+  ///
+  /// If the market value is not known, the absolute cost basis is
+  /// reported instead.
+  pub fn notional_value(&self) -> Num {
+    let value = self
+      .market_value()
+      .unwrap_or_else(|| self.cost_basis.clone());
+    if value.is_negative() {
+      -value
+    } else {
+      value
+    }
+  }
+
+  /// Retrieve the position's unrealized profit/loss as a fraction of
+  /// its cost basis.
+  ///
+  /// The value reported by Alpaca is used if present, otherwise it is
+  /// calculated from the market value. `None` is returned if neither is
+  /// known or if the cost basis is zero.
+  pub fn unrealized_plpc(&self) -> Option<Num> {
+    if let Some(plpc) = &self.unrealized_gain_total_percent {
+      return Some(plpc.clone())
+    }
+
+    if self.cost_basis.is_zero() {
+      return None
+    }
+
+    let cost_basis = if self.cost_basis.is_negative() {
+      -self.cost_basis.clone()
+    } else {
+      self.cost_basis.clone()
+    };
+    self
+      .market_value()
+      .map(|value| (value - &self.cost_basis) / cost_basis)
+  }
+
+  /// Calculate the position's weight in the account's portfolio, i.e.,
+  /// its market value as a fraction of the portfolio value.
+  ///
+  /// The weight of short positions is negative. Zero is reported if the
+  /// portfolio value is zero or the position's market value is not
+  /// known.
+  pub fn weight_in_portfolio(&self, account: &Account) -> Num {
+    if account.portfolio_value.is_zero() {
+      return Num::from(0)
+    }
+
+    self
+      .market_value()
+      .map(|value| value / &account.portfolio_value)
+      .unwrap_or_else(|| Num::from(0))
+  }
+
+  /// Check whether the position is a long one.
+  #[inline]
+  pub fn is_long(&self) -> bool {
+    self.side == Side::Long
+  }
+
+  /// Check whether the position is a short one.
+  #[inline]
+  pub fn is_short(&self) -> bool {
+    self.side == Side::Short
+  }
+
+  /// Calculate the return of the current price relative to `reference`
+  /// (as a factor of 1), taking into account the position's side.
+  fn return_relative_to(&self, reference: Option<&Num>) -> Num {
+    match (&self.current_price, reference) {
+      (Some(current), Some(reference)) if !reference.is_zero() => {
+        let change = (current - reference) / reference;
+        match self.side {
+          Side::Long | Side::Unknown => change,
+          Side::Short => -change,
+        }
+      },
+      _ => Num::from(0),
+    }
+  }
+
+  /// Calculate the position's return for the day (as a factor of 1),
+  /// i.e., the change of the current price relative to the last day's
+  /// price.
+  ///
+  /// Short positions gain when the price falls. Zero is reported if
+  /// either price is not known or the last day's price is zero.
+  #[inline]
+  pub fn intraday_return_pct(&self) -> Num {
+    self.return_relative_to(self.last_day_price.as_ref())
+  }
+
+  /// Calculate the position's total return (as a factor of 1), i.e.,
+  /// the change of the current price relative to the average entry
+  /// price.
+  ///
+  /// Short positions gain when the price falls. Zero is reported if
+  /// the current price is not known or the average entry price is
+  /// zero.
+  #[inline]
+  pub fn total_return_pct(&self) -> Num {
+    self.return_relative_to(Some(&self.average_entry_price))
+  }
+
+  /// Liquidate the position at market, by means of the [`Delete`]
   /// endpoint.
+  ///
+  /// See [`Client::close_position`] for closing the position with a
+  /// limit order instead.
+  #[inline]
+  pub async fn close(&self, client: &Client) -> Result<order::Order, RequestError<DeleteError>> {
+    let symbol = asset::Symbol::Sym(self.symbol.clone());
+    client.issue::<Delete>(&symbol).await
+  }
+
+  /// Create a request for a limit order closing the position in its
+  /// entirety.
+  ///
+  /// The order sells a long position and buys back a short one.
+  pub fn close_limit_req(
+    &self,
+    limit_price: Num,
+    time_in_force: order::TimeInForce,
+  ) -> order::CreateReq {
+    let side = order::Side::closing_for(self.side);
+
+    order::CreateReqInit {
+      type_: order::Type::Limit,
+      limit_price: Some(limit_price),
+      time_in_force,
+      ..Default::default()
+    }
+    .init(
+      self.symbol.clone(),
+      side,
+      order::Amount::quantity(self.quantity.clone()),
+    )
+  }
+}
+
+
+/// The difference between two snapshots of positions, keyed by symbol.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PositionDiff {
+  /// Positions present only in the newer snapshot.
+  pub added: Vec<Position>,
+  /// Positions present only in the older snapshot, i.e., positions
+  /// that have been closed.
+  pub removed: Vec<Position>,
+  /// Positions whose quantity, side, or average entry price changed,
+  /// as pairs of the old and the new position.
+  pub changed: Vec<(Position, Position)>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl PositionDiff {
+  /// Compute the difference between the `old` and the `new` snapshot
+  /// of positions.
+  ///
+  /// Changes to fields other than the quantity, side, and average entry
+  /// price (such as the current price or market value) are not
+  /// considered.
+  pub fn new(old: &[Position], new: &[Position]) -> Self {
+    let find = |positions: &[Position], symbol: &str| {
+      positions
+        .iter()
+        .find(|position| position.symbol == symbol)
+        .cloned()
+    };
+
+    let mut diff = Self::default();
+    for position in new {
+      match find(old, &position.symbol) {
+        None => diff.added.push(position.clone()),
+        Some(before) => {
+          if before.quantity != position.quantity
+            || before.side != position.side
+            || before.average_entry_price != position.average_entry_price
+          {
+            diff.changed.push((before, position.clone()))
+          }
+        },
+      }
+    }
+
+    diff.removed = old
+      .iter()
+      .filter(|position| find(new, &position.symbol).is_none())
+      .cloned()
+      .collect();
+    diff
+  }
+
+  /// Check whether no positions were added, removed, or changed.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+
+/// A GET request to be made to the /v2/positions/{symbol} endpoint, as
+/// issued through [`GetInCurrency`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct GetReq {
+  /// The symbol or asset ID of the position to retrieve.
+  #[serde(skip)]
+  pub symbol: asset::Symbol,
+  /// The currency in which to report monetary values, e.g., "EUR".
+  ///
+  /// If not set, values are reported in USD.
+  #[serde(rename = "currency", skip_serializing_if = "Option::is_none")]
+  pub currency: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`GetReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct GetReqInit {
+  /// See `GetReq::currency`.
+  pub currency: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl GetReqInit {
+  /// Create a [`GetReq`] from a `GetReqInit`.
+  #[inline]
+  pub fn init(self, symbol: asset::Symbol) -> GetReq {
+    GetReq {
+      symbol,
+      currency: self.currency,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/positions/{symbol_or_asset_id} endpoint.
   pub Get(asset::Symbol),
   Ok => Position, [
     /// The position with the given ID was retrieved successfully.
@@ -118,14 +448,40 @@ Endpoint! {
 
   #[inline]
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/positions/{input}").into()
+    path!("/v2/positions/{}", input).into()
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/positions/{symbol_or_asset_id} endpoint, optionally reporting
+  /// monetary values in a currency other than USD.
+  pub GetInCurrency(GetReq),
+  Ok => Position, [
+    /// The position with the given ID was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetInCurrencyError, [
+    /// No position was found for the given symbol.
+    /* 404 */ NOT_FOUND => NotFound,
+  ]
+
+  #[inline]
+  fn path(input: &Self::Input) -> Str {
+    path!("/v2/positions/{}", input.symbol).into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let query = to_query(input)?;
+    Ok((!query.is_empty()).then(|| query.into()))
   }
 }
 
 
 Endpoint! {
   /// The representation of a DELETE request to the
-  /// /v2/positions/{symbol} endpoint.
+  /// /v2/positions/{symbol_or_asset_id} endpoint.
   pub Delete(asset::Symbol),
   Ok => order::Order, [
     /// The position was liquidated successfully.
@@ -143,7 +499,120 @@ Endpoint! {
 
   #[inline]
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/positions/{input}").into()
+    path!("/v2/positions/{}", input).into()
+  }
+}
+
+
+/// The way to close a position, as used by [`Client::close_position`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Close {
+  /// Liquidate the position at market, by means of the [`Delete`]
+  /// endpoint.
+  #[default]
+  Market,
+  /// Close the position by submitting a limit order for its entire
+  /// quantity.
+  Limit {
+    /// The limit price of the closing order.
+    limit_price: Num,
+    /// How long the closing order remains in effect.
+    time_in_force: order::TimeInForce,
+  },
+}
+
+
+/// An error as reported by [`Client::close_position`].
+#[derive(Debug, ThisError)]
+pub enum CloseError {
+  /// Liquidating the position at market failed.
+  #[error("failed to liquidate position")]
+  Delete(#[source] RequestError<DeleteError>),
+  /// Submitting the closing limit order failed.
+  #[error("failed to submit closing order")]
+  Create(#[source] RequestError<order::CreateError>),
+}
+
+
+/// The interval at which [`Client::close_position_and_wait`] checks
+/// whether the position is gone.
+const CLOSE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+
+/// An error as reported by [`Client::close_position_and_wait`].
+#[derive(Debug, ThisError)]
+pub enum CloseAndWaitError {
+  /// Liquidating the position at market failed.
+  #[error("failed to liquidate position")]
+  Delete(#[source] RequestError<DeleteError>),
+  /// Checking whether the position is still open failed.
+  #[error("failed to retrieve position")]
+  Get(#[source] RequestError<GetError>),
+  /// The position was not closed in its entirety before the timeout
+  /// elapsed.
+  #[error("the position was not closed in time")]
+  TimedOut,
+}
+
+
+impl Client {
+  /// Close the provided position in the requested way.
+  ///
+  /// The resulting order is reported. Note that a limit order is only
+  /// accepted if none of the position's quantity is tied up in other
+  /// open orders.
+  pub async fn close_position(
+    &self,
+    position: &Position,
+    close: Close,
+  ) -> Result<order::Order, CloseError> {
+    match close {
+      Close::Market => position.close(self).await.map_err(CloseError::Delete),
+      Close::Limit {
+        limit_price,
+        time_in_force,
+      } => {
+        let request = position.close_limit_req(limit_price, time_in_force);
+        self
+          .issue::<order::Create>(&request)
+          .await
+          .map_err(CloseError::Create)
+      },
+    }
+  }
+
+  /// Liquidate the position in the provided symbol at market and wait
+  /// for it to be closed, for at most `timeout`.
+  ///
+  /// The position is polled until it no longer exists. A position
+  /// that got closed only partially, i.e., one whose quantity shrank
+  /// but did not reach zero, is waited on further. The order
+  /// liquidating the position is reported once it is flat.
+  pub async fn close_position_and_wait(
+    &self,
+    symbol: asset::Symbol,
+    timeout: Duration,
+  ) -> Result<order::Order, CloseAndWaitError> {
+    let wait = async {
+      let order = self
+        .issue::<Delete>(&symbol)
+        .await
+        .map_err(CloseAndWaitError::Delete)?;
+
+      loop {
+        match self.issue::<Get>(&symbol).await {
+          Ok(..) => (),
+          Err(RequestError::Endpoint(GetError::NotFound(..))) => break Ok(order),
+          Err(err) => break Err(CloseAndWaitError::Get(err)),
+        }
+        let () = sleep(CLOSE_POLL_INTERVAL).await;
+      }
+    };
+
+    tokio::time::timeout(timeout, wait)
+      .await
+      .unwrap_or(Err(CloseAndWaitError::TimedOut))
   }
 }
 
@@ -152,12 +621,28 @@ Endpoint! {
 mod tests {
   use super::*;
 
+  use std::collections::HashMap;
+
   use serde_json::from_str as from_json;
   use serde_json::to_string as to_json;
 
+  use http_endpoint::Endpoint as _;
+
+  use proptest::collection::vec;
+  use proptest::proptest;
+
   use test_log::test;
 
+  use uuid::Uuid;
+
+  use crate::api::v2::num_util::check_round_trip;
+  use crate::api::v2::num_util::decimal;
+  use crate::api::v2::num_util::signed_decimal;
+  use crate::api::v2::positions::ListInCurrency;
+  use crate::api::v2::positions::ListReq;
   use crate::api_info::ApiInfo;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::Reply;
   use crate::Client;
   use crate::RequestError;
 
@@ -169,11 +654,141 @@ mod tests {
     assert_eq!(!Side::Short, Side::Long);
   }
 
-  /// Make sure that we can deserialize and serialize a `Position`
-  /// object.
+  /// Check that we can deserialize a `Side` object, including sides we
+  /// do not know about.
   #[test]
-  fn deserialize_serialize_reference_position() {
-    let json = r#"{
+  fn parse_side() {
+    assert_eq!(from_json::<Side>(r#""long""#).unwrap(), Side::Long);
+    assert_eq!(from_json::<Side>(r#""short""#).unwrap(), Side::Short);
+    assert_eq!(from_json::<Side>(r#""flat""#).unwrap(), Side::Unknown);
+  }
+
+  /// Check that a crypto currency symbol is percent-encoded in the
+  /// path of position related requests.
+  #[test]
+  fn encode_crypto_symbol_path() {
+    let symbol = asset::Symbol::Sym("BTC/USD".to_string());
+    assert_eq!(Get::path(&symbol), "/v2/positions/BTC%2FUSD");
+    assert_eq!(Delete::path(&symbol), "/v2/positions/BTC%2FUSD");
+
+    let symbol = asset::Symbol::SymExchg("AAPL".to_string(), asset::Exchange::Nasdaq);
+    assert_eq!(Get::path(&symbol), "/v2/positions/AAPL:NASDAQ");
+
+    let request = GetReqInit::default().init(symbol);
+    assert_eq!(GetInCurrency::path(&request), "/v2/positions/AAPL:NASDAQ");
+  }
+
+  /// Check that position related requests can identify the position
+  /// by the asset ID instead of the symbol.
+  #[test]
+  fn asset_id_path() {
+    let id = asset::Id(Uuid::parse_str("b0b6dd9d-8b9b-48a9-ba46-b9d54906e415").unwrap());
+    let symbol = asset::Symbol::from(id);
+    let expected = "/v2/positions/b0b6dd9d-8b9b-48a9-ba46-b9d54906e415";
+    assert_eq!(Get::path(&symbol), expected);
+    assert_eq!(Delete::path(&symbol), expected);
+
+    let symbol = asset::Symbol::Sym("AAPL".to_string());
+    assert_eq!(Get::path(&symbol), "/v2/positions/AAPL");
+    assert_eq!(Delete::path(&symbol), "/v2/positions/AAPL");
+  }
+
+  /// Check that the currency to report values in is only included in
+  /// the query string if set.
+  #[test]
+  fn serialize_currency_query() {
+    let symbol = asset::Symbol::Sym("AAPL".to_string());
+    let request = GetReqInit::default().init(symbol.clone());
+    assert_eq!(GetInCurrency::query(&request).unwrap(), None);
+
+    let request = GetReqInit {
+      currency: Some("EUR".to_string()),
+      ..Default::default()
+    }
+    .init(symbol);
+    assert_eq!(
+      GetInCurrency::query(&request).unwrap().unwrap(),
+      "currency=EUR"
+    );
+
+    assert_eq!(ListInCurrency::query(&ListReq::default()).unwrap(), None);
+
+    let request = ListReq {
+      currency: Some("GBP".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(
+      ListInCurrency::query(&request).unwrap().unwrap(),
+      "currency=GBP"
+    );
+  }
+
+  /// Create a position in the given symbol, with the given quantity
+  /// and average entry price.
+  fn position_in(symbol: &str, quantity: i64, price: i64) -> Position {
+    let mut position = from_json::<Position>(POSITION).unwrap();
+    position.symbol = symbol.to_string();
+    position.quantity = Num::from(quantity);
+    position.average_entry_price = Num::from(price);
+    position
+  }
+
+  /// Check that positions can be stored in a map keyed by their
+  /// symbol.
+  #[test]
+  fn position_map_by_key() {
+    let positions = [
+      position_in("AAPL", 5, 100),
+      position_in("SPY", 2, 500),
+      position_in("AAPL", 7, 110),
+    ];
+
+    let map = positions
+      .iter()
+      .map(|position| (position.key().to_string(), position))
+      .collect::<HashMap<_, _>>();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["AAPL"].quantity, Num::from(7));
+    assert_eq!(map["SPY"].quantity, Num::from(2));
+    assert_eq!(positions[1].key(), "SPY");
+  }
+
+  /// Check that we report added, closed, and changed positions when
+  /// diffing two snapshots.
+  #[test]
+  fn diff_positions() {
+    let old = [
+      position_in("AAPL", 10, 170),
+      position_in("MSFT", 5, 400),
+      position_in("SPY", 2, 500),
+    ];
+    let new = [
+      position_in("AAPL", 15, 170),
+      position_in("SPY", 2, 500),
+      position_in("TSLA", 7, 180),
+    ];
+
+    let diff = PositionDiff::new(&old, &new);
+    assert_eq!(diff.added, vec![new[2].clone()]);
+    assert_eq!(diff.removed, vec![old[1].clone()]);
+    assert_eq!(diff.changed, vec![(old[0].clone(), new[0].clone())]);
+    assert!(!diff.is_empty());
+
+    // A change to the average entry price is reported as well, while a
+    // change in the current price alone is not.
+    let mut new = old.clone();
+    new[1].average_entry_price = Num::from(390);
+    new[2].current_price = Some(Num::from(510));
+    let diff = PositionDiff::new(&old, &new);
+    assert!(diff.added.is_empty());
+    assert!(diff.removed.is_empty());
+    assert_eq!(diff.changed, vec![(old[1].clone(), new[1].clone())]);
+
+    assert!(PositionDiff::new(&old, &old).is_empty());
+  }
+
+  /// A reference position as returned by the server.
+  const POSITION: &str = r#"{
     "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
     "symbol": "AAPL",
     "exchange": "NASDAQ",
@@ -193,8 +808,60 @@ mod tests {
     "change_today": "0.0084"
 }"#;
 
+  /// A reference order liquidating a fractional AAPL position.
+  const ORDER: &str = r#"{
+    "id": "904837e3-3b76-47ec-b432-046db621571b",
+    "client_order_id": "my-order",
+    "created_at": "2018-10-05T05:48:59Z",
+    "updated_at": "2018-10-05T05:48:59Z",
+    "submitted_at": "2018-10-05T05:48:59Z",
+    "filled_at": null,
+    "expired_at": null,
+    "canceled_at": null,
+    "asset_id": "f801f835-bfe6-4a9d-a6b1-ccbb84bfd75f",
+    "symbol": "AAPL",
+    "asset_class": "us_equity",
+    "qty": "4.623",
+    "filled_qty": "0",
+    "type": "market",
+    "order_class": "simple",
+    "side": "sell",
+    "time_in_force": "day",
+    "limit_price": null,
+    "stop_price": null,
+    "filled_avg_price": null,
+    "status": "accepted",
+    "extended_hours": false,
+    "legs": null
+}"#;
+
+  /// The names of the non-negative money fields of a [`Position`].
+  const MONEY_FIELDS: [&str; 6] = [
+    "avg_entry_price",
+    "qty",
+    "qty_available",
+    "market_value",
+    "current_price",
+    "lastday_price",
+  ];
+
+  /// The names of the money fields of a [`Position`] that may be
+  /// negative.
+  const SIGNED_MONEY_FIELDS: [&str; 6] = [
+    "cost_basis",
+    "unrealized_pl",
+    "unrealized_plpc",
+    "unrealized_intraday_pl",
+    "unrealized_intraday_plpc",
+    "change_today",
+  ];
+
+  /// Make sure that we can deserialize and serialize a `Position`
+  /// object.
+  #[test]
+  fn deserialize_serialize_reference_position() {
     let pos =
-      from_json::<Position>(&to_json(&from_json::<Position>(json).unwrap()).unwrap()).unwrap();
+      from_json::<Position>(&to_json(&from_json::<Position>(POSITION).unwrap()).unwrap()).unwrap();
     assert_eq!(pos.symbol, "AAPL");
     assert_eq!(pos.exchange, asset::Exchange::Nasdaq);
     assert_eq!(pos.asset_class, asset::Class::UsEquity);
@@ -213,6 +880,337 @@ mod tests {
     assert_eq!(pos.change_today, Some(Num::new(84, 10000)));
   }
 
+  proptest! {
+    /// Check that arbitrary values of money fields of a [`Position`]
+    /// survive a round trip through JSON.
+    #[test]
+    fn round_trip_money_fields(
+      decimals in vec(decimal(), MONEY_FIELDS.len()),
+      signed in vec(signed_decimal(), SIGNED_MONEY_FIELDS.len()),
+    ) {
+      let position = from_json::<Position>(POSITION).unwrap();
+      let () = check_round_trip(&position, &MONEY_FIELDS, &decimals)?;
+      let () = check_round_trip(&position, &SIGNED_MONEY_FIELDS, &signed)?;
+    }
+  }
+
+  /// A captured position holding a fractional number of shares.
+  const FRACTIONAL_POSITION: &str = r#"{
+    "asset_id": "b0b6dd9d-8b9b-48a9-ba46-b9d54906e415",
+    "symbol": "AAPL",
+    "exchange": "NASDAQ",
+    "asset_class": "us_equity",
+    "asset_marginable": true,
+    "avg_entry_price": "170.4",
+    "qty": "2.5",
+    "qty_available": "1.25",
+    "side": "long",
+    "market_value": "450",
+    "cost_basis": "426",
+    "unrealized_pl": "24",
+    "unrealized_plpc": null,
+    "unrealized_intraday_pl": "2.5",
+    "unrealized_intraday_plpc": "0.0055866",
+    "current_price": "180",
+    "lastday_price": "179",
+    "change_today": "0.0055866"
+}"#;
+
+  /// A captured short position.
+  const SHORT_POSITION: &str = r#"{
+    "asset_id": "8ccae427-5dd0-45b3-b5fe-7ba5e422c766",
+    "symbol": "TSLA",
+    "exchange": "NASDAQ",
+    "asset_class": "us_equity",
+    "asset_marginable": true,
+    "avg_entry_price": "200",
+    "qty": "-4",
+    "qty_available": "-4",
+    "side": "short",
+    "market_value": "-720",
+    "cost_basis": "-800",
+    "unrealized_pl": "80",
+    "unrealized_plpc": "0.1",
+    "unrealized_intraday_pl": "40",
+    "unrealized_intraday_plpc": "0.0526315789473684",
+    "current_price": "180",
+    "lastday_price": "190",
+    "change_today": "-0.0526315789473684"
+}"#;
+
+  /// Check that we calculate the intraday and total return of long and
+  /// short positions correctly.
+  #[test]
+  fn position_returns() {
+    let mut position = from_json::<Position>(FRACTIONAL_POSITION).unwrap();
+    assert!(position.is_long());
+    assert!(!position.is_short());
+    // (180 - 179) / 179
+    assert_eq!(position.intraday_return_pct(), Num::new(1, 179));
+    // (180 - 170.4) / 170.4
+    assert_eq!(position.total_return_pct(), Num::new(4, 71));
+
+    position.last_day_price = Some(Num::from(0));
+    position.average_entry_price = Num::from(0);
+    assert_eq!(position.intraday_return_pct(), Num::from(0));
+    assert_eq!(position.total_return_pct(), Num::from(0));
+
+    let mut position = from_json::<Position>(SHORT_POSITION).unwrap();
+    assert!(position.is_short());
+    assert!(!position.is_long());
+    // -(180 - 190) / 190
+    assert_eq!(position.intraday_return_pct(), Num::new(1, 19));
+    // -(180 - 200) / 200
+    assert_eq!(position.total_return_pct(), Num::new(1, 10));
+
+    position.current_price = None;
+    assert_eq!(position.intraday_return_pct(), Num::from(0));
+    assert_eq!(position.total_return_pct(), Num::from(0));
+  }
+
+  /// Create an `Account` with the given portfolio value.
+  fn account(portfolio_value: &str) -> Account {
+    let json = format!(
+      r#"{{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "admin_configurations": {{}},
+  "user_configurations": null,
+  "account_number": "PALPACA_123",
+  "status": "ACTIVE",
+  "crypto_status": "ACTIVE",
+  "currency": "USD",
+  "buying_power": "0.0",
+  "regt_buying_power": "0.0",
+  "daytrading_buying_power": "0.0",
+  "options_buying_power": "0.0",
+  "effective_buying_power": "0.0",
+  "non_marginable_buying_power": "0.0",
+  "bod_dtbp": "0.0",
+  "cash": "0.0",
+  "accrued_fees": "0.0",
+  "portfolio_value": "{portfolio_value}",
+  "pattern_day_trader": false,
+  "trade_suspended_by_user": false,
+  "trading_blocked": false,
+  "transfers_blocked": false,
+  "account_blocked": false,
+  "created_at": "2018-10-01T13:35:25Z",
+  "shorting_enabled": true,
+  "multiplier": "1",
+  "long_market_value": "0.0",
+  "short_market_value": "0.0",
+  "position_market_value": "0.0",
+  "equity": "{portfolio_value}",
+  "last_equity": "{portfolio_value}",
+  "initial_margin": "0.0",
+  "maintenance_margin": "0.0",
+  "last_maintenance_margin": "0.0",
+  "sma": "0.0",
+  "daytrade_count": 0,
+  "balance_asof": "2018-10-01",
+  "crypto_tier": 1,
+  "options_trading_level": 0,
+  "intraday_adjustments": "0.0",
+  "pending_reg_taf_fees": "0.0"
+}}"#
+    );
+    from_json::<Account>(&json).unwrap()
+  }
+
+  /// Check that we can parse a position holding a fractional number of
+  /// shares and compute derived metrics for it.
+  #[test]
+  fn fractional_position_helpers() {
+    let position = from_json::<Position>(FRACTIONAL_POSITION).unwrap();
+    assert_eq!(position.quantity, Num::new(25, 10));
+    assert_eq!(position.quantity_available, Num::new(125, 100));
+
+    assert_eq!(position.market_value(), Some(Num::from(450)));
+    assert_eq!(position.notional_value(), Num::from(450));
+    // (450 - 426) / 426
+    assert_eq!(position.unrealized_plpc(), Some(Num::new(4, 71)));
+
+    let account = account("1800");
+    assert_eq!(position.weight_in_portfolio(&account), Num::new(1, 4));
+  }
+
+  /// Check that derived metrics are calculated from the current price
+  /// if not reported, and that we guard against division by zero.
+  #[test]
+  fn position_helpers_fallback() {
+    let mut position = from_json::<Position>(FRACTIONAL_POSITION).unwrap();
+    position.market_value = None;
+    position.side = Side::Short;
+    position.cost_basis = Num::from(-426);
+
+    assert_eq!(position.market_value(), Some(Num::from(-450)));
+    assert_eq!(position.notional_value(), Num::from(450));
+    assert_eq!(position.unrealized_plpc(), Some(Num::new(-4, 71)));
+    assert_eq!(
+      position.weight_in_portfolio(&account("1800")),
+      Num::new(-1, 4)
+    );
+    assert_eq!(position.weight_in_portfolio(&account("0")), Num::from(0));
+
+    position.cost_basis = Num::from(0);
+    assert_eq!(position.unrealized_plpc(), None);
+
+    position.current_price = None;
+    assert_eq!(position.market_value(), None);
+    position.cost_basis = Num::from(-426);
+    assert_eq!(position.notional_value(), Num::from(426));
+    assert_eq!(position.weight_in_portfolio(&account("1800")), Num::from(0));
+  }
+
+  /// Verify that we report the quantity locked up in open orders.
+  #[test]
+  fn locked_quantity() {
+    let mut position = from_json::<Position>(FRACTIONAL_POSITION).unwrap();
+    assert!(position.quantity > position.quantity_available);
+    assert_eq!(position.locked_qty(), Num::new(125, 100));
+
+    position.quantity_available = position.quantity.clone();
+    assert_eq!(position.locked_qty(), Num::from(0));
+
+    // Short positions report a negative available quantity.
+    let mut position = from_json::<Position>(SHORT_POSITION).unwrap();
+    assert_eq!(position.locked_qty(), Num::from(0));
+    position.quantity_available = Num::from(-1);
+    assert_eq!(position.locked_qty(), Num::from(3));
+  }
+
+  /// Check that `Position::close` liquidates the position by its
+  /// symbol.
+  #[test(tokio::test)]
+  async fn close_position() {
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", ORDER)]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let position = from_json::<Position>(FRACTIONAL_POSITION).unwrap();
+    let order = position.close(&client).await.unwrap();
+    assert_eq!(order.symbol, position.symbol);
+    assert_eq!(order.side, order::Side::Sell);
+    assert_eq!(requests.lines(), ["DELETE /v2/positions/AAPL"]);
+  }
+
+  /// Check that `Client::close_position_and_wait` keeps waiting while
+  /// a position is only partially closed and resolves once it is flat.
+  #[test(tokio::test)]
+  async fn close_position_and_wait() {
+    let not_found = r#"{"code":40410000,"message":"position does not exist"}"#;
+    let replies = vec![
+      Reply::respond("200 OK", ORDER),
+      Reply::respond("200 OK", to_json(&position_in("AAPL", 5, 100)).unwrap()),
+      Reply::respond("200 OK", to_json(&position_in("AAPL", 2, 100)).unwrap()),
+      Reply::respond("404 Not Found", not_found),
+    ];
+    let (url, requests) = serve_sequence(replies).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let symbol = asset::Symbol::Sym("AAPL".to_string());
+    let order = client
+      .close_position_and_wait(symbol, Duration::from_secs(10))
+      .await
+      .unwrap();
+    assert_eq!(order.symbol, "AAPL");
+    assert_eq!(order.side, order::Side::Sell);
+
+    assert_eq!(
+      requests.lines(),
+      vec![
+        "DELETE /v2/positions/AAPL",
+        "GET /v2/positions/AAPL",
+        "GET /v2/positions/AAPL",
+        "GET /v2/positions/AAPL",
+      ]
+    );
+  }
+
+  /// Make sure that `Client::close_position_and_wait` reports a
+  /// timeout if the position does not get closed.
+  #[test(tokio::test)]
+  async fn close_position_and_wait_timeout() {
+    let position = to_json(&position_in("AAPL", 5, 100)).unwrap();
+    let mut replies = vec![Reply::respond("200 OK", ORDER)];
+    replies.extend((0..10).map(|_| Reply::respond("200 OK", position.clone())));
+    let (url, _requests) = serve_sequence(replies).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let symbol = asset::Symbol::Sym("AAPL".to_string());
+    let result = client
+      .close_position_and_wait(symbol, Duration::from_millis(600))
+      .await;
+    assert!(
+      matches!(result, Err(CloseAndWaitError::TimedOut)),
+      "{result:?}"
+    );
+  }
+
+  /// Check that closing a position with a limit order inverts the
+  /// position's side.
+  #[test]
+  fn close_with_limit_order() {
+    let position = from_json::<Position>(FRACTIONAL_POSITION).unwrap();
+    assert!(position.is_long());
+
+    let request = position.close_limit_req(Num::new(15050, 100), order::TimeInForce::Day);
+    assert_eq!(request.symbol, asset::Symbol::Sym("AAPL".to_string()));
+    assert_eq!(request.side, order::Side::Sell);
+    assert_eq!(request.type_, order::Type::Limit);
+    assert_eq!(request.amount, order::Amount::quantity(position.quantity));
+    assert_eq!(request.limit_price, Some(Num::new(15050, 100)));
+    assert_eq!(request.time_in_force, order::TimeInForce::Day);
+
+    let position = from_json::<Position>(SHORT_POSITION).unwrap();
+    assert!(position.is_short());
+
+    let request = position.close_limit_req(Num::from(250), order::TimeInForce::UntilCanceled);
+    assert_eq!(request.symbol, asset::Symbol::Sym("TSLA".to_string()));
+    assert_eq!(request.side, order::Side::Buy);
+    assert_eq!(request.type_, order::Type::Limit);
+    assert_eq!(request.amount, order::Amount::quantity(position.quantity));
+    assert_eq!(request.limit_price, Some(Num::from(250)));
+    assert_eq!(request.time_in_force, order::TimeInForce::UntilCanceled);
+  }
+
+  /// Check that we report the signed quantity of long and short
+  /// positions correctly.
+  #[test]
+  fn signed_qty() {
+    let position = |side, qty| {
+      let json = format!(
+        r#"{{
+    "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "symbol": "AAPL",
+    "exchange": "NASDAQ",
+    "asset_class": "us_equity",
+    "avg_entry_price": "100.0",
+    "qty": "{qty}",
+    "qty_available": "{qty}",
+    "side": "{side}",
+    "market_value": "600.0",
+    "cost_basis": "500.0",
+    "unrealized_pl": "100.0",
+    "unrealized_plpc": "0.20",
+    "unrealized_intraday_pl": "10.0",
+    "unrealized_intraday_plpc": "0.0084",
+    "current_price": "120.0",
+    "lastday_price": "119.0",
+    "change_today": "0.0084"
+}}"#
+      );
+      from_json::<Position>(&json).unwrap()
+    };
+
+    assert_eq!(position("long", "5").signed_qty(), Num::from(5));
+    assert_eq!(position("short", "-5").signed_qty(), Num::from(-5));
+    assert_eq!(position("long", "0.5").signed_qty(), Num::new(1, 2));
+    assert_eq!(position("short", "-0.5").signed_qty(), Num::new(-1, 2));
+  }
+
   /// Check that we can parse a position with a fractional quantity.
   #[test]
   fn parse_fractional_position() {
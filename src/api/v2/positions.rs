@@ -1,10 +1,39 @@
 // Copyright (C) 2019-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use futures::future::join_all;
+
+use num_decimal::Num;
+
+use serde::Serialize;
+use serde_urlencoded::to_string as to_query;
+
+use crate::api::v2::account;
 use crate::api::v2::position::Position;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
+/// A GET request to be made to the /v2/positions endpoint, as issued
+/// through [`ListInCurrency`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ListReq {
+  /// The currency in which to report monetary values, e.g., "EUR".
+  ///
+  /// If not set, values are reported in USD.
+  #[serde(rename = "currency", skip_serializing_if = "Option::is_none")]
+  pub currency: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
 Endpoint! {
   /// The representation of a GET request to the /v2/positions endpoint.
   pub List(()),
@@ -21,6 +50,193 @@ Endpoint! {
 }
 
 
+Endpoint! {
+  /// The representation of a GET request to the /v2/positions endpoint,
+  /// optionally reporting monetary values in a currency other than
+  /// USD.
+  pub ListInCurrency(ListReq),
+  Ok => Vec<Position>, [
+    /// The list of positions was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => ListInCurrencyError, []
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/positions".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let query = to_query(input)?;
+    Ok((!query.is_empty()).then(|| query.into()))
+  }
+}
+
+
+/// A discrepancy between a cached position and the authoritative one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PositionDiscrepancy {
+  /// The position is cached, but does not actually exist (anymore).
+  ///
+  /// The cached position is attached.
+  Phantom(Position),
+  /// The position exists, but is not part of the cache.
+  Untracked(Position),
+  /// The cached position's signed quantity differs from the actual
+  /// one.
+  QuantityMismatch {
+    /// The cached signed quantity.
+    cached: Num,
+    /// The authoritative position.
+    actual: Position,
+  },
+}
+
+
+/// The result of reconciling cached positions with the authoritative
+/// ones, as produced by [`Client::reconcile_positions`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionReconciliation {
+  /// The authoritative positions.
+  pub positions: Vec<Position>,
+  /// The discrepancies found, keyed by symbol.
+  pub discrepancies: BTreeMap<String, PositionDiscrepancy>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl PositionReconciliation {
+  /// Reconcile `cached` positions with the `actual` ones.
+  ///
+  /// Signed quantities differing by no more than one billionth of a
+  /// share, the precision at which Alpaca reports fractional
+  /// quantities, are considered equal.
+  pub fn new(cached: &[Position], actual: Vec<Position>) -> Self {
+    let tolerance = Num::new(1, 1_000_000_000);
+    let mut discrepancies = BTreeMap::new();
+
+    for position in &actual {
+      match cached
+        .iter()
+        .find(|cached| cached.symbol == position.symbol)
+      {
+        Some(cached) => {
+          let cached = cached.signed_qty();
+          let mut difference = &cached - position.signed_qty();
+          if difference.is_negative() {
+            difference = -difference;
+          }
+
+          if difference > tolerance {
+            let discrepancy = PositionDiscrepancy::QuantityMismatch {
+              cached,
+              actual: position.clone(),
+            };
+            let _prev = discrepancies.insert(position.symbol.clone(), discrepancy);
+          }
+        },
+        None => {
+          let discrepancy = PositionDiscrepancy::Untracked(position.clone());
+          let _prev = discrepancies.insert(position.symbol.clone(), discrepancy);
+        },
+      }
+    }
+
+    for position in cached {
+      if !actual.iter().any(|actual| actual.symbol == position.symbol) {
+        let discrepancy = PositionDiscrepancy::Phantom(position.clone());
+        let _prev = discrepancies.insert(position.symbol.clone(), discrepancy);
+      }
+    }
+
+    Self {
+      positions: actual,
+      discrepancies,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Check whether the cached positions matched the authoritative
+  /// ones.
+  #[inline]
+  pub fn is_consistent(&self) -> bool {
+    self.discrepancies.is_empty()
+  }
+}
+
+
+/// Positions aggregated across multiple accounts, as produced by
+/// [`Client::aggregate_positions`].
+#[derive(Debug)]
+pub struct AggregatedPositions {
+  /// The signed quantities held, summed across accounts and keyed by
+  /// symbol.
+  pub quantities: HashMap<String, Num>,
+  /// The accounts for which positions could not be retrieved, along
+  /// with the error reported for each.
+  pub failed: Vec<(account::Id, RequestError<ListError>)>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+impl Client {
+  /// Retrieve the positions of the provided accounts and sum up their
+  /// signed quantities per symbol.
+  ///
+  /// Positions are retrieved concurrently, with requests scoped to
+  /// each account as per [`Client::for_account`]. A failure to
+  /// retrieve the positions of an account does not abort the
+  /// aggregation, but is reported in
+  /// [`AggregatedPositions::failed`] instead. Symbols with offsetting
+  /// positions are reported with a quantity of zero.
+  pub async fn aggregate_positions(&self, account_ids: &[account::Id]) -> AggregatedPositions {
+    let lists = account_ids.iter().map(|id| {
+      let client = self.for_account(*id);
+      async move { (*id, client.issue::<List>(&()).await) }
+    });
+
+    let mut quantities = HashMap::<_, Num>::new();
+    let mut failed = Vec::new();
+    for (id, result) in join_all(lists).await {
+      match result {
+        Ok(positions) => {
+          for position in positions {
+            let quantity = position.signed_qty();
+            let total = quantities.entry(position.symbol).or_default();
+            *total += quantity;
+          }
+        },
+        Err(err) => failed.push((id, err)),
+      }
+    }
+
+    AggregatedPositions {
+      quantities,
+      failed,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Compare cached positions, e.g., as maintained based on trade
+  /// updates, with the authoritative ones, which are retrieved.
+  ///
+  /// This is useful for detecting drift after a gap in the stream of
+  /// trade updates. Please refer to [`PositionReconciliation::new`]
+  /// for details on how positions are compared.
+  pub async fn reconcile_positions(
+    &self,
+    cached: &[Position],
+  ) -> Result<PositionReconciliation, RequestError<ListError>> {
+    let actual = self.issue::<List>(&()).await?;
+    Ok(PositionReconciliation::new(cached, actual))
+  }
+}
+
+
 // TODO: There is the possibility to issue a DELETE against the
 //       /v2/positions endpoint in order to liquidate all open
 //       positions, which may be interesting to use. However, that
@@ -31,10 +247,180 @@ Endpoint! {
 mod tests {
   use super::*;
 
+  use serde_json::from_str as from_json;
+  use serde_json::to_string as to_json;
+
   use test_log::test;
 
+  use uuid::Uuid;
+
+  use crate::api::v2::position::Side;
   use crate::api_info::ApiInfo;
-  use crate::Client;
+  use crate::test_util::serve_with;
+  use crate::test_util::Reply;
+
+
+  /// Create a position in `symbol` with the given signed quantity.
+  fn position(symbol: &str, qty: Num) -> Position {
+    let side = if qty.is_negative() { "short" } else { "long" };
+    let json = format!(
+      r#"{{
+    "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+    "symbol": "{symbol}",
+    "exchange": "NASDAQ",
+    "asset_class": "us_equity",
+    "avg_entry_price": "100.0",
+    "qty": "{qty}",
+    "qty_available": "{qty}",
+    "side": "{side}",
+    "market_value": "600.0",
+    "cost_basis": "500.0",
+    "unrealized_pl": "100.0",
+    "unrealized_plpc": "0.20",
+    "unrealized_intraday_pl": "10.0",
+    "unrealized_intraday_plpc": "0.0084",
+    "current_price": "120.0",
+    "lastday_price": "119.0",
+    "change_today": "0.0084"
+}}"#
+    );
+    from_json::<Position>(&json).unwrap()
+  }
+
+
+  /// Check that matching positions are considered consistent, even if
+  /// quantities differ within the rounding tolerance.
+  #[test]
+  fn reconcile_consistent_positions() {
+    let cached = [
+      position("AAPL", Num::new(3_333_333_333u64, 10_000_000_000u64)),
+      position("SPY", Num::from(-5)),
+    ];
+    let actual = vec![
+      position("SPY", Num::from(-5)),
+      position("AAPL", Num::new(333_333_333, 1_000_000_000)),
+    ];
+    let reconciliation = PositionReconciliation::new(&cached, actual.clone());
+    assert!(reconciliation.is_consistent());
+    assert_eq!(reconciliation.positions, actual);
+  }
+
+  /// Verify that we report quantity drift, phantom cached positions,
+  /// and untracked actual positions.
+  #[test]
+  fn reconcile_discrepancies() {
+    let cached = [
+      position("AAPL", Num::from(10)),
+      position("MSFT", Num::from(3)),
+      position("SPY", Num::from(2)),
+    ];
+    let actual = vec![
+      position("AAPL", Num::from(7)),
+      position("SPY", Num::from(-2)),
+      position("VOO", Num::new(1, 2)),
+    ];
+    let reconciliation = PositionReconciliation::new(&cached, actual.clone());
+    assert!(!reconciliation.is_consistent());
+
+    let discrepancies = &reconciliation.discrepancies;
+    assert_eq!(discrepancies.len(), 4);
+    assert_eq!(
+      discrepancies["AAPL"],
+      PositionDiscrepancy::QuantityMismatch {
+        cached: Num::from(10),
+        actual: actual[0].clone(),
+      }
+    );
+    assert_eq!(
+      discrepancies["MSFT"],
+      PositionDiscrepancy::Phantom(cached[1].clone())
+    );
+    // A flipped side is a mismatch as well.
+    match &discrepancies["SPY"] {
+      PositionDiscrepancy::QuantityMismatch { cached, actual } => {
+        assert_eq!(cached, &Num::from(2));
+        assert_eq!(actual.side, Side::Short);
+      },
+      discrepancy => panic!("unexpected discrepancy: {discrepancy:?}"),
+    }
+    assert_eq!(
+      discrepancies["VOO"],
+      PositionDiscrepancy::Untracked(actual[2].clone())
+    );
+  }
+
+
+  /// Serve the positions of the provided accounts on a local socket,
+  /// based on the account ID header of each request. Requests for
+  /// other accounts are denied.
+  async fn serve_accounts(accounts: Vec<(account::Id, Vec<Position>)>) -> String {
+    let (url, _requests) = serve_with(move |request| {
+      let request = request.to_lowercase();
+      let positions = accounts.iter().find_map(|(id, positions)| {
+        let header = format!("apca-account-id: {}\r\n", id.as_hyphenated());
+        request.contains(&header).then_some(positions)
+      });
+      match positions {
+        Some(positions) => Reply::respond("200 OK", to_json(positions).unwrap()),
+        None => Reply::respond(
+          "403 Forbidden",
+          r#"{"code":40310000,"message":"access denied"}"#,
+        ),
+      }
+    })
+    .await;
+    url
+  }
+
+  /// Check that positions are aggregated across accounts, with
+  /// failures being reported separately.
+  #[test(tokio::test)]
+  async fn aggregate_account_positions() {
+    let first = account::Id(Uuid::from_u128(1));
+    let second = account::Id(Uuid::from_u128(2));
+    let unknown = account::Id(Uuid::from_u128(3));
+    let accounts = vec![
+      (
+        first,
+        vec![
+          position("AAPL", Num::from(5)),
+          position("SPY", Num::from(-2)),
+          position("MSFT", Num::new(1, 2)),
+        ],
+      ),
+      (
+        second,
+        vec![
+          position("AAPL", Num::from(3)),
+          position("SPY", Num::from(2)),
+          position("VOO", Num::from(-4)),
+        ],
+      ),
+    ];
+    let url = serve_accounts(accounts).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let aggregated = client.aggregate_positions(&[first, unknown, second]).await;
+    let quantities = &aggregated.quantities;
+    assert_eq!(quantities.len(), 4);
+    assert_eq!(quantities["AAPL"], Num::from(8));
+    assert_eq!(quantities["SPY"], Num::from(0));
+    assert_eq!(quantities["MSFT"], Num::new(1, 2));
+    assert_eq!(quantities["VOO"], Num::from(-4));
+
+    assert_eq!(aggregated.failed.len(), 1);
+    let (id, err) = &aggregated.failed[0];
+    assert_eq!(id, &unknown);
+    assert!(
+      matches!(err, RequestError::Endpoint(ListError::NotPermitted(..))),
+      "{err:?}"
+    );
+
+    let aggregated = client.aggregate_positions(&[]).await;
+    assert!(aggregated.quantities.is_empty());
+    assert!(aggregated.failed.is_empty());
+  }
 
 
   #[test(tokio::test)]
@@ -2,17 +2,31 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
+use chrono::DateTime;
+use chrono::Utc;
+
+use futures::channel::mpsc::unbounded;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
 use futures::stream::Fuse;
 use futures::stream::Map;
 use futures::stream::SplitSink;
 use futures::stream::SplitStream;
 use futures::FutureExt as _;
 use futures::Sink;
+use futures::Stream as FutStream;
 use futures::StreamExt as _;
 
+use num_decimal::Num;
+
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::from_slice as json_from_slice;
@@ -20,29 +34,38 @@ use serde_json::from_str as json_from_str;
 use serde_json::to_string as to_json;
 use serde_json::Error as JsonError;
 
+use thiserror::Error as ThisError;
+
 use tokio::net::TcpStream;
 
 use tungstenite::MaybeTlsStream;
 use tungstenite::WebSocketStream;
 
+use uuid::Uuid;
+
 use websocket_util::subscribe;
 use websocket_util::subscribe::MessageStream;
 use websocket_util::tungstenite::Error as WebSocketError;
 use websocket_util::wrap;
 use websocket_util::wrap::Wrapper;
 
+use crate::api::v2::account;
 use crate::api::v2::order;
 use crate::api_info::ApiInfo;
 use crate::subscribable::Subscribable;
+use crate::util::strict_variant;
+use crate::util::UnknownVariant;
 use crate::websocket::connect;
 use crate::websocket::MessageResult;
+use crate::Client;
 use crate::Error;
+use crate::RequestError;
 
 
-/// The status of an order, as reported as part of a `OrderUpdate`.
+/// The event an [`OrderUpdate`] is reporting.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[non_exhaustive]
-pub enum OrderStatus {
+pub enum OrderEvent {
   /// The order has been received by Alpaca, and routed to exchanges for
   /// execution.
   #[serde(rename = "new")]
@@ -101,14 +124,25 @@ pub enum OrderStatus {
   /// for day), but remaining settlement calculations are still pending.
   #[serde(rename = "calculated")]
   Calculated,
-  /// Any other status that we have not accounted for.
+  /// Any other event that we have not accounted for.
   ///
-  /// Note that having any such status should be considered a bug.
+  /// Note that having any such event should be considered a bug.
   #[doc(hidden)]
   #[serde(other, rename(serialize = "unknown"))]
   Unknown,
 }
 
+impl UnknownVariant for OrderEvent {
+  #[inline]
+  fn is_unknown(&self) -> bool {
+    matches!(self, Self::Unknown)
+  }
+}
+
+/// The former name of [`OrderEvent`].
+#[deprecated(note = "use OrderEvent instead")]
+pub type OrderStatus = OrderEvent;
+
 
 /// An enumeration of the different event streams.
 #[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -117,6 +151,9 @@ pub enum StreamType {
   /// A stream for order updates.
   #[serde(rename = "trade_updates")]
   OrderUpdates,
+  /// A stream for account updates.
+  #[serde(rename = "account_updates")]
+  AccountUpdates,
 }
 
 
@@ -235,11 +272,93 @@ pub enum OrderMessage {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct OrderUpdate {
   /// The event that occurred.
-  #[serde(rename = "event")]
-  pub event: OrderStatus,
+  #[serde(rename = "event", deserialize_with = "strict_variant")]
+  pub event: OrderEvent,
   /// The order that received an update.
   #[serde(rename = "order")]
   pub order: order::Order,
+  /// The time at which the event occurred.
+  #[serde(rename = "timestamp", default)]
+  pub timestamp: Option<DateTime<Utc>>,
+  /// The ID uniquely identifying the execution, if any.
+  #[serde(rename = "execution_id", default)]
+  pub execution_id: Option<Uuid>,
+  /// The price per share of a fill or partial fill.
+  #[serde(rename = "price", default)]
+  pub price: Option<Num>,
+  /// The number of shares of a fill or partial fill.
+  #[serde(rename = "qty", default)]
+  pub qty: Option<Num>,
+  /// The position size (negative for short positions) after a fill or
+  /// partial fill.
+  #[serde(rename = "position_qty", default)]
+  pub position_qty: Option<Num>,
+}
+
+
+/// A representation of an account update that we receive through the
+/// "account_updates" stream.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AccountUpdate {
+  /// The account's ID.
+  #[serde(rename = "id")]
+  pub id: account::Id,
+  /// The account's status.
+  #[serde(rename = "status", deserialize_with = "strict_variant")]
+  pub status: account::Status,
+  /// The currency the account uses.
+  #[serde(rename = "currency")]
+  pub currency: String,
+  /// The cash balance.
+  #[serde(rename = "cash")]
+  pub cash: Num,
+  /// The cash balance that can be withdrawn.
+  #[serde(rename = "cash_withdrawable")]
+  pub cash_withdrawable: Num,
+  /// The time the account was created at.
+  #[serde(rename = "created_at")]
+  pub created_at: DateTime<Utc>,
+  /// The time the account was last updated at.
+  #[serde(rename = "updated_at", default)]
+  pub updated_at: Option<DateTime<Utc>>,
+  /// The time the account was deleted at, if it was.
+  #[serde(rename = "deleted_at", default)]
+  pub deleted_at: Option<DateTime<Utc>>,
+}
+
+
+/// An update as received through the [`Updates`] stream.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+#[allow(clippy::large_enum_variant)]
+pub enum Update {
+  /// An update to an order.
+  Order(OrderUpdate),
+  /// An update to the account.
+  Account(AccountUpdate),
+}
+
+
+/// An enum representing the different messages we may receive over the
+/// websocket channel when listening to both order and account updates.
+#[derive(Debug, Deserialize, Serialize)]
+#[doc(hidden)]
+#[serde(tag = "stream", content = "data")]
+#[allow(clippy::large_enum_variant)]
+pub enum UpdateMessage {
+  /// An order update.
+  #[serde(rename = "trade_updates")]
+  OrderUpdate(OrderUpdate),
+  /// An account update.
+  #[serde(rename = "account_updates")]
+  AccountUpdate(AccountUpdate),
+  /// A control message indicating whether or not we were authenticated
+  /// successfully.
+  #[serde(rename = "authorization")]
+  AuthenticationMessage(Authentication),
+  /// A control message detailing the streams we are subscribed to.
+  #[serde(rename = "listening")]
+  ListeningMessage(Streams<'static>),
 }
 
 
@@ -282,89 +401,207 @@ impl subscribe::Message for ParsedMessage {
 }
 
 
-/// A subscription allowing certain control operations pertaining order
-/// update retrieval.
-#[derive(Debug)]
-pub struct Subscription<S>(subscribe::Subscription<S, ParsedMessage, wrap::Message>);
+/// A websocket message received while listening to both order and
+/// account updates that we tried to parse.
+type ParsedUpdate = MessageResult<Result<UpdateMessage, JsonError>, WebSocketError>;
 
-impl<S> Subscription<S>
+impl subscribe::Message for ParsedUpdate {
+  type UserMessage = Result<Result<Update, JsonError>, WebSocketError>;
+  type ControlMessage = ControlMessage;
+
+  fn classify(self) -> subscribe::Classification<Self::UserMessage, Self::ControlMessage> {
+    match self {
+      MessageResult::Ok(Ok(message)) => match message {
+        UpdateMessage::OrderUpdate(update) => {
+          subscribe::Classification::UserMessage(Ok(Ok(Update::Order(update))))
+        },
+        UpdateMessage::AccountUpdate(update) => {
+          subscribe::Classification::UserMessage(Ok(Ok(Update::Account(update))))
+        },
+        UpdateMessage::AuthenticationMessage(authentication) => {
+          subscribe::Classification::ControlMessage(ControlMessage::AuthenticationMessage(
+            authentication,
+          ))
+        },
+        UpdateMessage::ListeningMessage(streams) => {
+          subscribe::Classification::ControlMessage(ControlMessage::ListeningMessage(streams))
+        },
+      },
+      MessageResult::Ok(Err(err)) => subscribe::Classification::UserMessage(Ok(Err(err))),
+      MessageResult::Err(err) => subscribe::Classification::UserMessage(Err(err)),
+    }
+  }
+
+  #[inline]
+  fn is_error(user_message: &Self::UserMessage) -> bool {
+    user_message
+      .as_ref()
+      .map(|result| result.is_err())
+      .unwrap_or(true)
+  }
+}
+
+
+/// Authenticate the connection using Alpaca credentials.
+async fn authenticate<S, M>(
+  subscription: &mut subscribe::Subscription<S, M, wrap::Message>,
+  key_id: &str,
+  secret: &str,
+) -> Result<Result<(), Error>, S::Error>
 where
   S: Sink<wrap::Message> + Unpin,
+  M: subscribe::Message<ControlMessage = ControlMessage>,
 {
-  /// Authenticate the connection using Alpaca credentials.
-  async fn authenticate(
-    &mut self,
-    key_id: &str,
-    secret: &str,
-  ) -> Result<Result<(), Error>, S::Error> {
-    let request = Authenticate::Request {
-      key_id: key_id.into(),
-      secret: secret.into(),
-    };
-    let json = match to_json(&request) {
-      Ok(json) => json,
-      Err(err) => return Ok(Err(Error::Json(err))),
-    };
-    let message = wrap::Message::Text(json);
-    let response = self.0.send(message).await?;
-
-    match response {
-      Some(response) => match response {
-        Ok(ControlMessage::AuthenticationMessage(authentication)) => {
-          if authentication.status != AuthenticationStatus::Authorized {
-            return Ok(Err(Error::Str("authentication not successful".into())))
-          }
-          Ok(Ok(()))
-        },
-        Ok(_) => Ok(Err(Error::Str(
-          "server responded with an unexpected message".into(),
-        ))),
-        Err(()) => Ok(Err(Error::Str("failed to authenticate with server".into()))),
+  let request = Authenticate::Request {
+    key_id: key_id.into(),
+    secret: secret.into(),
+  };
+  let json = match to_json(&request) {
+    Ok(json) => json,
+    Err(err) => return Ok(Err(Error::Json(err))),
+  };
+  let message = wrap::Message::Text(json);
+  let response = subscription.send(message).await?;
+
+  match response {
+    Some(response) => match response {
+      Ok(ControlMessage::AuthenticationMessage(authentication)) => {
+        if authentication.status != AuthenticationStatus::Authorized {
+          return Ok(Err(Error::Str("authentication not successful".into())))
+        }
+        Ok(Ok(()))
       },
-      None => Ok(Err(Error::Str(
-        "stream was closed before authorization message was received".into(),
+      Ok(_) => Ok(Err(Error::Str(
+        "server responded with an unexpected message".into(),
       ))),
-    }
+      Err(()) => Ok(Err(Error::Str("failed to authenticate with server".into()))),
+    },
+    None => Ok(Err(Error::Str(
+      "stream was closed before authorization message was received".into(),
+    ))),
   }
+}
 
-  /// Subscribe and listen to order updates.
-  async fn listen(&mut self) -> Result<Result<(), Error>, S::Error> {
-    let streams = Streams::from([StreamType::OrderUpdates].as_ref());
-    let request = Listen::Request(streams);
-    let json = match to_json(&request) {
-      Ok(json) => json,
-      Err(err) => return Ok(Err(Error::Json(err))),
-    };
-    let message = wrap::Message::Text(json);
-    let response = self.0.send(message).await?;
-
-    match response {
-      Some(response) => match response {
-        Ok(ControlMessage::ListeningMessage(streams)) => {
-          if !streams.streams.contains(&StreamType::OrderUpdates) {
-            return Ok(Err(Error::Str(
-              "server did not subscribe us to order update stream".into(),
-            )))
-          }
-          Ok(Ok(()))
-        },
-        Ok(_) => Ok(Err(Error::Str(
-          "server responded with an unexpected message".into(),
-        ))),
-        Err(()) => Ok(Err(Error::Str(
-          "failed to listen to order update stream".into(),
-        ))),
+
+/// Subscribe and listen to the given streams.
+async fn listen<S, M>(
+  subscription: &mut subscribe::Subscription<S, M, wrap::Message>,
+  stream_types: &[StreamType],
+) -> Result<Result<(), Error>, S::Error>
+where
+  S: Sink<wrap::Message> + Unpin,
+  M: subscribe::Message<ControlMessage = ControlMessage>,
+{
+  let streams = Streams::from(stream_types);
+  let request = Listen::Request(streams);
+  let json = match to_json(&request) {
+    Ok(json) => json,
+    Err(err) => return Ok(Err(Error::Json(err))),
+  };
+  let message = wrap::Message::Text(json);
+  let response = subscription.send(message).await?;
+
+  match response {
+    Some(response) => match response {
+      Ok(ControlMessage::ListeningMessage(streams)) => {
+        if !stream_types
+          .iter()
+          .all(|stream_type| streams.streams.contains(stream_type))
+        {
+          return Ok(Err(Error::Str(
+            "server did not subscribe us to order update stream".into(),
+          )))
+        }
+        Ok(Ok(()))
       },
-      None => Ok(Err(Error::Str(
-        "stream was closed before listen message was received".into(),
+      Ok(_) => Ok(Err(Error::Str(
+        "server responded with an unexpected message".into(),
       ))),
-    }
+      Err(()) => Ok(Err(Error::Str(
+        "failed to listen to order update stream".into(),
+      ))),
+    },
+    None => Ok(Err(Error::Str(
+      "stream was closed before listen message was received".into(),
+    ))),
   }
 }
 
 
-type Stream = Map<Wrapper<WebSocketStream<MaybeTlsStream<TcpStream>>>, MapFn>;
-type MapFn = fn(Result<wrap::Message, WebSocketError>) -> ParsedMessage;
+/// A subscription allowing certain control operations pertaining order
+/// update retrieval.
+#[derive(Debug)]
+pub struct Subscription<S>(subscribe::Subscription<S, ParsedMessage, wrap::Message>);
+
+
+/// A subscription allowing certain control operations pertaining the
+/// retrieval of order and account updates.
+#[derive(Debug)]
+pub struct UpdatesSubscription<S>(subscribe::Subscription<S, ParsedUpdate, wrap::Message>);
+
+
+type Stream<M> = Map<Wrapper<WebSocketStream<MaybeTlsStream<TcpStream>>>, MapFn<M>>;
+type MapFn<M> = fn(Result<wrap::Message, WebSocketError>) -> M;
+type SubscribeStream<M> = Fuse<MessageStream<SplitStream<Stream<M>>, M>>;
+type SubscribeSink<M> = SplitSink<Stream<M>, wrap::Message>;
+
+
+/// Connect to the update stream, authenticate, and listen to the
+/// provided streams.
+#[allow(clippy::type_complexity)]
+async fn connect_streams<M, T>(
+  api_info: &ApiInfo,
+  map: MapFn<M>,
+  stream_types: &[StreamType],
+) -> Result<
+  (
+    SubscribeStream<M>,
+    subscribe::Subscription<SubscribeSink<M>, M, wrap::Message>,
+  ),
+  Error,
+>
+where
+  M: subscribe::Message<
+      UserMessage = Result<Result<T, JsonError>, WebSocketError>,
+      ControlMessage = ControlMessage,
+    > + Send,
+  T: Send,
+{
+  let ApiInfo {
+    api_stream_url: url,
+    key_id,
+    secret,
+    user_agent,
+    ..
+  } = api_info;
+
+  let stream = connect(url, user_agent).await?.map(map);
+  let (send, recv) = stream.split();
+  let (stream, mut subscription) = subscribe::subscribe(recv, send);
+  let mut stream = stream.fuse();
+
+  let authenticate = authenticate(&mut subscription, key_id, secret).boxed();
+  let () = subscribe::drive::<M, _, _>(authenticate, &mut stream)
+    .await
+    .map_err(|result| {
+      result
+        .map(|result| Error::Json(result.err().unwrap()))
+        .map_err(Error::WebSocket)
+        .unwrap_or_else(|err| err)
+    })???;
+
+  let listen = listen(&mut subscription, stream_types).boxed();
+  let () = subscribe::drive::<M, _, _>(listen, &mut stream)
+    .await
+    .map_err(|result| {
+      result
+        .map(|result| Error::Json(result.err().unwrap()))
+        .map_err(Error::WebSocket)
+        .unwrap_or_else(|err| err)
+    })???;
+
+  Ok((stream, subscription))
+}
 
 
 /// A type used for requesting a subscription to the "trade_updates"
@@ -375,8 +612,8 @@ pub enum OrderUpdates {}
 #[async_trait]
 impl Subscribable for OrderUpdates {
   type Input = ApiInfo;
-  type Subscription = Subscription<SplitSink<Stream, wrap::Message>>;
-  type Stream = Fuse<MessageStream<SplitStream<Stream>, ParsedMessage>>;
+  type Subscription = Subscription<SubscribeSink<ParsedMessage>>;
+  type Stream = SubscribeStream<ParsedMessage>;
 
   async fn connect(api_info: &Self::Input) -> Result<(Self::Stream, Self::Subscription), Error> {
     fn map(result: Result<wrap::Message, WebSocketError>) -> ParsedMessage {
@@ -386,40 +623,324 @@ impl Subscribable for OrderUpdates {
       }))
     }
 
-    let ApiInfo {
-      api_stream_url: url,
-      key_id,
-      secret,
-      ..
-    } = api_info;
-
-    let stream = connect(url).await?.map(map as MapFn);
-    let (send, recv) = stream.split();
-    let (stream, subscription) = subscribe::subscribe(recv, send);
-    let mut stream = stream.fuse();
-
-    let mut subscription = Subscription(subscription);
-    let authenticate = subscription.authenticate(key_id, secret).boxed();
-    let () = subscribe::drive::<ParsedMessage, _, _>(authenticate, &mut stream)
-      .await
-      .map_err(|result| {
-        result
-          .map(|result| Error::Json(result.unwrap_err()))
-          .map_err(Error::WebSocket)
-          .unwrap_or_else(|err| err)
-      })???;
-
-    let listen = subscription.listen().boxed();
-    let () = subscribe::drive::<ParsedMessage, _, _>(listen, &mut stream)
-      .await
-      .map_err(|result| {
-        result
-          .map(|result| Error::Json(result.unwrap_err()))
-          .map_err(Error::WebSocket)
-          .unwrap_or_else(|err| err)
-      })???;
+    let (stream, subscription) =
+      connect_streams(api_info, map, &[StreamType::OrderUpdates]).await?;
+    Ok((stream, Subscription(subscription)))
+  }
+}
+
+
+/// A type used for requesting a subscription to both the
+/// "trade_updates" and "account_updates" event streams, yielding
+/// [`Update`] objects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Updates {}
+
+#[async_trait]
+impl Subscribable for Updates {
+  type Input = ApiInfo;
+  type Subscription = UpdatesSubscription<SubscribeSink<ParsedUpdate>>;
+  type Stream = SubscribeStream<ParsedUpdate>;
+
+  async fn connect(api_info: &Self::Input) -> Result<(Self::Stream, Self::Subscription), Error> {
+    fn map(result: Result<wrap::Message, WebSocketError>) -> ParsedUpdate {
+      MessageResult::from(result.map(|message| match message {
+        wrap::Message::Text(string) => json_from_str::<UpdateMessage>(&string),
+        wrap::Message::Binary(data) => json_from_slice::<UpdateMessage>(&data),
+      }))
+    }
+
+    let stream_types = [StreamType::OrderUpdates, StreamType::AccountUpdates];
+    let (stream, subscription) = connect_streams(api_info, map, &stream_types).await?;
+    Ok((stream, UpdatesSubscription(subscription)))
+  }
+}
+
+
+/// The shared state of a [`ReplayBuffer`].
+#[derive(Debug)]
+struct ReplayState {
+  /// The most recent order updates, oldest first.
+  history: VecDeque<OrderUpdate>,
+  /// The maximum number of updates to retain.
+  capacity: usize,
+  /// The attached consumers.
+  consumers: Vec<UnboundedSender<OrderUpdate>>,
+}
+
+
+/// A bounded buffer of the most recent order updates, replaying them to
+/// consumers attaching late.
+///
+/// Updates passing through a stream wrapped via
+/// [`ReplayBuffer::record`] are retained, up to the configured
+/// capacity, with the oldest one being evicted first. Consumers
+/// created via [`ReplayBuffer::attach`] first receive all retained
+/// updates and then all subsequently recorded ones.
+///
+/// The buffer itself never holds more than `capacity` updates. However,
+/// each attached consumer is backed by an unbounded channel, so a
+/// consumer that does not keep up accumulates updates until it is
+/// dropped.
+#[derive(Clone, Debug)]
+pub struct ReplayBuffer {
+  state: Arc<Mutex<ReplayState>>,
+}
+
+impl ReplayBuffer {
+  /// Create a new `ReplayBuffer` retaining at most `capacity` updates.
+  pub fn new(capacity: usize) -> Self {
+    let state = ReplayState {
+      history: VecDeque::with_capacity(capacity),
+      capacity,
+      consumers: Vec::new(),
+    };
+
+    Self {
+      state: Arc::new(Mutex::new(state)),
+    }
+  }
+
+  /// Record an order update, forwarding it to all attached consumers.
+  fn push(&self, update: &OrderUpdate) {
+    let mut state = self.state.lock().unwrap();
+    if state.capacity > 0 {
+      if state.history.len() == state.capacity {
+        let _evicted = state.history.pop_front();
+      }
+      let () = state.history.push_back(update.clone());
+    }
+
+    // Consumers that got dropped are removed as we go.
+    let () = state
+      .consumers
+      .retain(|consumer| consumer.unbounded_send(update.clone()).is_ok());
+  }
+
+  /// Wrap an order update stream, as created by subscribing to
+  /// [`OrderUpdates`], recording all successfully decoded updates
+  /// passing through it.
+  ///
+  /// The returned stream yields the same items as the wrapped one and
+  /// has to be polled for updates to be recorded.
+  pub fn record<S, E>(&self, stream: S) -> impl FutStream<Item = S::Item>
+  where
+    S: FutStream<Item = Result<Result<OrderUpdate, JsonError>, E>>,
+  {
+    let buffer = self.clone();
+    stream.inspect(move |item| {
+      if let Ok(Ok(update)) = item {
+        let () = buffer.push(update);
+      }
+    })
+  }
+
+  /// Attach a new consumer, receiving all currently retained updates
+  /// followed by all updates recorded from now on.
+  pub fn attach(&self) -> UnboundedReceiver<OrderUpdate> {
+    let (sender, receiver) = unbounded();
+    let mut state = self.state.lock().unwrap();
+    state.history.iter().for_each(|update| {
+      // The receiver is still around, so sending cannot fail.
+      let _result = sender.unbounded_send(update.clone());
+    });
+    let () = state.consumers.push(sender);
+    receiver
+  }
+
+  /// Retrieve the currently retained updates, oldest first.
+  pub fn history(&self) -> Vec<OrderUpdate> {
+    let state = self.state.lock().unwrap();
+    state.history.iter().cloned().collect()
+  }
+}
+
+
+/// An individual execution of an order, as reported by a fill or
+/// partial fill update.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Execution {
+  /// The ID uniquely identifying the execution, if reported.
+  pub execution_id: Option<Uuid>,
+  /// The time at which the execution occurred, if reported.
+  pub timestamp: Option<DateTime<Utc>>,
+  /// The price per share of the execution.
+  pub price: Num,
+  /// The number of shares executed.
+  pub quantity: Num,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A summary of the executions of an order, reconstructed from its
+/// fill and partial fill updates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionSummary {
+  /// The individual executions, in the order they were reported.
+  pub executions: Vec<Execution>,
+  /// The total number of shares executed.
+  pub quantity: Num,
+  /// The volume weighted average execution price, if anything got
+  /// executed.
+  pub average_price: Option<Num>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl ExecutionSummary {
+  /// Summarize the executions of the order with the provided ID from
+  /// the given order updates.
+  ///
+  /// Updates for other orders as well as ones other than fills and
+  /// partial fills are ignored, as are fills lacking price or
+  /// quantity information.
+  pub fn from_updates<'u, I>(id: order::Id, updates: I) -> Self
+  where
+    I: IntoIterator<Item = &'u OrderUpdate>,
+  {
+    let executions = updates
+      .into_iter()
+      .filter(|update| update.order.id == id)
+      .filter(|update| matches!(update.event, OrderEvent::PartialFill | OrderEvent::Filled))
+      .filter_map(|update| match (&update.price, &update.qty) {
+        (Some(price), Some(quantity)) => Some(Execution {
+          execution_id: update.execution_id,
+          timestamp: update.timestamp,
+          price: price.clone(),
+          quantity: quantity.clone(),
+          _non_exhaustive: (),
+        }),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+
+    let (quantity, value) = executions.iter().fold(
+      (Num::from(0), Num::from(0)),
+      |(quantity, value), execution| {
+        (
+          quantity + &execution.quantity,
+          value + &execution.price * &execution.quantity,
+        )
+      },
+    );
+    let average_price = if quantity.is_zero() {
+      None
+    } else {
+      Some(value / &quantity)
+    };
+
+    Self {
+      executions,
+      quantity,
+      average_price,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// An error as reported by [`Client::wait_for_fill`].
+#[derive(Debug, ThisError)]
+pub enum WaitForFillError {
+  /// Connecting to the trade updates stream failed.
+  #[error("failed to subscribe to trade updates")]
+  Stream(#[source] Error),
+  /// Looking up the order failed.
+  #[error("failed to look up order")]
+  Lookup(#[source] RequestError<order::GetError>),
+  /// Receiving or decoding a trade update failed.
+  #[error("failed to receive trade update")]
+  Update(#[source] Error),
+  /// The order got canceled.
+  #[error("the order got canceled")]
+  Canceled(Box<order::Order>),
+  /// The order got rejected.
+  #[error("the order got rejected")]
+  Rejected(Box<order::Order>),
+  /// The order expired.
+  #[error("the order expired")]
+  Expired(Box<order::Order>),
+  /// The trade updates stream ended before the order was filled.
+  #[error("the trade updates stream ended")]
+  StreamEnded,
+  /// The order was not filled within the provided timeout.
+  #[error("timed out waiting for the order to be filled")]
+  TimedOut,
+}
+
+
+/// Wait for the order with the provided ID to be filled, based on the
+/// result of a one-shot `lookup` of the order followed by the updates
+/// reported by `stream`.
+async fn await_fill<St, L>(
+  id: order::Id,
+  mut stream: St,
+  lookup: L,
+) -> Result<order::Order, WaitForFillError>
+where
+  St: FutStream<Item = Result<Result<OrderUpdate, JsonError>, WebSocketError>> + Unpin,
+  L: Future<Output = Result<order::Order, RequestError<order::GetError>>>,
+{
+  // The order may have reached its final state before we subscribed,
+  // in which case the stream will never report it.
+  let order = lookup.await.map_err(WaitForFillError::Lookup)?;
+  match order.status {
+    order::Status::Filled => return Ok(order),
+    order::Status::Canceled => return Err(WaitForFillError::Canceled(Box::new(order))),
+    order::Status::Rejected => return Err(WaitForFillError::Rejected(Box::new(order))),
+    order::Status::Expired => return Err(WaitForFillError::Expired(Box::new(order))),
+    _ => (),
+  }
+
+  while let Some(result) = stream.next().await {
+    let update = result
+      .map_err(|err| WaitForFillError::Update(Error::WebSocket(err)))?
+      .map_err(|err| WaitForFillError::Update(Error::Json(err)))?;
 
-    Ok((stream, subscription))
+    if update.order.id != id {
+      continue
+    }
+
+    match update.event {
+      OrderEvent::Filled => return Ok(update.order),
+      OrderEvent::Canceled => return Err(WaitForFillError::Canceled(Box::new(update.order))),
+      OrderEvent::Rejected => return Err(WaitForFillError::Rejected(Box::new(update.order))),
+      OrderEvent::Expired => return Err(WaitForFillError::Expired(Box::new(update.order))),
+      _ => (),
+    }
+  }
+  Err(WaitForFillError::StreamEnded)
+}
+
+
+impl Client {
+  /// Wait for the order with the provided ID to be filled, for at most
+  /// `timeout`.
+  ///
+  /// The order is tracked by means of the trade updates stream. Once
+  /// subscribed, the order is looked up once, so that a fill that
+  /// happened before the subscription was established is not missed.
+  /// An order that got canceled, rejected, or that expired results
+  /// in an error.
+  pub async fn wait_for_fill(
+    &self,
+    id: order::Id,
+    timeout: Duration,
+  ) -> Result<order::Order, WaitForFillError> {
+    let wait = async {
+      let (stream, _subscription) = self
+        .subscribe::<OrderUpdates>()
+        .await
+        .map_err(WaitForFillError::Stream)?;
+      await_fill(id, stream, self.issue::<order::Get>(&id)).await
+    };
+
+    tokio::time::timeout(timeout, wait)
+      .await
+      .unwrap_or(Err(WaitForFillError::TimedOut))
   }
 }
 
@@ -428,6 +949,8 @@ impl Subscribable for OrderUpdates {
 mod tests {
   use super::*;
 
+  use std::str::FromStr as _;
+
   use futures::channel::oneshot::channel;
   use futures::future::ok;
   use futures::future::ready;
@@ -509,13 +1032,202 @@ mod tests {
     let message = json_from_str::<OrderMessage>(json).unwrap();
     match message {
       OrderMessage::OrderUpdate(update) => {
-        assert_eq!(update.event, OrderStatus::New);
+        assert_eq!(update.event, OrderEvent::New);
         assert_eq!(update.order.side, order::Side::Buy);
+        assert_eq!(
+          update.timestamp,
+          Some(DateTime::from_str("2021-12-09T19:48:46.182987144Z").unwrap())
+        );
+        assert_eq!(update.price, None);
+        assert_eq!(update.qty, None);
+        assert_eq!(update.position_qty, None);
+      },
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    }
+  }
+
+  /// Create an order update message for the given event, with the
+  /// given fill related fields.
+  fn fill_update(event: &str, filled_qty: &str, fields: &str) -> String {
+    let status = match event {
+      "fill" => "filled",
+      "partial_fill" => "partially_filled",
+      _ => "new",
+    };
+    format!(
+      r#"{{
+  "stream":"trade_updates","data":{{
+    "event":"{event}","execution_id":"11111111-2222-3333-4444-555555555555","order":{{
+      "asset_class":"us_equity","asset_id":"11111111-2222-3333-4444-555555555555",
+      "canceled_at":null,"client_order_id":"11111111-2222-3333-4444-555555555555",
+      "created_at":"2021-12-09T19:48:46.176628398Z","expired_at":null,
+      "extended_hours":false,"failed_at":null,"filled_at":"2021-12-09T19:48:47Z",
+      "filled_avg_price":"179.08","filled_qty":"{filled_qty}","hwm":null,
+      "id":"11111111-2222-3333-4444-555555555555","legs":null,"limit_price":"180",
+      "notional":null,"order_class":"simple","order_type":"limit","qty":"10",
+      "replaced_at":null,"replaced_by":null,"replaces":null,"side":"buy",
+      "status":"{status}","stop_price":null,"submitted_at":"2021-12-09T19:48:46.175261379Z",
+      "symbol":"AAPL","time_in_force":"day","trail_percent":null,"trail_price":null,
+      "type":"limit","updated_at":"2021-12-09T19:48:47Z"
+    }},"timestamp":"2021-12-09T19:48:47Z",{fields}
+  }}
+}}"#
+    )
+  }
+
+  /// Check that the executions of an order can be reconstructed from
+  /// its partial fills.
+  #[test]
+  fn summarize_executions() {
+    let updates = [
+      fill_update("new", "0", r#""qty":null"#),
+      fill_update("partial_fill", "2", r#""price":"100","qty":"2""#),
+      fill_update("partial_fill", "5", r#""price":"101","qty":"3""#),
+      fill_update("partial_fill", "10", r#""price":"102","qty":"5""#),
+    ]
+    .iter()
+    .map(|json| match json_from_str::<OrderMessage>(json).unwrap() {
+      OrderMessage::OrderUpdate(update) => update,
+      message => panic!("Decoded unexpected message variant: {message:?}"),
+    })
+    .collect::<Vec<_>>();
+
+    let id = updates[0].order.id;
+    let summary = ExecutionSummary::from_updates(id, &updates);
+    assert_eq!(summary.executions.len(), 3);
+    assert_eq!(summary.executions[1].price, Num::from(101));
+    assert_eq!(summary.executions[1].quantity, Num::from(3));
+    assert_eq!(summary.quantity, Num::from(10));
+    assert_eq!(summary.average_price, Some(Num::new(1013, 10)));
+
+    let other = order::Id(Uuid::nil());
+    let summary = ExecutionSummary::from_updates(other, &updates);
+    assert_eq!(summary.executions, Vec::new());
+    assert_eq!(summary.quantity, Num::from(0));
+    assert_eq!(summary.average_price, None);
+  }
+
+  /// Check that we can decode a fill order update, including the fill
+  /// details.
+  #[test]
+  fn decode_fill_update() {
+    let json = fill_update(
+      "fill",
+      "10",
+      r#""price":"179.08","qty":"4","position_qty":"10""#,
+    );
+    let message = json_from_str::<OrderMessage>(&json).unwrap();
+    match message {
+      OrderMessage::OrderUpdate(update) => {
+        assert_eq!(update.event, OrderEvent::Filled);
+        assert_eq!(
+          update.execution_id,
+          Some(Uuid::parse_str("11111111-2222-3333-4444-555555555555").unwrap())
+        );
+        assert_eq!(update.price, Some(Num::new(17908, 100)));
+        assert_eq!(update.qty, Some(Num::from(4)));
+        assert_eq!(update.position_qty, Some(Num::from(10)));
       },
       _ => panic!("Decoded unexpected message variant: {message:?}"),
     }
   }
 
+  /// Check that we can decode a partial fill order update, including
+  /// the fill details.
+  #[test]
+  fn decode_partial_fill_update() {
+    let json = fill_update(
+      "partial_fill",
+      "6",
+      r#""price":"179.1","qty":"6","position_qty":"-4""#,
+    );
+    let message = json_from_str::<OrderMessage>(&json).unwrap();
+    match message {
+      OrderMessage::OrderUpdate(update) => {
+        assert_eq!(update.event, OrderEvent::PartialFill);
+        assert_eq!(
+          update.timestamp,
+          Some(DateTime::from_str("2021-12-09T19:48:47Z").unwrap())
+        );
+        assert_eq!(update.price, Some(Num::new(1791, 10)));
+        assert_eq!(update.qty, Some(Num::from(6)));
+        assert_eq!(update.position_qty, Some(Num::from(-4)));
+        assert_eq!(update.order.filled_quantity, Num::from(6));
+      },
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    }
+  }
+
+  /// Check that an unknown event is decoded into the fallback variant.
+  #[test]
+  fn decode_unknown_event() {
+    let json = fill_update("some_new_event", "0", r#""qty":null"#);
+    let message = json_from_str::<OrderMessage>(&json).unwrap();
+    match message {
+      OrderMessage::OrderUpdate(update) => assert_eq!(update.event, OrderEvent::Unknown),
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    }
+  }
+
+  /// A captured account update frame.
+  const ACCOUNT_UPDATE: &str = r#"{
+  "stream":"account_updates","data":{
+    "id":"ef505a9a-2f3c-4b8a-be95-6b6f185f8a03","created_at":"2018-02-26T19:22:31Z",
+    "updated_at":"2018-02-27T18:16:24Z","deleted_at":null,"status":"ACTIVE",
+    "currency":"USD","cash":"1241.54","cash_withdrawable":"523.71"
+  }
+}"#;
+
+  /// Check that an account update frame is decoded and routed to the
+  /// account variant of [`Update`].
+  #[test]
+  fn decode_account_update() {
+    let message = json_from_str::<UpdateMessage>(ACCOUNT_UPDATE).unwrap();
+    let parsed = ParsedUpdate::Ok(Ok(message));
+    let update = match subscribe::Message::classify(parsed) {
+      subscribe::Classification::UserMessage(Ok(Ok(update))) => update,
+      _ => panic!("account update was not classified as user message"),
+    };
+
+    match update {
+      Update::Account(update) => {
+        assert_eq!(
+          update.id,
+          account::Id(Uuid::parse_str("ef505a9a-2f3c-4b8a-be95-6b6f185f8a03").unwrap())
+        );
+        assert_eq!(update.status, account::Status::Active);
+        assert_eq!(update.currency, "USD");
+        assert_eq!(update.cash, Num::new(124154, 100));
+        assert_eq!(update.cash_withdrawable, Num::new(52371, 100));
+        assert_eq!(
+          update.created_at,
+          DateTime::<Utc>::from_str("2018-02-26T19:22:31Z").unwrap()
+        );
+        assert_eq!(
+          update.updated_at,
+          Some(DateTime::from_str("2018-02-27T18:16:24Z").unwrap())
+        );
+        assert_eq!(update.deleted_at, None);
+      },
+      _ => panic!("decoded unexpected update variant: {update:?}"),
+    }
+  }
+
+  /// Check that an order update frame is routed to the order variant
+  /// of [`Update`].
+  #[test]
+  fn decode_order_update_as_update() {
+    let json = fill_update("fill", "10", r#""qty":"10""#);
+    let message = json_from_str::<UpdateMessage>(&json).unwrap();
+    let parsed = ParsedUpdate::Ok(Ok(message));
+    match subscribe::Message::classify(parsed) {
+      subscribe::Classification::UserMessage(Ok(Ok(Update::Order(update)))) => {
+        assert_eq!(update.event, OrderEvent::Filled)
+      },
+      _ => panic!("order update was not classified as such"),
+    }
+  }
+
   /// Verify that we can decode a authentication control message.
   #[test]
   fn decode_authentication() {
@@ -757,6 +1469,172 @@ mod tests {
       .unwrap();
   }
 
+  /// Check that a consumer attaching to a [`ReplayBuffer`] late
+  /// receives the most recent updates.
+  #[test(tokio::test)]
+  async fn replay_recent_updates() {
+    let (sender, receiver) = channel();
+
+    let test = |mut stream: WebSocketStream| async move {
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(STREAM_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(STREAM_RESP)))
+        .await?;
+
+      let () = receiver.await.unwrap();
+
+      for event in ["new", "partial_fill", "fill"] {
+        let update = fill_update(event, "0", r#""qty":"1""#);
+        stream.send(Message::Text(update.into())).await?;
+      }
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    };
+
+    let (stream, _subscription) = mock_stream::<OrderUpdates, _, _>(test).await.unwrap();
+    let () = sender.send(()).unwrap();
+
+    let buffer = ReplayBuffer::new(2);
+    let early = buffer.attach();
+
+    let count = buffer
+      .record(stream)
+      .map_err(Error::from)
+      .try_fold(0, |count, _| ready(Ok(count + 1)))
+      .await
+      .unwrap();
+    assert_eq!(count, 3);
+
+    let late = buffer.attach();
+    // Drop the buffer, and with it all senders, so that the receivers
+    // terminate.
+    drop(buffer);
+
+    let events = |updates: Vec<OrderUpdate>| {
+      updates
+        .into_iter()
+        .map(|update| update.event)
+        .collect::<Vec<_>>()
+    };
+
+    let early = early.collect::<Vec<_>>().await;
+    assert_eq!(
+      events(early),
+      vec![OrderEvent::New, OrderEvent::PartialFill, OrderEvent::Filled]
+    );
+
+    let late = late.collect::<Vec<_>>().await;
+    assert_eq!(
+      events(late),
+      vec![OrderEvent::PartialFill, OrderEvent::Filled]
+    );
+  }
+
+  /// Check that we can stream order and account updates combined.
+  #[test(tokio::test)]
+  async fn stream_updates() {
+    const UPDATES_REQ: &str =
+      r#"{"action":"listen","data":{"streams":["trade_updates","account_updates"]}}"#;
+    const UPDATES_RESP: &str =
+      r#"{"stream":"listening","data":{"streams":["trade_updates","account_updates"]}}"#;
+
+    let (sender, receiver) = channel();
+
+    let test = |mut stream: WebSocketStream| async move {
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(UPDATES_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(UPDATES_RESP)))
+        .await?;
+
+      let () = receiver.await.unwrap();
+
+      let update = fill_update("new", "0", r#""qty":"0""#);
+      stream.send(Message::Text(update.into())).await?;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(ACCOUNT_UPDATE)))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    };
+
+    let (stream, _subscription) = mock_stream::<Updates, _, _>(test).await.unwrap();
+    let () = sender.send(()).unwrap();
+
+    let updates = stream
+      .map_err(Error::from)
+      .and_then(|result| ready(result.map_err(Error::from)))
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    assert_eq!(updates.len(), 2);
+    assert!(matches!(&updates[0], Update::Order(update) if update.event == OrderEvent::New));
+    assert!(matches!(&updates[1], Update::Account(update) if update.currency == "USD"));
+  }
+
+  /// Check that waiting for an order fill resolves with the order as
+  /// reported by the `fill` event on the trade updates stream.
+  #[test(tokio::test)]
+  async fn wait_for_order_fill() {
+    let test = |mut stream: WebSocketStream| async move {
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(STREAM_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(STREAM_RESP)))
+        .await?;
+
+      for (event, filled_qty) in [("partial_fill", "4"), ("fill", "10")] {
+        let update = fill_update(event, filled_qty, r#""qty":"4""#);
+        stream.send(Message::Text(update.into())).await?;
+      }
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    };
+
+    let (stream, _subscription) = mock_stream::<OrderUpdates, _, _>(test).await.unwrap();
+
+    let json = fill_update("new", "0", r#""qty":"0""#);
+    let order = match json_from_str::<OrderMessage>(&json).unwrap() {
+      OrderMessage::OrderUpdate(update) => update.order,
+      message => panic!("Decoded unexpected message variant: {message:?}"),
+    };
+    assert_eq!(order.status, order::Status::New);
+
+    let id = order.id;
+    let filled = await_fill(id, stream, ok(order)).await.unwrap();
+    assert_eq!(filled.id, id);
+    assert_eq!(filled.status, order::Status::Filled);
+    assert_eq!(filled.filled_quantity, Num::from(10));
+  }
+
   /// Test the end-to-end workflow of streaming an order update for a
   /// newly created order.
   #[test(tokio::test)]
@@ -14,6 +14,7 @@ use serde::Serialize;
 
 use serde_json::from_slice as from_json;
 use serde_json::to_vec as to_json;
+use serde_urlencoded::to_string as to_query;
 
 use uuid::Uuid;
 
@@ -75,6 +76,7 @@ pub struct CreateReq {
   pub symbols: Vec<String>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
+  #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
@@ -116,6 +118,31 @@ pub type UpdateReq = CreateReq;
 pub type UpdateReqInit = CreateReqInit;
 
 
+/// The body of a request to add an asset to a watchlist.
+#[derive(Debug, Serialize)]
+struct AddAssetBody<'s> {
+  /// The symbol of the asset to add.
+  #[serde(rename = "symbol")]
+  symbol: &'s str,
+}
+
+
+/// The query of a request addressing a watchlist by name.
+#[derive(Debug, Serialize)]
+struct NameQuery<'s> {
+  /// The name of the watchlist.
+  #[serde(rename = "name")]
+  name: &'s str,
+}
+
+/// Create the query string for a request addressing the watchlist
+/// with the given name.
+fn name_query(name: &str) -> Result<Option<Str>, serde_urlencoded::ser::Error> {
+  let query = to_query(NameQuery { name })?;
+  Ok(Some(query.into()))
+}
+
+
 Endpoint! {
   /// The representation of a POST request to the /v2/watchlists endpoint.
   pub Create(CreateReq),
@@ -161,7 +188,7 @@ Endpoint! {
   ]
 
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/watchlists/{}", input.as_simple()).into()
+    path!("/v2/watchlists/{}", input).into()
   }
 }
 
@@ -184,7 +211,7 @@ Endpoint! {
 
   fn path(input: &Self::Input) -> Str {
     let (id, _) = input;
-    format!("/v2/watchlists/{}", id.as_simple()).into()
+    path!("/v2/watchlists/{}", id).into()
   }
 
   #[inline]
@@ -201,6 +228,68 @@ Endpoint! {
 }
 
 
+Endpoint! {
+  /// The representation of a POST request to the
+  /// /v2/watchlists/{watchlist-id} endpoint, adding the asset with the
+  /// given symbol to the watchlist.
+  pub AddAsset((Id, String)),
+  Ok => Watchlist, [
+    /// The asset was added to the watchlist successfully.
+    /* 200 */ OK,
+  ],
+  Err => AddAssetError, [
+    /// No watchlist was found with the given ID.
+    /* 404 */ NOT_FOUND => NotFound,
+    /// The symbol is not valid or otherwise could not be added.
+    /* 422 */ UNPROCESSABLE_ENTITY => InvalidInput,
+  ]
+
+  fn path(input: &Self::Input) -> Str {
+    let (id, _) = input;
+    path!("/v2/watchlists/{}", id).into()
+  }
+
+  #[inline]
+  fn method() -> Method {
+    Method::POST
+  }
+
+  fn body(input: &Self::Input) -> Result<Option<Bytes>, Self::ConversionError> {
+    let (_, symbol) = input;
+    let json = to_json(&AddAssetBody { symbol })?;
+    let bytes = Bytes::from(json);
+    Ok(Some(bytes))
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a DELETE request to the
+  /// /v2/watchlists/{watchlist-id}/{symbol} endpoint, removing the
+  /// asset with the given symbol from the watchlist.
+  pub RemoveAsset((Id, String)),
+  Ok => Watchlist, [
+    /// The asset was removed from the watchlist successfully.
+    /* 200 */ OK,
+  ],
+  Err => RemoveAssetError, [
+    /// No watchlist was found with the given ID or the symbol is not
+    /// part of it.
+    /* 404 */ NOT_FOUND => NotFound,
+  ]
+
+  fn path(input: &Self::Input) -> Str {
+    let (id, symbol) = input;
+    path!("/v2/watchlists/{}/{}", id, symbol).into()
+  }
+
+  #[inline]
+  fn method() -> Method {
+    Method::DELETE
+  }
+}
+
+
 EndpointNoParse! {
   /// The representation of a DELETE request to the
   /// /v2/watchlists/{watchlist-id} endpoint.
@@ -220,7 +309,115 @@ EndpointNoParse! {
   }
 
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/watchlists/{}", input.as_simple()).into()
+    path!("/v2/watchlists/{}", input).into()
+  }
+
+  #[inline]
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    debug_assert_eq!(body, b"");
+    Ok(())
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/watchlists:by_name endpoint, retrieving the watchlist with
+  /// the given name.
+  pub GetByName(String),
+  Ok => Watchlist, [
+    /// The watchlist object with the given name was retrieved
+    /// successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetByNameError, [
+    /// No watchlist was found with the given name.
+    /* 404 */ NOT_FOUND => NotFound,
+  ]
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/watchlists:by_name".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    name_query(input).map_err(Into::into)
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a PUT request to the
+  /// /v2/watchlists:by_name endpoint, updating the watchlist with the
+  /// given name.
+  pub UpdateByName((String, UpdateReq)),
+  Ok => Watchlist, [
+    /// The watchlist object with the given name was updated
+    /// successfully.
+    /* 200 */ OK,
+  ],
+  Err => UpdateByNameError, [
+    /// No watchlist was found with the given name.
+    /* 404 */ NOT_FOUND => NotFound,
+    /// The watchlist name was not unique or other parts of the input
+    /// are not valid.
+    /* 422 */ UNPROCESSABLE_ENTITY => InvalidInput,
+  ]
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/watchlists:by_name".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let (name, _) = input;
+    name_query(name).map_err(Into::into)
+  }
+
+  #[inline]
+  fn method() -> Method {
+    Method::PUT
+  }
+
+  fn body(input: &Self::Input) -> Result<Option<Bytes>, Self::ConversionError> {
+    let (_, request) = input;
+    let json = to_json(request)?;
+    let bytes = Bytes::from(json);
+    Ok(Some(bytes))
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a DELETE request to the
+  /// /v2/watchlists:by_name endpoint, deleting the watchlist with the
+  /// given name.
+  pub DeleteByName(String),
+  Ok => (), [
+    /// The watchlist was deleted successfully.
+    /* 204 */ NO_CONTENT,
+  ],
+  Err => DeleteByNameError, [
+    /// No watchlist was found with the given name.
+    /* 404 */ NOT_FOUND => NotFound,
+  ]
+
+  #[inline]
+  fn method() -> Method {
+    Method::DELETE
+  }
+
+  #[inline]
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/watchlists:by_name".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    name_query(input).map_err(Into::into)
   }
 
   #[inline]
@@ -239,14 +436,74 @@ EndpointNoParse! {
 mod tests {
   use super::*;
 
+  use http_endpoint::Endpoint;
+
   use crate::api::v2::account;
   use crate::api_info::ApiInfo;
+  use crate::test_util::serve_once_with_status;
   use crate::Client;
   use crate::RequestError;
 
   use test_log::test;
 
 
+  /// Check that we serialize the body of an "add asset" request
+  /// correctly.
+  #[test]
+  fn serialize_add_asset_body() {
+    let id = Id(Uuid::parse_str("fb306e55-16d3-4118-8c3d-c1615fcd4c03").unwrap());
+    let body = AddAsset::body(&(id, "AAPL".to_string())).unwrap().unwrap();
+    assert_eq!(&body[..], br#"{"symbol":"AAPL"}"#);
+  }
+
+  /// Check that the path of a "remove asset" request contains the
+  /// symbol to remove.
+  #[test]
+  fn remove_asset_path() {
+    let id = Id(Uuid::parse_str("fb306e55-16d3-4118-8c3d-c1615fcd4c03").unwrap());
+    let path = RemoveAsset::path(&(id, "AAPL".to_string()));
+    assert_eq!(path, "/v2/watchlists/fb306e5516d341188c3dc1615fcd4c03/AAPL");
+  }
+
+  /// Check that the name of a watchlist is properly encoded into the
+  /// query of requests addressing it by name.
+  #[test]
+  fn by_name_query() {
+    let name = "my list".to_string();
+    assert_eq!(GetByName::path(&name), "/v2/watchlists:by_name");
+    assert_eq!(GetByName::query(&name).unwrap().unwrap(), "name=my+list");
+    assert_eq!(DeleteByName::query(&name).unwrap().unwrap(), "name=my+list");
+
+    let name = "tech & co/50%".to_string();
+    let request = UpdateReqInit::default().init("new name");
+    let input = (name, request);
+    assert_eq!(
+      UpdateByName::query(&input).unwrap().unwrap(),
+      "name=tech+%26+co%2F50%25"
+    );
+    let body = UpdateByName::body(&input).unwrap().unwrap();
+    assert_eq!(&body[..], br#"{"name":"new name","symbols":[]}"#);
+  }
+
+  /// Make sure that we report the appropriate error when a watchlist
+  /// with a given name does not exist.
+  #[test(tokio::test)]
+  async fn get_by_name_not_found() {
+    let body = r#"{"code":40410000,"message":"nope"}"#;
+    let url = serve_once_with_status("404 Not Found", body).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let err = client
+      .issue::<GetByName>(&"does not exist".to_string())
+      .await
+      .unwrap_err();
+    match err {
+      RequestError::Endpoint(GetByNameError::NotFound(_)) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
   /// Check that we can create, retrieve, and delete a watchlist.
   #[test(tokio::test)]
   async fn create_get_delete() {
@@ -278,6 +535,45 @@ mod tests {
     assert_eq!(watchlist.account_id, account.id);
   }
 
+  /// Check that we can retrieve, update, and delete a watchlist by a
+  /// name containing spaces and special characters.
+  #[test(tokio::test)]
+  async fn get_update_delete_by_name() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let name = format!("my list & {}", Uuid::new_v4());
+    let request = CreateReqInit {
+      symbols: vec!["AAPL".to_string()],
+      ..Default::default()
+    }
+    .init(&name);
+
+    let created = client.issue::<Create>(&request).await.unwrap();
+    let retrieved = client.issue::<GetByName>(&name).await;
+    let request = UpdateReqInit {
+      symbols: vec!["SPY".to_string()],
+      ..Default::default()
+    }
+    .init(&name);
+    let updated = client.issue::<UpdateByName>(&(name.clone(), request)).await;
+    let deleted = client.issue::<DeleteByName>(&name).await;
+    if deleted.is_err() {
+      let () = client.issue::<Delete>(&created.id).await.unwrap();
+    }
+
+    assert_eq!(retrieved.unwrap().id, created.id);
+    let updated = updated.unwrap();
+    assert_eq!(updated.id, created.id);
+    assert_eq!(updated.assets[0].symbol, "SPY");
+    let () = deleted.unwrap();
+
+    let err = client.issue::<Get>(&created.id).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(GetError::NotFound(_)) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
   /// Check that we get back the expected error when attempting to
   /// create a watchlist with a name that is already taken.
   #[test(tokio::test)]
@@ -364,6 +660,56 @@ mod tests {
     assert_eq!(symbols, vec!["AMZN", "SPY"]);
   }
 
+  /// Check that we can add an asset to and remove one from a
+  /// watchlist.
+  #[test(tokio::test)]
+  async fn add_remove_asset() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let request = CreateReqInit {
+      symbols: vec!["AAPL".to_string()],
+      ..Default::default()
+    }
+    .init(Uuid::new_v4().to_string());
+
+    let created = client.issue::<Create>(&request).await.unwrap();
+    let added = client
+      .issue::<AddAsset>(&(created.id, "SPY".to_string()))
+      .await;
+    let removed = client
+      .issue::<RemoveAsset>(&(created.id, "AAPL".to_string()))
+      .await;
+    let () = client.issue::<Delete>(&created.id).await.unwrap();
+
+    let symbols = |watchlist: Watchlist| {
+      watchlist
+        .assets
+        .into_iter()
+        .map(|asset| asset.symbol)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(symbols(added.unwrap()), vec!["AAPL", "SPY"]);
+    assert_eq!(symbols(removed.unwrap()), vec!["SPY"]);
+  }
+
+  /// Verify that we report the appropriate error when attempting to
+  /// remove an asset from a watchlist that does not exist.
+  #[test(tokio::test)]
+  async fn remove_asset_non_existent() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let id = Id(Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap());
+    let err = client
+      .issue::<RemoveAsset>(&(id, "AAPL".to_string()))
+      .await
+      .unwrap_err();
+    match err {
+      RequestError::Endpoint(RemoveAssetError::NotFound(_)) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
   /// Verify that we report the appropriate error when attempting to
   /// delete a watchlist that does not exist.
   #[test(tokio::test)]
@@ -1,12 +1,18 @@
 // Copyright (C) 2019-2023 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::collections::HashMap;
 use std::env::var_os;
 use std::ffi::OsString;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
 
 use url::Url;
 
 use crate::api::API_BASE_URL;
+use crate::api::DEFAULT_USER_AGENT;
+use crate::api::LIVE_API_BASE_URL;
 use crate::data::DATA_BASE_URL;
 use crate::data::DATA_STREAM_BASE_URL;
 use crate::Error;
@@ -15,6 +21,10 @@ use crate::Error;
 const ENV_API_BASE_URL: &str = "APCA_API_BASE_URL";
 /// The URL of the websocket stream portion of the Trading API to use.
 const ENV_API_STREAM_URL: &str = "APCA_API_STREAM_URL";
+/// The base URL of the Data API to use.
+const ENV_DATA_BASE_URL: &str = "APCA_API_DATA_URL";
+/// The trading environment to use, if no explicit base URL is set.
+const ENV_ENVIRONMENT: &str = "APCA_ENVIRONMENT";
 /// The environment variable representing the key ID.
 const ENV_KEY_ID: &str = "APCA_API_KEY_ID";
 /// The environment variable representing the secret key.
@@ -23,6 +33,7 @@ const ENV_SECRET: &str = "APCA_API_SECRET_KEY";
 
 /// Convert a Trading API base URL into the corresponding one for
 /// websocket streaming.
+#[allow(clippy::result_large_err)]
 fn make_api_stream_url(base_url: Url) -> Result<Url, Error> {
   let mut url = base_url;
   url.set_scheme("wss").map_err(|()| {
@@ -33,6 +44,139 @@ fn make_api_stream_url(base_url: Url) -> Result<Url, Error> {
 }
 
 
+/// Retrieve the key-value pairs of the section named `profile` from a
+/// configuration in a simple INI (or TOML) format.
+///
+/// Lines starting with `#` or `;` are treated as comments. Values may
+/// optionally be enclosed in double quotes.
+#[allow(clippy::result_large_err)]
+fn parse_profile(config: &str, profile: &str) -> Result<HashMap<String, String>, Error> {
+  let mut values = None;
+  let mut in_profile = false;
+
+  for (idx, line) in config.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+      continue
+    }
+
+    if let Some(section) = line.strip_prefix('[') {
+      let section = section.strip_suffix(']').ok_or_else(|| {
+        Error::Str(format!("line {}: malformed section header: {line}", idx + 1).into())
+      })?;
+      in_profile = section.trim() == profile;
+      if in_profile {
+        let _values = values.get_or_insert_with(HashMap::new);
+      }
+      continue
+    }
+
+    let (key, value) = line.split_once('=').ok_or_else(|| {
+      Error::Str(format!("line {}: expected key-value pair: {line}", idx + 1).into())
+    })?;
+
+    if let (true, Some(values)) = (in_profile, values.as_mut()) {
+      let value = value.trim();
+      let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+      let _prev = values.insert(key.trim().to_string(), value.to_string());
+    }
+  }
+
+  values.ok_or_else(|| Error::Str(format!("profile `{profile}` not found").into()))
+}
+
+
+/// Determine the Trading API base URL to use, along with the
+/// environment it corresponds to, from the values of the
+/// `APCA_API_BASE_URL` and `APCA_ENVIRONMENT` variables.
+///
+/// An explicitly provided base URL takes precedence over the
+/// environment.
+#[allow(clippy::result_large_err)]
+fn api_base_url_from_env(
+  base_url: Option<OsString>,
+  environment: Option<OsString>,
+) -> Result<(Url, Environment), Error> {
+  if let Some(base_url) = base_url {
+    let base_url = base_url.into_string().map_err(|_| {
+      Error::Str(format!("{ENV_API_BASE_URL} environment variable is not a valid string").into())
+    })?;
+    let base_url = Url::parse(&base_url)?;
+    let environment = Environment::from_url(&base_url);
+    return Ok((base_url, environment))
+  }
+
+  let environment = environment
+    .map(|environment| {
+      environment
+        .into_string()
+        .map_err(|_| {
+          Error::Str(format!("{ENV_ENVIRONMENT} environment variable is not a valid string").into())
+        })
+        .and_then(|environment| Environment::from_str(&environment))
+    })
+    .transpose()?
+    .unwrap_or(Environment::Paper);
+
+  // SANITY: Only the paper and live environments can be parsed and
+  //         both have a statically known base URL.
+  let base_url = Url::parse(environment.api_base_url().unwrap()).unwrap();
+  Ok((base_url, environment))
+}
+
+
+/// The trading environment an [`ApiInfo`] object is targeting.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Environment {
+  /// The paper trading environment.
+  Paper,
+  /// The live trading environment, operating on real money.
+  Live,
+  /// A custom base URL not corresponding to a known environment, e.g.,
+  /// that of a mock server.
+  Custom,
+}
+
+impl Environment {
+  /// Infer the environment from a Trading API base URL.
+  fn from_url(url: &Url) -> Self {
+    match url.host_str() {
+      Some("paper-api.alpaca.markets") => Self::Paper,
+      Some("api.alpaca.markets") => Self::Live,
+      _ => Self::Custom,
+    }
+  }
+
+  /// Retrieve the Trading API base URL of the environment, if it is a
+  /// known one.
+  fn api_base_url(self) -> Option<&'static str> {
+    match self {
+      Self::Paper => Some(API_BASE_URL),
+      Self::Live => Some(LIVE_API_BASE_URL),
+      Self::Custom => None,
+    }
+  }
+}
+
+impl FromStr for Environment {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "paper" => Ok(Self::Paper),
+      "live" => Ok(Self::Live),
+      _ => Err(Error::Str(
+        format!("{ENV_ENVIRONMENT} environment variable has unsupported value: {s}").into(),
+      )),
+    }
+  }
+}
+
+
 /// An object encapsulating the information used for working with the
 /// Alpaca API.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -50,34 +194,79 @@ pub struct ApiInfo {
   pub key_id: String,
   /// The secret to use for authentication.
   pub secret: String,
+  /// The trading environment targeted.
+  pub environment: Environment,
+  /// The user agent to identify as, to HTTP and websocket servers
+  /// alike.
+  pub(crate) user_agent: String,
 }
 
 impl ApiInfo {
+  /// Create an `ApiInfo` for paper trading using the given
+  /// credentials.
+  pub fn paper(key_id: impl ToString, secret: impl ToString) -> Self {
+    // SANITY: The paper trading base URL is known to be valid.
+    Self::from_parts(API_BASE_URL, key_id, secret).unwrap()
+  }
+
+  /// Create an `ApiInfo` for live trading using the given
+  /// credentials.
+  pub fn live(key_id: impl ToString, secret: impl ToString) -> Self {
+    // SANITY: The live trading base URL is known to be valid.
+    Self::from_parts(LIVE_API_BASE_URL, key_id, secret).unwrap()
+  }
+
   /// Create an `ApiInfo` from the required data. Note that using this
   /// constructor the websocket URL will be inferred based on the base
-  /// URL provided.
+  /// URL provided, as will the [`Environment`].
   ///
   /// # Errors
   /// - [`Error::Url`](crate::Error::Url) If `api_base_url` cannot be parsed
   ///   into a [`url::Url`](url::Url).
+  #[allow(clippy::result_large_err)]
   pub fn from_parts(
     api_base_url: impl AsRef<str>,
     key_id: impl ToString,
     secret: impl ToString,
+  ) -> Result<Self, Error> {
+    Self::from_parts_with_data(api_base_url, DATA_BASE_URL, key_id, secret)
+  }
+
+  /// Create an `ApiInfo` from the required data, using distinct base
+  /// URLs for the Trading API and the Data API.
+  ///
+  /// Market data requests are directed at `data_base_url`, while all
+  /// other requests target `api_base_url`. Everything else is handled
+  /// as per [`ApiInfo::from_parts`].
+  ///
+  /// # Errors
+  /// - [`Error::Url`](crate::Error::Url) If `api_base_url` or
+  ///   `data_base_url` cannot be parsed into a
+  ///   [`url::Url`](url::Url).
+  #[allow(clippy::result_large_err)]
+  pub fn from_parts_with_data(
+    api_base_url: impl AsRef<str>,
+    data_base_url: impl AsRef<str>,
+    key_id: impl ToString,
+    secret: impl ToString,
   ) -> Result<Self, Error> {
     let api_base_url = Url::parse(api_base_url.as_ref())?;
     let api_stream_url = make_api_stream_url(api_base_url.clone())?;
+    let data_base_url = Url::parse(data_base_url.as_ref())?;
+    let environment = Environment::from_url(&api_base_url);
 
     Ok(Self {
       api_base_url,
       api_stream_url,
+      data_base_url,
       // We basically only work with statically defined URL parts here
       // which we know can be parsed successfully, so unwrapping is
       // fine.
-      data_base_url: Url::parse(DATA_BASE_URL).unwrap(),
       data_stream_base_url: Url::parse(DATA_STREAM_BASE_URL).unwrap(),
       key_id: key_id.to_string(),
       secret: secret.to_string(),
+      environment,
+      user_agent: DEFAULT_USER_AGENT.to_string(),
     })
   }
 
@@ -87,26 +276,26 @@ impl ApiInfo {
   /// environment and performs some preliminary validation on it. The
   /// following information is used:
   /// - the Alpaca Trading API base URL is retrieved from the
-  ///   `APCA_API_BASE_URL` variable
+  ///   `APCA_API_BASE_URL` variable; if it is not set, the URL of the
+  ///   environment (`paper` or `live`) specified by the
+  ///   `APCA_ENVIRONMENT` variable is used, defaulting to paper trading
   /// - the Alpaca Trading API stream URL is retrieved from the
   ///   `APCA_API_STREAM_URL` variable
+  /// - the Alpaca Data API base URL is retrieved from the
+  ///   `APCA_API_DATA_URL` variable; if it is not set, the default
+  ///   Data API URL is used
   /// - the Alpaca account key ID is retrieved from the
   ///   `APCA_API_KEY_ID` variable
   /// - the Alpaca account secret is retrieved from the
   ///   `APCA_API_SECRET_KEY` variable
   ///
   /// # Notes
-  /// - Neither of the two data APIs can be configured via the
-  ///   environment currently; defaults will be used
-  #[allow(unused_qualifications)]
+  /// - The data streaming API cannot be configured via the environment
+  ///   currently; its default will be used
+  #[allow(clippy::result_large_err, unused_qualifications)]
   pub fn from_env() -> Result<Self, Error> {
-    let api_base_url = var_os(ENV_API_BASE_URL)
-      .unwrap_or_else(|| OsString::from(API_BASE_URL))
-      .into_string()
-      .map_err(|_| {
-        Error::Str(format!("{ENV_API_BASE_URL} environment variable is not a valid string").into())
-      })?;
-    let api_base_url = Url::parse(&api_base_url)?;
+    let (api_base_url, environment) =
+      api_base_url_from_env(var_os(ENV_API_BASE_URL), var_os(ENV_ENVIRONMENT))?;
 
     let api_stream_url = var_os(ENV_API_STREAM_URL)
       .map(Result::<_, Error>::Ok)
@@ -124,6 +313,14 @@ impl ApiInfo {
       })?;
     let api_stream_url = Url::parse(&api_stream_url)?;
 
+    let data_base_url = var_os(ENV_DATA_BASE_URL)
+      .unwrap_or_else(|| OsString::from(DATA_BASE_URL))
+      .into_string()
+      .map_err(|_| {
+        Error::Str(format!("{ENV_DATA_BASE_URL} environment variable is not a valid string").into())
+      })?;
+    let data_base_url = Url::parse(&data_base_url)?;
+
     let key_id = var_os(ENV_KEY_ID)
       .ok_or_else(|| Error::Str(format!("{ENV_KEY_ID} environment variable not found").into()))?
       .into_string()
@@ -141,15 +338,78 @@ impl ApiInfo {
     Ok(Self {
       api_base_url,
       api_stream_url,
+      data_base_url,
       // We basically only work with statically defined URL parts here
       // which we know can be parsed successfully, so unwrapping is
       // fine.
-      data_base_url: Url::parse(DATA_BASE_URL).unwrap(),
       data_stream_base_url: Url::parse(DATA_STREAM_BASE_URL).unwrap(),
       key_id,
       secret,
+      environment,
+      user_agent: DEFAULT_USER_AGENT.to_string(),
     })
   }
+
+  /// Create an `ApiInfo` object from a profile in a credentials file.
+  ///
+  /// The file is expected to be in a simple INI format, with one
+  /// section per profile, each providing a `key_id`, a `secret`, and
+  /// optionally a `base_url` for the Trading API:
+  /// ```ini
+  /// [paper]
+  /// key_id = "PKXXXXXXXXXXXXXXXXXX"
+  /// secret = "YYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYY"
+  ///
+  /// [live]
+  /// key_id = "AKXXXXXXXXXXXXXXXXXX"
+  /// secret = "ZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZ"
+  /// ```
+  /// If no base URL is provided, that of the environment named like
+  /// the profile (`paper` or `live`) is used, defaulting to paper
+  /// trading for other profile names. The websocket URL is inferred
+  /// from the base URL, as per [`ApiInfo::from_parts`].
+  ///
+  /// # Errors
+  /// - [`Error::Str`](crate::Error::Str) If the file cannot be read,
+  ///   is malformed, or lacks the profile or any of its required
+  ///   values
+  /// - [`Error::Url`](crate::Error::Url) If the base URL cannot be
+  ///   parsed
+  #[allow(clippy::result_large_err)]
+  pub fn from_file(path: impl AsRef<Path>, profile: &str) -> Result<Self, Error> {
+    let path = path.as_ref();
+    let config = read_to_string(path).map_err(|err| {
+      Error::Str(format!("failed to read credentials file {}: {err}", path.display()).into())
+    })?;
+
+    Self::from_config(&config, profile).map_err(|err| match err {
+      Error::Str(err) => Error::Str(format!("{}: {err}", path.display()).into()),
+      err => err,
+    })
+  }
+
+  /// Create an `ApiInfo` object from a profile in the provided
+  /// credentials file contents.
+  #[allow(clippy::result_large_err)]
+  fn from_config(config: &str, profile: &str) -> Result<Self, Error> {
+    let mut values = parse_profile(config, profile)?;
+    let mut take = |key| {
+      values
+        .remove(key)
+        .ok_or_else(|| Error::Str(format!("profile `{profile}` does not provide a `{key}`").into()))
+    };
+
+    let key_id = take("key_id")?;
+    let secret = take("secret")?;
+    let base_url = take("base_url").unwrap_or_else(|_| {
+      let environment = Environment::from_str(profile).unwrap_or(Environment::Paper);
+      // SANITY: Only the paper and live environments can be parsed and
+      //         both have a statically known base URL.
+      environment.api_base_url().unwrap().to_string()
+    });
+
+    Self::from_parts(base_url, key_id, secret)
+  }
 }
 
 
@@ -170,5 +430,131 @@ mod tests {
     assert_eq!(api_info.api_base_url.as_str(), api_base_url);
     assert_eq!(api_info.key_id, key_id);
     assert_eq!(api_info.secret, secret);
+    assert_eq!(api_info.environment, Environment::Paper);
+
+    let api_info = ApiInfo::from_parts("http://127.0.0.1:8080", key_id, secret).unwrap();
+    assert_eq!(api_info.environment, Environment::Custom);
+    assert_eq!(
+      api_info.data_base_url.as_str(),
+      "https://data.alpaca.markets/"
+    );
+
+    let api_info =
+      ApiInfo::from_parts_with_data(api_base_url, "http://127.0.0.1:8081", key_id, secret).unwrap();
+    assert_eq!(api_info.api_base_url.as_str(), api_base_url);
+    assert_eq!(api_info.data_base_url.as_str(), "http://127.0.0.1:8081/");
+    assert_eq!(api_info.environment, Environment::Paper);
+
+    let result = ApiInfo::from_parts_with_data(api_base_url, "invalid", key_id, secret);
+    assert!(result.is_err());
+  }
+
+  /// Check that the paper and live constructors select the expected
+  /// base URLs.
+  #[test]
+  fn paper_live() {
+    let api_info = ApiInfo::paper("key", "secret");
+    assert_eq!(
+      api_info.api_base_url.as_str(),
+      "https://paper-api.alpaca.markets/"
+    );
+    assert_eq!(
+      api_info.api_stream_url.as_str(),
+      "wss://paper-api.alpaca.markets/stream"
+    );
+    assert_eq!(api_info.environment, Environment::Paper);
+
+    let api_info = ApiInfo::live("key", "secret");
+    assert_eq!(
+      api_info.api_base_url.as_str(),
+      "https://api.alpaca.markets/"
+    );
+    assert_eq!(
+      api_info.api_stream_url.as_str(),
+      "wss://api.alpaca.markets/stream"
+    );
+    assert_eq!(api_info.environment, Environment::Live);
+  }
+
+  /// Check that the base URL and environment are resolved correctly
+  /// from environment variables.
+  #[test]
+  fn base_url_env_precedence() {
+    let (url, environment) = api_base_url_from_env(None, None).unwrap();
+    assert_eq!(url.as_str(), "https://paper-api.alpaca.markets/");
+    assert_eq!(environment, Environment::Paper);
+
+    let (url, environment) = api_base_url_from_env(None, Some("live".into())).unwrap();
+    assert_eq!(url.as_str(), "https://api.alpaca.markets/");
+    assert_eq!(environment, Environment::Live);
+
+    // An explicit base URL takes precedence.
+    let base_url = Some("https://paper-api.alpaca.markets".into());
+    let (url, environment) = api_base_url_from_env(base_url, Some("live".into())).unwrap();
+    assert_eq!(url.as_str(), "https://paper-api.alpaca.markets/");
+    assert_eq!(environment, Environment::Paper);
+
+    let base_url = Some("http://localhost:1234".into());
+    let (url, environment) = api_base_url_from_env(base_url, None).unwrap();
+    assert_eq!(url.as_str(), "http://localhost:1234/");
+    assert_eq!(environment, Environment::Custom);
+
+    let result = api_base_url_from_env(None, Some("sandbox".into()));
+    assert!(result.is_err());
+  }
+
+  /// Check that we can select profiles from a credentials file.
+  #[test]
+  fn from_config_profiles() {
+    let config = r#"
+# Credentials for both environments.
+[paper]
+key_id = "PKXXXXXXXXXXXXXXXXXX"
+secret = "YYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYY"
+
+[live]
+key_id = AKXXXXXXXXXXXXXXXXXX
+secret = ZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZ
+
+[mock]
+key_id = "key"
+secret = "secret"
+base_url = "http://127.0.0.1:8080"
+"#;
+
+    let api_info = ApiInfo::from_config(config, "paper").unwrap();
+    assert_eq!(api_info.key_id, "PKXXXXXXXXXXXXXXXXXX");
+    assert_eq!(api_info.secret, "YYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYYY");
+    assert_eq!(api_info, ApiInfo::paper(&api_info.key_id, &api_info.secret));
+
+    let api_info = ApiInfo::from_config(config, "live").unwrap();
+    assert_eq!(api_info.key_id, "AKXXXXXXXXXXXXXXXXXX");
+    assert_eq!(api_info.secret, "ZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    assert_eq!(api_info, ApiInfo::live(&api_info.key_id, &api_info.secret));
+
+    let api_info = ApiInfo::from_config(config, "mock").unwrap();
+    assert_eq!(api_info.api_base_url.as_str(), "http://127.0.0.1:8080/");
+    assert_eq!(api_info.environment, Environment::Custom);
+
+    let err = ApiInfo::from_config(config, "sandbox").unwrap_err();
+    assert_eq!(err.to_string(), "profile `sandbox` not found");
+
+    let err = ApiInfo::from_config("[paper]\nkey_id = key\n", "paper").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "profile `paper` does not provide a `secret`"
+    );
+  }
+
+  /// Check that a missing credentials file is reported properly.
+  #[test]
+  fn from_missing_file() {
+    let err = ApiInfo::from_file("/does/not/exist.ini", "paper").unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .starts_with("failed to read credentials file /does/not/exist.ini"),
+      "{err}"
+    );
   }
 }
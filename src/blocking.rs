@@ -0,0 +1,169 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io::Error as IoError;
+
+use http_endpoint::Endpoint;
+
+use tokio::runtime::Builder;
+use tokio::runtime::Runtime;
+
+use crate::ApiInfo;
+use crate::Client as AsyncClient;
+use crate::Paginated;
+use crate::RequestError;
+
+
+/// A blocking client for interacting with the Alpaca API.
+///
+/// The client wraps the asynchronous [`Client`][crate::Client] along
+/// with a dedicated single threaded runtime, on which each request is
+/// run to completion.
+///
+/// # Notes
+/// - none of the client's methods must be invoked from within an
+///   asynchronous context (such as a future being polled by a `tokio`
+///   runtime); doing so results in a panic
+#[derive(Debug)]
+pub struct Client {
+  /// The wrapped asynchronous client.
+  client: AsyncClient,
+  /// The runtime used for driving requests.
+  runtime: Runtime,
+}
+
+impl Client {
+  /// Instantiate a new `Client` using the given `ApiInfo` object.
+  #[inline]
+  pub fn new(api_info: ApiInfo) -> Result<Self, IoError> {
+    Self::from_async(AsyncClient::new(api_info))
+  }
+
+  /// Create a `Client` wrapping the provided asynchronous one.
+  pub fn from_async(client: AsyncClient) -> Result<Self, IoError> {
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    Ok(Self { client, runtime })
+  }
+
+  /// Issue a request, blocking until it completed.
+  ///
+  /// This is the blocking equivalent of
+  /// [`Client::issue`][crate::Client::issue].
+  #[inline]
+  pub fn issue<R>(&self, input: &R::Input) -> Result<R::Output, RequestError<R::Error>>
+  where
+    R: Endpoint,
+  {
+    self.runtime.block_on(self.client.issue::<R>(input))
+  }
+
+  /// Issue a request to a paginated endpoint, blocking until all pages
+  /// have been retrieved.
+  ///
+  /// This is the blocking equivalent of
+  /// [`Client::issue_all`][crate::Client::issue_all].
+  #[inline]
+  pub fn issue_all<R>(&self, input: &R::Input) -> Result<Vec<R::Item>, RequestError<R::Error>>
+  where
+    R: Paginated,
+    R::Input: Clone,
+  {
+    self.runtime.block_on(self.client.issue_all::<R>(input))
+  }
+
+  /// Retrieve the `ApiInfo` object used by this `Client` instance.
+  #[inline]
+  pub fn api_info(&self) -> &ApiInfo {
+    self.client.api_info()
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use num_decimal::Num;
+
+  use test_log::test;
+
+  use crate::api::v2::account;
+  use crate::test_util::serve_once_blocking;
+
+
+  /// The account object served by our mock server.
+  const ACCOUNT: &str = r#"{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "admin_configurations": {},
+  "user_configurations": null,
+  "account_number": "PALPACA_123",
+  "status": "ACTIVE",
+  "crypto_status": "ACTIVE",
+  "currency": "USD",
+  "buying_power": "0.0",
+  "regt_buying_power": "0.0",
+  "daytrading_buying_power": "0.0",
+  "options_buying_power": "0.0",
+  "effective_buying_power": "0.0",
+  "non_marginable_buying_power": "0.0",
+  "bod_dtbp": "0.0",
+  "cash": "1000.00",
+  "accrued_fees": "0.0",
+  "pending_transfer_in": "0.0",
+  "portfolio_value": "5000.00",
+  "pattern_day_trader": false,
+  "trade_suspended_by_user": false,
+  "trading_blocked": false,
+  "transfers_blocked": false,
+  "account_blocked": false,
+  "created_at": "2018-10-01T13:35:25Z",
+  "shorting_enabled": true,
+  "multiplier": "2",
+  "long_market_value": "7000.00",
+  "short_market_value": "-3000.00",
+  "position_market_value": "4000.00",
+  "equity": "5000.00",
+  "last_equity": "5000.00",
+  "initial_margin": "5000.00",
+  "maintenance_margin": "3000.00",
+  "last_maintenance_margin": "3000.00",
+  "sma": "0.0",
+  "daytrade_count": 0,
+  "balance_asof": "2018-10-01",
+  "crypto_tier": 1,
+  "options_trading_level": 2,
+  "intraday_adjustments": "0.0",
+  "pending_reg_taf_fees": "0.0"
+}"#;
+
+
+  /// Check that we can retrieve the account synchronously.
+  #[test]
+  fn request_account() {
+    let url = serve_once_blocking("200 OK", ACCOUNT);
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info).unwrap();
+
+    let account = client.issue::<account::Get>(&()).unwrap();
+    assert_eq!(account.currency, "USD");
+    assert_eq!(account.cash, Num::from(1000));
+    assert_eq!(account.status, account::Status::Active);
+  }
+
+  /// Check that endpoint errors are reported by the blocking client.
+  #[test]
+  fn request_account_not_permitted() {
+    let url = serve_once_blocking(
+      "403 Forbidden",
+      r#"{"code":40310000,"message":"forbidden"}"#,
+    );
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info).unwrap();
+
+    let err = client.issue::<account::Get>(&()).unwrap_err();
+    match err {
+      RequestError::Endpoint(account::GetError::NotPermitted(..)) => (),
+      err => panic!("received unexpected error: {err:?}"),
+    }
+  }
+}
@@ -6,45 +6,85 @@ use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
 use std::future::Future;
+use std::io::Error as IoError;
+use std::io::ErrorKind;
 use std::str::from_utf8;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
+use chrono::DateTime;
+use chrono::TimeZone as _;
+use chrono::Utc;
+
+use async_trait::async_trait;
+use http::header::ETAG;
+use http::header::IF_NONE_MATCH;
+use http::header::USER_AGENT;
 use http::request::Builder as HttpRequestBuilder;
 use http::HeaderMap;
 use http::HeaderValue;
+use http::Method;
 use http::Request;
 use http::Response;
+use http::StatusCode;
 use http_body_util::BodyExt;
 use http_body_util::Full;
+
 use http_endpoint::Endpoint;
 
+use hyper::body::Body as _;
 use hyper::body::Bytes;
 use hyper::body::Incoming;
-use hyper::Error as HyperError;
 use hyper_tls::HttpsConnector;
 use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::client::legacy::Builder as HttpClientBuilder;
 use hyper_util::client::legacy::Client as HttpClient;
 use hyper_util::rt::TokioExecutor;
 
+use serde_json::to_vec as to_json;
+use serde_json::Value;
+
+use tokio::time::timeout;
+
 use tracing::debug;
+use tracing::field;
 use tracing::field::debug;
 use tracing::field::DebugValue;
 use tracing::instrument;
 use tracing::span;
 use tracing::trace;
 use tracing::Level;
+use tracing::Span;
 use tracing_futures::Instrument;
 
 use url::Url;
 
+use uuid::Uuid;
+
+use crate::api::v2::account;
+use crate::api::v2::assets::AssetsCache;
+use crate::api::v2::clock::ClockCache;
+use crate::api::v2::clock::DEFAULT_CLOCK_TTL;
+use crate::api::HDR_ACCOUNT_ID;
+use crate::api::HDR_CLIENT_REQUEST_ID;
 use crate::api::HDR_KEY_ID;
 use crate::api::HDR_SECRET;
 use crate::api_info::ApiInfo;
+use crate::endpoint::ConversionError;
 use crate::error::RequestError;
+use crate::raw::Raw;
+use crate::raw::RawApi;
+use crate::raw::RawError;
 use crate::subscribable::Subscribable;
+use crate::util::with_strict;
 use crate::Error;
 
 
+/// The default maximum size of a response body, in bytes.
+const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+
 /// A type providing a debug representation of HTTP headers, with
 /// sensitive data being masked out.
 struct DebugHeaders<'h> {
@@ -68,6 +108,23 @@ impl Debug for DebugHeaders<'_> {
 }
 
 
+/// A type providing a debug representation of an HTTP request body.
+///
+/// Bodies may contain sensitive data and so they are only included
+/// when the `trace-bodies` feature is enabled.
+struct DebugBody<'b>(&'b Full<Bytes>);
+
+impl Debug for DebugBody<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    if cfg!(feature = "trace-bodies") {
+      Debug::fmt(self.0, f)
+    } else {
+      f.write_str("<omitted>")
+    }
+  }
+}
+
+
 /// A type providing a debug representation of an HTTP request, with
 /// sensitive data being masked out.
 struct DebugRequest<'r> {
@@ -87,7 +144,7 @@ impl Debug for DebugRequest<'_> {
           headers: self.request.headers(),
         },
       )
-      .field("body", self.request.body())
+      .field("body", &DebugBody(self.request.body()))
       .finish()
   }
 }
@@ -99,26 +156,198 @@ fn debug_request(request: &Request<Full<Bytes>>) -> DebugValue<DebugRequest<'_>>
 }
 
 
+/// Append `path` to the path of `url`, preserving any prefix the base
+/// URL may contain (e.g., when going through a proxy).
+fn join_path(url: &mut Url, path: &str) {
+  let prefix = url.path().trim_end_matches('/');
+  let path = path.trim_start_matches('/');
+  let joined = format!("{prefix}/{path}");
+  let () = url.set_path(&joined);
+}
+
+
+/// The name of the header carrying the ID Alpaca assigned to a request.
+const HDR_REQUEST_ID: &str = "x-request-id";
+/// The name of the header carrying the number of remaining requests in
+/// the current rate limit window.
+const HDR_RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+/// The name of the header carrying the time at which the current rate
+/// limit window resets, as a UNIX timestamp.
+const HDR_RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+
+/// Meta data about a response, as extracted from its HTTP headers.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResponseMeta {
+  /// The ID Alpaca assigned to the request. Alpaca support asks for
+  /// it when investigating issues.
+  pub request_id: Option<String>,
+  /// The number of requests remaining in the current rate limit
+  /// window.
+  pub rate_limit_remaining: Option<u64>,
+  /// The time at which the current rate limit window resets.
+  pub rate_limit_reset: Option<DateTime<Utc>>,
+  /// The entity tag of the returned resource, if any.
+  pub etag: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl ResponseMeta {
+  /// Extract response meta data from a set of HTTP headers.
+  ///
+  /// Headers that are absent or malformed are reported as `None`.
+  fn from_headers(headers: &HeaderMap<HeaderValue>) -> Self {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    Self {
+      request_id: header(HDR_REQUEST_ID).map(str::to_string),
+      rate_limit_remaining: header(HDR_RATE_LIMIT_REMAINING).and_then(|value| value.parse().ok()),
+      rate_limit_reset: header(HDR_RATE_LIMIT_RESET)
+        .and_then(|value| value.parse().ok())
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+      etag: header(ETAG.as_str()).map(str::to_string),
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
 /// A builder for creating customized `Client` objects.
 #[derive(Debug)]
 pub struct Builder {
   builder: HttpClientBuilder,
+  max_response_size: usize,
+  request_timeout: Option<Duration>,
+  strict: bool,
+  gzip: bool,
+  user_agent: Option<String>,
+  clock_ttl: Duration,
 }
 
 impl Builder {
   /// Adjust the maximum number of idle connections per host.
+  ///
+  /// This method is equivalent to [`Builder::pool_max_idle_per_host`].
   #[inline]
   pub fn max_idle_per_host(&mut self, max_idle: usize) -> &mut Self {
+    self.pool_max_idle_per_host(max_idle)
+  }
+
+  /// Adjust the maximum number of idle connections kept in the
+  /// connection pool per host.
+  ///
+  /// By default the number of idle connections is not limited. A value
+  /// of `0` disables reuse of connections altogether.
+  #[inline]
+  pub fn pool_max_idle_per_host(&mut self, max_idle: usize) -> &mut Self {
     let _ = self.builder.pool_max_idle_per_host(max_idle);
     self
   }
 
+  /// Adjust the time after which idle connections are evicted from the
+  /// connection pool.
+  ///
+  /// Defaults to 90 seconds. `None` keeps idle connections around
+  /// indefinitely.
+  #[inline]
+  pub fn pool_idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+    let _ = self.builder.pool_idle_timeout(timeout);
+    self
+  }
+
+  /// Adjust the maximum size of a response body, in bytes.
+  ///
+  /// Responses with a body larger than that are rejected with a
+  /// [`RequestError::ResponseTooLarge`] error. For compressed
+  /// responses the limit applies to both the compressed and the
+  /// uncompressed data.
+  #[inline]
+  pub fn max_response_size(&mut self, max_size: usize) -> &mut Self {
+    self.max_response_size = max_size;
+    self
+  }
+
+  /// Adjust the time after which a request is abandoned.
+  ///
+  /// The timeout covers the entire request, from connection
+  /// establishment until the response body has been received. An
+  /// abandoned request is reported as a [`RequestError::Io`] error of
+  /// kind [`ErrorKind::TimedOut`]. Note that the server may still have
+  /// processed such a request. Defaults to `None`, i.e., no timeout.
+  #[inline]
+  pub fn request_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+    self.request_timeout = timeout;
+    self
+  }
+
+  /// Enable or disable strict deserialization of enum values.
+  ///
+  /// By default, enum values reported by the API that are not known to
+  /// this crate, e.g., an account or order status introduced after its
+  /// release, are mapped to a catch-all variant. In strict mode, the
+  /// response is instead rejected with a conversion error, helping to
+  /// catch changes to the API early. Defaults to `false`.
+  #[inline]
+  pub fn strict(&mut self, strict: bool) -> &mut Self {
+    self.strict = strict;
+    self
+  }
+
+  /// Enable or disable requesting gzip compressed responses.
+  ///
+  /// If enabled, requests advertise support for gzip and compressed
+  /// responses are decompressed transparently. Streaming connections
+  /// are not affected. Defaults to `true`, but only has an effect if
+  /// the `gzip` feature is enabled.
+  #[inline]
+  pub fn gzip(&mut self, gzip: bool) -> &mut Self {
+    self.gzip = gzip;
+    self
+  }
+
+  /// Set the user agent to identify as.
+  ///
+  /// The user agent is sent along with every request as well as when
+  /// establishing streaming connections via [`Client::subscribe`].
+  /// Defaults to `apca/<version>`.
+  #[inline]
+  pub fn user_agent(&mut self, user_agent: String) -> &mut Self {
+    self.user_agent = Some(user_agent);
+    self
+  }
+
+  /// Adjust the time for which the market clock is cached by
+  /// [`Client::cached_clock`].
+  ///
+  /// Defaults to five seconds. A value of zero disables caching.
+  #[inline]
+  pub fn clock_ttl(&mut self, ttl: Duration) -> &mut Self {
+    self.clock_ttl = ttl;
+    self
+  }
+
   /// Build the final `Client` object.
-  pub fn build(&self, api_info: ApiInfo) -> Client {
+  pub fn build(&self, mut api_info: ApiInfo) -> Client {
     let https = HttpsConnector::new();
     let client = self.builder.build(https);
 
-    Client { api_info, client }
+    if let Some(user_agent) = &self.user_agent {
+      api_info.user_agent = user_agent.clone();
+    }
+
+    Client {
+      api_info,
+      client,
+      account_id: None,
+      max_response_size: self.max_response_size,
+      request_timeout: self.request_timeout,
+      strict: self.strict,
+      gzip: self.gzip,
+      assets_cache: Arc::default(),
+      clock_cache: Arc::new(ClockCache::new(self.clock_ttl)),
+    }
   }
 }
 
@@ -136,7 +365,15 @@ impl Default for Builder {
     let mut builder = HttpClient::builder(TokioExecutor::new());
     let _ = builder.pool_max_idle_per_host(0);
 
-    Self { builder }
+    Self {
+      builder,
+      max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+      request_timeout: None,
+      strict: false,
+      gzip: true,
+      user_agent: None,
+      clock_ttl: DEFAULT_CLOCK_TTL,
+    }
   }
 
   #[cfg(not(test))]
@@ -144,6 +381,12 @@ impl Default for Builder {
   fn default() -> Self {
     Self {
       builder: HttpClient::builder(TokioExecutor::new()),
+      max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+      request_timeout: None,
+      strict: false,
+      gzip: true,
+      user_agent: None,
+      clock_ttl: DEFAULT_CLOCK_TTL,
     }
   }
 }
@@ -155,6 +398,13 @@ impl Default for Builder {
 pub struct Client {
   api_info: ApiInfo,
   client: HttpClient<HttpsConnector<HttpConnector>, Full<Bytes>>,
+  account_id: Option<account::Id>,
+  max_response_size: usize,
+  request_timeout: Option<Duration>,
+  strict: bool,
+  gzip: bool,
+  assets_cache: Arc<AssetsCache>,
+  clock_cache: Arc<ClockCache>,
 }
 
 impl Client {
@@ -171,30 +421,80 @@ impl Client {
     Builder::default().build(api_info)
   }
 
-  /// Add "gzip" as an accepted encoding to the request.
-  #[cfg(feature = "gzip")]
-  fn maybe_add_gzip_header(request: &mut Request<Full<Bytes>>) {
+  /// Create a new `Client` scoping all requests to the account with
+  /// the provided ID, e.g., a sub-account managed through the broker
+  /// API.
+  ///
+  /// The returned client shares its configuration and connection pool
+  /// with `self`, which itself is left unchanged. Note that only HTTP
+  /// requests are scoped; streaming connections established through
+  /// [`Client::subscribe`] are not.
+  pub fn for_account(&self, account_id: account::Id) -> Self {
+    Self {
+      api_info: self.api_info.clone(),
+      client: self.client.clone(),
+      account_id: Some(account_id),
+      max_response_size: self.max_response_size,
+      request_timeout: self.request_timeout,
+      strict: self.strict,
+      gzip: self.gzip,
+      assets_cache: self.assets_cache.clone(),
+      clock_cache: self.clock_cache.clone(),
+    }
+  }
+
+  /// Add the headers required for authenticating a request, along with
+  /// the account to scope it to, if any.
+  ///
+  /// The user agent and a newly generated client request ID are added
+  /// as well.
+  fn authenticate(&self, builder: HttpRequestBuilder) -> HttpRequestBuilder {
+    let builder = builder
+      .header(USER_AGENT, self.api_info.user_agent.as_str())
+      .header(HDR_CLIENT_REQUEST_ID, Uuid::new_v4().to_string())
+      .header(HDR_KEY_ID, self.api_info.key_id.as_str())
+      .header(HDR_SECRET, self.api_info.secret.as_str());
+
+    match &self.account_id {
+      Some(account::Id(id)) => builder.header(HDR_ACCOUNT_ID, id.to_string()),
+      None => builder,
+    }
+  }
+
+  /// Add "gzip" as an accepted encoding to the request, if support for
+  /// it is enabled.
+  fn maybe_add_gzip_header(&self, request: &mut Request<Full<Bytes>>) {
     use http::header::ACCEPT_ENCODING;
 
-    let _ = request
-      .headers_mut()
-      .insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+    if cfg!(feature = "gzip") && self.gzip {
+      let _ = request
+        .headers_mut()
+        .insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+    }
   }
 
-  /// An implementation stub not actually doing anything.
-  #[cfg(not(feature = "gzip"))]
-  fn maybe_add_gzip_header(_request: &mut Request<Full<Bytes>>) {}
+  /// Retrieve the configured base URL of the given API.
+  fn base_url(&self, api: RawApi) -> Url {
+    match api {
+      RawApi::Trading => self.api_info.api_base_url.clone(),
+      RawApi::Data => self.api_info.data_base_url.clone(),
+    }
+  }
 
   /// Create a `Request` to the endpoint.
   fn request<R>(&self, input: &R::Input) -> Result<Request<Full<Bytes>>, R::Error>
   where
     R: Endpoint,
   {
-    let mut url = R::base_url()
-      .map(|url| Url::parse(url.as_ref()).expect("endpoint definition contains invalid URL"))
-      .unwrap_or_else(|| self.api_info.api_base_url.clone());
+    let mut url = match R::base_url() {
+      Some(url) => match RawApi::from_base_url(&url) {
+        Some(api) => self.base_url(api),
+        None => Url::parse(url.as_ref()).expect("endpoint definition contains invalid URL"),
+      },
+      None => self.base_url(RawApi::Trading),
+    };
 
-    url.set_path(&R::path(input));
+    let () = join_path(&mut url, &R::path(input));
     url.set_query(R::query(input)?.as_ref().map(AsRef::as_ref));
 
     let body = match R::body(input)? {
@@ -203,41 +503,51 @@ impl Client {
       Some(Cow::Owned(vec)) => Bytes::from(vec),
     };
 
-    let mut request = HttpRequestBuilder::new()
+    let builder = HttpRequestBuilder::new()
       .method(R::method())
-      .uri(url.as_str())
-      // Add required authentication information.
-      .header(HDR_KEY_ID, self.api_info.key_id.as_str())
-      .header(HDR_SECRET, self.api_info.secret.as_str())
-      .body(Full::new(body))?;
+      .uri(url.as_str());
+    // Add required authentication information.
+    let mut request = self.authenticate(builder).body(Full::new(body))?;
 
 
-    Self::maybe_add_gzip_header(&mut request);
+    let () = self.maybe_add_gzip_header(&mut request);
     Ok(request)
   }
 
-  async fn retrieve_raw_body(response: Incoming) -> Result<Bytes, HyperError> {
+  async fn retrieve_raw_body<E>(
+    mut body: Incoming,
+    max_size: usize,
+  ) -> Result<Bytes, RequestError<E>> {
     // We unconditionally wait for the full body to be received
     // before even evaluating the header. That is mostly done for
     // simplicity and it shouldn't really matter anyway because most
     // if not all requests evaluate the body on success and on error
-    // the server shouldn't send back much.
-    // TODO: However, there may be one case that has the potential
-    //       to cause trouble: when we receive, for example, the
-    //       list of all orders it now needs to be stored in memory
-    //       in its entirety. That may blow things.
-    let bytes = BodyExt::collect(response)
-      .await
-      // SANITY: The operation is infallible.
-      .unwrap()
-      .to_bytes();
-    Ok(bytes)
+    // the server shouldn't send back much. To not blow up on
+    // excessively large bodies, we bail out as soon as the configured
+    // limit is exceeded, though.
+    if body.size_hint().lower() > max_size as u64 {
+      return Err(RequestError::ResponseTooLarge(max_size))
+    }
+
+    let mut buffer = Vec::new();
+    while let Some(frame) = body.frame().await {
+      if let Ok(data) = frame?.into_data() {
+        if buffer.len() + data.len() > max_size {
+          return Err(RequestError::ResponseTooLarge(max_size))
+        }
+        buffer.extend_from_slice(&data);
+      }
+    }
+    Ok(buffer.into())
   }
 
   /// Retrieve the HTTP body, possible uncompressing it if it was gzip
   /// encoded.
   #[cfg(feature = "gzip")]
-  async fn retrieve_body<E>(response: Response<Incoming>) -> Result<Bytes, RequestError<E>> {
+  async fn retrieve_body<E>(
+    response: Response<Incoming>,
+    max_size: usize,
+  ) -> Result<Bytes, RequestError<E>> {
     use async_compression::futures::bufread::GzipDecoder;
     use futures::AsyncReadExt as _;
     use http::header::CONTENT_ENCODING;
@@ -245,11 +555,20 @@ impl Client {
     let (parts, body) = response.into_parts();
     let encoding = parts.headers.get(CONTENT_ENCODING);
 
-    let bytes = Self::retrieve_raw_body(body).await?;
+    let bytes = Self::retrieve_raw_body(body, max_size).await?;
     let bytes = match encoding {
       Some(value) if value == HeaderValue::from_static("gzip") => {
         let mut buffer = Vec::new();
-        let _count = GzipDecoder::new(&*bytes).read_to_end(&mut buffer).await?;
+        // Read at most one byte more than permitted, so that we can
+        // detect whether the limit got exceeded.
+        let limit = (max_size as u64).saturating_add(1);
+        let _count = GzipDecoder::new(&*bytes)
+          .take(limit)
+          .read_to_end(&mut buffer)
+          .await?;
+        if buffer.len() > max_size {
+          return Err(RequestError::ResponseTooLarge(max_size))
+        }
         buffer.into()
       },
       _ => bytes,
@@ -260,8 +579,11 @@ impl Client {
 
   /// Retrieve the HTTP body.
   #[cfg(not(feature = "gzip"))]
-  async fn retrieve_body<E>(response: Response<Incoming>) -> Result<Bytes, RequestError<E>> {
-    let bytes = Self::retrieve_raw_body(response.into_body()).await?;
+  async fn retrieve_body<E>(
+    response: Response<Incoming>,
+    max_size: usize,
+  ) -> Result<Bytes, RequestError<E>> {
+    let bytes = Self::retrieve_raw_body(response.into_body(), max_size).await?;
     Ok(bytes)
   }
 
@@ -276,24 +598,176 @@ impl Client {
     let result = self.request::<R>(input);
     async move {
       let request = result.map_err(RequestError::Endpoint)?;
-      let span = span!(
-        Level::INFO,
-        "issue",
-        method = display(request.method()),
-        uri = display(request.uri())
-      );
-      self.issue_::<R>(request).instrument(span).await
+      let (output, _meta) = self.issue_request::<R>(request).await?;
+      Ok(output)
     }
   }
 
-  /// Issue a request.
+  /// Create and issue a request just like [`Client::issue`], but also
+  /// report meta data about the response, such as the request ID
+  /// assigned by Alpaca and the current rate limit state.
+  pub fn issue_with_meta<R>(
+    &self,
+    input: &R::Input,
+  ) -> impl Future<Output = Result<(R::Output, ResponseMeta), RequestError<R::Error>>> + '_
+  where
+    R: Endpoint,
+  {
+    let result = self.request::<R>(input);
+    async move {
+      let request = result.map_err(RequestError::Endpoint)?;
+      self.issue_request::<R>(request).await
+    }
+  }
+
+  /// Create and issue a request that is conditional on the resource
+  /// not matching the provided entity tag.
+  ///
+  /// If the server reports the resource as not modified, `None` is
+  /// reported in lieu of an output and the response body is not
+  /// evaluated.
+  pub(crate) async fn issue_conditional<R>(
+    &self,
+    input: &R::Input,
+    etag: Option<&str>,
+  ) -> Result<(Option<R::Output>, ResponseMeta), RequestError<R::Error>>
+  where
+    R: Endpoint,
+  {
+    let mut request = self.request::<R>(input).map_err(RequestError::Endpoint)?;
+    if let Some(etag) = etag {
+      let etag = HeaderValue::from_str(etag)
+        .map_err(|err| RequestError::Endpoint(R::Error::from(http::Error::from(err))))?;
+      let _prev = request.headers_mut().insert(IF_NONE_MATCH, etag);
+    }
+
+    self
+      .issue_request_with::<R, _, _>(request, |status, body| {
+        if status == StatusCode::NOT_MODIFIED {
+          Ok(None)
+        } else {
+          R::evaluate(status, body).map(Some)
+        }
+      })
+      .await
+  }
+
+  /// Issue an arbitrary request against the trading or market data API
+  /// and report the JSON response.
+  ///
+  /// This is a lower-level escape hatch for endpoints the crate does
+  /// not model (yet); prefer [`Client::issue`] with a proper endpoint
+  /// definition whenever possible. The request is authenticated and
+  /// otherwise subject to the same configuration as all others, e.g.,
+  /// the request timeout. `path` is relative to the base URL of the
+  /// selected API and `query`, if present, is used verbatim as the
+  /// URL's query string. An empty response body is reported as
+  /// [`Value::Null`].
+  pub async fn issue_raw(
+    &self,
+    method: Method,
+    api: RawApi,
+    path: &str,
+    query: Option<&str>,
+    body: Option<&Value>,
+  ) -> Result<Value, RequestError<RawError>> {
+    let mut url = self.base_url(api);
+    let () = join_path(&mut url, path);
+    url.set_query(query);
+
+    let body = match body {
+      Some(body) => to_json(body)
+        .map(Bytes::from)
+        .map_err(|err| RequestError::Endpoint(RawError::from(ConversionError::from(err))))?,
+      None => Bytes::new(),
+    };
+
+    let builder = HttpRequestBuilder::new().method(method).uri(url.as_str());
+    let mut request = self
+      .authenticate(builder)
+      .body(Full::new(body))
+      .map_err(|err| RequestError::Endpoint(RawError::from(err)))?;
+
+    let () = self.maybe_add_gzip_header(&mut request);
+    self
+      .issue_request::<Raw>(request)
+      .await
+      .map(|(output, _meta)| output)
+  }
+
+  /// Issue an already constructed request for the provided endpoint,
+  /// honoring the configured request timeout.
+  #[inline]
+  async fn issue_request<R>(
+    &self,
+    request: Request<Full<Bytes>>,
+  ) -> Result<(R::Output, ResponseMeta), RequestError<R::Error>>
+  where
+    R: Endpoint,
+  {
+    self
+      .issue_request_with::<R, _, _>(request, R::evaluate)
+      .await
+  }
+
+  /// Issue an already constructed request for the provided endpoint,
+  /// evaluating the response by means of `evaluate`.
+  async fn issue_request_with<R, T, F>(
+    &self,
+    request: Request<Full<Bytes>>,
+    evaluate: F,
+  ) -> Result<(T, ResponseMeta), RequestError<R::Error>>
+  where
+    R: Endpoint,
+    F: FnOnce(StatusCode, &[u8]) -> Result<T, R::Error>,
+  {
+    // The span's status and request ID fields are recorded once the
+    // response arrived, its duration once the request completed.
+    let span = span!(
+      Level::INFO,
+      "issue",
+      method = display(request.method()),
+      uri = display(request.uri()),
+      path = request.uri().path(),
+      client_request_id = request
+        .headers()
+        .get(HDR_CLIENT_REQUEST_ID)
+        .and_then(|id| id.to_str().ok()),
+      status = field::Empty,
+      request_id = field::Empty,
+      duration_ms = field::Empty,
+    );
+    let start = Instant::now();
+    let issue = self
+      .issue_::<R, _, _>(request, &span, evaluate)
+      .instrument(span.clone());
+    let result = match self.request_timeout {
+      Some(duration) => timeout(duration, issue).await.unwrap_or_else(|_elapsed| {
+        Err(RequestError::Io(IoError::new(
+          ErrorKind::TimedOut,
+          "request timed out",
+        )))
+      }),
+      None => issue.await,
+    };
+
+    let duration = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let _span = span.record("duration_ms", duration);
+    result
+  }
+
+  /// Issue a request, recording information about the response in the
+  /// provided span.
   #[allow(clippy::cognitive_complexity)]
-  async fn issue_<R>(
+  async fn issue_<R, T, F>(
     &self,
     request: Request<Full<Bytes>>,
-  ) -> Result<R::Output, RequestError<R::Error>>
+    span: &Span,
+    evaluate: F,
+  ) -> Result<(T, ResponseMeta), RequestError<R::Error>>
   where
     R: Endpoint,
+    F: FnOnce(StatusCode, &[u8]) -> Result<T, R::Error>,
   {
     debug!("requesting");
     trace!(request = debug_request(&request));
@@ -302,15 +776,25 @@ impl Client {
     let status = result.status();
     debug!(status = debug(&status));
     trace!(response = debug(&result));
+    let meta = ResponseMeta::from_headers(result.headers());
 
-    let bytes = Self::retrieve_body::<R::Error>(result).await?;
+    let _span = span.record("status", status.as_u16());
+    if let Some(request_id) = &meta.request_id {
+      let _span = span.record("request_id", request_id.as_str());
+    }
+
+    let bytes = Self::retrieve_body::<R::Error>(result, self.max_response_size).await?;
     let body = bytes.as_ref();
-    match from_utf8(body) {
-      Ok(s) => trace!(body = display(&s)),
-      Err(b) => trace!(body = display(&b)),
+    if cfg!(feature = "trace-bodies") {
+      match from_utf8(body) {
+        Ok(s) => trace!(body = display(&s)),
+        Err(b) => trace!(body = display(&b)),
+      }
     }
 
-    R::evaluate(status, body).map_err(RequestError::Endpoint)
+    let output =
+      with_strict(self.strict, || evaluate(status, body)).map_err(RequestError::Endpoint)?;
+    Ok((output, meta))
   }
 
   /// Subscribe to the given subscribable in order to receive updates.
@@ -334,6 +818,58 @@ impl Client {
   pub fn api_info(&self) -> &ApiInfo {
     &self.api_info
   }
+
+  /// Retrieve the cache of asset lists used by
+  /// [`Client::list_assets_cached`].
+  ///
+  /// The cache is shared with clients created through
+  /// [`Client::for_account`].
+  #[inline]
+  pub fn assets_cache(&self) -> &AssetsCache {
+    &self.assets_cache
+  }
+
+  /// Retrieve the cache of the market clock used by
+  /// [`Client::cached_clock`].
+  ///
+  /// The cache is shared with clients created through
+  /// [`Client::for_account`].
+  #[inline]
+  pub fn clock_cache(&self) -> &ClockCache {
+    &self.clock_cache
+  }
+}
+
+
+/// A trait abstracting over the issuing of requests to endpoints.
+///
+/// The trait is implemented by [`Client`] and allows for code to be
+/// generic over it, e.g., for substituting a mock in tests. A mock
+/// implementation is provided by the `testing` feature in the form of
+/// `testing::MockClient`.
+#[async_trait]
+pub trait ApiClient {
+  /// Create and issue a request and decode the response.
+  async fn issue<R>(&self, input: &R::Input) -> Result<R::Output, RequestError<R::Error>>
+  where
+    R: Endpoint + 'static,
+    R::Input: Sync,
+    R::Output: Send,
+    R::Error: Send;
+}
+
+#[async_trait]
+impl ApiClient for Client {
+  #[inline]
+  async fn issue<R>(&self, input: &R::Input) -> Result<R::Output, RequestError<R::Error>>
+  where
+    R: Endpoint + 'static,
+    R::Input: Sync,
+    R::Output: Send,
+    R::Error: Send,
+  {
+    Client::issue::<R>(self, input).await
+  }
 }
 
 
@@ -341,11 +877,31 @@ impl Client {
 mod tests {
   use super::*;
 
+  use std::collections::HashMap;
+  use std::sync::Arc;
+  use std::sync::Mutex;
+
   use http::StatusCode;
 
   use test_log::test;
 
+  use tracing::field::Field;
+  use tracing::field::Visit;
+  use tracing::subscriber::set_default;
+  use tracing::Event as TraceEvent;
+  use tracing::Metadata;
+  use tracing::Subscriber;
+
+  use uuid::Uuid;
+
+  #[cfg(feature = "market-data")]
+  use crate::data::v2::bars::Bars;
   use crate::endpoint::ApiError;
+  use crate::test_util::serve_once;
+  use crate::test_util::serve_once_with_status;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::Reply;
+  use crate::test_util::Response;
   use crate::Str;
 
 
@@ -360,6 +916,319 @@ mod tests {
   }
 
 
+  Endpoint! {
+    GetNull(()),
+    Ok => (), [
+      /* 200 */ OK,
+    ],
+    Err => GetNullError, []
+
+    fn path(_input: &Self::Input) -> Str {
+      "/v2/null".into()
+    }
+  }
+
+
+  Endpoint! {
+    GetDataNull(()),
+    Ok => (), [
+      /* 200 */ OK,
+    ],
+    Err => GetDataNullError, []
+
+    fn base_url() -> Option<Str> {
+      RawApi::Data.base_url()
+    }
+
+    fn path(_input: &Self::Input) -> Str {
+      "/v2/null".into()
+    }
+  }
+
+
+  #[cfg(feature = "market-data")]
+  Endpoint! {
+    GetBars(()),
+    Ok => Bars, [
+      /* 200 */ OK,
+    ],
+    Err => GetBarsError, []
+
+    fn path(_input: &Self::Input) -> Str {
+      "/v2/stocks/AAPL/bars".into()
+    }
+  }
+
+
+  /// A span as captured by a `SpanRecorder`.
+  #[derive(Debug, Default)]
+  struct RecordedSpan {
+    /// The name of the span.
+    name: &'static str,
+    /// The ID of the span's parent, if any.
+    parent: Option<u64>,
+    /// The span's fields, in their debug representation.
+    fields: HashMap<&'static str, String>,
+  }
+
+  impl Visit for RecordedSpan {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+      let _prev = self.fields.insert(field.name(), format!("{value:?}"));
+    }
+  }
+
+  /// A tracing subscriber recording all spans created.
+  #[derive(Default)]
+  struct SpanRecorder {
+    /// The recorded spans, indexed by their ID minus one.
+    spans: Arc<Mutex<Vec<RecordedSpan>>>,
+    /// The stack of currently entered spans.
+    entered: Mutex<Vec<u64>>,
+  }
+
+  impl Subscriber for SpanRecorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+      true
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+      let parent = match attrs.parent() {
+        Some(parent) => Some(parent.into_u64()),
+        None if attrs.is_contextual() => self.entered.lock().unwrap().last().copied(),
+        None => None,
+      };
+      let mut span = RecordedSpan {
+        name: attrs.metadata().name(),
+        parent,
+        ..Default::default()
+      };
+      let () = attrs.record(&mut span);
+
+      let mut spans = self.spans.lock().unwrap();
+      let () = spans.push(span);
+      span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+      let mut spans = self.spans.lock().unwrap();
+      let () = values.record(&mut spans[span.into_u64() as usize - 1]);
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, _event: &TraceEvent<'_>) {}
+
+    fn enter(&self, span: &span::Id) {
+      let () = self.entered.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, _span: &span::Id) {
+      let _span = self.entered.lock().unwrap().pop();
+    }
+  }
+
+
+  /// Check that issuing a request emits a span carrying the request's
+  /// method, path, status, request IDs, and duration, nested under the
+  /// caller's span.
+  #[test(tokio::test)]
+  async fn emit_request_span() {
+    let spans = Arc::new(Mutex::new(Vec::new()));
+    let subscriber = SpanRecorder {
+      spans: spans.clone(),
+      ..Default::default()
+    };
+    let _guard = set_default(subscriber);
+
+    let response = Response::new("200 OK", "null").header("x-request-id", "abc-123");
+    let (url, _requests) = serve_sequence(vec![response.into()]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let span = span!(Level::INFO, "caller");
+    let () = client.issue::<GetNull>(&()).instrument(span).await.unwrap();
+
+    let spans = spans.lock().unwrap();
+    let caller = spans.iter().position(|span| span.name == "caller").unwrap();
+    let issue = spans.iter().find(|span| span.name == "issue").unwrap();
+    assert_eq!(issue.parent, Some(caller as u64 + 1));
+    assert_eq!(issue.fields["method"], "GET");
+    assert_eq!(issue.fields["path"], r#""/v2/null""#);
+    assert_eq!(issue.fields["status"], "200");
+    assert_eq!(issue.fields["request_id"], r#""abc-123""#);
+    let client_request_id = issue.fields["client_request_id"].trim_matches('"');
+    assert!(Uuid::parse_str(client_request_id).is_ok());
+    assert!(issue.fields.contains_key("duration_ms"));
+  }
+
+  /// Check that requests carry the configured user agent and a client
+  /// request ID.
+  #[test(tokio::test)]
+  async fn send_user_agent() {
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+    let request = requests.all().remove(0).to_lowercase();
+    let expected = format!("user-agent: apca/{}\r\n", env!("CARGO_PKG_VERSION"));
+    assert!(request.contains(&expected), "{request}");
+    assert!(request.contains("x-client-request-id: "), "{request}");
+
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder()
+      .user_agent("my-app/1.2".to_string())
+      .build(api_info);
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+    let request = requests.all().remove(0).to_lowercase();
+    assert!(request.contains("user-agent: my-app/1.2\r\n"), "{request}");
+  }
+
+  /// Check that a client scoped to an account attaches the account
+  /// header to requests, while the original client does not.
+  #[test(tokio::test)]
+  async fn account_scoped_client() {
+    let id = Uuid::parse_str("904837e3-3b76-47ec-b432-046db621571b").unwrap();
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let scoped = client.for_account(account::Id(id));
+
+    let () = scoped.issue::<GetNull>(&()).await.unwrap();
+    let request = requests.all().remove(0).to_lowercase();
+    assert!(
+      request.contains("apca-account-id: 904837e3-3b76-47ec-b432-046db621571b\r\n"),
+      "{request}"
+    );
+
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client { api_info, ..client };
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+    let request = requests.all().remove(0).to_lowercase();
+    assert!(request.contains("apca-api-key-id: key\r\n"), "{request}");
+    assert!(!request.contains("apca-account-id"), "{request}");
+  }
+
+  /// Check that requests advertise gzip support, unless disabled.
+  #[test(tokio::test)]
+  async fn accept_gzip_encoding() {
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+    let request = requests.all().remove(0).to_lowercase();
+    assert_eq!(
+      request.contains("accept-encoding: gzip\r\n"),
+      cfg!(feature = "gzip"),
+      "{request}"
+    );
+
+    let (url, requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder().gzip(false).build(api_info);
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+    let request = requests.all().remove(0).to_lowercase();
+    assert!(!request.contains("accept-encoding"), "{request}");
+  }
+
+  /// Check that a gzip encoded response body is decompressed before
+  /// being deserialized.
+  #[cfg(all(feature = "gzip", feature = "market-data"))]
+  #[test(tokio::test)]
+  async fn decode_gzip_body() {
+    use async_compression::futures::bufread::GzipEncoder;
+    use futures::AsyncReadExt as _;
+    use num_decimal::Num;
+
+    let json = br#"{
+  "bars": [{
+    "t": "2021-02-01T16:01:00Z",
+    "o": 133.32,
+    "h": 133.74,
+    "l": 133.31,
+    "c": 133.5,
+    "v": 9876,
+    "n": 50,
+    "vw": 133.5
+  }],
+  "symbol": "AAPL",
+  "next_page_token": null
+}"#;
+    let mut body = Vec::new();
+    let _count = GzipEncoder::new(&json[..])
+      .read_to_end(&mut body)
+      .await
+      .unwrap();
+    assert_ne!(body, json);
+
+    let response = Response::new("200 OK", body).header("content-encoding", "gzip");
+    let (url, _requests) = serve_sequence(vec![response.into()]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let bars = client.issue::<GetBars>(&()).await.unwrap();
+    assert_eq!(bars.symbol, "AAPL");
+    assert_eq!(bars.bars.len(), 1);
+    assert_eq!(bars.bars[0].close, Num::new(1335, 10));
+  }
+
+  /// Make sure that Data API requests target the configured data base
+  /// URL, while Trading API requests target the trading one.
+  #[test(tokio::test)]
+  async fn distinct_base_urls() {
+    let (api_url, api_requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let (data_url, data_requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let api_info = ApiInfo::from_parts_with_data(&api_url, &data_url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let () = client.issue::<GetDataNull>(&()).await.unwrap();
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+
+    let api_request = api_requests.all().remove(0).to_lowercase();
+    let data_addr = data_url.strip_prefix("http://").unwrap();
+    assert!(api_request.starts_with("get /v2/null "), "{api_request}");
+    assert!(!api_request.contains(data_addr), "{api_request}");
+
+    let data_request = data_requests.all().remove(0).to_lowercase();
+    let api_addr = api_url.strip_prefix("http://").unwrap();
+    assert!(data_request.starts_with("get /v2/null "), "{data_request}");
+    assert!(data_request.contains(data_addr), "{data_request}");
+    assert!(!data_request.contains(api_addr), "{data_request}");
+  }
+
+  /// Verify that endpoint paths are appended to any path prefix of the
+  /// configured base URLs.
+  #[test(tokio::test)]
+  async fn prefixed_base_urls() {
+    let (api_url, api_requests) = serve_sequence(vec![Reply::respond("200 OK", "null")]).await;
+    let (data_url, data_requests) = serve_sequence(vec![
+      Reply::respond("200 OK", "null"),
+      Reply::respond("200 OK", "null"),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts_with_data(
+      format!("{api_url}/alpaca"),
+      format!("{data_url}/alpaca-data/"),
+      "key",
+      "secret",
+    )
+    .unwrap();
+    let client = Client::new(api_info);
+
+    let () = client.issue::<GetNull>(&()).await.unwrap();
+    let () = client.issue::<GetDataNull>(&()).await.unwrap();
+    let _value = client
+      .issue_raw(Method::GET, RawApi::Data, "/v2/raw", None, None)
+      .await
+      .unwrap();
+
+    assert_eq!(api_requests.lines(), ["GET /alpaca/v2/null"]);
+    assert_eq!(
+      data_requests.lines(),
+      ["GET /alpaca-data/v2/null", "GET /alpaca-data/v2/raw"]
+    );
+  }
+
   /// Check that we can retrieve the `ApiInfo` object used by a client.
   #[test]
   fn client_api_info() {
@@ -391,6 +1260,7 @@ mod tests {
     match err {
       RequestError::Endpoint(GetNotFoundError::UnexpectedStatus(status, message)) => {
         let expected = ApiError {
+          code: None,
           message: "endpoint not found".to_string(),
         };
         assert_eq!(message, Ok(expected));
@@ -399,4 +1269,219 @@ mod tests {
       _ => panic!("Received unexpected error: {err:?}"),
     };
   }
+
+  /// Check that a response body exceeding the configured maximum size
+  /// is rejected.
+  #[test(tokio::test)]
+  async fn response_too_large() {
+    let url = serve_once(vec![b'x'; 1025]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder().max_response_size(1024).build(api_info);
+
+    let err = client.issue::<GetNotFound>(&()).await.unwrap_err();
+    match err {
+      RequestError::ResponseTooLarge(max_size) => assert_eq!(max_size, 1024),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that a request not answered in time is abandoned.
+  #[test(tokio::test)]
+  async fn request_timeout() {
+    let (url, _requests) = serve_sequence(vec![Reply::Hang]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder()
+      .request_timeout(Some(Duration::from_millis(100)))
+      .build(api_info);
+
+    let err = client.issue::<GetNotFound>(&()).await.unwrap_err();
+    match err {
+      RequestError::Io(err) => assert_eq!(err.kind(), ErrorKind::TimedOut),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that response meta data is extracted from the headers of a
+  /// response.
+  #[test(tokio::test)]
+  async fn response_meta() {
+    let response = Response::new("200 OK", "null")
+      .header("x-request-id", "3f06bd7b41a53417ba7ba4a9ae9d5b90")
+      .header("x-ratelimit-limit", "200")
+      .header("x-ratelimit-remaining", "197")
+      .header("x-ratelimit-reset", "1705708800");
+    let (url, _requests) = serve_sequence(vec![response.into()]).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let ((), meta) = client.issue_with_meta::<GetNull>(&()).await.unwrap();
+    assert_eq!(
+      meta.request_id.as_deref(),
+      Some("3f06bd7b41a53417ba7ba4a9ae9d5b90")
+    );
+    assert_eq!(meta.rate_limit_remaining, Some(197));
+    assert_eq!(
+      meta.rate_limit_reset,
+      Some(
+        DateTime::parse_from_rfc3339("2024-01-20T00:00:00Z")
+          .unwrap()
+          .into()
+      )
+    );
+  }
+
+  /// Check that missing response meta data headers are reported as
+  /// absent.
+  #[test(tokio::test)]
+  async fn response_meta_missing() {
+    let url = serve_once(b"null".to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let ((), meta) = client.issue_with_meta::<GetNull>(&()).await.unwrap();
+    assert_eq!(meta, ResponseMeta::default());
+  }
+
+  /// Check that connection pool settings are applied to the underlying
+  /// HTTP client and that requests still succeed with them.
+  #[test(tokio::test)]
+  async fn pool_configuration() {
+    let mut builder = Client::builder();
+    let _ = builder
+      .pool_max_idle_per_host(4)
+      .pool_idle_timeout(Some(Duration::from_secs(30)));
+
+    let debug = format!("{:?}", builder.builder);
+    assert!(debug.contains("max_idle_per_host: 4"), "{debug}");
+    assert!(debug.contains("idle_timeout: Some(30s)"), "{debug}");
+
+    let url = serve_once(b"null".to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = builder.build(api_info);
+
+    let err = client.issue::<GetNotFound>(&()).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(GetNotFoundError::UnexpectedStatus(status, _)) => {
+        assert_eq!(status, StatusCode::OK)
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that a structured error body reported by the API is made
+  /// available as part of the endpoint error.
+  #[test(tokio::test)]
+  async fn structured_error_body() {
+    let body = br#"{"code": 40310000, "message": "insufficient buying power"}"#;
+    let url = serve_once_with_status("403 Forbidden", body.to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let err = client.issue::<GetNotFound>(&()).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(GetNotFoundError::NotPermitted(Ok(err))) => {
+        assert_eq!(err.code, Some(40310000));
+        assert_eq!(err.message, "insufficient buying power");
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that the raw response body is preserved for an error body
+  /// that is not valid JSON.
+  #[test(tokio::test)]
+  async fn unstructured_error_body() {
+    let body = b"<html>forbidden</html>";
+    let url = serve_once_with_status("403 Forbidden", body.to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let err = client.issue::<GetNotFound>(&()).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(GetNotFoundError::NotPermitted(Err(raw))) => {
+        assert_eq!(raw, body.to_vec());
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that we can issue a raw request and retrieve the JSON
+  /// response.
+  #[test(tokio::test)]
+  async fn issue_raw_request() {
+    let body = br#"{"id":"904837e3-3b76-47ec-b432-046db621571b","preview":true}"#;
+    let url = serve_once(body.to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let value = client
+      .issue_raw(
+        Method::GET,
+        RawApi::Trading,
+        "/v2/preview",
+        Some("limit=1"),
+        None,
+      )
+      .await
+      .unwrap();
+    assert_eq!(value["id"], "904837e3-3b76-47ec-b432-046db621571b");
+    assert_eq!(value["preview"], true);
+
+    let url = serve_once_with_status("204 No Content", Vec::new()).await;
+    let mut api_info = ApiInfo::from_parts("http://example.com", "key", "secret").unwrap();
+    api_info.data_base_url = Url::parse(&url).unwrap();
+    let client = Client::new(api_info);
+
+    let value = client
+      .issue_raw(
+        Method::DELETE,
+        RawApi::Data,
+        "/v2/preview",
+        None,
+        Some(&Value::Bool(true)),
+      )
+      .await
+      .unwrap();
+    assert_eq!(value, Value::Null);
+  }
+
+  /// Check that errors reported in response to a raw request are mapped
+  /// as for regular endpoints.
+  #[test(tokio::test)]
+  async fn issue_raw_request_error() {
+    let body = br#"{"code": 40310000, "message": "insufficient buying power"}"#;
+    let url = serve_once_with_status("403 Forbidden", body.to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let err = client
+      .issue_raw(Method::POST, RawApi::Trading, "/v2/orders", None, None)
+      .await
+      .unwrap_err();
+    match err {
+      RequestError::Endpoint(RawError::NotPermitted(Ok(err))) => {
+        assert_eq!(err.code, Some(40310000));
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that a response body within the configured maximum size is
+  /// accepted.
+  #[test(tokio::test)]
+  async fn response_within_limit() {
+    let url = serve_once(b"null".to_vec()).await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::builder().max_response_size(4).build(api_info);
+
+    let err = client.issue::<GetNotFound>(&()).await.unwrap_err();
+    match err {
+      // `GetNotFound` does not expect an HTTP 200 status, but the body
+      // got read successfully, which is all that we care about.
+      RequestError::Endpoint(GetNotFoundError::UnexpectedStatus(status, _)) => {
+        assert_eq!(status, StatusCode::OK)
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
 }
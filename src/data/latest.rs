@@ -0,0 +1,507 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use futures::future::join;
+use futures::future::join_all;
+
+use num_decimal::Num;
+
+use thiserror::Error;
+
+use crate::data::v1beta3::crypto;
+use crate::data::v2::last_trades;
+use crate::data::MAX_SYMBOLS_PER_REQUEST;
+use crate::ApiError;
+use crate::Client;
+use crate::RequestError;
+
+
+/// The symbol of an asset to retrieve the latest price for.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Symbol {
+  /// An equity symbol, e.g., "AAPL".
+  Equity(String),
+  /// A crypto currency pair, e.g., "BTC/USD".
+  Crypto(String),
+}
+
+impl Symbol {
+  /// Retrieve the textual representation of the symbol.
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    match self {
+      Self::Equity(symbol) | Self::Crypto(symbol) => symbol,
+    }
+  }
+}
+
+impl Display for Symbol {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+    fmt.write_str(self.as_str())
+  }
+}
+
+impl FromStr for Symbol {
+  type Err = ParseSymbolError;
+
+  /// Parse a symbol, treating it as a crypto currency pair if it
+  /// contains a slash and as an equity symbol otherwise.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if s.is_empty() {
+      return Err(ParseSymbolError::Empty)
+    }
+
+    if s.contains(char::is_whitespace) {
+      return Err(ParseSymbolError::Whitespace(s.to_string()))
+    }
+
+    match s.split('/').collect::<Vec<_>>().as_slice() {
+      [_] => Ok(Self::Equity(s.to_string())),
+      [base, quote] if !base.is_empty() && !quote.is_empty() => Ok(Self::Crypto(s.to_string())),
+      _ => Err(ParseSymbolError::InvalidPair(s.to_string())),
+    }
+  }
+}
+
+
+/// An error indicating that a [`Symbol`] could not be parsed.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[non_exhaustive]
+pub enum ParseSymbolError {
+  /// The symbol is empty.
+  #[error("the symbol is empty")]
+  Empty,
+  /// The symbol contains whitespace.
+  #[error("the symbol {0:?} contains whitespace")]
+  Whitespace(String),
+  /// The symbol contains a slash but is not a crypto currency pair of
+  /// the form "BASE/QUOTE".
+  #[error("invalid crypto currency pair {0:?}: expected BASE/QUOTE")]
+  InvalidPair(String),
+}
+
+
+/// An error reported for an individual symbol by
+/// [`Client::latest_prices`].
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum PriceError {
+  /// The symbol was reported as invalid.
+  #[error("the symbol is invalid")]
+  InvalidSymbol,
+  /// No trade was reported for the symbol, e.g., because it does not
+  /// exist.
+  #[error("no trade was reported for the symbol")]
+  NotFound,
+  /// The request for the symbol's asset class failed as a whole.
+  #[error("failed to retrieve latest trades")]
+  Request(#[source] Arc<dyn StdError + Send + Sync>),
+}
+
+
+/// Extract the symbol reported as invalid by an API error, if any.
+fn invalid_symbol(error: &Result<ApiError, Vec<u8>>) -> Option<&str> {
+  match error {
+    Ok(ApiError { message, .. }) => message.strip_prefix("invalid symbol: "),
+    Err(..) => None,
+  }
+}
+
+/// Extract the equity symbol reported as invalid, if any.
+fn invalid_equity(error: &last_trades::GetError) -> Option<&str> {
+  match error {
+    last_trades::GetError::InvalidInput(error) => invalid_symbol(error),
+    _ => None,
+  }
+}
+
+/// Extract the crypto currency symbol reported as invalid, if any.
+fn invalid_crypto(error: &crypto::LatestTradesError) -> Option<&str> {
+  match error {
+    crypto::LatestTradesError::InvalidInput(error) => invalid_symbol(error),
+    _ => None,
+  }
+}
+
+
+/// Retrieve the latest prices for all of the provided symbols of a
+/// single asset class, using `issue` for issuing the actual requests.
+///
+/// Symbols are split into batches of at most
+/// [`MAX_SYMBOLS_PER_REQUEST`], which are requested concurrently.
+async fn fetch<E, F, R>(
+  symbols: Vec<String>,
+  invalid: fn(&E) -> Option<&str>,
+  issue: F,
+) -> Vec<(String, Result<Num, PriceError>)>
+where
+  E: StdError + Send + Sync + 'static,
+  F: FnMut(Vec<String>) -> R,
+  R: Future<Output = Result<Vec<(String, Num)>, RequestError<E>>>,
+{
+  let issue = RefCell::new(issue);
+  let batches = symbols
+    .chunks(MAX_SYMBOLS_PER_REQUEST)
+    .map(|batch| fetch_batch(batch.to_vec(), invalid, &issue));
+
+  join_all(batches).await.into_iter().flatten().collect()
+}
+
+
+/// Retrieve the latest prices for a single batch of symbols.
+///
+/// Symbols reported as invalid are removed from the request, which is
+/// then retried for the remaining ones.
+async fn fetch_batch<E, F, R>(
+  mut symbols: Vec<String>,
+  invalid: fn(&E) -> Option<&str>,
+  issue: &RefCell<F>,
+) -> Vec<(String, Result<Num, PriceError>)>
+where
+  E: StdError + Send + Sync + 'static,
+  F: FnMut(Vec<String>) -> R,
+  R: Future<Output = Result<Vec<(String, Num)>, RequestError<E>>>,
+{
+  let mut prices = Vec::with_capacity(symbols.len());
+
+  let trades = loop {
+    if symbols.is_empty() {
+      break Vec::new()
+    }
+
+    // The borrow only lasts for creating the request future, so that
+    // batches can be in flight concurrently.
+    let request = (issue.borrow_mut())(symbols.clone());
+    let error = match request.await {
+      Ok(trades) => break trades,
+      Err(RequestError::Endpoint(error)) => {
+        let index =
+          invalid(&error).and_then(|invalid| symbols.iter().position(|symbol| symbol == invalid));
+
+        if let Some(index) = index {
+          prices.push((symbols.remove(index), Err(PriceError::InvalidSymbol)));
+          continue
+        }
+        RequestError::Endpoint(error)
+      },
+      Err(error) => error,
+    };
+
+    let error = Arc::new(error) as Arc<dyn StdError + Send + Sync>;
+    let failed = symbols
+      .into_iter()
+      .map(|symbol| (symbol, Err(PriceError::Request(error.clone()))));
+    prices.extend(failed);
+    return prices
+  };
+
+  // Symbols that do not exist are silently omitted from the response.
+  let found = symbols.into_iter().map(|symbol| {
+    let price = trades
+      .iter()
+      .find(|(traded, _)| traded == &symbol)
+      .map(|(_, price)| price.clone())
+      .ok_or(PriceError::NotFound);
+    (symbol, price)
+  });
+  prices.extend(found);
+  prices
+}
+
+
+/// The implementation of [`Client::latest_prices`], using
+/// `issue_equity` and `issue_crypto` for issuing the requests for the
+/// respective asset class.
+async fn latest_prices_with<FE, RE, FC, RC>(
+  symbols: &[Symbol],
+  issue_equity: FE,
+  issue_crypto: FC,
+) -> HashMap<Symbol, Result<Num, PriceError>>
+where
+  FE: FnMut(Vec<String>) -> RE,
+  RE: Future<Output = Result<Vec<(String, Num)>, RequestError<last_trades::GetError>>>,
+  FC: FnMut(Vec<String>) -> RC,
+  RC: Future<Output = Result<Vec<(String, Num)>, RequestError<crypto::LatestTradesError>>>,
+{
+  let mut equities = Vec::new();
+  let mut cryptos = Vec::new();
+
+  for symbol in symbols {
+    match symbol {
+      Symbol::Equity(symbol) => equities.push(symbol.clone()),
+      Symbol::Crypto(symbol) => cryptos.push(symbol.clone()),
+    }
+  }
+
+  let (equities, cryptos) = join(
+    fetch(equities, invalid_equity, issue_equity),
+    fetch(cryptos, invalid_crypto, issue_crypto),
+  )
+  .await;
+
+  let equities = equities
+    .into_iter()
+    .map(|(symbol, price)| (Symbol::Equity(symbol), price));
+  let cryptos = cryptos
+    .into_iter()
+    .map(|(symbol, price)| (Symbol::Crypto(symbol), price));
+  equities.chain(cryptos).collect()
+}
+
+
+impl Client {
+  /// Retrieve the latest trade prices for a mixture of equity and
+  /// crypto currency symbols.
+  ///
+  /// The requests for the two asset classes are issued concurrently.
+  /// Long lists of symbols are split into multiple requests, which are
+  /// issued concurrently as well. Failures are reported on a per-symbol basis: a symbol that is
+  /// invalid or for which no trade could be found does not prevent
+  /// prices for the remaining ones from being retrieved.
+  pub async fn latest_prices(
+    &self,
+    symbols: &[Symbol],
+  ) -> HashMap<Symbol, Result<Num, PriceError>> {
+    latest_prices_with(
+      symbols,
+      |symbols| async move {
+        let request = last_trades::GetReqInit::default().init(symbols);
+        self
+          .issue::<last_trades::Get>(&request)
+          .await
+          .map(|trades| {
+            trades
+              .into_iter()
+              .map(|(symbol, trade)| (symbol, trade.price))
+              .collect()
+          })
+      },
+      |symbols| async move {
+        let request = crypto::LatestTradesReqInit::default().init(symbols);
+        self
+          .issue::<crypto::LatestTrades>(&request)
+          .await
+          .map(|trades| {
+            trades
+              .into_iter()
+              .map(|(symbol, trade)| (symbol, trade.price))
+              .collect()
+          })
+      },
+    )
+    .await
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use futures::future::ready;
+  use futures::future::Ready;
+
+  use test_log::test;
+
+
+  /// Check that equity and crypto currency symbols are parsed and
+  /// round-trip through their textual representation.
+  #[test]
+  fn parse_symbols() {
+    let symbols = [
+      ("AAPL", Symbol::Equity("AAPL".to_string())),
+      ("BRK.B", Symbol::Equity("BRK.B".to_string())),
+      ("BTC/USD", Symbol::Crypto("BTC/USD".to_string())),
+      ("ETH/BTC", Symbol::Crypto("ETH/BTC".to_string())),
+    ];
+
+    for (s, expected) in symbols {
+      let symbol = Symbol::from_str(s).unwrap();
+      assert_eq!(symbol, expected);
+      assert_eq!(symbol.to_string(), s);
+      assert_eq!(symbol.as_str(), s);
+    }
+  }
+
+  /// Make sure that malformed symbols are rejected with a descriptive
+  /// error.
+  #[test]
+  fn parse_malformed_symbols() {
+    assert_eq!(Symbol::from_str("").unwrap_err(), ParseSymbolError::Empty);
+
+    let err = Symbol::from_str("BTC/").unwrap_err();
+    assert_eq!(err, ParseSymbolError::InvalidPair("BTC/".to_string()));
+    assert_eq!(
+      err.to_string(),
+      r#"invalid crypto currency pair "BTC/": expected BASE/QUOTE"#
+    );
+
+    assert!(matches!(
+      Symbol::from_str("/USD"),
+      Err(ParseSymbolError::InvalidPair(..))
+    ));
+    assert!(matches!(
+      Symbol::from_str("BTC/USD/EUR"),
+      Err(ParseSymbolError::InvalidPair(..))
+    ));
+    assert!(matches!(
+      Symbol::from_str("BTC USD"),
+      Err(ParseSymbolError::Whitespace(..))
+    ));
+  }
+
+  /// Check that we retrieve prices for a mixture of equity and crypto
+  /// symbols, reporting invalid and non-existent ones individually.
+  #[test(tokio::test)]
+  async fn latest_prices_mixed() {
+    let symbols = [
+      Symbol::Equity("AAPL".to_string()),
+      Symbol::Equity("ABC123".to_string()),
+      Symbol::Crypto("BTC/USD".to_string()),
+      Symbol::Crypto("NOPE/USD".to_string()),
+    ];
+
+    let prices = latest_prices_with(
+      &symbols,
+      |symbols| {
+        let result = if symbols.iter().any(|symbol| symbol == "ABC123") {
+          let error = ApiError {
+            code: Some(40010001),
+            message: "invalid symbol: ABC123".to_string(),
+          };
+          Err(RequestError::Endpoint(last_trades::GetError::InvalidInput(
+            Ok(error),
+          )))
+        } else {
+          assert_eq!(symbols, vec!["AAPL"]);
+          Ok(vec![("AAPL".to_string(), Num::new(16786, 100))])
+        };
+        ready(result)
+      },
+      |symbols| {
+        assert_eq!(symbols, vec!["BTC/USD", "NOPE/USD"]);
+        ready(Ok(vec![("BTC/USD".to_string(), Num::new(676671, 10))]))
+      },
+    )
+    .await;
+
+    assert_eq!(prices.len(), 4);
+    assert_eq!(
+      prices[&Symbol::Equity("AAPL".to_string())]
+        .as_ref()
+        .unwrap(),
+      &Num::new(16786, 100)
+    );
+    assert!(matches!(
+      prices[&Symbol::Equity("ABC123".to_string())],
+      Err(PriceError::InvalidSymbol)
+    ));
+    assert_eq!(
+      prices[&Symbol::Crypto("BTC/USD".to_string())]
+        .as_ref()
+        .unwrap(),
+      &Num::new(676671, 10)
+    );
+    assert!(matches!(
+      prices[&Symbol::Crypto("NOPE/USD".to_string())],
+      Err(PriceError::NotFound)
+    ));
+  }
+
+  /// Check that symbols exceeding the per-request limit are split into
+  /// multiple requests, with the results merged.
+  #[test(tokio::test)]
+  async fn latest_prices_batched() {
+    let count = MAX_SYMBOLS_PER_REQUEST * 2 + 5;
+    let mut symbols = (0..count)
+      .map(|i| Symbol::Equity(format!("SYM{i}")))
+      .collect::<Vec<_>>();
+    symbols.push(Symbol::Equity("ABC123".to_string()));
+
+    let mut requests = 0;
+    let prices = latest_prices_with(
+      &symbols,
+      |symbols| {
+        requests += 1;
+        assert!(
+          symbols.len() <= MAX_SYMBOLS_PER_REQUEST,
+          "{}",
+          symbols.len()
+        );
+
+        let result = if symbols.iter().any(|symbol| symbol == "ABC123") {
+          let error = ApiError {
+            code: Some(40010001),
+            message: "invalid symbol: ABC123".to_string(),
+          };
+          Err(RequestError::Endpoint(last_trades::GetError::InvalidInput(
+            Ok(error),
+          )))
+        } else {
+          let trades = symbols
+            .into_iter()
+            .map(|symbol| (symbol, Num::from(1)))
+            .collect();
+          Ok(trades)
+        };
+        ready(result)
+      },
+      |_symbols| -> Ready<Result<_, RequestError<crypto::LatestTradesError>>> { unreachable!() },
+    )
+    .await;
+
+    // Three batches, one of which got retried without the invalid
+    // symbol.
+    assert_eq!(requests, 4);
+    assert_eq!(prices.len(), count + 1);
+    for i in 0..count {
+      let symbol = Symbol::Equity(format!("SYM{i}"));
+      assert_eq!(prices[&symbol].as_ref().unwrap(), &Num::from(1));
+    }
+    assert!(matches!(
+      prices[&Symbol::Equity("ABC123".to_string())],
+      Err(PriceError::InvalidSymbol)
+    ));
+  }
+
+  /// Make sure that a failed request for one asset class does not
+  /// affect prices of the other.
+  #[test(tokio::test)]
+  async fn latest_prices_request_error() {
+    let symbols = [
+      Symbol::Equity("SPY".to_string()),
+      Symbol::Crypto("ETH/USD".to_string()),
+    ];
+
+    let prices = latest_prices_with(
+      &symbols,
+      |_symbols| ready(Ok(vec![("SPY".to_string(), Num::from(500))])),
+      |_symbols| {
+        ready(Err(RequestError::Endpoint(
+          crypto::LatestTradesError::InvalidInput(Err(b"bad request".to_vec())),
+        )))
+      },
+    )
+    .await;
+
+    assert_eq!(
+      prices[&Symbol::Equity("SPY".to_string())].as_ref().unwrap(),
+      &Num::from(500)
+    );
+    assert!(matches!(
+      prices[&Symbol::Crypto("ETH/USD".to_string())],
+      Err(PriceError::Request(..))
+    ));
+  }
+}
@@ -1,10 +1,24 @@
 // Copyright (C) 2020-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+/// Functionality for retrieval of latest prices across asset classes.
+#[cfg(feature = "market-data")]
+pub mod latest;
+/// Definitions for the v1beta1 version of the Alpaca Data API.
+#[cfg(feature = "market-data")]
+pub mod v1beta1;
+/// Definitions for the v1beta3 version of the Alpaca Data API.
+#[cfg(feature = "market-data")]
+pub mod v1beta3;
 /// Definitions for the second version of the Alpaca Data API.
+#[cfg(feature = "market-data")]
 pub mod v2;
 
 /// The API base URL used for retrieving market data.
 pub(crate) const DATA_BASE_URL: &str = "https://data.alpaca.markets";
 /// The base URL for streaming market data over a websocket connection.
 pub(crate) const DATA_STREAM_BASE_URL: &str = "wss://stream.data.alpaca.markets";
+/// The maximum number of symbols we include in a single multi-symbol
+/// request. Longer lists of symbols are split into multiple requests.
+#[cfg(feature = "market-data")]
+pub(crate) const MAX_SYMBOLS_PER_REQUEST: usize = 100;
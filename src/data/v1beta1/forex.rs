@@ -0,0 +1,339 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use num_decimal::Num;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::from_slice as from_json;
+use serde_urlencoded::to_string as to_query;
+
+use crate::data::v2::bars::TimeFrame;
+use crate::data::v2::Sort;
+use crate::raw::RawApi;
+use crate::util::string_slice_to_str;
+use crate::Str;
+
+
+/// An exchange rate for a currency pair.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Rate {
+  /// The time stamp of this rate.
+  #[serde(rename = "t")]
+  pub time: DateTime<Utc>,
+  /// The bid price.
+  #[serde(rename = "bp")]
+  pub bid_price: Num,
+  /// The mid price.
+  #[serde(rename = "mp")]
+  pub mid_price: Num,
+  /// The ask price.
+  #[serde(rename = "ap")]
+  pub ask_price: Num,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A GET request to be made to the /v1beta1/forex/latest/rates
+/// endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct LatestReq {
+  /// The currency pairs to retrieve the latest rates for, e.g.,
+  /// "USDJPY".
+  #[serde(rename = "currency_pairs", serialize_with = "string_slice_to_str")]
+  pub currency_pairs: Vec<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`LatestReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct LatestReqInit {
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl LatestReqInit {
+  /// Create a [`LatestReq`] from a `LatestReqInit`.
+  #[inline]
+  pub fn init<I, S>(self, currency_pairs: I) -> LatestReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    LatestReq {
+      currency_pairs: currency_pairs.into_iter().map(S::into).collect(),
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a GET request to the
+  /// /v1beta1/forex/latest/rates endpoint.
+  pub Latest(LatestReq),
+  Ok => Vec<(String, Rate)>, [
+    /// The latest rates were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => LatestError, [
+    /// Some of the provided data was invalid or not found.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v1beta1/forex/latest/rates".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    /// A helper object for parsing the response to a `Latest` request.
+    #[derive(Deserialize)]
+    struct Response {
+      /// A mapping from currency pairs to rates.
+      // We use a `BTreeMap` here to have a consistent ordering of
+      // rates.
+      rates: BTreeMap<String, Rate>,
+    }
+
+    from_json::<Response>(body)
+      .map(|response| response.rates.into_iter().collect())
+      .map_err(Self::ConversionError::from)
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+/// A GET request to be made to the /v1beta1/forex/rates endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ListReq {
+  /// The currency pairs to retrieve historical rates for.
+  #[serde(rename = "currency_pairs", serialize_with = "string_slice_to_str")]
+  pub currency_pairs: Vec<String>,
+  /// Filter rates equal to or after this time.
+  #[serde(rename = "start")]
+  pub start: DateTime<Utc>,
+  /// Filter rates equal to or before this time.
+  #[serde(rename = "end")]
+  pub end: DateTime<Utc>,
+  /// The resolution of the rates to retrieve. Defaults to five minutes
+  /// on the server side.
+  #[serde(rename = "timeframe", skip_serializing_if = "Option::is_none")]
+  pub timeframe: Option<TimeFrame>,
+  /// The maximum number of rates to be returned for each page.
+  #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+  pub limit: Option<usize>,
+  /// The order in which to report rates. Defaults to ascending in
+  /// time, in which case the parameter is not sent at all.
+  #[serde(rename = "sort", skip_serializing_if = "Sort::is_ascending")]
+  pub sort: Sort,
+  /// Pagination token to continue from.
+  #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`ListReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ListReqInit {
+  /// See `ListReq::timeframe`.
+  pub timeframe: Option<TimeFrame>,
+  /// See `ListReq::limit`.
+  pub limit: Option<usize>,
+  /// See `ListReq::sort`.
+  pub sort: Sort,
+  /// See `ListReq::page_token`.
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl ListReqInit {
+  /// Create a [`ListReq`] from a `ListReqInit`.
+  #[inline]
+  pub fn init<I, S>(self, currency_pairs: I, start: DateTime<Utc>, end: DateTime<Utc>) -> ListReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    ListReq {
+      currency_pairs: currency_pairs.into_iter().map(S::into).collect(),
+      start,
+      end,
+      timeframe: self.timeframe,
+      limit: self.limit,
+      sort: self.sort,
+      page_token: self.page_token,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// A page of historical rates as returned by the /v1beta1/forex/rates
+/// endpoint.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Rates {
+  /// A mapping from currency pairs to their rates.
+  #[serde(rename = "rates")]
+  pub rates: BTreeMap<String, Vec<Rate>>,
+  /// The token to provide to a request to get the next page of rates
+  /// for this request.
+  #[serde(rename = "next_page_token")]
+  pub next_page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the /v1beta1/forex/rates
+  /// endpoint.
+  pub List(ListReq),
+  Ok => Rates, [
+    /// The historical rates were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => ListError, [
+    /// Some of the provided data was invalid or not found.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v1beta1/forex/rates".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use chrono::TimeZone as _;
+
+  use http_endpoint::Endpoint as _;
+
+  use serde_json::json;
+  use serde_json::to_vec as to_json;
+
+  use test_log::test;
+
+
+  /// Check that a latest rates response for two currency pairs
+  /// survives a round trip through serialization.
+  #[test]
+  fn latest_rates_round_trip() {
+    let response = br#"{
+      "rates": {
+        "USDJPY": {
+          "bp": 149.012,
+          "mp": 149.0175,
+          "ap": 149.023,
+          "t": "2024-01-19T15:20:00Z"
+        },
+        "EURUSD": {
+          "bp": 1.0887,
+          "mp": 1.08875,
+          "ap": 1.0888,
+          "t": "2024-01-19T15:20:01Z"
+        }
+      }
+    }"#;
+
+    let rates = Latest::parse(response).unwrap();
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[0].0, "EURUSD");
+    assert_eq!(rates[0].1.bid_price, Num::new(10887, 10000));
+    assert_eq!(rates[0].1.mid_price, Num::new(108875, 100000));
+    assert_eq!(rates[0].1.ask_price, Num::new(10888, 10000));
+    assert_eq!(rates[1].0, "USDJPY");
+    assert_eq!(rates[1].1.mid_price, Num::new(1490175, 10000));
+    assert_eq!(
+      rates[1].1.time,
+      DateTime::parse_from_rfc3339("2024-01-19T15:20:00Z").unwrap()
+    );
+
+    let map = rates.iter().cloned().collect::<BTreeMap<_, _>>();
+    let serialized = to_json(&json!({ "rates": map })).unwrap();
+    assert_eq!(Latest::parse(&serialized).unwrap(), rates);
+  }
+
+  /// Check that we serialize a historical rates request as expected.
+  #[test]
+  fn serialize_list_request_query() {
+    let start = Utc.timestamp_opt(1705622400, 0).unwrap();
+    let end = Utc.timestamp_opt(1705708800, 0).unwrap();
+    let request = ListReqInit {
+      timeframe: Some(TimeFrame::one_hour()),
+      page_token: Some("abc".to_string()),
+      ..Default::default()
+    }
+    .init(["USDJPY", "EURUSD"], start, end);
+
+    let query = List::query(&request).unwrap().unwrap();
+    let expected = "currency_pairs=USDJPY%2CEURUSD&start=2024-01-19T00%3A00%3A00Z&end=2024-01-20T00%3A00%3A00Z&timeframe=1Hour&page_token=abc";
+    assert_eq!(query, expected);
+  }
+
+  /// Check that we can parse a page of historical rates.
+  #[test]
+  fn parse_rates_page() {
+    let response = br#"{
+      "next_page_token": "VVNESlBZfDIwMjQtMDEtMTlUMDE6MDA6MDBa",
+      "rates": {
+        "USDJPY": [
+          {"bp": 148.1, "mp": 148.105, "ap": 148.11, "t": "2024-01-19T00:00:00Z"},
+          {"bp": 148.2, "mp": 148.205, "ap": 148.21, "t": "2024-01-19T01:00:00Z"}
+        ]
+      }
+    }"#;
+
+    let page = List::parse(response).unwrap();
+    assert_eq!(
+      page.next_page_token.as_deref(),
+      Some("VVNESlBZfDIwMjQtMDEtMTlUMDE6MDA6MDBa")
+    );
+    let rates = &page.rates["USDJPY"];
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[1].ask_price, Num::new(14821, 100));
+  }
+}
@@ -0,0 +1,169 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use num_decimal::Num;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::from_slice as from_json;
+use serde_urlencoded::to_string as to_query;
+
+use crate::raw::RawApi;
+use crate::util::string_slice_to_str;
+use crate::Str;
+
+
+/// A crypto currency trade.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Trade {
+  /// Time of the trade.
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+  /// The price of the trade.
+  #[serde(rename = "p")]
+  pub price: Num,
+  /// The size of the trade, which may be fractional.
+  #[serde(rename = "s")]
+  pub size: Num,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A GET request to be made to the /v1beta3/crypto/us/latest/trades
+/// endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct LatestTradesReq {
+  /// The symbols to retrieve the last trade for, e.g., "BTC/USD".
+  #[serde(rename = "symbols", serialize_with = "string_slice_to_str")]
+  pub symbols: Vec<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`LatestTradesReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct LatestTradesReqInit {
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl LatestTradesReqInit {
+  /// Create a [`LatestTradesReq`] from a `LatestTradesReqInit`.
+  #[inline]
+  pub fn init<I, S>(self, symbols: I) -> LatestTradesReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    LatestTradesReq {
+      symbols: symbols.into_iter().map(S::into).collect(),
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a GET request to the
+  /// /v1beta3/crypto/us/latest/trades endpoint.
+  pub LatestTrades(LatestTradesReq),
+  Ok => Vec<(String, Trade)>, [
+    /// The last trades were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => LatestTradesError, [
+    /// Some of the provided data was invalid or not found.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v1beta3/crypto/us/latest/trades".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    /// A helper object for parsing the response to a `LatestTrades`
+    /// request.
+    #[derive(Deserialize)]
+    struct Response {
+      /// A mapping from symbols to trade objects.
+      // We use a `BTreeMap` here to have a consistent ordering of
+      // trades.
+      trades: BTreeMap<String, Trade>,
+    }
+
+    from_json::<Response>(body)
+      .map(|response| response.trades.into_iter().collect())
+      .map_err(Self::ConversionError::from)
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use http_endpoint::Endpoint as _;
+
+  use test_log::test;
+
+
+  /// Check that we serialize a latest trades request as expected.
+  #[test]
+  fn serialize_latest_trades_request_query() {
+    let request = LatestTradesReqInit::default().init(["BTC/USD", "ETH/USD"]);
+    let query = LatestTrades::query(&request).unwrap().unwrap();
+    assert_eq!(query, "symbols=BTC%2FUSD%2CETH%2FUSD");
+  }
+
+  /// Check that we can parse the reference trades from the
+  /// documentation.
+  #[test]
+  fn parse_reference_trades() {
+    let response = br#"{
+      "trades": {
+        "BTC/USD": {
+          "i": 4355855040738683600,
+          "p": 67667.1,
+          "s": 0.000185,
+          "t": "2024-03-25T14:49:52.711Z",
+          "tks": "B"
+        }
+      }
+    }"#;
+
+    let trades = LatestTrades::parse(response).unwrap();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].0, "BTC/USD");
+    assert_eq!(
+      trades[0].1.timestamp,
+      DateTime::parse_from_rfc3339("2024-03-25T14:49:52.711Z").unwrap()
+    );
+    assert_eq!(trades[0].1.price, Num::new(676671, 10));
+    assert_eq!(trades[0].1.size, Num::new(185, 1000000));
+  }
+}
@@ -0,0 +1,410 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+
+use num_decimal::Num;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_urlencoded::to_string as to_query;
+
+use crate::data::v2::Sort;
+use crate::raw::RawApi;
+use crate::util::string_slice_to_str;
+use crate::util::vec_from_str;
+use crate::Str;
+
+
+/// A single price reported for an opening or closing auction.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct AuctionPrice {
+  /// The time stamp of the price.
+  #[serde(rename = "t")]
+  pub time: DateTime<Utc>,
+  /// The auction price.
+  #[serde(rename = "p")]
+  pub price: Num,
+  /// The auction size.
+  #[serde(rename = "s")]
+  pub size: u64,
+  /// The code of the exchange the price was reported by.
+  #[serde(rename = "x")]
+  pub exchange: String,
+  /// The condition attached to the price.
+  #[serde(rename = "c")]
+  pub condition: String,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// The opening and closing auctions of a single trading day.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Auction {
+  /// The trading day the auctions took place on.
+  #[serde(rename = "d")]
+  pub date: NaiveDate,
+  /// The prices reported for the opening auction.
+  #[serde(rename = "o", default, deserialize_with = "vec_from_str")]
+  pub opening: Vec<AuctionPrice>,
+  /// The prices reported for the closing auction.
+  #[serde(rename = "c", default, deserialize_with = "vec_from_str")]
+  pub closing: Vec<AuctionPrice>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A page of auctions for a single symbol, as returned by the
+/// /v2/stocks/{symbol}/auctions endpoint.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Auctions {
+  /// The list of returned auctions.
+  #[serde(rename = "auctions", deserialize_with = "vec_from_str")]
+  pub auctions: Vec<Auction>,
+  /// The symbol the auctions correspond to.
+  #[serde(rename = "symbol")]
+  pub symbol: String,
+  /// The token to provide to a request to get the next page of
+  /// auctions for this request.
+  #[serde(rename = "next_page_token")]
+  pub next_page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A page of auctions for multiple symbols, as returned by the
+/// /v2/stocks/auctions endpoint.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct MultiAuctions {
+  /// A mapping from symbols to their auctions.
+  #[serde(rename = "auctions", default)]
+  pub auctions: HashMap<String, Vec<Auction>>,
+  /// The token to provide to a request to get the next page of
+  /// auctions for this request.
+  #[serde(rename = "next_page_token")]
+  pub next_page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A GET request to be made to the /v2/stocks/{symbol}/auctions
+/// endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct GetReq {
+  /// The symbol to retrieve auctions for.
+  #[serde(skip)]
+  pub symbol: String,
+  /// Filter auctions equal to or after this time.
+  #[serde(rename = "start")]
+  pub start: DateTime<Utc>,
+  /// Filter auctions equal to or before this time.
+  #[serde(rename = "end")]
+  pub end: DateTime<Utc>,
+  /// The maximum number of auctions to be returned for each page.
+  #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+  pub limit: Option<usize>,
+  /// The order in which to report auctions. Defaults to ascending in
+  /// time, in which case the parameter is not sent at all.
+  #[serde(rename = "sort", skip_serializing_if = "Sort::is_ascending")]
+  pub sort: Sort,
+  /// Pagination token to continue from.
+  #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A GET request to be made to the /v2/stocks/auctions endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ListReq {
+  /// The symbols to retrieve auctions for.
+  #[serde(rename = "symbols", serialize_with = "string_slice_to_str")]
+  pub symbols: Vec<String>,
+  /// Filter auctions equal to or after this time.
+  #[serde(rename = "start")]
+  pub start: DateTime<Utc>,
+  /// Filter auctions equal to or before this time.
+  #[serde(rename = "end")]
+  pub end: DateTime<Utc>,
+  /// The maximum number of auctions to be returned for each page,
+  /// across all symbols.
+  #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+  pub limit: Option<usize>,
+  /// The order in which to report auctions. Defaults to ascending in
+  /// time, in which case the parameter is not sent at all.
+  #[serde(rename = "sort", skip_serializing_if = "Sort::is_ascending")]
+  pub sort: Sort,
+  /// Pagination token to continue from.
+  #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`GetReq`] and [`ListReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReqInit {
+  /// See `GetReq::limit`.
+  pub limit: Option<usize>,
+  /// See `GetReq::sort`.
+  pub sort: Sort,
+  /// See `GetReq::page_token`.
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl ReqInit {
+  /// Create a [`GetReq`] for a single symbol from a `ReqInit`.
+  #[inline]
+  pub fn init<S>(self, symbol: S, start: DateTime<Utc>, end: DateTime<Utc>) -> GetReq
+  where
+    S: Into<String>,
+  {
+    GetReq {
+      symbol: symbol.into(),
+      start,
+      end,
+      limit: self.limit,
+      sort: self.sort,
+      page_token: self.page_token,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Create a [`ListReq`] for multiple symbols from a `ReqInit`.
+  #[inline]
+  pub fn init_multi<I, S>(self, symbols: I, start: DateTime<Utc>, end: DateTime<Utc>) -> ListReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    ListReq {
+      symbols: symbols.into_iter().map(S::into).collect(),
+      start,
+      end,
+      limit: self.limit,
+      sort: self.sort,
+      page_token: self.page_token,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the
+  /// /v2/stocks/{symbol}/auctions endpoint.
+  pub Get(GetReq),
+  Ok => Auctions, [
+    /// The auction information was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetError, [
+    /// Some of the provided data was invalid or not found.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(input: &Self::Input) -> Str {
+    path!("/v2/stocks/{}/auctions", input.symbol).into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the /v2/stocks/auctions
+  /// endpoint.
+  pub List(ListReq),
+  Ok => MultiAuctions, [
+    /// The auction information was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => ListError, [
+    /// One of the provided symbols was invalid or not found.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/stocks/auctions".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+
+  use chrono::TimeZone as _;
+
+  use http_endpoint::Endpoint as _;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+  use crate::Client;
+
+
+  /// A captured response to a single symbol auctions request.
+  const AUCTIONS: &str = r#"{
+  "auctions": [
+    {
+      "d": "2024-01-02",
+      "o": [
+        {"c": "@", "p": 187.15, "s": 1220577, "t": "2024-01-02T14:30:01.837881409Z", "x": "P"},
+        {"c": "Q", "p": 187.15, "s": 2511895, "t": "2024-01-02T14:30:01.932813926Z", "x": "Q"}
+      ],
+      "c": [
+        {"c": "M", "p": 185.64, "s": 6972779, "t": "2024-01-02T21:00:00.180534538Z", "x": "Q"}
+      ]
+    },
+    {
+      "d": "2024-01-03",
+      "o": [
+        {"c": "Q", "p": 184.22, "s": 1253521, "t": "2024-01-03T14:30:01.646394427Z", "x": "Q"}
+      ],
+      "c": null
+    }
+  ],
+  "symbol": "AAPL",
+  "next_page_token": "QUFQTHwyMDI0LTAxLTAz"
+}"#;
+
+
+  /// Check that we can deserialize a captured auctions response,
+  /// including the nested opening and closing prices.
+  #[test]
+  fn parse_reference_auctions() {
+    let auctions = Get::parse(AUCTIONS.as_bytes()).unwrap();
+    assert_eq!(auctions.symbol, "AAPL");
+    assert_eq!(
+      auctions.next_page_token.as_deref(),
+      Some("QUFQTHwyMDI0LTAxLTAz")
+    );
+    assert_eq!(auctions.auctions.len(), 2);
+
+    let first = &auctions.auctions[0];
+    assert_eq!(first.date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+    assert_eq!(first.opening.len(), 2);
+    assert_eq!(
+      first.opening[0].time,
+      DateTime::<Utc>::from_str("2024-01-02T14:30:01.837881409Z").unwrap()
+    );
+    assert_eq!(first.opening[0].price, Num::new(18715, 100));
+    assert_eq!(first.opening[0].size, 1220577);
+    assert_eq!(first.opening[0].exchange, "P");
+    assert_eq!(first.opening[0].condition, "@");
+    assert_eq!(first.opening[1].exchange, "Q");
+    assert_eq!(first.closing.len(), 1);
+    assert_eq!(first.closing[0].price, Num::new(18564, 100));
+    assert_eq!(first.closing[0].condition, "M");
+
+    let second = &auctions.auctions[1];
+    assert_eq!(second.opening.len(), 1);
+    assert_eq!(second.closing, Vec::new());
+  }
+
+  /// Check that we can deserialize a multi-symbol auctions response.
+  #[test]
+  fn parse_multi_auctions() {
+    let response = br#"{
+  "auctions": {
+    "AAPL": [
+      {"d": "2024-01-02", "o": [], "c": [
+        {"c": "M", "p": 185.64, "s": 6972779, "t": "2024-01-02T21:00:00.180534538Z", "x": "Q"}
+      ]}
+    ],
+    "MSFT": [
+      {"d": "2024-01-02", "o": [
+        {"c": "Q", "p": 373.86, "s": 402365, "t": "2024-01-02T14:30:01.209085806Z", "x": "Q"}
+      ], "c": []}
+    ]
+  },
+  "next_page_token": null
+}"#;
+
+    let auctions = List::parse(response).unwrap();
+    assert_eq!(auctions.next_page_token, None);
+    assert_eq!(auctions.auctions.len(), 2);
+    assert_eq!(
+      auctions.auctions["AAPL"][0].closing[0].price,
+      Num::new(18564, 100)
+    );
+    assert_eq!(
+      auctions.auctions["MSFT"][0].opening[0].price,
+      Num::new(37386, 100)
+    );
+  }
+
+  /// Check that we serialize a multi-symbol auctions request as
+  /// expected.
+  #[test]
+  fn serialize_list_request_query() {
+    let start = Utc.timestamp_opt(1704153600, 0).unwrap();
+    let end = Utc.timestamp_opt(1704240000, 0).unwrap();
+    let request = ReqInit {
+      limit: Some(10),
+      sort: Sort::Descending,
+      ..Default::default()
+    }
+    .init_multi(["AAPL", "MSFT"], start, end);
+
+    let query = List::query(&request).unwrap().unwrap();
+    let expected = "symbols=AAPL%2CMSFT&start=2024-01-02T00%3A00%3A00Z&end=2024-01-03T00%3A00%3A00Z&limit=10&sort=desc";
+    assert_eq!(query, expected);
+  }
+
+  /// Verify that we can retrieve the auctions for a symbol.
+  #[test(tokio::test)]
+  async fn request_auctions() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let start = DateTime::from_str("2024-01-02T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2024-01-05T00:00:00Z").unwrap();
+    let request = ReqInit::default().init("AAPL", start, end);
+    let auctions = client.issue::<Get>(&request).await.unwrap();
+    assert_eq!(auctions.symbol, "AAPL");
+    assert_eq!(auctions.auctions.len(), 3);
+  }
+}
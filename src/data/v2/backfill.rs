@@ -0,0 +1,439 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::future::Future;
+use std::pin::Pin;
+use std::vec::IntoIter;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::TimeZone as _;
+use chrono::Utc;
+
+use futures::ready;
+use futures::stream::FusedStream;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::FutureExt as _;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use num_decimal::Num;
+
+use serde_json::Error as JsonError;
+
+use thiserror::Error as ThisError;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+
+use crate::data::v2::bars;
+use crate::data::v2::bars::TimeFrame;
+use crate::data::v2::stream::drive;
+use crate::data::v2::stream::Bar;
+use crate::data::v2::stream::Data;
+use crate::data::v2::stream::MarketData;
+use crate::data::v2::stream::RealtimeData;
+use crate::data::v2::stream::Source;
+use crate::data::v2::stream::SourceVariant;
+use crate::data::v2::Feed;
+use crate::data::v2::Sort;
+use crate::Client;
+use crate::Error;
+use crate::RequestError;
+use crate::Subscribable;
+
+
+/// The maximum number of bars the API reports in a single page.
+const MAX_PAGE_LIMIT: usize = 10_000;
+/// The factor by which to widen the time range requested for the
+/// backfill beyond what the number of bars would cover back to back,
+/// accounting for periods without trading, such as nights and
+/// weekends.
+const LOOKBACK_FACTOR: usize = 7;
+
+
+/// The stream type of a connection to the real time market data stream
+/// using source `S`.
+type LiveStream<S> = <RealtimeData<S> as Subscribable>::Stream;
+/// The subscription type of a connection to the real time market data
+/// stream using source `S`.
+type LiveSubscription<S> = <RealtimeData<S> as Subscribable>::Subscription;
+
+
+/// An error as reported by [`Client::bars_then_stream`].
+#[derive(Debug, ThisError)]
+pub enum BackfillError {
+  /// Connecting to or subscribing on the real time market data stream
+  /// failed.
+  #[error("failed to subscribe to real time bars")]
+  Stream(#[source] Error),
+  /// Retrieval of the historical bars failed.
+  #[error("failed to retrieve historical bars")]
+  Bars(#[source] RequestError<bars::ListError>),
+}
+
+
+/// A stream reporting a set of historical bars followed by the items
+/// of a real time market data stream, as created by
+/// [`Client::bars_then_stream`].
+///
+/// Real time bars for the backfilled symbol that are not more recent
+/// than the last historical bar are dropped, so that no bar is
+/// reported twice at the seam between the two.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Backfilled<St> {
+  /// The historical bars not yet reported, in ascending order.
+  backfill: IntoIter<Bar>,
+  /// The symbol the historical bars belong to.
+  symbol: String,
+  /// The time stamp of the most recent historical bar, if any.
+  seam: Option<DateTime<Utc>>,
+  /// The real time market data stream.
+  live: St,
+}
+
+impl<St> Backfilled<St> {
+  /// Create a new `Backfilled` object reporting `backfill`, which is
+  /// expected to be sorted by time, before the items of `live`.
+  fn new(symbol: String, backfill: Vec<Bar>, live: St) -> Self {
+    Self {
+      seam: backfill.last().map(|bar| bar.timestamp),
+      backfill: backfill.into_iter(),
+      symbol,
+      live,
+    }
+  }
+
+  /// Retrieve the time stamp of the most recent historical bar, if
+  /// any.
+  #[inline]
+  pub fn seam(&self) -> Option<DateTime<Utc>> {
+    self.seam
+  }
+
+  /// Check whether the provided real time bar was covered by the
+  /// historical bars already.
+  fn is_backfilled(&self, bar: &Bar) -> bool {
+    bar.symbol == self.symbol && self.seam.map_or(false, |seam| bar.timestamp <= seam)
+  }
+}
+
+impl<St, Q, T> Stream for Backfilled<St>
+where
+  St: Stream<Item = Result<Result<Data<Bar, Q, T>, JsonError>, WebSocketError>> + Unpin,
+{
+  type Item = St::Item;
+
+  fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    if let Some(bar) = self.backfill.next() {
+      return Poll::Ready(Some(Ok(Ok(Data::Bar(bar)))))
+    }
+
+    loop {
+      match ready!(self.live.poll_next_unpin(ctx)) {
+        Some(Ok(Ok(Data::Bar(bar)))) if self.is_backfilled(&bar) => continue,
+        item => return Poll::Ready(item),
+      }
+    }
+  }
+}
+
+impl<St, Q, T> FusedStream for Backfilled<St>
+where
+  St: FusedStream<Item = Result<Result<Data<Bar, Q, T>, JsonError>, WebSocketError>> + Unpin,
+{
+  #[inline]
+  fn is_terminated(&self) -> bool {
+    self.backfill.len() == 0 && self.live.is_terminated()
+  }
+}
+
+
+/// Convert a historical bar into its real time equivalent.
+fn to_stream_bar(symbol: &str, bar: bars::Bar) -> Bar {
+  Bar {
+    symbol: symbol.to_string(),
+    open_price: bar.open,
+    high_price: bar.high,
+    low_price: bar.low,
+    close_price: bar.close,
+    volume: Num::from(bar.volume),
+    timestamp: bar.time,
+  }
+}
+
+
+/// Map a real time data source to the equivalent historical feed, if
+/// any.
+fn feed<S>() -> Option<Feed>
+where
+  S: Source,
+{
+  match S::source() {
+    SourceVariant::PathComponent("iex") => Some(Feed::IEX),
+    SourceVariant::PathComponent("sip") => Some(Feed::SIP),
+    _ => None,
+  }
+}
+
+
+/// Retrieve up to `limit` of the most recent bars as described by
+/// `request`, using `issue` for issuing the actual requests.
+///
+/// Bars are requested in descending order, page by page, and reported
+/// in ascending order.
+async fn fetch<F, R>(
+  mut request: bars::ListReq,
+  limit: usize,
+  mut issue: F,
+) -> Result<Vec<bars::Bar>, RequestError<bars::ListError>>
+where
+  F: FnMut(bars::ListReq) -> R,
+  R: Future<Output = Result<bars::Bars, RequestError<bars::ListError>>>,
+{
+  let mut result = Vec::with_capacity(limit.min(MAX_PAGE_LIMIT));
+  request.sort = Sort::Descending;
+
+  while result.len() < limit {
+    request.limit = Some((limit - result.len()).min(MAX_PAGE_LIMIT));
+
+    let page = issue(request.clone()).await?;
+    result.extend(page.bars);
+
+    match page.next_page_token {
+      Some(token) => request.page_token = Some(token),
+      None => break,
+    }
+  }
+
+  result.truncate(limit);
+  result.reverse();
+  Ok(result)
+}
+
+
+impl Client {
+  /// Subscribe to real time bars for the provided symbol, preceded by
+  /// up to `backfill_limit` of its most recent historical bars.
+  ///
+  /// The subscription is established before historical bars are
+  /// retrieved, so that no bar falls into the gap between the two. Real
+  /// time bars already covered by the historical ones are dropped. Note
+  /// that the real time stream reports minute bars, so `timeframe`
+  /// should be [`TimeFrame::one_minute`] for the two to line up.
+  ///
+  /// Historical bars are retrieved from the feed matching the data
+  /// source `S`, if any.
+  pub async fn bars_then_stream<S>(
+    &self,
+    symbol: &str,
+    timeframe: TimeFrame,
+    backfill_limit: usize,
+  ) -> Result<(Backfilled<LiveStream<S>>, LiveSubscription<S>), BackfillError>
+  where
+    S: Source,
+  {
+    let (mut stream, mut subscription) = self
+      .subscribe::<RealtimeData<S>>()
+      .await
+      .map_err(BackfillError::Stream)?;
+
+    let mut data = MarketData::default();
+    let () = data.set_bars(vec![symbol.to_string()]);
+
+    let subscribe = subscription.subscribe(&data).boxed();
+    let error = match drive(subscribe, &mut stream).await {
+      Ok(Ok(Ok(_))) => None,
+      Ok(Ok(Err(error))) => Some(error),
+      Ok(Err(error)) | Err(Err(error)) => Some(Error::WebSocket(error)),
+      Err(Ok(Err(error))) => Some(Error::Json(error)),
+      Err(Ok(Ok(data))) => Some(Error::Str(
+        format!("failed to subscribe to bars: {data:?}").into(),
+      )),
+    };
+
+    if let Some(error) = error {
+      return Err(BackfillError::Stream(error))
+    }
+
+    let backfill = if backfill_limit > 0 {
+      let end = Utc::now();
+      let count = backfill_limit.saturating_mul(LOOKBACK_FACTOR);
+      // An excessive look back simply covers all the data there is.
+      let start = i64::try_from(count)
+        .ok()
+        .and_then(|count| timeframe.duration().num_milliseconds().checked_mul(count))
+        .and_then(|lookback| end.checked_sub_signed(Duration::milliseconds(lookback)))
+        .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap());
+
+      let request = bars::ListReqInit {
+        feed: feed::<S>(),
+        ..Default::default()
+      }
+      .init(symbol, start, end, timeframe);
+
+      fetch(request, backfill_limit, |request| async move {
+        self.issue::<bars::List>(&request).await
+      })
+      .await
+      .map_err(BackfillError::Bars)?
+    } else {
+      Vec::new()
+    };
+
+    let backfill = backfill
+      .into_iter()
+      .map(|bar| to_stream_bar(symbol, bar))
+      .collect();
+    let stream = Backfilled::new(symbol.to_string(), backfill, stream);
+    Ok((stream, subscription))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+
+  use futures::future::ready;
+  use futures::stream::iter;
+
+  use test_log::test;
+
+  use crate::data::v2::stream::Quote;
+  use crate::data::v2::stream::Trade;
+
+
+  /// Create a real time bar for the given symbol and time.
+  fn bar(symbol: &str, time: &str) -> Bar {
+    Bar {
+      symbol: symbol.to_string(),
+      open_price: Num::from(10),
+      high_price: Num::from(12),
+      low_price: Num::from(9),
+      close_price: Num::from(11),
+      volume: Num::from(100),
+      timestamp: DateTime::from_str(time).unwrap(),
+    }
+  }
+
+  /// Create a historical bar for the given time.
+  fn historical_bar(time: &str) -> bars::Bar {
+    bars::Bar {
+      time: DateTime::from_str(time).unwrap(),
+      open: Num::from(10),
+      close: Num::from(11),
+      high: Num::from(12),
+      low: Num::from(9),
+      volume: 100,
+      weighted_average: Num::from(11),
+      trade_count: 0,
+      _non_exhaustive: (),
+    }
+  }
+
+
+  /// Check that no bar is reported twice at the seam between
+  /// historical and real time bars.
+  #[test(tokio::test)]
+  async fn no_duplicate_at_seam() {
+    let backfill = vec![
+      bar("AAPL", "2021-02-22T19:13:00Z"),
+      bar("AAPL", "2021-02-22T19:14:00Z"),
+      bar("AAPL", "2021-02-22T19:15:00Z"),
+    ];
+    let live = iter(vec![
+      Ok(Ok(Data::<Bar, Quote, Trade>::Bar(bar(
+        "AAPL",
+        "2021-02-22T19:14:00Z",
+      )))),
+      Ok(Ok(Data::Bar(bar("AAPL", "2021-02-22T19:15:00Z")))),
+      Ok(Ok(Data::Bar(bar("MSFT", "2021-02-22T19:15:00Z")))),
+      Ok(Ok(Data::Bar(bar("AAPL", "2021-02-22T19:16:00Z")))),
+    ]);
+
+    let stream = Backfilled::new("AAPL".to_string(), backfill, live);
+    assert_eq!(
+      stream.seam(),
+      Some(DateTime::from_str("2021-02-22T19:15:00Z").unwrap())
+    );
+
+    let bars = stream
+      .map(|result| match result {
+        Ok(Ok(Data::Bar(bar))) => bar,
+        _ => panic!("unexpected item"),
+      })
+      .collect::<Vec<_>>()
+      .await;
+
+    let aapl = bars
+      .iter()
+      .filter(|bar| bar.symbol == "AAPL")
+      .map(|bar| bar.timestamp)
+      .collect::<Vec<_>>();
+    assert_eq!(aapl.len(), 4);
+    assert!(aapl.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(
+      aapl.last().unwrap(),
+      &DateTime::<Utc>::from_str("2021-02-22T19:16:00Z").unwrap()
+    );
+
+    // Bars for other symbols are unaffected by the seam.
+    assert_eq!(bars.iter().filter(|bar| bar.symbol == "MSFT").count(), 1);
+  }
+
+  /// Verify that the most recent historical bars are retrieved page by
+  /// page and reported in ascending order.
+  #[test(tokio::test)]
+  async fn fetch_most_recent_bars() {
+    let start = DateTime::from_str("2021-02-22T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-02-23T00:00:00Z").unwrap();
+    let request = bars::ListReqInit::default().init("AAPL", start, end, TimeFrame::one_minute());
+
+    let mut requests = Vec::new();
+    let bars = fetch(request, 3, |request| {
+      let page = if request.page_token.is_none() {
+        bars::Bars {
+          bars: vec![
+            historical_bar("2021-02-22T19:16:00Z"),
+            historical_bar("2021-02-22T19:15:00Z"),
+          ],
+          symbol: "AAPL".to_string(),
+          next_page_token: Some("next".to_string()),
+          _non_exhaustive: (),
+        }
+      } else {
+        bars::Bars {
+          bars: vec![historical_bar("2021-02-22T19:14:00Z")],
+          symbol: "AAPL".to_string(),
+          next_page_token: Some("more".to_string()),
+          _non_exhaustive: (),
+        }
+      };
+      requests.push((request.sort, request.limit));
+      ready(Ok(page))
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(
+      requests,
+      vec![(Sort::Descending, Some(3)), (Sort::Descending, Some(1))]
+    );
+    let times = bars
+      .iter()
+      .map(|bar| bar.time.to_rfc3339())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      times,
+      vec![
+        "2021-02-22T19:14:00+00:00",
+        "2021-02-22T19:15:00+00:00",
+        "2021-02-22T19:16:00+00:00",
+      ]
+    );
+  }
+}
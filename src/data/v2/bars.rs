@@ -1,36 +1,256 @@
 // Copyright (C) 2021-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::str::FromStr;
+
 use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveTime;
+use chrono::TimeZone as _;
 use chrono::Utc;
 
 use num_decimal::Num;
 
 use serde::Deserialize;
 use serde::Serialize;
+use serde::Serializer;
 use serde_urlencoded::to_string as to_query;
 
+use thiserror::Error as ThisError;
+
+use tracing::warn;
+
+use crate::api::v2::calendar::market_offset;
+use crate::data::v2::corporate_actions;
+use crate::data::v2::corporate_actions::CashDividend;
+use crate::data::v2::corporate_actions::CorporateActions;
+use crate::data::v2::corporate_actions::Split;
+use crate::data::v2::range::check_time_range;
 use crate::data::v2::Feed;
-use crate::data::DATA_BASE_URL;
+use crate::data::v2::Sort;
+use crate::endpoint::ConversionError;
+use crate::raw::RawApi;
 use crate::util::vec_from_str;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
-/// An enumeration of the various supported time frames.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+/// The unit of a [`TimeFrame`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[non_exhaustive]
-pub enum TimeFrame {
-  /// A time frame of one minute.
-  #[serde(rename = "1Min")]
-  OneMinute,
-  /// A time frame of one hour.
-  #[serde(rename = "1Hour")]
-  OneHour,
-  /// A time frame of one day.
-  #[serde(rename = "1Day")]
-  OneDay,
+pub enum Unit {
+  /// A minute.
+  Minute,
+  /// An hour.
+  Hour,
+  /// A day.
+  Day,
+  /// A week.
+  Week,
+  /// A month.
+  Month,
+}
+
+impl Unit {
+  /// Retrieve the suffix used for representing the unit in a time
+  /// frame, e.g., "Min" for minutes.
+  fn suffix(&self) -> &'static str {
+    match self {
+      Self::Minute => "Min",
+      Self::Hour => "Hour",
+      Self::Day => "Day",
+      Self::Week => "Week",
+      Self::Month => "Month",
+    }
+  }
+
+  /// Check whether `amount` is a valid multiplier for the unit, as
+  /// accepted by Alpaca.
+  fn is_valid_amount(&self, amount: u32) -> bool {
+    match self {
+      Self::Minute => (1..=59).contains(&amount),
+      Self::Hour => (1..=23).contains(&amount),
+      Self::Day | Self::Week => amount == 1,
+      Self::Month => matches!(amount, 1 | 2 | 3 | 4 | 6 | 12),
+    }
+  }
+
+  /// Retrieve a textual description of the multipliers accepted for
+  /// the unit.
+  fn valid_amounts(&self) -> &'static str {
+    match self {
+      Self::Minute => "between 1 and 59",
+      Self::Hour => "between 1 and 23",
+      Self::Day | Self::Week => "1",
+      Self::Month => "one of 1, 2, 3, 4, 6, or 12",
+    }
+  }
+}
+
+
+/// An error indicating that a time frame is malformed or not
+/// supported by Alpaca.
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum InvalidTimeFrame {
+  /// The multiplier is missing or not a number, e.g., "Min".
+  #[error("invalid time frame {0}: missing or malformed multiplier")]
+  InvalidAmount(String),
+  /// The unit is not known, e.g., "5Xyz".
+  #[error("invalid time frame {0}: unknown unit (expected Min, Hour, Day, Week, or Month)")]
+  UnknownUnit(String),
+  /// The multiplier is not supported for the unit, e.g., "0Min".
+  #[error("invalid time frame {0}: multiplier must be {1}")]
+  UnsupportedAmount(String, &'static str),
+}
+
+
+/// A time frame, i.e., the period covered by a single bar, for example
+/// five minutes.
+///
+/// Alpaca only accepts certain multipliers per unit: 1-59 for minutes,
+/// 1-23 for hours, 1 for days and weeks, and 1, 2, 3, 4, 6, or 12 for
+/// months.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimeFrame {
+  /// The multiplier of the unit.
+  amount: u32,
+  /// The unit.
+  unit: Unit,
 }
 
+impl TimeFrame {
+  /// Create a new `TimeFrame` spanning `amount` times `unit`.
+  pub fn new(amount: u32, unit: Unit) -> Result<Self, InvalidTimeFrame> {
+    if unit.is_valid_amount(amount) {
+      Ok(Self { amount, unit })
+    } else {
+      Err(InvalidTimeFrame::UnsupportedAmount(
+        format!("{amount}{}", unit.suffix()),
+        unit.valid_amounts(),
+      ))
+    }
+  }
+
+  /// Create a time frame of one minute.
+  #[inline]
+  pub const fn one_minute() -> Self {
+    Self {
+      amount: 1,
+      unit: Unit::Minute,
+    }
+  }
+
+  /// Create a time frame of one hour.
+  #[inline]
+  pub const fn one_hour() -> Self {
+    Self {
+      amount: 1,
+      unit: Unit::Hour,
+    }
+  }
+
+  /// Create a time frame of one day.
+  #[inline]
+  pub const fn one_day() -> Self {
+    Self {
+      amount: 1,
+      unit: Unit::Day,
+    }
+  }
+
+  /// Create a time frame of one week.
+  #[inline]
+  pub const fn one_week() -> Self {
+    Self {
+      amount: 1,
+      unit: Unit::Week,
+    }
+  }
+
+  /// Create a time frame of one month.
+  #[inline]
+  pub const fn one_month() -> Self {
+    Self {
+      amount: 1,
+      unit: Unit::Month,
+    }
+  }
+
+  /// Retrieve the multiplier of the time frame's unit.
+  #[inline]
+  pub fn amount(&self) -> u32 {
+    self.amount
+  }
+
+  /// Retrieve the time frame's unit.
+  #[inline]
+  pub fn unit(&self) -> Unit {
+    self.unit
+  }
+
+  /// Retrieve the duration covered by a single bar of this time frame.
+  ///
+  /// Months are assumed to be 28 days long, so that the duration is
+  /// never overestimated.
+  pub(crate) fn duration(&self) -> Duration {
+    let unit = match self.unit {
+      Unit::Minute => Duration::minutes(1),
+      Unit::Hour => Duration::hours(1),
+      Unit::Day => Duration::days(1),
+      Unit::Week => Duration::weeks(1),
+      Unit::Month => Duration::days(28),
+    };
+    unit * i32::try_from(self.amount).unwrap_or(i32::MAX)
+  }
+}
+
+impl Display for TimeFrame {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+    write!(fmt, "{}{}", self.amount, self.unit.suffix())
+  }
+}
+
+impl FromStr for TimeFrame {
+  type Err = InvalidTimeFrame;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, suffix) = s.split_at(split);
+    let unit = match suffix {
+      "Min" => Unit::Minute,
+      "Hour" => Unit::Hour,
+      "Day" => Unit::Day,
+      "Week" => Unit::Week,
+      "Month" => Unit::Month,
+      _ => return Err(InvalidTimeFrame::UnknownUnit(s.to_string())),
+    };
+    let amount = amount
+      .parse()
+      .map_err(|_| InvalidTimeFrame::InvalidAmount(s.to_string()))?;
+    Self::new(amount, unit)
+  }
+}
+
+impl Serialize for TimeFrame {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    serializer.collect_str(self)
+  }
+}
+
+
+/// The estimated number of bars above which we warn about a request
+/// likely requiring extensive pagination.
+const LARGE_REQUEST_BARS: u64 = 100_000;
+
 
 /// An enumeration of the possible adjustments.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
@@ -81,15 +301,64 @@ pub struct ListReq {
   /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
   #[serde(rename = "feed")]
   pub feed: Option<Feed>,
+  /// The order in which to report bars. Defaults to ascending in time,
+  /// in which case the parameter is not sent at all.
+  #[serde(rename = "sort", skip_serializing_if = "Sort::is_ascending")]
+  pub sort: Sort,
   /// If provided we will pass a page token to continue where we left off.
   #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
   pub page_token: Option<String>,
+  /// An optional upper bound on the estimated number of bars covered
+  /// by the request.
+  ///
+  /// Requests exceeding it are rejected before being issued. See
+  /// [`ListReq::estimated_bars`].
+  #[serde(skip)]
+  pub max_bars: Option<u64>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
+impl ListReq {
+  /// Estimate the number of bars covered by the request's time range.
+  ///
+  /// The estimate is an upper bound based on wall clock time and does
+  /// not take into account trading hours, weekends, or holidays.
+  pub fn estimated_bars(&self) -> u64 {
+    let span = self.end.signed_duration_since(self.start);
+    if span < Duration::zero() {
+      return 0
+    }
+
+    let count = span.num_seconds() / self.timeframe.duration().num_seconds();
+    // Both ends of the range are inclusive.
+    u64::try_from(count).unwrap_or(u64::MAX).saturating_add(1)
+  }
+
+  /// Check the request's estimated size, warning if it is excessively
+  /// large and failing if it exceeds the configured guard.
+  fn check_size(&self) -> Result<(), ConversionError> {
+    let estimate = self.estimated_bars();
+    if estimate > LARGE_REQUEST_BARS {
+      warn!(
+        symbol = display(&self.symbol),
+        timeframe = debug(self.timeframe),
+        estimate,
+        "bars request covers a very large number of bars"
+      );
+    }
+
+    match self.max_bars {
+      Some(max_bars) if estimate > max_bars => Err(ConversionError::Validation(format!(
+        "bars request covers an estimated {estimate} bars, exceeding limit of {max_bars}"
+      ))),
+      _ => Ok(()),
+    }
+  }
+}
+
 
 /// A helper for initializing [`ListReq`] objects.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -100,8 +369,12 @@ pub struct ListReqInit {
   pub adjustment: Option<Adjustment>,
   /// See `ListReq::feed`.
   pub feed: Option<Feed>,
+  /// See `ListReq::sort`.
+  pub sort: Sort,
   /// See `ListReq::page_token`.
   pub page_token: Option<String>,
+  /// See `ListReq::max_bars`.
+  pub max_bars: Option<u64>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   pub _non_exhaustive: (),
@@ -128,7 +401,9 @@ impl ListReqInit {
       limit: self.limit,
       adjustment: self.adjustment,
       feed: self.feed,
+      sort: self.sort,
       page_token: self.page_token,
+      max_bars: self.max_bars,
       _non_exhaustive: (),
     }
   }
@@ -157,14 +432,63 @@ pub struct Bar {
   #[serde(rename = "v")]
   pub volume: usize,
   /// The volume weighted average price.
-  #[serde(rename = "vw")]
+  ///
+  /// Not all feeds report this value, in which case it is zero.
+  #[serde(rename = "vw", default)]
   pub weighted_average: Num,
+  /// The number of trades in the bar.
+  ///
+  /// Not all feeds report this value, in which case it is zero.
+  #[serde(rename = "n", default)]
+  pub trade_count: u64,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
+impl Bar {
+  /// Check whether the bar's open, high, low, and close prices are
+  /// consistent with each other.
+  #[inline]
+  pub fn is_valid(&self) -> bool {
+    self.validate().is_ok()
+  }
+
+  /// Validate the bar's open, high, low, and close prices, reporting
+  /// the first violated invariant.
+  pub fn validate(&self) -> Result<(), BarError> {
+    if self.high < self.low {
+      return Err(BarError::HighBelowLow)
+    }
+
+    let range = &self.low..=&self.high;
+    if !range.contains(&&self.open) {
+      return Err(BarError::OpenOutOfRange)
+    }
+    if !range.contains(&&self.close) {
+      return Err(BarError::CloseOutOfRange)
+    }
+    Ok(())
+  }
+}
+
+
+/// An error describing an inconsistency in a [`Bar`]'s prices.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ThisError)]
+#[non_exhaustive]
+pub enum BarError {
+  /// The high price is below the low price.
+  #[error("the high price is below the low price")]
+  HighBelowLow,
+  /// The open price is outside of the low-high range.
+  #[error("the open price is outside of the low-high range")]
+  OpenOutOfRange,
+  /// The close price is outside of the low-high range.
+  #[error("the close price is outside of the low-high range")]
+  CloseOutOfRange,
+}
+
 
 /// A collection of bars as returned by the API. This is one page of
 /// bars.
@@ -187,6 +511,247 @@ pub struct Bars {
 }
 
 
+/// A corporate action affecting a bar.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Event {
+  /// A forward stock split.
+  ForwardSplit(Split),
+  /// A reverse stock split.
+  ReverseSplit(Split),
+  /// A dividend paid out in cash.
+  CashDividend(CashDividend),
+}
+
+impl Event {
+  /// Retrieve the event's ex-date, i.e., the first trading day it is
+  /// reflected in prices.
+  fn ex_date(&self) -> NaiveDate {
+    match self {
+      Self::ForwardSplit(split) | Self::ReverseSplit(split) => split.ex_date,
+      Self::CashDividend(dividend) => dividend.ex_date,
+    }
+  }
+}
+
+
+/// A bar annotated with the corporate actions taking effect with it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AnnotatedBar {
+  /// The bar.
+  pub bar: Bar,
+  /// The corporate actions taking effect with the bar.
+  pub events: Vec<Event>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// Retrieve the date of the trading day a bar belongs to.
+fn market_date(bar: &Bar) -> NaiveDate {
+  bar
+    .time
+    .with_timezone(&market_offset(bar.time.naive_utc().date()))
+    .naive_local()
+    .date()
+}
+
+
+/// Annotate a series of bars with the provided corporate actions.
+///
+/// Each corporate action is attached to the first bar of its ex-date
+/// or, if there is no bar for that date, to the first bar after it.
+/// Corporate actions with an ex-date outside of the time range covered
+/// by the bars are dropped. Bars are expected to be sorted by time.
+pub fn annotate(bars: Vec<Bar>, actions: CorporateActions) -> Vec<AnnotatedBar> {
+  let CorporateActions {
+    forward_splits,
+    reverse_splits,
+    cash_dividends,
+    ..
+  } = actions;
+
+  let mut events = forward_splits
+    .into_iter()
+    .map(Event::ForwardSplit)
+    .chain(reverse_splits.into_iter().map(Event::ReverseSplit))
+    .chain(cash_dividends.into_iter().map(Event::CashDividend))
+    .collect::<Vec<_>>();
+  events.sort_by_key(Event::ex_date);
+
+  let first = bars.first().map(market_date);
+  let mut events = events
+    .into_iter()
+    .skip_while(|event| Some(event.ex_date()) < first)
+    .peekable();
+
+  bars
+    .into_iter()
+    .map(|bar| {
+      let date = market_date(&bar);
+      let mut annotated = Vec::new();
+      while let Some(event) = events.next_if(|event| event.ex_date() <= date) {
+        annotated.push(event)
+      }
+
+      AnnotatedBar {
+        bar,
+        events: annotated,
+        _non_exhaustive: (),
+      }
+    })
+    .collect()
+}
+
+
+/// An error as reported by [`list_with_corporate_actions`].
+#[derive(Debug, ThisError)]
+pub enum AnnotatedListError {
+  /// Retrieval of the bars failed.
+  #[error("failed to retrieve bars")]
+  Bars(#[source] RequestError<ListError>),
+  /// Retrieval of the corporate actions failed.
+  #[error("failed to retrieve corporate actions")]
+  CorporateActions(#[source] RequestError<corporate_actions::ListError>),
+}
+
+
+/// Retrieve a page of bars annotated with the splits and dividends
+/// taking effect over the requested time range.
+///
+/// Corporate actions are retrieved for the request's symbol and time
+/// range and attached to the bars as described for [`annotate`].
+pub async fn list_with_corporate_actions(
+  client: &Client,
+  request: &ListReq,
+) -> Result<(Vec<AnnotatedBar>, Option<String>), AnnotatedListError> {
+  let bars = client
+    .issue::<List>(request)
+    .await
+    .map_err(AnnotatedListError::Bars)?;
+
+  let date = |time: &DateTime<Utc>| {
+    time
+      .with_timezone(&market_offset(time.naive_utc().date()))
+      .naive_local()
+      .date()
+  };
+
+  let mut actions_request = corporate_actions::ListReqInit {
+    types: vec![
+      corporate_actions::Type::ForwardSplit,
+      corporate_actions::Type::ReverseSplit,
+      corporate_actions::Type::CashDividend,
+    ],
+    start: Some(date(&request.start)),
+    end: Some(date(&request.end)),
+    ..Default::default()
+  }
+  .init([request.symbol.clone()]);
+
+  let mut actions = CorporateActions::default();
+  loop {
+    let page = client
+      .issue::<corporate_actions::List>(&actions_request)
+      .await
+      .map_err(AnnotatedListError::CorporateActions)?;
+
+    let CorporateActions {
+      forward_splits,
+      reverse_splits,
+      cash_dividends,
+      ..
+    } = page.corporate_actions;
+    actions.forward_splits.extend(forward_splits);
+    actions.reverse_splits.extend(reverse_splits);
+    actions.cash_dividends.extend(cash_dividends);
+
+    match page.next_page_token {
+      Some(token) => actions_request.page_token = Some(token),
+      None => break,
+    }
+  }
+
+  Ok((annotate(bars.bars, actions), bars.next_page_token))
+}
+
+
+/// Calculate the running session VWAP over a series of bars.
+///
+/// For each bar, the returned series contains the bar's time along with
+/// the volume weighted average price over all bars of the same trading
+/// session up to and including it, i.e., the cumulative product of each
+/// bar's own volume weighted average price and volume divided by the
+/// cumulative volume. The calculation is reset with the first bar of
+/// each trading day. As long as no volume has been traded in a session,
+/// the bar's close price is reported. Bars are expected to be sorted
+/// by time.
+pub fn session_vwap(bars: &[Bar]) -> Vec<(DateTime<Utc>, Num)> {
+  let mut session = None;
+  let mut value = Num::from(0);
+  let mut volume = Num::from(0);
+
+  bars
+    .iter()
+    .map(|bar| {
+      let date = market_date(bar);
+      if session != Some(date) {
+        session = Some(date);
+        value = Num::from(0);
+        volume = Num::from(0);
+      }
+
+      let bar_volume = Num::from(bar.volume);
+      value += &bar.weighted_average * &bar_volume;
+      volume += bar_volume;
+
+      let vwap = if volume.is_zero() {
+        bar.close.clone()
+      } else {
+        &value / &volume
+      };
+      (bar.time, vwap)
+    })
+    .collect()
+}
+
+
+/// Retrieve the minute bars of the regular trading session on `date`
+/// and calculate the running session VWAP over them.
+///
+/// Please refer to [`session_vwap`] for details on the calculation.
+pub async fn list_session_vwap(
+  client: &Client,
+  symbol: &str,
+  date: NaiveDate,
+  feed: Option<Feed>,
+) -> Result<Vec<(DateTime<Utc>, Num)>, RequestError<ListError>> {
+  let time = |hour, minute| {
+    // SANITY: The provided times are all valid and the market's UTC
+    //         offset is fixed, making the local time unambiguous.
+    let time = date.and_time(NaiveTime::from_hms_opt(hour, minute, 0).unwrap());
+    market_offset(date)
+      .from_local_datetime(&time)
+      .unwrap()
+      .with_timezone(&Utc)
+  };
+
+  // The bar starting at the close already belongs to the after hours
+  // session.
+  let start = time(9, 30);
+  let end = time(16, 0) - Duration::seconds(1);
+  let request = ListReqInit {
+    feed,
+    ..Default::default()
+  }
+  .init(symbol, start, end, TimeFrame::one_minute());
+
+  let bars = client.issue_all::<List>(&request).await?;
+  Ok(session_vwap(&bars))
+}
+
+
 Endpoint! {
   /// The representation of a GET request to the /v2/stocks/{symbol}/bars endpoint.
   pub List(ListReq),
@@ -200,14 +765,16 @@ Endpoint! {
   ]
 
   fn base_url() -> Option<Str> {
-    Some(DATA_BASE_URL.into())
+    RawApi::Data.base_url()
   }
 
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/stocks/{}/bars", input.symbol).into()
+    path!("/v2/stocks/{}/bars", input.symbol).into()
   }
 
   fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let () = check_time_range(&input.start, &input.end)?;
+    let () = input.check_size()?;
     Ok(Some(to_query(input)?.into()))
   }
 }
@@ -218,7 +785,11 @@ mod tests {
   use super::*;
 
   use std::ops::RangeInclusive;
-  use std::str::FromStr as _;
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use std::sync::Arc;
+
+  use http::StatusCode;
 
   use http_endpoint::Endpoint;
 
@@ -226,7 +797,17 @@ mod tests {
 
   use test_log::test;
 
+  use tracing::span;
+  use tracing::subscriber::with_default;
+  use tracing::Event as TraceEvent;
+  use tracing::Level;
+  use tracing::Metadata;
+  use tracing::Subscriber;
+
   use crate::api_info::ApiInfo;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::Reply;
+  use crate::ApiErrorKind;
   use crate::Client;
   use crate::RequestError;
 
@@ -240,6 +821,463 @@ mod tests {
   }
 
 
+  /// A tracing subscriber counting the warnings emitted.
+  #[derive(Default)]
+  struct WarnCounter(Arc<AtomicUsize>);
+
+  impl Subscriber for WarnCounter {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+      true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+      span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &TraceEvent<'_>) {
+      if *event.metadata().level() == Level::WARN {
+        let _count = self.0.fetch_add(1, Ordering::Relaxed);
+      }
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+  }
+
+
+  /// Create a `Bar` with the given open, high, low, and close prices.
+  fn bar(open: i64, high: i64, low: i64, close: i64) -> Bar {
+    Bar {
+      time: DateTime::from_str("2021-02-01T16:01:00Z").unwrap(),
+      open: Num::from(open),
+      close: Num::from(close),
+      high: Num::from(high),
+      low: Num::from(low),
+      volume: 100,
+      weighted_average: Num::from(close),
+      trade_count: 0,
+      _non_exhaustive: (),
+    }
+  }
+
+
+  /// Create a minute bar at the given time with the provided volume
+  /// weighted average price and volume.
+  fn minute_bar(time: &str, weighted_average: Num, volume: usize) -> Bar {
+    Bar {
+      time: DateTime::from_str(time).unwrap(),
+      open: weighted_average.clone(),
+      close: weighted_average.clone(),
+      high: weighted_average.clone(),
+      low: weighted_average.clone(),
+      volume,
+      weighted_average,
+      trade_count: 0,
+      _non_exhaustive: (),
+    }
+  }
+
+
+  /// Check that time frames are serialized as expected by Alpaca.
+  #[test]
+  fn serialize_time_frames() {
+    let timeframes = [
+      (TimeFrame::one_minute(), "1Min"),
+      (TimeFrame::new(5, Unit::Minute).unwrap(), "5Min"),
+      (TimeFrame::new(15, Unit::Minute).unwrap(), "15Min"),
+      (TimeFrame::new(2, Unit::Hour).unwrap(), "2Hour"),
+      (TimeFrame::one_day(), "1Day"),
+      (TimeFrame::one_week(), "1Week"),
+      (TimeFrame::new(3, Unit::Month).unwrap(), "3Month"),
+    ];
+
+    for (timeframe, expected) in timeframes {
+      assert_eq!(timeframe.to_string(), expected);
+      assert_eq!(
+        serde_json::to_string(&timeframe).unwrap(),
+        format!(r#""{expected}""#)
+      );
+      assert_eq!(TimeFrame::from_str(expected).unwrap(), timeframe);
+    }
+  }
+
+  /// Make sure that time frames with out-of-range multipliers are
+  /// rejected.
+  #[test]
+  fn reject_invalid_time_frames() {
+    let err = TimeFrame::new(90, Unit::Minute).unwrap_err();
+    assert_eq!(
+      err,
+      InvalidTimeFrame::UnsupportedAmount("90Min".to_string(), "between 1 and 59")
+    );
+    assert_eq!(
+      err.to_string(),
+      "invalid time frame 90Min: multiplier must be between 1 and 59"
+    );
+
+    assert!(TimeFrame::new(0, Unit::Minute).is_err());
+    assert!(TimeFrame::new(24, Unit::Hour).is_err());
+    assert!(TimeFrame::new(2, Unit::Day).is_err());
+    assert!(TimeFrame::new(5, Unit::Month).is_err());
+
+    assert_eq!(TimeFrame::from_str("90Min").unwrap_err(), err);
+    assert!(TimeFrame::from_str("Min").is_err());
+    assert!(TimeFrame::from_str("5Sec").is_err());
+  }
+
+  /// Check that malformed time frames are rejected with a descriptive
+  /// error.
+  #[test]
+  fn parse_malformed_time_frames() {
+    let err = TimeFrame::from_str("0Min").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "invalid time frame 0Min: multiplier must be between 1 and 59"
+    );
+
+    let err = TimeFrame::from_str("5Xyz").unwrap_err();
+    assert_eq!(err, InvalidTimeFrame::UnknownUnit("5Xyz".to_string()));
+    assert_eq!(
+      err.to_string(),
+      "invalid time frame 5Xyz: unknown unit (expected Min, Hour, Day, Week, or Month)"
+    );
+
+    let err = TimeFrame::from_str("Hour").unwrap_err();
+    assert_eq!(err, InvalidTimeFrame::InvalidAmount("Hour".to_string()));
+
+    let err = TimeFrame::from_str("2Day").unwrap_err();
+    assert_eq!(
+      err,
+      InvalidTimeFrame::UnsupportedAmount("2Day".to_string(), "1")
+    );
+
+    assert!(matches!(
+      TimeFrame::from_str(""),
+      Err(InvalidTimeFrame::UnknownUnit(..))
+    ));
+    assert!(matches!(
+      TimeFrame::from_str("-5Min"),
+      Err(InvalidTimeFrame::UnknownUnit(..))
+    ));
+  }
+
+  /// Check that we calculate the running session VWAP over a single
+  /// session of minute bars correctly.
+  #[test]
+  fn calculate_session_vwap() {
+    let bars = [
+      minute_bar("2021-02-01T14:30:00Z", Num::from(10), 0),
+      minute_bar("2021-02-01T14:31:00Z", Num::from(10), 100),
+      minute_bar("2021-02-01T14:32:00Z", Num::from(12), 300),
+      minute_bar("2021-02-01T14:33:00Z", Num::new(105, 10), 0),
+      minute_bar("2021-02-01T14:34:00Z", Num::from(9), 100),
+    ];
+
+    let vwap = session_vwap(&bars);
+    let expected = [
+      // No volume has been traded yet, so the close price is used.
+      Num::from(10),
+      Num::from(10),
+      Num::new(115, 10),
+      Num::new(115, 10),
+      Num::from(11),
+    ];
+    assert_eq!(vwap.len(), expected.len());
+    for ((time, vwap), (bar, expected)) in vwap.iter().zip(bars.iter().zip(expected)) {
+      assert_eq!(*time, bar.time);
+      assert_eq!(*vwap, expected);
+    }
+  }
+
+  /// Make sure that the session VWAP is reset with each trading day.
+  #[test]
+  fn reset_session_vwap() {
+    let bars = [
+      minute_bar("2021-02-01T20:59:00Z", Num::from(10), 100),
+      minute_bar("2021-02-02T14:30:00Z", Num::from(20), 100),
+    ];
+
+    let vwap = session_vwap(&bars);
+    assert_eq!(vwap[0].1, Num::from(10));
+    assert_eq!(vwap[1].1, Num::from(20));
+    assert_eq!(session_vwap(&[]), Vec::new());
+  }
+
+  /// Check that a bar with consistent prices is reported as valid.
+  #[test]
+  fn validate_valid_bar() {
+    let valid = bar(10, 12, 9, 11);
+    assert!(valid.is_valid());
+    assert_eq!(valid.validate(), Ok(()));
+
+    let flat = bar(10, 10, 10, 10);
+    assert!(flat.is_valid());
+  }
+
+  /// Check that each violated OHLC invariant is detected.
+  #[test]
+  fn validate_invalid_bars() {
+    let inverted = bar(10, 9, 12, 11);
+    assert!(!inverted.is_valid());
+    assert_eq!(inverted.validate(), Err(BarError::HighBelowLow));
+
+    let open_too_high = bar(13, 12, 9, 11);
+    assert_eq!(open_too_high.validate(), Err(BarError::OpenOutOfRange));
+
+    let close_too_low = bar(10, 12, 9, 8);
+    assert_eq!(close_too_low.validate(), Err(BarError::CloseOutOfRange));
+  }
+
+  /// Check that we estimate the number of bars covered by a request
+  /// correctly.
+  #[test]
+  fn estimate_bars() {
+    let start = DateTime::from_str("2021-02-01T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-02-02T00:00:00Z").unwrap();
+
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_minute());
+    assert_eq!(request.estimated_bars(), 1441);
+
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_hour());
+    assert_eq!(request.estimated_bars(), 25);
+
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
+    assert_eq!(request.estimated_bars(), 2);
+
+    let request = ListReqInit::default().init("AAPL", end, start, TimeFrame::one_day());
+    assert_eq!(request.estimated_bars(), 0);
+  }
+
+  /// Verify that a warning is emitted for a request covering a very
+  /// large number of bars, but not for a small one.
+  #[test]
+  fn warn_on_large_request() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let subscriber = WarnCounter(Arc::clone(&count));
+
+    with_default(subscriber, || {
+      let start = DateTime::from_str("2016-01-01T00:00:00Z").unwrap();
+      let end = DateTime::from_str("2021-01-01T00:00:00Z").unwrap();
+      let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
+      let _query = List::query(&request).unwrap();
+      assert_eq!(count.load(Ordering::Relaxed), 0);
+
+      let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_minute());
+      let _query = List::query(&request).unwrap();
+      assert_eq!(count.load(Ordering::Relaxed), 1);
+    });
+  }
+
+  /// Check that the error reported for a SIP request by an account
+  /// lacking the necessary subscription is classified as such.
+  #[test]
+  fn classify_sip_not_permitted() {
+    let body = br#"{"message":"subscription does not permit querying recent SIP data"}"#;
+    match List::evaluate(StatusCode::FORBIDDEN, body) {
+      Err(ListError::NotPermitted(Ok(error))) => {
+        assert_eq!(error.kind(), ApiErrorKind::SipNotPermitted)
+      },
+      result => panic!("unexpected result: {result:?}"),
+    }
+
+    // The message does not override a distinct error code.
+    let body =
+      br#"{"code":40110000,"message":"subscription does not permit querying recent SIP data"}"#;
+    match List::evaluate(StatusCode::FORBIDDEN, body) {
+      Err(ListError::NotPermitted(Ok(error))) => {
+        assert_eq!(error.kind(), ApiErrorKind::InvalidCredentials)
+      },
+      result => panic!("unexpected result: {result:?}"),
+    }
+  }
+
+  /// Verify that no feed is requested by default and that the default
+  /// feed is IEX.
+  #[test]
+  fn serialize_default_feed() {
+    let start = DateTime::from_str("2021-01-04T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-01-05T00:00:00Z").unwrap();
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
+    let query = List::query(&request).unwrap().unwrap();
+    assert!(!query.contains("feed"), "{query}");
+
+    let request = ListReqInit {
+      feed: Some(Feed::default()),
+      ..Default::default()
+    }
+    .init("AAPL", start, end, TimeFrame::one_day());
+    let query = List::query(&request).unwrap().unwrap();
+    assert!(query.contains("&feed=iex"), "{query}");
+  }
+
+  /// Make sure that a request exceeding the configured bar guard is
+  /// rejected before being issued.
+  #[test(tokio::test)]
+  async fn reject_request_exceeding_guard() {
+    let api_info =
+      ApiInfo::from_parts("https://paper-api.alpaca.markets/", "XXXX", "YYYY").unwrap();
+    let client = Client::new(api_info);
+    let start = DateTime::from_str("2016-01-01T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-01-01T00:00:00Z").unwrap();
+    let request = ListReqInit {
+      max_bars: Some(10_000),
+      ..Default::default()
+    }
+    .init("AAPL", start, end, TimeFrame::one_minute());
+
+    let err = client.issue::<List>(&request).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(ListError::Conversion(ConversionError::Validation(..))) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Make sure that a request whose time range ends before it starts
+  /// is rejected before being issued.
+  #[test(tokio::test)]
+  async fn reject_reversed_time_range() {
+    let api_info =
+      ApiInfo::from_parts("https://paper-api.alpaca.markets/", "XXXX", "YYYY").unwrap();
+    let client = Client::new(api_info);
+    let start = DateTime::from_str("2021-01-05T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-01-04T00:00:00Z").unwrap();
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
+
+    let err = client.issue::<List>(&request).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(ListError::Conversion(ConversionError::InvalidTimeRange {
+        start: s,
+        end: e,
+      })) => {
+        assert_eq!(s, start);
+        assert_eq!(e, end);
+      },
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Check that a valid time range is passed through unchanged.
+  #[test]
+  fn serialize_valid_time_range() {
+    let start = DateTime::from_str("2021-01-04T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2021-01-04T00:00:00Z").unwrap();
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
+    let query = List::query(&request).unwrap().unwrap();
+    assert_eq!(
+      query,
+      "start=2021-01-04T00%3A00%3A00Z&end=2021-01-04T00%3A00%3A00Z&timeframe=1Day"
+    );
+  }
+
+  /// Create a daily bar for the given date.
+  fn daily_bar(date: &str) -> Bar {
+    Bar {
+      // Daily bars start at midnight in the market's time zone.
+      time: DateTime::from_str(&format!("{date}T05:00:00Z")).unwrap(),
+      open: Num::from(100),
+      close: Num::from(101),
+      high: Num::from(102),
+      low: Num::from(99),
+      volume: 1000,
+      weighted_average: Num::from(100),
+      trade_count: 0,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Create a 4:1 forward split for the given ex-date.
+  fn split(ex_date: &str) -> Split {
+    Split {
+      symbol: "AAPL".to_string(),
+      new_rate: Num::from(4),
+      old_rate: Num::from(1),
+      process_date: NaiveDate::from_str(ex_date).unwrap(),
+      ex_date: NaiveDate::from_str(ex_date).unwrap(),
+      record_date: None,
+      payable_date: None,
+      _non_exhaustive: (),
+    }
+  }
+
+  /// Check that corporate actions are attached to the correct bars.
+  #[test]
+  fn annotate_bars_with_split() {
+    let bars = vec![
+      daily_bar("2020-08-27"),
+      daily_bar("2020-08-28"),
+      daily_bar("2020-08-31"),
+      daily_bar("2020-09-01"),
+    ];
+    let actions = CorporateActions {
+      forward_splits: vec![
+        // Before the first bar; should be dropped.
+        split("2020-08-26"),
+        // On the ex-date of the third bar.
+        split("2020-08-31"),
+        // After the last bar; should be dropped.
+        split("2020-09-02"),
+      ],
+      ..Default::default()
+    };
+
+    let annotated = annotate(bars.clone(), actions);
+    assert_eq!(annotated.len(), bars.len());
+    for (annotated, bar) in annotated.iter().zip(&bars) {
+      assert_eq!(&annotated.bar, bar);
+    }
+
+    assert_eq!(annotated[0].events, Vec::new());
+    assert_eq!(annotated[1].events, Vec::new());
+    assert_eq!(
+      annotated[2].events,
+      vec![Event::ForwardSplit(split("2020-08-31"))]
+    );
+    assert_eq!(annotated[3].events, Vec::new());
+  }
+
+  /// Check that a corporate action with an ex-date for which no bar
+  /// exists is attached to the next bar.
+  #[test]
+  fn annotate_bars_with_gap() {
+    let bars = vec![daily_bar("2020-08-28"), daily_bar("2020-09-01")];
+    let actions = CorporateActions {
+      forward_splits: vec![split("2020-08-31")],
+      ..Default::default()
+    };
+
+    let annotated = annotate(bars, actions);
+    assert_eq!(annotated[0].events, Vec::new());
+    assert_eq!(
+      annotated[1].events,
+      vec![Event::ForwardSplit(split("2020-08-31"))]
+    );
+  }
+
+  /// Check that we can retrieve bars annotated with corporate actions.
+  #[test(tokio::test)]
+  async fn request_bars_with_corporate_actions() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let start = DateTime::from_str("2020-08-27T00:00:00Z").unwrap();
+    let end = DateTime::from_str("2020-09-02T00:00:00Z").unwrap();
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
+
+    let (bars, _token) = list_with_corporate_actions(&client, &request)
+      .await
+      .unwrap();
+    let bar = bars.iter().find(|bar| !bar.events.is_empty()).unwrap();
+    assert_eq!(
+      market_date(&bar.bar),
+      NaiveDate::from_ymd_opt(2020, 8, 31).unwrap()
+    );
+    assert!(matches!(bar.events[0], Event::ForwardSplit(..)));
+  }
+
   /// Verify that we can properly parse a reference bar response.
   #[test]
   fn parse_reference_bars() {
@@ -252,6 +1290,7 @@ mod tests {
         "l": 133.31,
         "c": 133.5,
         "v": 9876,
+        "n": 120,
         "vw": 133.4
 
       },
@@ -280,10 +1319,31 @@ mod tests {
     assert_eq!(bars[0].high, Num::new(13374, 100));
     assert_eq!(bars[0].low, Num::new(13331, 100));
     assert_eq!(bars[0].weighted_average, Num::new(1334, 10));
+    assert_eq!(bars[0].trade_count, 120);
+    assert_eq!(bars[1].trade_count, 0);
     assert_eq!(res.symbol, "AAPL".to_string());
     assert!(res.next_page_token.is_some())
   }
 
+  /// Make sure that a bar lacking the volume weighted average price
+  /// and trade count can be parsed, with both defaulting to zero.
+  #[test]
+  fn parse_bar_without_vwap_and_trade_count() {
+    let response = r#"{
+  "t": "2021-02-01T16:01:00Z",
+  "o": 133.32,
+  "h": 133.74,
+  "l": 133.31,
+  "c": 133.5,
+  "v": 9876
+}"#;
+
+    let bar = from_json::<Bar>(response).unwrap();
+    assert_eq!(bar.volume, 9876);
+    assert_eq!(bar.weighted_average, Num::from(0));
+    assert_eq!(bar.trade_count, 0);
+  }
+
   /// Check that we can decode a response containing no bars correctly.
   #[test(tokio::test)]
   async fn no_bars() {
@@ -291,12 +1351,57 @@ mod tests {
     let client = Client::new(api_info);
     let start = DateTime::from_str("2021-11-05T00:00:00Z").unwrap();
     let end = DateTime::from_str("2021-11-05T00:00:00Z").unwrap();
-    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::OneDay);
+    let request = ListReqInit::default().init("AAPL", start, end, TimeFrame::one_day());
 
     let res = client.issue::<List>(&request).await.unwrap();
     assert_eq!(res.bars, Vec::new())
   }
 
+  /// Check that the session VWAP is calculated over the minute bars of
+  /// the regular trading session on the given day.
+  #[test(tokio::test)]
+  async fn request_session_vwap() {
+    let (url, requests) = serve_sequence(vec![Reply::respond(
+      "200 OK",
+      r#"{
+        "bars": [
+          {"t": "2023-06-14T13:30:00Z", "o": 1, "h": 1, "l": 1, "c": 1, "v": 100, "n": 1, "vw": 436},
+          {"t": "2023-06-14T13:31:00Z", "o": 1, "h": 1, "l": 1, "c": 1, "v": 300, "n": 1, "vw": 440}
+        ],
+        "symbol": "SPY",
+        "next_page_token": null
+      }"#,
+    )])
+    .await;
+    let api_info = ApiInfo::from_parts_with_data(&url, &url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+    let date = NaiveDate::from_ymd_opt(2023, 6, 14).unwrap();
+
+    let vwap = list_session_vwap(&client, "SPY", date, Some(Feed::IEX))
+      .await
+      .unwrap();
+    assert_eq!(
+      vwap,
+      vec![
+        (
+          DateTime::<Utc>::from_str("2023-06-14T13:30:00Z").unwrap(),
+          Num::from(436)
+        ),
+        (
+          DateTime::<Utc>::from_str("2023-06-14T13:31:00Z").unwrap(),
+          Num::from(439)
+        ),
+      ]
+    );
+
+    let line = requests.lines().remove(0);
+    assert!(line.starts_with("GET /v2/stocks/SPY/bars?"), "{line}");
+    assert!(
+      line.contains("start=2023-06-14T13%3A30%3A00Z&end=2023-06-14T19%3A59%3A59Z"),
+      "{line}"
+    );
+  }
+
   /// Check that we can request historic bar data for a stock.
   #[test(tokio::test)]
   async fn request_bars() {
@@ -308,7 +1413,7 @@ mod tests {
       limit: Some(2),
       ..Default::default()
     }
-    .init("AAPL", start, end, TimeFrame::OneDay);
+    .init("AAPL", start, end, TimeFrame::one_day());
 
     let res = client.issue::<List>(&request).await.unwrap();
     let bars = res.bars;
@@ -346,7 +1451,7 @@ mod tests {
       limit: Some(2),
       ..Default::default()
     }
-    .init("AAPL", start, end, TimeFrame::OneDay);
+    .init("AAPL", start, end, TimeFrame::one_day());
 
     let mut res = client.issue::<List>(&request).await.unwrap();
     let bars = res.bars;
@@ -373,7 +1478,7 @@ mod tests {
       adjustment: Some(adjustment),
       ..Default::default()
     }
-    .init("AAPL", start, end, TimeFrame::OneDay);
+    .init("AAPL", start, end, TimeFrame::one_day());
 
     client.issue::<List>(&request).await.unwrap()
   }
@@ -444,7 +1549,7 @@ mod tests {
       feed: Some(Feed::SIP),
       ..Default::default()
     }
-    .init("AAPL", start, end, TimeFrame::OneDay);
+    .init("AAPL", start, end, TimeFrame::one_day());
 
     let result = client.issue::<List>(&request).await;
     // Unfortunately we can't really know whether the user has the
@@ -469,7 +1574,7 @@ mod tests {
       page_token: Some("123456789abcdefghi".to_string()),
       ..Default::default()
     }
-    .init("SPY", start, end, TimeFrame::OneMinute);
+    .init("SPY", start, end, TimeFrame::one_minute());
 
     let err = client.issue::<List>(&request).await.unwrap_err();
     match err {
@@ -487,7 +1592,7 @@ mod tests {
 
     let start = DateTime::from_str("2022-02-01T00:00:00Z").unwrap();
     let end = DateTime::from_str("2022-02-20T00:00:00Z").unwrap();
-    let request = ListReqInit::default().init("ABC123", start, end, TimeFrame::OneDay);
+    let request = ListReqInit::default().init("ABC123", start, end, TimeFrame::one_day());
 
     let err = client.issue::<List>(&request).await.unwrap_err();
     match err {
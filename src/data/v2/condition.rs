@@ -0,0 +1,209 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+
+/// The tape, i.e., the consolidated feed, that a trade or quote was
+/// reported on.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Tape {
+  /// Tape A, covering NYSE listed securities.
+  A,
+  /// Tape B, covering NYSE Arca, NYSE American, and other regional
+  /// exchange listed securities.
+  B,
+  /// Tape C, covering Nasdaq listed securities.
+  C,
+}
+
+impl Tape {
+  /// Decode a tape from its single letter code, as reported by the
+  /// API.
+  pub fn from_code(code: &str) -> Option<Self> {
+    match code {
+      "A" => Some(Self::A),
+      "B" => Some(Self::B),
+      "C" => Some(Self::C),
+      _ => None,
+    }
+  }
+}
+
+
+/// A trade condition, as reported in the condition codes of a trade.
+///
+/// The set of conditions covers the codes shared by the CTA (tapes
+/// A and B) and UTP (tape C) plans.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Condition {
+  /// A regular sale.
+  Regular,
+  /// An acquisition.
+  Acquisition,
+  /// A bunched trade.
+  BunchedTrade,
+  /// A cash sale.
+  CashSale,
+  /// A distribution.
+  Distribution,
+  /// An intermarket sweep order execution.
+  IntermarketSweep,
+  /// A bunched trade reported late.
+  BunchedSoldTrade,
+  /// A trade at a price varying from the prevailing market.
+  PriceVariation,
+  /// A trade of less than a round lot.
+  OddLot,
+  /// A trade executed under SEC Rule 155.
+  Rule155,
+  /// A trade reported late, but in sequence.
+  SoldLast,
+  /// The official closing price of a market center.
+  MarketCenterOfficialClose,
+  /// A trade settling on the next day.
+  NextDay,
+  /// The opening print of a market center.
+  OpeningPrints,
+  /// A trade referring to a price from earlier in the day.
+  PriorReferencePrice,
+  /// The official opening price of a market center.
+  MarketCenterOfficialOpen,
+  /// A trade with seller defined settlement terms.
+  Seller,
+  /// A trade that is part of a split.
+  SplitTrade,
+  /// A trade executed outside of regular trading hours.
+  FormT,
+  /// A trade executed outside of regular trading hours and reported
+  /// out of sequence.
+  ExtendedHoursOutOfSequence,
+  /// A contingent trade.
+  ContingentTrade,
+  /// A trade priced at the average of a series of executions.
+  AveragePrice,
+  /// A cross trade, e.g., as part of an auction.
+  CrossTrade,
+  /// A regular trade reported during a system test.
+  YellowFlag,
+  /// A trade reported out of sequence.
+  OutOfSequence,
+  /// A trade of a stopped stock.
+  StoppedStock,
+  /// A trade priced by means of a derivative instrument.
+  DerivativelyPriced,
+  /// A re-opening print.
+  ReopeningPrints,
+  /// The closing print of a market center.
+  ClosingPrints,
+  /// A qualified contingent trade.
+  QualifiedContingentTrade,
+  /// A corrected consolidated closing price.
+  CorrectedConsolidatedClose,
+}
+
+impl Condition {
+  /// Decode a condition from its code, as reported by the API.
+  ///
+  /// `None` is returned for codes not known to us.
+  pub fn from_code(code: &str) -> Option<Self> {
+    let condition = match code {
+      "@" | " " => Self::Regular,
+      "A" => Self::Acquisition,
+      "B" => Self::BunchedTrade,
+      "C" => Self::CashSale,
+      "D" => Self::Distribution,
+      "F" => Self::IntermarketSweep,
+      "G" => Self::BunchedSoldTrade,
+      "H" => Self::PriceVariation,
+      "I" => Self::OddLot,
+      "K" => Self::Rule155,
+      "L" => Self::SoldLast,
+      "M" => Self::MarketCenterOfficialClose,
+      "N" => Self::NextDay,
+      "O" => Self::OpeningPrints,
+      "P" => Self::PriorReferencePrice,
+      "Q" => Self::MarketCenterOfficialOpen,
+      "R" => Self::Seller,
+      "S" => Self::SplitTrade,
+      "T" => Self::FormT,
+      "U" => Self::ExtendedHoursOutOfSequence,
+      "V" => Self::ContingentTrade,
+      "W" => Self::AveragePrice,
+      "X" => Self::CrossTrade,
+      "Y" => Self::YellowFlag,
+      "Z" => Self::OutOfSequence,
+      "1" => Self::StoppedStock,
+      "4" => Self::DerivativelyPriced,
+      "5" => Self::ReopeningPrints,
+      "6" => Self::ClosingPrints,
+      "7" => Self::QualifiedContingentTrade,
+      "9" => Self::CorrectedConsolidatedClose,
+      _ => return None,
+    };
+    Some(condition)
+  }
+
+  /// Check whether the condition marks a regular trade.
+  ///
+  /// Trades carrying irregular conditions are excluded by Alpaca when
+  /// aggregating bars, as their prices do not necessarily reflect the
+  /// prevailing market.
+  pub fn is_regular(&self) -> bool {
+    !matches!(
+      self,
+      Self::CashSale
+        | Self::BunchedSoldTrade
+        | Self::PriceVariation
+        | Self::OddLot
+        | Self::MarketCenterOfficialClose
+        | Self::NextDay
+        | Self::PriorReferencePrice
+        | Self::MarketCenterOfficialOpen
+        | Self::Seller
+        | Self::FormT
+        | Self::ExtendedHoursOutOfSequence
+        | Self::ContingentTrade
+        | Self::AveragePrice
+        | Self::OutOfSequence
+        | Self::DerivativelyPriced
+        | Self::QualifiedContingentTrade
+        | Self::CorrectedConsolidatedClose
+    )
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use test_log::test;
+
+
+  /// Check that we can decode a few known condition codes.
+  #[test]
+  fn decode_conditions() {
+    assert_eq!(Condition::from_code("@"), Some(Condition::Regular));
+    assert_eq!(Condition::from_code(" "), Some(Condition::Regular));
+    assert_eq!(Condition::from_code("I"), Some(Condition::OddLot));
+    assert_eq!(Condition::from_code("T"), Some(Condition::FormT));
+    assert_eq!(Condition::from_code("Z"), Some(Condition::OutOfSequence));
+    assert_eq!(Condition::from_code("F"), Some(Condition::IntermarketSweep));
+    assert_eq!(Condition::from_code("?"), None);
+
+    assert!(Condition::Regular.is_regular());
+    assert!(Condition::IntermarketSweep.is_regular());
+    assert!(!Condition::OddLot.is_regular());
+    assert!(!Condition::OutOfSequence.is_regular());
+  }
+
+  /// Verify that we can decode tapes.
+  #[test]
+  fn decode_tape() {
+    assert_eq!(Tape::from_code("A"), Some(Tape::A));
+    assert_eq!(Tape::from_code("B"), Some(Tape::B));
+    assert_eq!(Tape::from_code("C"), Some(Tape::C));
+    assert_eq!(Tape::from_code("D"), None);
+  }
+}
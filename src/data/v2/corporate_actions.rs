@@ -0,0 +1,373 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::NaiveDate;
+
+use num_decimal::Num;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_urlencoded::to_string as to_query;
+
+use crate::raw::RawApi;
+use crate::util::enum_slice_to_str;
+use crate::util::string_slice_to_str;
+use crate::util::vec_from_str;
+use crate::Str;
+
+
+/// An enumeration of the supported corporate action types.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum Type {
+  /// A forward stock split.
+  #[serde(rename = "forward_split")]
+  ForwardSplit,
+  /// A reverse stock split.
+  #[serde(rename = "reverse_split")]
+  ReverseSplit,
+  /// A dividend paid out in cash.
+  #[serde(rename = "cash_dividend")]
+  CashDividend,
+}
+
+
+/// A GET request to be made to the /v1/corporate-actions endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ListReq {
+  /// The symbols for which to retrieve corporate actions.
+  #[serde(rename = "symbols", serialize_with = "string_slice_to_str")]
+  pub symbols: Vec<String>,
+  /// The types of corporate actions to retrieve.
+  ///
+  /// If empty, all supported types are retrieved.
+  #[serde(rename = "types", serialize_with = "enum_slice_to_str")]
+  pub types: Vec<Type>,
+  /// Filter corporate actions processed on or after this date.
+  #[serde(rename = "start")]
+  pub start: Option<NaiveDate>,
+  /// Filter corporate actions processed on or before this date.
+  #[serde(rename = "end")]
+  pub end: Option<NaiveDate>,
+  /// The maximum number of corporate actions to return.
+  ///
+  /// It can be between 1 and 1000. Defaults to 100 if the provided
+  /// value is None.
+  #[serde(rename = "limit")]
+  pub limit: Option<usize>,
+  /// If provided we will pass a page token to continue where we left off.
+  #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`ListReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ListReqInit {
+  /// See `ListReq::types`.
+  pub types: Vec<Type>,
+  /// See `ListReq::start`.
+  pub start: Option<NaiveDate>,
+  /// See `ListReq::end`.
+  pub end: Option<NaiveDate>,
+  /// See `ListReq::limit`.
+  pub limit: Option<usize>,
+  /// See `ListReq::page_token`.
+  pub page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl ListReqInit {
+  /// Create a [`ListReq`] from a `ListReqInit`.
+  #[inline]
+  pub fn init<I, S>(self, symbols: I) -> ListReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    ListReq {
+      symbols: symbols.into_iter().map(S::into).collect(),
+      types: self.types,
+      start: self.start,
+      end: self.end,
+      limit: self.limit,
+      page_token: self.page_token,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// A stock split, as used for both forward and reverse splits.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Split {
+  /// The symbol of the affected asset.
+  #[serde(rename = "symbol")]
+  pub symbol: String,
+  /// The number of shares held after the split.
+  #[serde(rename = "new_rate")]
+  pub new_rate: Num,
+  /// The number of shares held before the split.
+  #[serde(rename = "old_rate")]
+  pub old_rate: Num,
+  /// The date the split was processed at.
+  #[serde(rename = "process_date")]
+  pub process_date: NaiveDate,
+  /// The date as of which shares trade at the split-adjusted price.
+  #[serde(rename = "ex_date")]
+  pub ex_date: NaiveDate,
+  /// The date as of which share ownership is determined.
+  #[serde(rename = "record_date")]
+  pub record_date: Option<NaiveDate>,
+  /// The date the additional shares were (or are to be) distributed.
+  #[serde(rename = "payable_date")]
+  pub payable_date: Option<NaiveDate>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A dividend paid out in cash.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CashDividend {
+  /// The symbol of the affected asset.
+  #[serde(rename = "symbol")]
+  pub symbol: String,
+  /// The amount paid per share.
+  #[serde(rename = "rate")]
+  pub rate: Num,
+  /// Whether the dividend is a special (non-recurring) one.
+  #[serde(rename = "special", default)]
+  pub special: bool,
+  /// Whether the dividend is paid by a foreign company.
+  #[serde(rename = "foreign", default)]
+  pub foreign: bool,
+  /// The date the dividend was processed at.
+  #[serde(rename = "process_date")]
+  pub process_date: NaiveDate,
+  /// The first date on which shares trade without the dividend.
+  #[serde(rename = "ex_date")]
+  pub ex_date: NaiveDate,
+  /// The date as of which share ownership is determined.
+  #[serde(rename = "record_date")]
+  pub record_date: Option<NaiveDate>,
+  /// The date the dividend was (or is to be) paid.
+  #[serde(rename = "payable_date")]
+  pub payable_date: Option<NaiveDate>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A collection of corporate actions, grouped by type.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct CorporateActions {
+  /// The list of forward splits.
+  #[serde(rename = "forward_splits", default, deserialize_with = "vec_from_str")]
+  pub forward_splits: Vec<Split>,
+  /// The list of reverse splits.
+  #[serde(rename = "reverse_splits", default, deserialize_with = "vec_from_str")]
+  pub reverse_splits: Vec<Split>,
+  /// The list of cash dividends.
+  #[serde(rename = "cash_dividends", default, deserialize_with = "vec_from_str")]
+  pub cash_dividends: Vec<CashDividend>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A page of corporate actions as returned by the
+/// /v1/corporate-actions endpoint.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CorporateActionsPage {
+  /// The corporate actions contained in this page.
+  #[serde(rename = "corporate_actions")]
+  pub corporate_actions: CorporateActions,
+  /// The token to provide to a request to get the next page of
+  /// corporate actions for this request.
+  #[serde(rename = "next_page_token")]
+  pub next_page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+Endpoint! {
+  /// The representation of a GET request to the /v1/corporate-actions
+  /// endpoint.
+  // Note that corporate actions are only available through the v1
+  // version of the market data API.
+  pub List(ListReq),
+  Ok => CorporateActionsPage, [
+    /// The corporate actions were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => ListError, [
+    /// A query parameter was invalid.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v1/corporate-actions".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use http_endpoint::Endpoint;
+
+  use serde_json::from_str as from_json;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+  use crate::Client;
+
+
+  /// Check that we can parse a reference corporate actions response.
+  #[test]
+  fn parse_reference_corporate_actions() {
+    let response = r#"{
+  "corporate_actions": {
+    "forward_splits": [
+      {
+        "symbol": "AAPL",
+        "new_rate": 4,
+        "old_rate": 1,
+        "process_date": "2020-08-31",
+        "ex_date": "2020-08-31",
+        "record_date": "2020-08-24",
+        "payable_date": "2020-08-28"
+      }
+    ],
+    "cash_dividends": [
+      {
+        "symbol": "AAPL",
+        "rate": 0.205,
+        "special": false,
+        "foreign": false,
+        "process_date": "2020-08-14",
+        "ex_date": "2020-08-07",
+        "record_date": "2020-08-10",
+        "payable_date": "2020-08-13"
+      }
+    ]
+  },
+  "next_page_token": null
+}"#;
+
+    let page = from_json::<CorporateActionsPage>(response).unwrap();
+    let actions = page.corporate_actions;
+    assert_eq!(actions.forward_splits.len(), 1);
+    assert_eq!(actions.reverse_splits, Vec::new());
+    assert_eq!(actions.cash_dividends.len(), 1);
+    assert!(page.next_page_token.is_none());
+
+    let split = &actions.forward_splits[0];
+    assert_eq!(split.symbol, "AAPL");
+    assert_eq!(split.new_rate, Num::from(4));
+    assert_eq!(split.old_rate, Num::from(1));
+    assert_eq!(split.ex_date, NaiveDate::from_ymd_opt(2020, 8, 31).unwrap());
+
+    let dividend = &actions.cash_dividends[0];
+    assert_eq!(dividend.rate, Num::new(205, 1000));
+    assert!(!dividend.special);
+    assert_eq!(
+      dividend.ex_date,
+      NaiveDate::from_ymd_opt(2020, 8, 7).unwrap()
+    );
+  }
+
+  /// Make sure that amounts are parsed into the same `Num` regardless
+  /// of whether they are reported as JSON strings or numbers.
+  #[test]
+  fn parse_string_and_number_amounts() {
+    let dividend = |rate| {
+      let json = format!(
+        r#"{{
+  "symbol": "AAPL",
+  "rate": {rate},
+  "process_date": "2020-08-14",
+  "ex_date": "2020-08-07",
+  "record_date": null,
+  "payable_date": null
+}}"#
+      );
+      from_json::<CashDividend>(&json).unwrap()
+    };
+
+    let string = dividend(r#""1.23""#);
+    let number = dividend("1.23");
+    assert_eq!(string.rate, Num::new(123, 100));
+    assert_eq!(string, number);
+
+    assert_eq!(dividend("4").rate, dividend(r#""4""#).rate);
+    assert_eq!(dividend("0.000001").rate, dividend(r#""0.000001""#).rate);
+  }
+
+  /// Check that a [`ListReq`] is serialized into the expected query
+  /// string.
+  #[test]
+  fn serialize_list_request_query() {
+    let request = ListReqInit {
+      types: vec![Type::ForwardSplit, Type::CashDividend],
+      start: Some(NaiveDate::from_ymd_opt(2020, 8, 1).unwrap()),
+      end: Some(NaiveDate::from_ymd_opt(2020, 9, 1).unwrap()),
+      ..Default::default()
+    }
+    .init(["AAPL", "MSFT"]);
+
+    let query = List::query(&request).unwrap().unwrap();
+    let expected =
+      "symbols=AAPL%2CMSFT&types=forward_split%2Ccash_dividend&start=2020-08-01&end=2020-09-01";
+    assert_eq!(query, expected);
+  }
+
+  /// Check that we can retrieve corporate actions for a stock.
+  #[test(tokio::test)]
+  async fn request_corporate_actions() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let request = ListReqInit {
+      types: vec![Type::ForwardSplit],
+      start: Some(NaiveDate::from_ymd_opt(2020, 8, 1).unwrap()),
+      end: Some(NaiveDate::from_ymd_opt(2020, 9, 30).unwrap()),
+      ..Default::default()
+    }
+    .init(["AAPL"]);
+
+    let page = client.issue::<List>(&request).await.unwrap();
+    let splits = page.corporate_actions.forward_splits;
+    assert_eq!(splits.len(), 1);
+    assert_eq!(splits[0].new_rate, Num::from(4));
+    assert_eq!(
+      splits[0].ex_date,
+      NaiveDate::from_ymd_opt(2020, 8, 31).unwrap()
+    );
+  }
+}
@@ -21,3 +21,12 @@ pub enum Feed {
   #[serde(rename = "sip")]
   SIP,
 }
+
+impl Default for Feed {
+  /// The default feed is [`IEX`][Feed::IEX], as it is the only one
+  /// usable without a paid market data subscription.
+  #[inline]
+  fn default() -> Self {
+    Self::IEX
+  }
+}
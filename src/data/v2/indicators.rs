@@ -0,0 +1,398 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::VecDeque;
+
+use futures::Stream;
+use futures::StreamExt as _;
+
+use num_decimal::Num;
+
+use crate::data::v2::stream::Bar;
+
+
+/// A technical indicator computed over a sequence of bars.
+///
+/// Indicators maintain their state internally and are fed one bar at
+/// a time, in chronological order. They can be applied to a stream of
+/// bars by means of [`apply`] or, if multiple indicators are to be
+/// computed over the same bars, by invoking [`Indicator::update`] on
+/// each of them.
+pub trait Indicator {
+  /// Feed the next bar to the indicator, reporting its new value.
+  ///
+  /// `None` is reported while the indicator is warming up, i.e., until
+  /// sufficiently many bars have been seen.
+  fn update(&mut self, bar: &Bar) -> Option<Num>;
+}
+
+
+/// A window of the most recent values seen.
+#[derive(Clone, Debug)]
+struct Window {
+  /// The number of values to keep.
+  period: usize,
+  /// The values, oldest first.
+  values: VecDeque<Num>,
+}
+
+impl Window {
+  /// Create a window keeping the `period` most recent values, with a
+  /// minimum of one.
+  fn new(period: usize) -> Self {
+    let period = period.max(1);
+    Self {
+      period,
+      values: VecDeque::with_capacity(period),
+    }
+  }
+
+  /// Push a value into the window, returning the one that got evicted
+  /// as a result, if any.
+  fn push(&mut self, value: Num) -> Option<Num> {
+    let evicted = if self.values.len() == self.period {
+      self.values.pop_front()
+    } else {
+      None
+    };
+    let () = self.values.push_back(value);
+    evicted
+  }
+
+  /// Check whether the window contains `period` values.
+  #[inline]
+  fn is_full(&self) -> bool {
+    self.values.len() == self.period
+  }
+}
+
+
+/// The simple moving average of bar close prices.
+#[derive(Clone, Debug)]
+pub struct Sma {
+  /// The most recent close prices.
+  window: Window,
+  /// The sum of the close prices in the window.
+  sum: Num,
+}
+
+impl Sma {
+  /// Create a simple moving average over `period` bars.
+  ///
+  /// A period of zero is treated as one.
+  pub fn new(period: usize) -> Self {
+    Self {
+      window: Window::new(period),
+      sum: Num::from(0),
+    }
+  }
+}
+
+impl Indicator for Sma {
+  fn update(&mut self, bar: &Bar) -> Option<Num> {
+    self.sum += &bar.close_price;
+    if let Some(evicted) = self.window.push(bar.close_price.clone()) {
+      self.sum -= evicted;
+    }
+
+    self
+      .window
+      .is_full()
+      .then(|| &self.sum / Num::from(self.window.period))
+  }
+}
+
+
+/// The exponential moving average of bar close prices.
+///
+/// The average is seeded with the simple moving average of the first
+/// `period` bars and subsequently weighs each close price with a
+/// factor of `2 / (period + 1)`.
+#[derive(Clone, Debug)]
+pub struct Ema {
+  /// The simple moving average used for seeding the average.
+  seed: Sma,
+  /// The weight of the most recent close price.
+  alpha: Num,
+  /// The current average, once warmed up.
+  value: Option<Num>,
+}
+
+impl Ema {
+  /// Create an exponential moving average over `period` bars.
+  ///
+  /// A period of zero is treated as one.
+  pub fn new(period: usize) -> Self {
+    let seed = Sma::new(period);
+    let alpha = Num::new(2, seed.window.period + 1);
+    Self {
+      seed,
+      alpha,
+      value: None,
+    }
+  }
+}
+
+impl Indicator for Ema {
+  fn update(&mut self, bar: &Bar) -> Option<Num> {
+    let value = match self.value.take() {
+      Some(value) => (&bar.close_price - &value) * &self.alpha + value,
+      None => self.seed.update(bar)?,
+    };
+    self.value = Some(value.clone());
+    Some(value)
+  }
+}
+
+
+/// The extreme bar price over a rolling window.
+#[derive(Clone, Debug)]
+struct Extreme {
+  /// The most recent prices.
+  window: Window,
+}
+
+impl Extreme {
+  /// Push a price into the window, reporting the extreme as selected
+  /// by `select` once the window is full.
+  fn update(&mut self, price: &Num, select: fn(&Num, &Num) -> bool) -> Option<Num> {
+    let _evicted = self.window.push(price.clone());
+    if !self.window.is_full() {
+      return None
+    }
+
+    self
+      .window
+      .values
+      .iter()
+      .reduce(|extreme, price| {
+        if select(price, extreme) {
+          price
+        } else {
+          extreme
+        }
+      })
+      .cloned()
+  }
+}
+
+
+/// The highest bar high price over a rolling window.
+#[derive(Clone, Debug)]
+pub struct RollingHigh(Extreme);
+
+impl RollingHigh {
+  /// Create a rolling high over `period` bars.
+  ///
+  /// A period of zero is treated as one.
+  pub fn new(period: usize) -> Self {
+    Self(Extreme {
+      window: Window::new(period),
+    })
+  }
+}
+
+impl Indicator for RollingHigh {
+  fn update(&mut self, bar: &Bar) -> Option<Num> {
+    self.0.update(&bar.high_price, |price, high| price > high)
+  }
+}
+
+
+/// The lowest bar low price over a rolling window.
+#[derive(Clone, Debug)]
+pub struct RollingLow(Extreme);
+
+impl RollingLow {
+  /// Create a rolling low over `period` bars.
+  ///
+  /// A period of zero is treated as one.
+  pub fn new(period: usize) -> Self {
+    Self(Extreme {
+      window: Window::new(period),
+    })
+  }
+}
+
+impl Indicator for RollingLow {
+  fn update(&mut self, bar: &Bar) -> Option<Num> {
+    self.0.update(&bar.low_price, |price, low| price < low)
+  }
+}
+
+
+/// Apply an indicator to a stream of bars, yielding each bar along
+/// with the indicator's value after it.
+pub fn apply<S, I>(stream: S, mut indicator: I) -> impl Stream<Item = (Bar, Option<Num>)>
+where
+  S: Stream<Item = Bar>,
+  I: Indicator,
+{
+  stream.map(move |bar| {
+    let value = indicator.update(&bar);
+    (bar, value)
+  })
+}
+
+/// Compute the simple moving average over `period` bars of a stream
+/// of bars, as per [`Sma`].
+#[inline]
+pub fn sma<S>(stream: S, period: usize) -> impl Stream<Item = (Bar, Option<Num>)>
+where
+  S: Stream<Item = Bar>,
+{
+  apply(stream, Sma::new(period))
+}
+
+/// Compute the exponential moving average over `period` bars of a
+/// stream of bars, as per [`Ema`].
+#[inline]
+pub fn ema<S>(stream: S, period: usize) -> impl Stream<Item = (Bar, Option<Num>)>
+where
+  S: Stream<Item = Bar>,
+{
+  apply(stream, Ema::new(period))
+}
+
+/// Compute the highest high over `period` bars of a stream of bars,
+/// as per [`RollingHigh`].
+#[inline]
+pub fn rolling_high<S>(stream: S, period: usize) -> impl Stream<Item = (Bar, Option<Num>)>
+where
+  S: Stream<Item = Bar>,
+{
+  apply(stream, RollingHigh::new(period))
+}
+
+/// Compute the lowest low over `period` bars of a stream of bars, as
+/// per [`RollingLow`].
+#[inline]
+pub fn rolling_low<S>(stream: S, period: usize) -> impl Stream<Item = (Bar, Option<Num>)>
+where
+  S: Stream<Item = Bar>,
+{
+  apply(stream, RollingLow::new(period))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+  use std::vec::IntoIter;
+
+  use chrono::DateTime;
+
+  use futures::stream::iter;
+  use futures::stream::Iter;
+
+  use test_log::test;
+
+
+  /// Create a bar with the given close price, with high and low prices
+  /// one above and below it, respectively.
+  fn bar(close: i64) -> Bar {
+    Bar {
+      symbol: "AAPL".to_string(),
+      open_price: Num::from(close),
+      high_price: Num::from(close + 1),
+      low_price: Num::from(close - 1),
+      close_price: Num::from(close),
+      volume: Num::from(100),
+      timestamp: DateTime::from_str("2024-01-02T15:00:00Z").unwrap(),
+    }
+  }
+
+  /// Feed the bars with the provided close prices to `f` and collect
+  /// the resulting indicator values.
+  async fn indicate<F, S>(closes: &[i64], f: F) -> Vec<Option<Num>>
+  where
+    F: FnOnce(Iter<IntoIter<Bar>>) -> S,
+    S: Stream<Item = (Bar, Option<Num>)>,
+  {
+    let bars = closes.iter().copied().map(bar).collect::<Vec<_>>();
+    let results = f(iter(bars)).collect::<Vec<_>>().await;
+    results
+      .into_iter()
+      .zip(closes)
+      .map(|((bar, value), close)| {
+        assert_eq!(bar.close_price, Num::from(*close));
+        value
+      })
+      .collect()
+  }
+
+  /// Check that the simple moving average matches hand-computed
+  /// values.
+  #[test(tokio::test)]
+  async fn simple_moving_average() {
+    let values = indicate(&[10, 11, 12, 13, 14, 20], |bars| sma(bars, 3)).await;
+    let expected = vec![
+      None,
+      None,
+      Some(Num::from(11)),
+      Some(Num::from(12)),
+      Some(Num::from(13)),
+      Some(Num::new(47, 3)),
+    ];
+    assert_eq!(values, expected);
+  }
+
+  /// Check that the exponential moving average matches hand-computed
+  /// values.
+  #[test(tokio::test)]
+  async fn exponential_moving_average() {
+    // With a period of three, the weight of each new close is 0.5 and
+    // the average is seeded with the SMA of the first three bars.
+    let values = indicate(&[10, 11, 12, 13, 14, 20], |bars| ema(bars, 3)).await;
+    let expected = vec![
+      None,
+      None,
+      Some(Num::from(11)),
+      Some(Num::from(12)),
+      Some(Num::from(13)),
+      Some(Num::new(33, 2)),
+    ];
+    assert_eq!(values, expected);
+
+    // With a period of four, the weight is 0.4.
+    let values = indicate(&[2, 4, 6, 8, 13], |bars| ema(bars, 4)).await;
+    let expected = vec![None, None, None, Some(Num::from(5)), Some(Num::new(41, 5))];
+    assert_eq!(values, expected);
+  }
+
+  /// Make sure that rolling highs and lows consider only the bars in
+  /// the window.
+  #[test(tokio::test)]
+  async fn rolling_high_low() {
+    let closes = [10, 14, 12, 9, 11];
+    let highs = indicate(&closes, |bars| rolling_high(bars, 2)).await;
+    let expected = [None, Some(15), Some(15), Some(13), Some(12)]
+      .map(|high| high.map(Num::from))
+      .to_vec();
+    assert_eq!(highs, expected);
+
+    let lows = indicate(&closes, |bars| rolling_low(bars, 3)).await;
+    let expected = [None, None, Some(9), Some(8), Some(8)]
+      .map(|low| low.map(Num::from))
+      .to_vec();
+    assert_eq!(lows, expected);
+  }
+
+  /// Check that multiple indicators can be computed over the same bars
+  /// and that a period of zero is treated as one.
+  #[test]
+  fn compose_indicators() {
+    let mut sma = Sma::new(2);
+    let mut high = RollingHigh::new(0);
+
+    let bar1 = bar(10);
+    assert_eq!(sma.update(&bar1), None);
+    assert_eq!(high.update(&bar1), Some(Num::from(11)));
+
+    let bar2 = bar(20);
+    assert_eq!(sma.update(&bar2), Some(Num::from(15)));
+    assert_eq!(high.update(&bar2), Some(Num::from(21)));
+  }
+}
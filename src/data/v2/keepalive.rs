@@ -0,0 +1,178 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::io;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::Future as _;
+use futures::Sink;
+use futures::SinkExt as _;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use tokio::time::sleep;
+use tokio::time::Instant;
+use tokio::time::Sleep;
+
+use tracing::error;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+use websocket_util::wrap;
+
+
+/// The default interval at which to ping the server.
+const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+
+/// Configuration of the connection health checks performed on a market
+/// data stream.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Keepalive {
+  /// The interval at which to send WebSocket pings to the server, if
+  /// any.
+  ///
+  /// A ping is only sent if no other frame was received during an
+  /// interval. If the server does not respond to it within another
+  /// interval, the connection is considered broken: the stream reports
+  /// an [`std::io::ErrorKind::TimedOut`] error and ends.
+  ///
+  /// Defaults to 30 seconds.
+  pub ping_interval: Option<Duration>,
+  /// The maximum amount of time to wait for a data frame, if any.
+  ///
+  /// If no frame at all is received within this window, the stream
+  /// reports an [`std::io::ErrorKind::TimedOut`] error and ends. Note
+  /// that the server legitimately stays silent for extended periods of
+  /// time when no market data is flowing for the subscribed symbols,
+  /// e.g., outside of trading hours, which is why this check is
+  /// disabled by default.
+  pub idle_timeout: Option<Duration>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl Default for Keepalive {
+  #[inline]
+  fn default() -> Self {
+    Self {
+      ping_interval: Some(DEFAULT_PING_INTERVAL),
+      idle_timeout: None,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// Check whether the provided error indicates a timed out connection,
+/// as reported when the server failed to respond to pings.
+fn is_timeout(error: &WebSocketError) -> bool {
+  matches!(error, WebSocketError::Io(error) if error.kind() == io::ErrorKind::TimedOut)
+}
+
+
+/// A wrapper around a stream & sink of WebSocket messages that ends the
+/// stream once the connection has been detected as broken, either
+/// because pings went unanswered or because no frame was received for
+/// the configured idle timeout.
+#[derive(Debug)]
+#[doc(hidden)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Watchdog<S> {
+  /// The wrapped stream & sink.
+  inner: S,
+  /// The maximum amount of time to wait for a frame, if any.
+  idle_timeout: Option<Duration>,
+  /// The timer firing once the idle timeout expired.
+  idle: Option<Pin<Box<Sleep>>>,
+  /// Whether the connection was detected as broken and the stream
+  /// ended.
+  done: bool,
+}
+
+impl<S> Watchdog<S> {
+  /// Create a new `Watchdog` object wrapping the provided stream.
+  pub(crate) fn new(inner: S, idle_timeout: Option<Duration>) -> Self {
+    Self {
+      inner,
+      idle_timeout,
+      idle: idle_timeout.map(|timeout| Box::pin(sleep(timeout))),
+      done: false,
+    }
+  }
+}
+
+impl<S> Stream for Watchdog<S>
+where
+  S: Stream<Item = Result<wrap::Message, WebSocketError>> + Unpin,
+{
+  type Item = S::Item;
+
+  fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    if self.done {
+      return Poll::Ready(None)
+    }
+
+    match self.inner.poll_next_unpin(ctx) {
+      Poll::Ready(Some(result)) => {
+        if let (Some(timeout), Some(idle)) = (self.idle_timeout, &mut self.idle) {
+          let () = idle.as_mut().reset(Instant::now() + timeout);
+        }
+
+        if let Err(error) = &result {
+          self.done = is_timeout(error);
+        }
+        Poll::Ready(Some(result))
+      },
+      Poll::Ready(None) => {
+        self.done = true;
+        Poll::Ready(None)
+      },
+      Poll::Pending => {
+        let expired = match &mut self.idle {
+          Some(idle) => idle.as_mut().poll(ctx).is_ready(),
+          None => false,
+        };
+
+        if expired {
+          error!(msg = "no data received within idle timeout");
+          self.done = true;
+
+          let error = io::Error::new(
+            io::ErrorKind::TimedOut,
+            "no data received within idle timeout",
+          );
+          Poll::Ready(Some(Err(WebSocketError::Io(error))))
+        } else {
+          Poll::Pending
+        }
+      },
+    }
+  }
+}
+
+impl<S> Sink<wrap::Message> for Watchdog<S>
+where
+  S: Sink<wrap::Message, Error = WebSocketError> + Unpin,
+{
+  type Error = WebSocketError;
+
+  fn poll_ready(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_ready_unpin(ctx)
+  }
+
+  fn start_send(mut self: Pin<&mut Self>, message: wrap::Message) -> Result<(), Self::Error> {
+    self.inner.start_send_unpin(message)
+  }
+
+  fn poll_flush(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_flush_unpin(ctx)
+  }
+
+  fn poll_close(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_close_unpin(ctx)
+  }
+}
@@ -0,0 +1,343 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::from_slice as from_json;
+use serde_urlencoded::to_string as to_query;
+
+use crate::data::v2::bars::Bar;
+use crate::data::v2::Feed;
+use crate::raw::RawApi;
+use crate::util::string_slice_to_str;
+use crate::Str;
+
+
+/// A GET request to be made to the /v2/stocks/{symbol}/bars/latest
+/// endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct GetReq {
+  /// The symbol to retrieve the latest bar for.
+  #[serde(skip)]
+  pub symbol: String,
+  /// The data feed to use.
+  ///
+  /// Defaults to [`IEX`][Feed::IEX] for free users and
+  /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
+  #[serde(rename = "feed")]
+  pub feed: Option<Feed>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`GetReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct GetReqInit {
+  /// See `GetReq::feed`.
+  pub feed: Option<Feed>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl GetReqInit {
+  /// Create a [`GetReq`] from a `GetReqInit`.
+  #[inline]
+  pub fn init<S>(self, symbol: S) -> GetReq
+  where
+    S: Into<String>,
+  {
+    GetReq {
+      symbol: symbol.into(),
+      feed: self.feed,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+/// A GET request to be made to the /v2/stocks/bars/latest endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ListReq {
+  /// The symbols to retrieve the latest bar for.
+  #[serde(rename = "symbols", serialize_with = "string_slice_to_str")]
+  pub symbols: Vec<String>,
+  /// The data feed to use.
+  ///
+  /// Defaults to [`IEX`][Feed::IEX] for free users and
+  /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
+  #[serde(rename = "feed")]
+  pub feed: Option<Feed>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`ListReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct ListReqInit {
+  /// See `ListReq::feed`.
+  pub feed: Option<Feed>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl ListReqInit {
+  /// Create a [`ListReq`] from a `ListReqInit`.
+  #[inline]
+  pub fn init<I, S>(self, symbols: I) -> ListReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    ListReq {
+      symbols: symbols.into_iter().map(S::into).collect(),
+      feed: self.feed,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a GET request to the
+  /// /v2/stocks/{symbol}/bars/latest endpoint.
+  pub Get(GetReq),
+  Ok => Bar, [
+    /// The latest bar was retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetError, [
+    /// The provided symbol was invalid or not found or the data feed is
+    /// not supported.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(input: &Self::Input) -> Str {
+    path!("/v2/stocks/{}/bars/latest", input.symbol).into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    /// A helper object for parsing the response to a `Get` request.
+    #[derive(Deserialize)]
+    struct Response {
+      /// The latest bar.
+      bar: Bar,
+    }
+
+    from_json::<Response>(body)
+      .map(|response| response.bar)
+      .map_err(Self::ConversionError::from)
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a GET request to the
+  /// /v2/stocks/bars/latest endpoint.
+  pub List(ListReq),
+  Ok => HashMap<String, Bar>, [
+    /// The latest bars were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => ListError, [
+    /// One of the provided symbols was invalid or not found or the data
+    /// feed is not supported.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/stocks/bars/latest".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    /// A helper object for parsing the response to a `List` request.
+    #[derive(Deserialize)]
+    struct Response {
+      /// A mapping from symbols to bar objects.
+      bars: HashMap<String, Bar>,
+    }
+
+    from_json::<Response>(body)
+      .map(|response| response.bars)
+      .map_err(Self::ConversionError::from)
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use chrono::DateTime;
+  use chrono::Duration;
+  use chrono::Utc;
+
+  use http_endpoint::Endpoint as _;
+
+  use num_decimal::Num;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+  use crate::Client;
+  use crate::RequestError;
+
+
+  /// Check that we can parse the reference single-symbol latest bar
+  /// response from the documentation.
+  #[test]
+  fn parse_reference_bar() {
+    let response = br#"{
+      "bar": {
+        "t": "2022-04-12T17:26:00Z",
+        "o": 168.16,
+        "h": 168.25,
+        "l": 168.03,
+        "c": 168.2,
+        "v": 9167,
+        "n": 113,
+        "vw": 168.137497
+      },
+      "symbol": "AAPL"
+    }"#;
+
+    let bar = Get::parse(response).unwrap();
+    assert_eq!(
+      bar.time,
+      DateTime::parse_from_rfc3339("2022-04-12T17:26:00Z").unwrap()
+    );
+    assert_eq!(bar.open, Num::new(16816, 100));
+    assert_eq!(bar.high, Num::new(16825, 100));
+    assert_eq!(bar.low, Num::new(16803, 100));
+    assert_eq!(bar.close, Num::new(1682, 10));
+    assert_eq!(bar.volume, 9167);
+  }
+
+  /// Check that we can parse the reference multi-symbol latest bars
+  /// response from the documentation.
+  #[test]
+  fn parse_reference_bars() {
+    let response = br#"{
+      "bars": {
+        "TSLA": {
+          "t": "2022-04-12T17:26:00Z",
+          "o": 1020.5,
+          "h": 1021,
+          "l": 1019.6,
+          "c": 1020.9,
+          "v": 2181,
+          "n": 86,
+          "vw": 1020.347137
+        },
+        "AAPL": {
+          "t": "2022-04-12T17:26:00Z",
+          "o": 168.16,
+          "h": 168.25,
+          "l": 168.03,
+          "c": 168.2,
+          "v": 9167,
+          "n": 113,
+          "vw": 168.137497
+        }
+      }
+    }"#;
+
+    let bars = List::parse(response).unwrap();
+    assert_eq!(bars.len(), 2);
+    assert_eq!(bars["AAPL"].open, Num::new(16816, 100));
+    assert_eq!(bars["AAPL"].volume, 9167);
+    assert_eq!(bars["TSLA"].open, Num::new(10205, 10));
+    assert_eq!(bars["TSLA"].volume, 2181);
+  }
+
+  /// Check that we serialize the multi-symbol request correctly.
+  #[test]
+  fn serialize_list_request_query() {
+    let request = ListReqInit {
+      feed: Some(Feed::IEX),
+      ..Default::default()
+    }
+    .init(["AAPL", "TSLA"]);
+
+    let query = List::query(&request).unwrap().unwrap();
+    assert_eq!(query, "symbols=AAPL%2CTSLA&feed=iex");
+  }
+
+  /// Verify that we can retrieve the latest bar for an asset.
+  #[test(tokio::test)]
+  async fn request_last_bar() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let req = GetReqInit::default().init("SPY");
+    let bar = client.issue::<Get>(&req).await.unwrap();
+    // Just as a rough sanity check, we require that the reported time
+    // is some time after two weeks before today. That should safely
+    // account for any combination of holidays, weekends, etc.
+    assert!(bar.time >= Utc::now() - Duration::try_weeks(2).unwrap());
+  }
+
+  /// Verify that we can retrieve the latest bars for multiple assets
+  /// at once.
+  #[test(tokio::test)]
+  async fn request_last_bars() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let req = ListReqInit::default().init(["MSFT", "SPY"]);
+    let bars = client.issue::<List>(&req).await.unwrap();
+    assert_eq!(bars.len(), 2);
+    assert!(bars["MSFT"].time >= Utc::now() - Duration::try_weeks(2).unwrap());
+    assert!(bars["SPY"].time >= Utc::now() - Duration::try_weeks(2).unwrap());
+  }
+
+  /// Check that we fail as expected when retrieving the latest bar for
+  /// an invalid symbol.
+  #[test(tokio::test)]
+  async fn request_last_bar_invalid_symbol() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let req = GetReqInit::default().init("ABC123");
+    let err = client.issue::<Get>(&req).await.unwrap_err();
+    match err {
+      RequestError::Endpoint(GetError::InvalidInput(_)) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+}
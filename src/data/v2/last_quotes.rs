@@ -1,11 +1,15 @@
 // Copyright (C) 2021-2024 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::future::Future;
 
 use chrono::DateTime;
 use chrono::Utc;
 
+use futures::future::try_join_all;
+
 use num_decimal::Num;
 
 use serde::Deserialize;
@@ -14,8 +18,13 @@ use serde_json::from_slice as from_json;
 use serde_urlencoded::to_string as to_query;
 
 use crate::data::v2::Feed;
-use crate::data::DATA_BASE_URL;
+use crate::data::v2::Tape;
+use crate::data::MAX_SYMBOLS_PER_REQUEST;
+use crate::raw::RawApi;
 use crate::util::string_slice_to_str;
+use crate::ApiError;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
@@ -26,6 +35,9 @@ pub struct GetReq {
   #[serde(rename = "symbols", serialize_with = "string_slice_to_str")]
   pub symbols: Vec<String>,
   /// The data feed to use.
+  ///
+  /// Defaults to [`IEX`][Feed::IEX] for free users and
+  /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
   #[serde(rename = "feed")]
   pub feed: Option<Feed>,
   /// The type is non-exhaustive and open to extension.
@@ -82,12 +94,28 @@ pub struct Quote {
   /// The bid size.
   #[serde(rename = "bs")]
   pub bid_size: u64,
+  /// The raw condition codes of the quote.
+  #[serde(rename = "c", default)]
+  pub conditions: Vec<String>,
+  /// The raw code of the tape the quote was reported on.
+  ///
+  /// Use [`Quote::decoded_tape`] to decode it.
+  #[serde(rename = "z", default)]
+  pub tape: Option<String>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
+impl Quote {
+  /// Decode the tape the quote was reported on, if known.
+  #[inline]
+  pub fn decoded_tape(&self) -> Option<Tape> {
+    self.tape.as_deref().and_then(Tape::from_code)
+  }
+}
+
 
 EndpointNoParse! {
   /// The representation of a GET request to the
@@ -104,7 +132,7 @@ EndpointNoParse! {
   ]
 
   fn base_url() -> Option<Str> {
-    Some(DATA_BASE_URL.into())
+    RawApi::Data.base_url()
   }
 
   fn path(_input: &Self::Input) -> Str {
@@ -147,6 +175,137 @@ EndpointNoParse! {
 }
 
 
+/// The result of a [`get_partial`] request.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PartialQuotes {
+  /// The retrieved quotes, in lexical order of their symbols.
+  pub quotes: Vec<(String, Quote)>,
+  /// The requested symbols for which no quote could be retrieved,
+  /// either because they were reported as invalid or because they do
+  /// not exist.
+  pub failed: Vec<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// Extract the symbol reported as invalid by a [`GetError`], if any.
+fn invalid_symbol(error: &GetError) -> Option<&str> {
+  match error {
+    GetError::InvalidInput(Ok(ApiError { message, .. })) => {
+      message.strip_prefix("invalid symbol: ")
+    },
+    _ => None,
+  }
+}
+
+
+/// The implementation of [`get_partial`], using `issue` for issuing
+/// the actual requests.
+///
+/// Symbols are split into batches of at most
+/// [`MAX_SYMBOLS_PER_REQUEST`], which are requested concurrently.
+async fn get_partial_with<F, R>(
+  request: &GetReq,
+  issue: F,
+) -> Result<PartialQuotes, RequestError<GetError>>
+where
+  F: FnMut(GetReq) -> R,
+  R: Future<Output = Result<Vec<(String, Quote)>, RequestError<GetError>>>,
+{
+  let issue = RefCell::new(issue);
+  let batches = request
+    .symbols
+    .chunks(MAX_SYMBOLS_PER_REQUEST)
+    .map(|symbols| {
+      let request = GetReq {
+        symbols: symbols.to_vec(),
+        ..request.clone()
+      };
+      get_partial_batch(request, &issue)
+    });
+
+  let mut partial = PartialQuotes::default();
+  for batch in try_join_all(batches).await? {
+    partial.quotes.extend(batch.quotes);
+    partial.failed.extend(batch.failed);
+  }
+  partial.quotes.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+  partial.failed.sort();
+  Ok(partial)
+}
+
+
+/// Retrieve the last quotes for a single batch of symbols.
+async fn get_partial_batch<F, R>(
+  mut request: GetReq,
+  issue: &RefCell<F>,
+) -> Result<PartialQuotes, RequestError<GetError>>
+where
+  F: FnMut(GetReq) -> R,
+  R: Future<Output = Result<Vec<(String, Quote)>, RequestError<GetError>>>,
+{
+  let mut failed = Vec::new();
+
+  let quotes = loop {
+    if request.symbols.is_empty() {
+      break Vec::new()
+    }
+
+    // The borrow only lasts for creating the request future, so that
+    // batches can be in flight concurrently.
+    let result = (issue.borrow_mut())(request.clone());
+    match result.await {
+      Ok(quotes) => break quotes,
+      Err(RequestError::Endpoint(error)) => {
+        let index = invalid_symbol(&error)
+          .and_then(|invalid| request.symbols.iter().position(|symbol| symbol == invalid));
+
+        match index {
+          Some(index) => failed.push(request.symbols.remove(index)),
+          None => return Err(RequestError::Endpoint(error)),
+        }
+      },
+      Err(error) => return Err(error),
+    }
+  };
+
+  // Symbols that do not exist are silently omitted from the response.
+  let missing = request
+    .symbols
+    .into_iter()
+    .filter(|symbol| !quotes.iter().any(|(quoted, _)| quoted == symbol));
+  failed.extend(missing);
+
+  Ok(PartialQuotes {
+    quotes,
+    failed,
+    _non_exhaustive: (),
+  })
+}
+
+
+/// Retrieve the last quotes for multiple symbols, tolerating invalid
+/// and non-existent ones.
+///
+/// A single invalid symbol causes Alpaca to reject the request as a
+/// whole. This function instead retries the request without any
+/// symbol reported as invalid, returning the quotes for all remaining
+/// symbols along with the list of symbols that could not be served.
+/// Long lists of symbols are split into multiple requests, which are
+/// issued concurrently.
+pub async fn get_partial(
+  client: &Client,
+  request: &GetReq,
+) -> Result<PartialQuotes, RequestError<GetError>> {
+  get_partial_with(request, |request| async move {
+    client.issue::<Get>(&request).await
+  })
+  .await
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -155,11 +314,11 @@ mod tests {
 
   use http_endpoint::Endpoint as _;
 
+  use futures::future::ready;
+
   use test_log::test;
 
   use crate::api_info::ApiInfo;
-  use crate::Client;
-  use crate::RequestError;
 
 
   /// Check that we can parse the reference quotes from the
@@ -206,6 +365,8 @@ mod tests {
     assert_eq!(aapl.ask_size, 1);
     assert_eq!(aapl.bid_price, Num::new(16803, 100));
     assert_eq!(aapl.bid_size, 1);
+    assert_eq!(aapl.conditions, vec!["R".to_string()]);
+    assert_eq!(aapl.decoded_tape(), Some(Tape::C));
 
     assert_eq!(quotes[1].0, "TSLA");
     let tsla = &quotes[1].1;
@@ -302,4 +463,122 @@ mod tests {
     let quotes = client.issue::<Get>(&req).await.unwrap();
     assert_eq!(quotes.len(), 1);
   }
+
+  /// Check that an invalid symbol in a multi-symbol request is
+  /// reported as failed while quotes for the remaining ones are still
+  /// retrieved.
+  #[test(tokio::test)]
+  async fn partial_quotes() {
+    let quote = || Quote {
+      time: Utc::now(),
+      ask_price: Num::from(2),
+      ask_size: 1,
+      bid_price: Num::from(1),
+      bid_size: 1,
+      conditions: Vec::new(),
+      tape: None,
+      _non_exhaustive: (),
+    };
+
+    let mut requests = 0;
+    let req = GetReqInit::default().init(["SPY", "ABC123", "NOSUCHSYMBOL"]);
+    let partial = get_partial_with(&req, |request| {
+      requests += 1;
+
+      let result = if request.symbols.iter().any(|symbol| symbol == "ABC123") {
+        let error = ApiError {
+          code: Some(40010001),
+          message: "invalid symbol: ABC123".to_string(),
+        };
+        Err(RequestError::Endpoint(GetError::InvalidInput(Ok(error))))
+      } else {
+        Ok(vec![("SPY".to_string(), quote())])
+      };
+      ready(result)
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(requests, 2);
+    assert_eq!(partial.quotes.len(), 1);
+    assert_eq!(partial.quotes[0].0, "SPY");
+    assert_eq!(partial.failed, vec!["ABC123", "NOSUCHSYMBOL"]);
+  }
+
+  /// Check that symbols exceeding the per-request limit are split into
+  /// multiple requests, with the results merged.
+  #[test(tokio::test)]
+  async fn partial_quotes_batched() {
+    let count = MAX_SYMBOLS_PER_REQUEST + 10;
+    let symbols = (0..count).map(|i| format!("SYM{i:03}"));
+    let req = GetReqInit::default().init(symbols.chain(["NOSUCHSYMBOL".to_string()]));
+
+    let quote = || Quote {
+      time: Utc::now(),
+      ask_price: Num::from(2),
+      ask_size: 1,
+      bid_price: Num::from(1),
+      bid_size: 1,
+      conditions: Vec::new(),
+      tape: None,
+      _non_exhaustive: (),
+    };
+
+    let mut requests = 0;
+    let partial = get_partial_with(&req, |request| {
+      requests += 1;
+      assert!(request.symbols.len() <= MAX_SYMBOLS_PER_REQUEST);
+
+      let quotes = request
+        .symbols
+        .into_iter()
+        .filter(|symbol| symbol != "NOSUCHSYMBOL")
+        .rev()
+        .map(|symbol| (symbol, quote()))
+        .collect();
+      ready(Ok(quotes))
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(requests, 2);
+    assert_eq!(partial.quotes.len(), count);
+    for (i, (symbol, _)) in partial.quotes.iter().enumerate() {
+      assert_eq!(symbol, &format!("SYM{i:03}"));
+    }
+    assert_eq!(partial.failed, vec!["NOSUCHSYMBOL"]);
+  }
+
+  /// Check that errors not related to a specific symbol are still
+  /// reported as such.
+  #[test(tokio::test)]
+  async fn partial_quotes_error() {
+    let req = GetReqInit::default().init(["SPY"]);
+    let err = get_partial_with(&req, |_request| {
+      ready(Err(RequestError::Endpoint(GetError::InvalidInput(Err(
+        b"bad feed".to_vec(),
+      )))))
+    })
+    .await
+    .unwrap_err();
+
+    match err {
+      RequestError::Endpoint(GetError::InvalidInput(Err(_))) => (),
+      _ => panic!("Received unexpected error: {err:?}"),
+    };
+  }
+
+  /// Verify that we can retrieve quotes for a mixture of valid and
+  /// invalid symbols.
+  #[test(tokio::test)]
+  async fn request_partial_quotes() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+
+    let req = GetReqInit::default().init(["SPY", "ABC123"]);
+    let partial = get_partial(&client, &req).await.unwrap();
+    assert_eq!(partial.quotes.len(), 1);
+    assert_eq!(partial.quotes[0].0, "SPY");
+    assert_eq!(partial.failed, vec!["ABC123"]);
+  }
 }
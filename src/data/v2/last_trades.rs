@@ -0,0 +1,241 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::from_slice as from_json;
+use serde_urlencoded::to_string as to_query;
+
+use crate::data::v2::trades::Trade;
+use crate::data::v2::Feed;
+use crate::raw::RawApi;
+use crate::util::string_slice_to_str;
+use crate::Str;
+
+
+/// A GET request to be made to the /v2/stocks/trades/latest endpoint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct GetReq {
+  /// The symbols to retrieve the last trade for.
+  #[serde(rename = "symbols", serialize_with = "string_slice_to_str")]
+  pub symbols: Vec<String>,
+  /// The data feed to use.
+  ///
+  /// Defaults to [`IEX`][Feed::IEX] for free users and
+  /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
+  #[serde(rename = "feed")]
+  pub feed: Option<Feed>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  #[serde(skip)]
+  pub _non_exhaustive: (),
+}
+
+
+/// A helper for initializing [`GetReq`] objects.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_copy_implementations)]
+pub struct GetReqInit {
+  /// See `GetReq::feed`.
+  pub feed: Option<Feed>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl GetReqInit {
+  /// Create a [`GetReq`] from a `GetReqInit`.
+  #[inline]
+  pub fn init<I, S>(self, symbols: I) -> GetReq
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    GetReq {
+      symbols: symbols.into_iter().map(S::into).collect(),
+      feed: self.feed,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a GET request to the
+  /// /v2/stocks/trades/latest endpoint.
+  pub Get(GetReq),
+  Ok => Vec<(String, Trade)>, [
+    /// The last trades were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => GetError, [
+    /// The provided symbol was invalid or not found or the data feed is
+    /// not supported.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/stocks/trades/latest".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    /// A helper object for parsing the response to a `Get` request.
+    #[derive(Deserialize)]
+    struct Response {
+      /// A mapping from symbols to trade objects.
+      // We use a `BTreeMap` here to have a consistent ordering of
+      // trades.
+      trades: BTreeMap<String, Trade>,
+    }
+
+    from_json::<Response>(body)
+      .map(|response| response.trades.into_iter().collect())
+      .map_err(Self::ConversionError::from)
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of a GET request to the
+  /// /v2/stocks/trades/latest endpoint, reporting the trades keyed by
+  /// their symbol.
+  ///
+  /// This endpoint is equivalent to [`Get`], except for the
+  /// representation of the result.
+  pub List(GetReq),
+  Ok => HashMap<String, Trade>, [
+    /// The last trades were retrieved successfully.
+    /* 200 */ OK,
+  ],
+  Err => ListError, [
+    /// The provided symbol was invalid or not found or the data feed is
+    /// not supported.
+    /* 400 */ BAD_REQUEST => InvalidInput,
+  ]
+
+  fn base_url() -> Option<Str> {
+    RawApi::Data.base_url()
+  }
+
+  fn path(_input: &Self::Input) -> Str {
+    "/v2/stocks/trades/latest".into()
+  }
+
+  fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    Ok(Some(to_query(input)?.into()))
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    /// A helper object for parsing the response to a `List` request.
+    #[derive(Deserialize)]
+    struct Response {
+      /// A mapping from symbols to trade objects.
+      trades: HashMap<String, Trade>,
+    }
+
+    from_json::<Response>(body)
+      .map(|response| response.trades)
+      .map_err(Self::ConversionError::from)
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use chrono::DateTime;
+
+  use http_endpoint::Endpoint as _;
+
+  use num_decimal::Num;
+
+  use test_log::test;
+
+
+  /// The reference response from the documentation.
+  const RESPONSE: &[u8] = br#"{
+      "trades": {
+        "TSLA": {
+          "t": "2022-04-12T17:05:06.936423531Z",
+          "x": "V",
+          "p": 995,
+          "s": 100,
+          "c": ["@"],
+          "i": 10741,
+          "z": "C"
+        },
+        "AAPL": {
+          "t": "2022-04-12T17:05:17.428334819Z",
+          "x": "V",
+          "p": 167.86,
+          "s": 99,
+          "c": ["@"],
+          "i": 7980,
+          "z": "C"
+        }
+      }
+    }"#;
+
+
+  /// Check that we can parse the reference trades from the
+  /// documentation.
+  #[test]
+  fn parse_reference_trades() {
+    let trades = Get::parse(RESPONSE).unwrap();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].0, "AAPL");
+    assert_eq!(
+      trades[0].1.timestamp,
+      DateTime::parse_from_rfc3339("2022-04-12T17:05:17.428334819Z").unwrap()
+    );
+    assert_eq!(trades[0].1.price, Num::new(16786, 100));
+    assert_eq!(trades[0].1.size, 99);
+    assert_eq!(trades[1].0, "TSLA");
+    assert_eq!(trades[1].1.price, Num::from(995));
+  }
+  /// Check that the multi-symbol response is unwrapped into a map
+  /// keyed by symbol.
+  #[test]
+  fn parse_reference_trades_map() {
+    let trades = List::parse(RESPONSE).unwrap();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades["AAPL"].price, Num::new(16786, 100));
+    assert_eq!(trades["AAPL"].size, 99);
+    assert_eq!(trades["TSLA"].price, Num::from(995));
+    assert_eq!(
+      trades["TSLA"].timestamp,
+      DateTime::parse_from_rfc3339("2022-04-12T17:05:06.936423531Z").unwrap()
+    );
+
+    let request = GetReqInit {
+      feed: Some(Feed::IEX),
+      ..Default::default()
+    }
+    .init(["AAPL", "TSLA"]);
+    assert_eq!(List::path(&request), "/v2/stocks/trades/latest");
+    assert_eq!(
+      List::query(&request).unwrap().unwrap(),
+      "symbols=AAPL%2CTSLA&feed=iex"
+    );
+  }
+}
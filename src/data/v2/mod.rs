@@ -1,18 +1,48 @@
 // Copyright (C) 2021-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod condition;
 mod feed;
+#[cfg(feature = "streaming")]
+mod keepalive;
+#[cfg(feature = "streaming")]
+mod observe;
+mod range;
+#[cfg(feature = "streaming")]
+mod shared;
+mod sort;
+#[cfg(feature = "streaming")]
+mod stats;
+#[cfg(feature = "streaming")]
 mod unfold;
 
+/// Functionality for retrieval of opening and closing auctions.
+pub mod auctions;
+/// Functionality for backfilling real-time bars with historical ones.
+#[cfg(feature = "streaming")]
+pub mod backfill;
 /// Definitions for retrieval of market data bars.
 pub mod bars;
+/// Functionality for retrieval of corporate actions.
+pub mod corporate_actions;
+/// Technical indicators computed over streamed bars.
+#[cfg(feature = "streaming")]
+pub mod indicators;
+/// Functionality for retrieval of most recent bars.
+pub mod last_bar;
 /// Functionality for retrieval of most recent quotes.
 pub mod last_quotes;
+/// Functionality for retrieval of most recent trades.
+pub mod last_trades;
 /// Functionality for retrieving historic quotes.
 pub mod quotes;
 /// Definitions for real-time streaming of market data.
+#[cfg(feature = "streaming")]
 pub mod stream;
 /// Definitions for retrieval of market data trades.
 pub mod trades;
 
+pub use condition::Condition;
+pub use condition::Tape;
 pub use feed::Feed;
+pub use sort::Sort;
@@ -0,0 +1,294 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::Sink;
+use futures::SinkExt as _;
+use futures::Stream;
+use futures::StreamExt as _;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::to_writer as json_to_writer;
+
+use tracing::error;
+use tracing::trace;
+
+use websocket_util::tungstenite::Error as WebSocketError;
+use websocket_util::wrap;
+
+use super::stats::Counters;
+
+
+/// The direction in which a frame travels.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Direction {
+  /// The frame was received from the server.
+  Inbound,
+  /// The frame is about to be sent to the server.
+  Outbound,
+}
+
+
+/// A raw WebSocket frame, as seen by an [`Observer`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Frame<'a> {
+  /// A text frame.
+  Text(&'a str),
+  /// A binary frame.
+  Binary(&'a [u8]),
+}
+
+impl<'a> From<&'a wrap::Message> for Frame<'a> {
+  #[inline]
+  fn from(message: &'a wrap::Message) -> Self {
+    match message {
+      wrap::Message::Text(text) => Self::Text(text),
+      wrap::Message::Binary(data) => Self::Binary(data),
+    }
+  }
+}
+
+
+/// A trait for observing the raw frames exchanged over a market data
+/// stream.
+///
+/// An observer sees every inbound frame before it is parsed as well as
+/// every outbound control message (such as authentication and
+/// subscription requests). It is invoked inline while the stream is
+/// being polled and, hence, should be cheap; expensive work should be
+/// handed off elsewhere.
+pub trait Observer: Send + Sync {
+  /// Observe a frame traveling in the given direction.
+  fn observe(&self, direction: Direction, frame: Frame<'_>);
+}
+
+impl<F> Observer for F
+where
+  F: Fn(Direction, Frame<'_>) + Send + Sync,
+{
+  #[inline]
+  fn observe(&self, direction: Direction, frame: Frame<'_>) {
+    self(direction, frame)
+  }
+}
+
+
+/// An [`Observer`] logging each frame using `tracing`, at trace level.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingObserver;
+
+impl Observer for TracingObserver {
+  fn observe(&self, direction: Direction, frame: Frame<'_>) {
+    match frame {
+      Frame::Text(text) => trace!(direction = debug(direction), text),
+      Frame::Binary(data) => trace!(direction = debug(direction), binary = debug(data)),
+    }
+  }
+}
+
+
+/// The payload of a frame as recorded by a [`Recorder`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) enum RecordedFrame {
+  /// A text frame.
+  #[serde(rename = "text")]
+  Text(String),
+  /// A binary frame.
+  #[serde(rename = "binary")]
+  Binary(Vec<u8>),
+}
+
+impl From<RecordedFrame> for wrap::Message {
+  #[inline]
+  fn from(frame: RecordedFrame) -> Self {
+    match frame {
+      RecordedFrame::Text(text) => Self::Text(text),
+      RecordedFrame::Binary(data) => Self::Binary(data),
+    }
+  }
+}
+
+
+/// A single line of a recording as produced by a [`Recorder`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Record {
+  /// The time at which the frame was received.
+  #[serde(rename = "time")]
+  pub time: DateTime<Utc>,
+  /// The recorded frame.
+  #[serde(flatten)]
+  pub frame: RecordedFrame,
+}
+
+
+/// An [`Observer`] recording each inbound frame, along with the time
+/// it was received at, to a writer.
+///
+/// Frames are written as newline-delimited JSON objects. Recordings
+/// can be played back using
+/// [`replay`][crate::data::v2::stream::replay].
+#[derive(Debug)]
+pub struct Recorder<W> {
+  /// The writer to record frames to.
+  writer: Mutex<W>,
+}
+
+impl<W> Recorder<W> {
+  /// Create a new `Recorder` writing frames to the provided writer.
+  #[inline]
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer: Mutex::new(writer),
+    }
+  }
+
+  /// Destroy the `Recorder`, returning the underlying writer.
+  #[inline]
+  pub fn into_inner(self) -> W {
+    self
+      .writer
+      .into_inner()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+impl<W> Observer for Recorder<W>
+where
+  W: Write + Send,
+{
+  fn observe(&self, direction: Direction, frame: Frame<'_>) {
+    if direction != Direction::Inbound {
+      return
+    }
+
+    let frame = match frame {
+      Frame::Text(text) => RecordedFrame::Text(text.to_string()),
+      Frame::Binary(data) => RecordedFrame::Binary(data.to_vec()),
+    };
+    let record = Record {
+      time: Utc::now(),
+      frame,
+    };
+
+    let mut writer = self
+      .writer
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let result = json_to_writer(&mut *writer, &record)
+      .map_err(Into::into)
+      .and_then(|()| writer.write_all(b"\n"));
+
+    if let Err(err) = result {
+      error!(msg = "failed to record frame", error = display(err));
+    }
+  }
+}
+
+
+/// A wrapper around a stream & sink of WebSocket messages that reports
+/// all messages passing through it to an optional [`Observer`] and
+/// accounts for inbound ones in the provided [`Counters`].
+#[doc(hidden)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Observed<S> {
+  /// The wrapped stream & sink.
+  inner: S,
+  /// The observer to report messages to, if any.
+  observer: Option<Arc<dyn Observer>>,
+  /// The counters to account inbound messages in.
+  counters: Arc<Counters>,
+}
+
+impl<S> Observed<S> {
+  /// Create a new `Observed` object wrapping the provided stream.
+  #[inline]
+  pub(crate) fn new(
+    inner: S,
+    observer: Option<Arc<dyn Observer>>,
+    counters: Arc<Counters>,
+  ) -> Self {
+    Self {
+      inner,
+      observer,
+      counters,
+    }
+  }
+}
+
+impl<S> Debug for Observed<S>
+where
+  S: Debug,
+{
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+    fmt
+      .debug_struct("Observed")
+      .field("inner", &self.inner)
+      .field("observer", &self.observer.is_some())
+      .field("counters", &self.counters)
+      .finish()
+  }
+}
+
+impl<S> Stream for Observed<S>
+where
+  S: Stream<Item = Result<wrap::Message, WebSocketError>> + Unpin,
+{
+  type Item = S::Item;
+
+  fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let poll = self.inner.poll_next_unpin(ctx);
+    if let Poll::Ready(Some(Ok(message))) = &poll {
+      let len = match message {
+        wrap::Message::Text(text) => text.len(),
+        wrap::Message::Binary(data) => data.len(),
+      };
+      let () = self.counters.record(len);
+
+      if let Some(observer) = &self.observer {
+        let () = observer.observe(Direction::Inbound, Frame::from(message));
+      }
+    }
+    poll
+  }
+}
+
+impl<S> Sink<wrap::Message> for Observed<S>
+where
+  S: Sink<wrap::Message, Error = WebSocketError> + Unpin,
+{
+  type Error = WebSocketError;
+
+  fn poll_ready(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_ready_unpin(ctx)
+  }
+
+  fn start_send(mut self: Pin<&mut Self>, message: wrap::Message) -> Result<(), Self::Error> {
+    if let Some(observer) = &self.observer {
+      let () = observer.observe(Direction::Outbound, Frame::from(&message));
+    }
+    self.inner.start_send_unpin(message)
+  }
+
+  fn poll_flush(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_flush_unpin(ctx)
+  }
+
+  fn poll_close(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_close_unpin(ctx)
+  }
+}
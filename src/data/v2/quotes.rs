@@ -4,13 +4,19 @@
 use chrono::DateTime;
 use chrono::Utc;
 
+use futures::Stream;
+
 use serde::Deserialize;
 use serde::Serialize;
 use serde_urlencoded::to_string as to_query;
 
+use crate::data::v2::range::check_time_range;
 use crate::data::v2::Feed;
-use crate::data::DATA_BASE_URL;
+use crate::data::v2::Sort;
+use crate::raw::RawApi;
 use crate::util::vec_from_str;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 /// A quote as returned by the /v2/stocks/{symbol}/quotes endpoint.
@@ -45,6 +51,8 @@ pub struct ListReqInit {
   pub limit: Option<usize>,
   /// See `ListReq::feed`.
   pub feed: Option<Feed>,
+  /// See `ListReq::sort`.
+  pub sort: Sort,
   /// See `ListReq::page_token`.
   pub page_token: Option<String>,
   /// The type is non-exhaustive and open to extension.
@@ -65,6 +73,7 @@ impl ListReqInit {
       end,
       limit: self.limit,
       feed: self.feed,
+      sort: self.sort,
       page_token: self.page_token,
       _non_exhaustive: (),
     }
@@ -92,8 +101,15 @@ pub struct ListReq {
   #[serde(rename = "limit")]
   pub limit: Option<usize>,
   /// The data feed to use.
+  ///
+  /// Defaults to [`IEX`][Feed::IEX] for free users and
+  /// [`SIP`][Feed::SIP] for users with an unlimited subscription.
   #[serde(rename = "feed")]
   pub feed: Option<Feed>,
+  /// The order in which to report quotes. Defaults to ascending in
+  /// time, in which case the parameter is not sent at all.
+  #[serde(rename = "sort", skip_serializing_if = "Sort::is_ascending")]
+  pub sort: Sort,
   /// Pagination token to continue from.
   #[serde(rename = "page_token")]
   pub page_token: Option<String>,
@@ -118,20 +134,37 @@ Endpoint! {
   ]
 
   fn base_url() -> Option<Str> {
-    Some(DATA_BASE_URL.into())
+    RawApi::Data.base_url()
   }
 
   #[inline]
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/stocks/{}/quotes", input.symbol).into()
+    path!("/v2/stocks/{}/quotes", input.symbol).into()
   }
 
   fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let () = check_time_range(&input.start, &input.end)?;
     Ok(Some(to_query(input)?.into()))
   }
 }
 
 
+impl Client {
+  /// Create a stream lazily yielding all quotes matching `request`,
+  /// transparently paging through the results.
+  ///
+  /// Please refer to [`Client::paginate`] for details on the
+  /// stream's behavior.
+  #[inline]
+  pub fn quotes_stream(
+    &self,
+    request: &ListReq,
+  ) -> impl Stream<Item = Result<Quote, RequestError<ListError>>> + '_ {
+    self.paginate::<List>(request)
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -140,13 +173,52 @@ mod tests {
 
   use num_decimal::Num;
 
+  use futures::StreamExt as _;
+  use futures::TryStreamExt as _;
+
+  use http_endpoint::Endpoint as _;
+
   use test_log::test;
 
   use crate::api_info::ApiInfo;
-  use crate::Client;
-  use crate::RequestError;
 
 
+  /// Check that we serialize a request with all filters set correctly.
+  #[test]
+  fn serialize_list_request_query() {
+    let start = DateTime::from_str("2022-01-04T13:35:00Z").unwrap();
+    let end = DateTime::from_str("2022-01-04T13:36:00Z").unwrap();
+    let request = ListReqInit {
+      limit: Some(50),
+      feed: Some(Feed::SIP),
+      sort: Sort::Descending,
+      page_token: Some("abc".to_string()),
+      ..Default::default()
+    }
+    .init("SPY", start, end);
+
+    assert_eq!(List::path(&request), "/v2/stocks/SPY/quotes");
+    let query = List::query(&request).unwrap().unwrap();
+    let expected = "start=2022-01-04T13%3A35%3A00Z&end=2022-01-04T13%3A36%3A00Z\
+                    &limit=50&feed=sip&sort=desc&page_token=abc";
+    assert_eq!(query, expected);
+  }
+
+  /// Make sure that the default sort order is not sent to the server.
+  #[test]
+  fn serialize_default_sort() {
+    let start = DateTime::from_str("2022-01-04T13:35:00Z").unwrap();
+    let end = DateTime::from_str("2022-01-04T13:36:00Z").unwrap();
+    let request = ListReqInit::default().init("SPY", start, end);
+    assert_eq!(request.sort, Sort::Ascending);
+
+    let query = List::query(&request).unwrap().unwrap();
+    assert_eq!(
+      query,
+      "start=2022-01-04T13%3A35%3A00Z&end=2022-01-04T13%3A36%3A00Z"
+    );
+  }
+
   /// Check that we can retrieve quotes for a specific time frame.
   #[test(tokio::test)]
   async fn request_quotes() {
@@ -252,4 +324,27 @@ mod tests {
       last_quotes = Some(quotes);
     }
   }
+
+  /// Check that we can lazily stream quotes across page boundaries.
+  #[test(tokio::test)]
+  async fn stream_quotes() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let start = DateTime::from_str("2022-01-04T13:35:00Z").unwrap();
+    let end = DateTime::from_str("2022-01-04T13:36:00Z").unwrap();
+    let request = ListReqInit {
+      limit: Some(2),
+      ..Default::default()
+    }
+    .init("SPY", start, end);
+
+    let quotes = client
+      .quotes_stream(&request)
+      .take(5)
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    assert_eq!(quotes.len(), 5);
+    assert!(quotes.windows(2).all(|pair| pair[0].time <= pair[1].time));
+  }
 }
@@ -0,0 +1,60 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use tracing::warn;
+
+use crate::endpoint::ConversionError;
+
+
+/// Check that the time range spanned by `start` and `end` is valid.
+///
+/// A range ending before it starts is rejected. A range ending in the
+/// future is permitted, but a warning is emitted, because Alpaca
+/// simply does not report data for the part that lies ahead.
+pub(crate) fn check_time_range(
+  start: &DateTime<Utc>,
+  end: &DateTime<Utc>,
+) -> Result<(), ConversionError> {
+  if start > end {
+    return Err(ConversionError::InvalidTimeRange {
+      start: *start,
+      end: *end,
+    })
+  }
+
+  if *end > Utc::now() {
+    warn!(end = display(end), "request time range ends in the future");
+  }
+  Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::str::FromStr as _;
+
+  use test_log::test;
+
+
+  /// Check that reversed time ranges are rejected while valid ones
+  /// pass.
+  #[test]
+  fn check_ranges() {
+    let start = DateTime::from_str("2022-01-04T13:35:00Z").unwrap();
+    let end = DateTime::from_str("2022-01-04T13:36:00Z").unwrap();
+
+    let () = check_time_range(&start, &end).unwrap();
+    let () = check_time_range(&start, &start).unwrap();
+
+    let err = check_time_range(&end, &start).unwrap_err();
+    assert!(
+      matches!(err, ConversionError::InvalidTimeRange { start: s, end: e } if s == end && e == start),
+      "{err}"
+    );
+  }
+}
@@ -0,0 +1,62 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::task::Context;
+use futures::task::Poll;
+use futures::Sink;
+use futures::SinkExt as _;
+
+
+/// A sink that can be shared between multiple owners.
+///
+/// All clones refer to the same underlying sink, meaning that, e.g.,
+/// closing one closes all of them.
+#[derive(Debug)]
+pub(crate) struct Shared<S> {
+  /// The wrapped sink.
+  inner: Arc<Mutex<S>>,
+}
+
+impl<S> Shared<S> {
+  /// Create a new `Shared` object wrapping the provided sink.
+  pub(crate) fn new(inner: S) -> Self {
+    Self {
+      inner: Arc::new(Mutex::new(inner)),
+    }
+  }
+}
+
+impl<S> Clone for Shared<S> {
+  fn clone(&self) -> Self {
+    Self {
+      inner: Arc::clone(&self.inner),
+    }
+  }
+}
+
+impl<S, M> Sink<M> for Shared<S>
+where
+  S: Sink<M> + Unpin,
+{
+  type Error = S::Error;
+
+  fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.lock().unwrap().poll_ready_unpin(ctx)
+  }
+
+  fn start_send(self: Pin<&mut Self>, message: M) -> Result<(), Self::Error> {
+    self.inner.lock().unwrap().start_send_unpin(message)
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.lock().unwrap().poll_flush_unpin(ctx)
+  }
+
+  fn poll_close(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.lock().unwrap().poll_close_unpin(ctx)
+  }
+}
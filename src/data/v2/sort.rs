@@ -0,0 +1,28 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::Serialize;
+
+
+/// The order in which historical data is reported.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum Sort {
+  /// Report data in ascending order, i.e., from older data points to
+  /// more recent ones.
+  #[default]
+  #[serde(rename = "asc")]
+  Ascending,
+  /// Report data in descending order, i.e., from more recent data
+  /// points to older ones.
+  #[serde(rename = "desc")]
+  Descending,
+}
+
+impl Sort {
+  /// Check whether this object is of the `Ascending` variant.
+  #[inline]
+  pub fn is_ascending(&self) -> bool {
+    matches!(self, Self::Ascending)
+  }
+}
@@ -0,0 +1,81 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use chrono::DateTime;
+use chrono::TimeZone as _;
+use chrono::Utc;
+
+
+/// Statistics about a real time market data stream, as retrieved via
+/// [`Subscription::stats`][crate::data::v2::stream::Subscription::stats].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StreamStats {
+  /// The number of WebSocket messages received from the server.
+  ///
+  /// Note that a single WebSocket message may contain multiple data
+  /// items, such as bars or quotes.
+  pub messages_received: u64,
+  /// The total size of all received WebSocket messages, in bytes.
+  pub bytes_received: u64,
+  /// The time at which the most recent WebSocket message was received,
+  /// if any.
+  pub last_message_at: Option<DateTime<Utc>>,
+  /// The number of times the connection was re-established.
+  ///
+  /// Connections are currently not re-established automatically and so
+  /// this count is always zero.
+  pub reconnects: u64,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+/// Counters backing [`StreamStats`], updated as messages arrive.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+  /// The number of messages received.
+  messages: AtomicU64,
+  /// The number of bytes received.
+  bytes: AtomicU64,
+  /// The time the last message was received at, in microseconds since
+  /// the Unix epoch, or zero if none was received yet.
+  last_message: AtomicI64,
+  /// The number of reconnects.
+  reconnects: AtomicU64,
+}
+
+impl Counters {
+  /// Account for an inbound message of `len` bytes.
+  pub(crate) fn record(&self, len: usize) {
+    let _count = self.messages.fetch_add(1, Ordering::Relaxed);
+    let _count = self.bytes.fetch_add(len as u64, Ordering::Relaxed);
+    let now = Utc::now();
+    let micros = now.timestamp() * 1_000_000 + i64::from(now.timestamp_subsec_micros());
+    let () = self.last_message.store(micros, Ordering::Relaxed);
+  }
+
+  /// Retrieve a snapshot of the current counter values.
+  pub(crate) fn snapshot(&self) -> StreamStats {
+    let last_message = self.last_message.load(Ordering::Relaxed);
+    let last_message_at = if last_message == 0 {
+      None
+    } else {
+      let secs = last_message.div_euclid(1_000_000);
+      let nanos = last_message.rem_euclid(1_000_000) as u32 * 1000;
+      Utc.timestamp_opt(secs, nanos).single()
+    };
+
+    StreamStats {
+      messages_received: self.messages.load(Ordering::Relaxed),
+      bytes_received: self.bytes.load(Ordering::Relaxed),
+      last_message_at,
+      reconnects: self.reconnects.load(Ordering::Relaxed),
+      _non_exhaustive: (),
+    }
+  }
+}
@@ -5,14 +5,19 @@ use std::borrow::Borrow as _;
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt::Debug;
+use std::io::BufRead;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
 use chrono::DateTime;
 use chrono::Utc;
 
+use futures::future::ready;
+use futures::stream::unfold;
 use futures::stream::Fuse;
 use futures::stream::FusedStream;
 use futures::stream::Map;
@@ -21,6 +26,7 @@ use futures::stream::SplitStream;
 use futures::Future;
 use futures::FutureExt as _;
 use futures::Sink;
+use futures::SinkExt as _;
 use futures::StreamExt as _;
 
 use num_decimal::Num;
@@ -39,6 +45,7 @@ use serde_json::Error as JsonError;
 use thiserror::Error as ThisError;
 
 use tokio::net::TcpStream;
+use tokio::time::sleep;
 
 use tungstenite::MaybeTlsStream;
 use tungstenite::WebSocketStream;
@@ -51,15 +58,29 @@ use websocket_util::tungstenite::Error as WebSocketError;
 use websocket_util::wrap;
 use websocket_util::wrap::Wrapper;
 
+use super::keepalive::Watchdog;
+use super::observe::Observed;
+use super::observe::Record;
+use super::shared::Shared;
+use super::stats::Counters;
 use super::unfold::Unfold;
 
 use crate::subscribable::Subscribable;
-use crate::websocket::connect;
+use crate::websocket::connect_with_ping_interval;
 use crate::websocket::MessageResult;
 use crate::ApiInfo;
+use crate::Client;
 use crate::Error;
 use crate::Str;
 
+pub use super::keepalive::Keepalive;
+pub use super::observe::Direction;
+pub use super::observe::Frame;
+pub use super::observe::Observer;
+pub use super::observe::Recorder;
+pub use super::observe::TracingObserver;
+pub use super::stats::StreamStats;
+
 
 type UserMessage<B, Q, T> = <ParsedMessage<B, Q, T> as subscribe::Message>::UserMessage;
 
@@ -91,6 +112,9 @@ pub enum SourceVariant {
   /// The source provided is a path component to be appended to an
   /// already present base URL.
   PathComponent(&'static str),
+  /// The source provided is the complete path to use with an already
+  /// present base URL.
+  Path(&'static str),
   /// The source provided is a complete URL.
   Url(String),
 }
@@ -194,6 +218,66 @@ where
 impl<URL> private::Sealed for CustomUrl<URL> {}
 
 
+/// Use Alpaca's crypto currency exchange as the data source.
+///
+/// Data from this source is best consumed via [`CryptoData`], which
+/// accounts for the crypto specific message schema.
+#[derive(Clone, Copy, Debug)]
+pub enum Crypto {}
+
+impl Source for Crypto {
+  #[inline]
+  fn source() -> SourceVariant {
+    SourceVariant::Path("v1beta3/crypto/us")
+  }
+}
+
+impl private::Sealed for Crypto {}
+
+
+/// A marker trait for sources providing options data, for use with
+/// [`OptionsData`].
+pub trait OptionsSource: Source {}
+
+
+/// Use the Options Price Reporting Authority (OPRA) as the data source
+/// for options.
+///
+/// This source is only usable with the unlimited options data plan.
+#[derive(Clone, Copy, Debug)]
+pub enum OPRA {}
+
+impl Source for OPRA {
+  #[inline]
+  fn source() -> SourceVariant {
+    SourceVariant::Path("v1beta1/opra")
+  }
+}
+
+impl OptionsSource for OPRA {}
+
+impl private::Sealed for OPRA {}
+
+
+/// Use Alpaca's indicative feed, a delayed derivative of OPRA data, as
+/// the data source for options.
+///
+/// This source is available unconditionally.
+#[derive(Clone, Copy, Debug)]
+pub enum Indicative {}
+
+impl Source for Indicative {
+  #[inline]
+  fn source() -> SourceVariant {
+    SourceVariant::Path("v1beta1/indicative")
+  }
+}
+
+impl OptionsSource for Indicative {}
+
+impl private::Sealed for Indicative {}
+
+
 /// A symbol.
 pub type Symbol = Str;
 
@@ -321,6 +405,108 @@ pub struct Trade {
 }
 
 
+/// The side of the taker of a crypto currency trade.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum TakerSide {
+  /// The taker bought.
+  #[serde(rename = "B")]
+  Buy,
+  /// The taker sold.
+  #[serde(rename = "S")]
+  Sell,
+  /// Any other side that we have not accounted for.
+  #[doc(hidden)]
+  #[serde(other, rename(serialize = "unknown"))]
+  Unknown,
+}
+
+
+/// A trade for a crypto currency.
+///
+/// Crypto currency quotes and bars share the schema of their equity
+/// counterparts and are represented by [`Quote`] and [`Bar`] objects,
+/// respectively.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CryptoTrade {
+  /// The trade's symbol, e.g., "BTC/USD".
+  #[serde(rename = "S")]
+  pub symbol: String,
+  /// The trade's ID.
+  #[serde(rename = "i")]
+  pub trade_id: u64,
+  /// The trade's price.
+  #[serde(rename = "p")]
+  pub trade_price: Num,
+  /// The trade's size, which may be fractional.
+  #[serde(rename = "s")]
+  pub trade_size: Num,
+  /// The side of the taker of the trade.
+  #[serde(rename = "tks")]
+  pub taker_side: TakerSide,
+  /// The trade's time stamp.
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+}
+
+
+/// A quote for an option contract.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OptionQuote {
+  /// The contract's symbol, e.g., "AAPL240419P00140000".
+  #[serde(rename = "S")]
+  pub symbol: String,
+  /// The bid's exchange code.
+  #[serde(rename = "bx")]
+  pub bid_exchange: String,
+  /// The bid's price.
+  #[serde(rename = "bp")]
+  pub bid_price: Num,
+  /// The bid's size.
+  #[serde(rename = "bs")]
+  pub bid_size: Num,
+  /// The ask's exchange code.
+  #[serde(rename = "ax")]
+  pub ask_exchange: String,
+  /// The ask's price.
+  #[serde(rename = "ap")]
+  pub ask_price: Num,
+  /// The ask's size.
+  #[serde(rename = "as")]
+  pub ask_size: Num,
+  /// The quote's condition.
+  #[serde(rename = "c")]
+  pub condition: String,
+  /// The quote's time stamp.
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+}
+
+
+/// A trade for an option contract.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OptionTrade {
+  /// The contract's symbol, e.g., "AAPL240419P00140000".
+  #[serde(rename = "S")]
+  pub symbol: String,
+  /// The trade's exchange code.
+  #[serde(rename = "x")]
+  pub exchange: String,
+  /// The trade's price.
+  #[serde(rename = "p")]
+  pub trade_price: Num,
+  /// The trade's size.
+  #[serde(rename = "s")]
+  pub trade_size: Num,
+  /// The trade's condition.
+  #[serde(rename = "c")]
+  pub condition: String,
+  /// The trade's time stamp.
+  #[serde(rename = "t")]
+  pub timestamp: DateTime<Utc>,
+}
+
+
 /// An error as reported by the Alpaca Stream API.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, ThisError)]
 #[error("{message} ({code})")]
@@ -333,6 +519,82 @@ pub struct StreamApiError {
   pub message: String,
 }
 
+impl StreamApiError {
+  /// Check whether the error is fatal, i.e., whether the server will
+  /// not serve any more data over the connection.
+  ///
+  /// Fatal are, for example, errors pertaining authentication or a
+  /// client being too slow to consume data. Non-fatal errors, such as
+  /// an invalid subscription request or an exceeded symbol limit,
+  /// leave the connection usable.
+  pub fn is_fatal(&self) -> bool {
+    matches!(
+      self.kind(),
+      StreamErrorKind::NotAuthenticated
+        | StreamErrorKind::AuthFailed
+        | StreamErrorKind::AuthTimeout
+        | StreamErrorKind::ConnectionLimitExceeded
+        | StreamErrorKind::SlowClient
+        | StreamErrorKind::NotEnabled
+    )
+  }
+
+  /// Classify the error based on its code.
+  pub fn kind(&self) -> StreamErrorKind {
+    match self.code {
+      400 => StreamErrorKind::InvalidSyntax,
+      401 => StreamErrorKind::NotAuthenticated,
+      402 => StreamErrorKind::AuthFailed,
+      403 => StreamErrorKind::AlreadyAuthenticated,
+      404 => StreamErrorKind::AuthTimeout,
+      405 => StreamErrorKind::SymbolLimitExceeded,
+      406 => StreamErrorKind::ConnectionLimitExceeded,
+      407 => StreamErrorKind::SlowClient,
+      408 => StreamErrorKind::NotEnabled,
+      409 => StreamErrorKind::InsufficientSubscription,
+      410 => StreamErrorKind::InvalidSubscription,
+      500 => StreamErrorKind::Internal,
+      _ => StreamErrorKind::Unknown,
+    }
+  }
+}
+
+
+/// The kind of an error reported by the Alpaca Stream API, as
+/// determined by [`StreamApiError::kind`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum StreamErrorKind {
+  /// The message sent was syntactically invalid (400).
+  InvalidSyntax,
+  /// An action was attempted before authenticating (401).
+  NotAuthenticated,
+  /// The provided credentials were rejected (402).
+  AuthFailed,
+  /// The connection was authenticated already (403).
+  AlreadyAuthenticated,
+  /// Authentication did not happen in time after connecting (404).
+  AuthTimeout,
+  /// The maximum number of subscribed symbols was exceeded (405).
+  SymbolLimitExceeded,
+  /// The maximum number of connections was exceeded (406).
+  ConnectionLimitExceeded,
+  /// The client did not consume data quickly enough (407).
+  SlowClient,
+  /// The account is not enabled for the stream (408).
+  NotEnabled,
+  /// The subscription does not permit access to the requested data
+  /// (409).
+  InsufficientSubscription,
+  /// The subscribe action is not valid for the feed (410).
+  InvalidSubscription,
+  /// An internal error occurred on the server (500).
+  Internal,
+  /// Any other error that we have not accounted for.
+  #[doc(hidden)]
+  Unknown,
+}
+
 
 /// An enum representing the different messages we may receive over our
 /// websocket channel.
@@ -360,6 +622,20 @@ pub enum DataMessage<B = Bar, Q = Quote, T = Trade> {
   /// An error reported by the Alpaca Stream API.
   #[serde(rename = "error")]
   Error(StreamApiError),
+  /// An error reported by the Alpaca Stream API that is to be surfaced
+  /// to the user through the stream.
+  ///
+  /// This variant is never received over the wire. It is
+  /// synthesized from [`DataMessage::Error`].
+  #[serde(skip)]
+  ErrorNotification(StreamApiError),
+  /// A confirmation of the current list of subscriptions that is to be
+  /// surfaced to the user through the stream.
+  ///
+  /// This variant is never received over the wire. It is
+  /// synthesized from [`DataMessage::Subscription`].
+  #[serde(skip)]
+  SubscriptionNotification(MarketData),
 }
 
 
@@ -373,6 +649,22 @@ pub enum Data<B = Bar, Q = Quote, T = Trade> {
   Quote(Q),
   /// A variant representing trade data for a given symbol.
   Trade(T),
+  /// An error reported by the Alpaca Stream API.
+  ///
+  /// Errors reported in response to a control operation (such as
+  /// [`Subscription::subscribe`]) are reported through said operation
+  /// as well. Check [`StreamApiError::is_fatal`] to decide whether the
+  /// connection remains usable.
+  Error(StreamApiError),
+  /// A confirmation of a change to the market data subscriptions, as
+  /// caused by a [`Subscription::subscribe`] or
+  /// [`Subscription::unsubscribe`] operation, listing all currently
+  /// active subscriptions.
+  ///
+  /// Note that this confirmation is only visible when the stream is
+  /// polled concurrently with the operation; [`drive`] does not
+  /// surface any data it encounters while driving the operation.
+  Subscription(MarketData),
 }
 
 impl<B, Q, T> Data<B, Q, T> {
@@ -393,19 +685,43 @@ impl<B, Q, T> Data<B, Q, T> {
   pub fn is_trade(&self) -> bool {
     matches!(self, Self::Trade(..))
   }
+
+  /// Check whether this object is of the `Error` variant.
+  #[inline]
+  pub fn is_error(&self) -> bool {
+    matches!(self, Self::Error(..))
+  }
+
+  /// Check whether this object is of the `Subscription` variant.
+  #[inline]
+  pub fn is_subscription(&self) -> bool {
+    matches!(self, Self::Subscription(..))
+  }
 }
 
 
-/// An enumeration of the supported control messages.
-#[derive(Debug)]
-#[doc(hidden)]
+/// A control message, as received interleaved with data over our
+/// websocket channel.
+///
+/// Control messages convey the outcome of control operations, such as
+/// authenticating or changing subscriptions. Errors and subscription
+/// confirmations are additionally surfaced through the stream, as
+/// [`Data::Error`] and [`Data::Subscription`], respectively, while
+/// errors in response to a control operation are reported by it as
+/// [`Error::StreamApi`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "T")]
+#[non_exhaustive]
 pub enum ControlMessage {
   /// A control message describing the current list of subscriptions.
+  #[serde(rename = "subscription")]
   Subscription(MarketData),
   /// A control message indicating that the last operation was
   /// successful.
+  #[serde(rename = "success")]
   Success,
   /// An error reported by the Alpaca Stream API.
+  #[serde(rename = "error")]
   Error(StreamApiError),
 }
 
@@ -435,6 +751,12 @@ impl<B, Q, T> subscribe::Message for ParsedMessage<B, Q, T> {
         DataMessage::Error(error) => {
           subscribe::Classification::ControlMessage(ControlMessage::Error(error))
         },
+        DataMessage::ErrorNotification(error) => {
+          subscribe::Classification::UserMessage(Ok(Ok(Data::Error(error))))
+        },
+        DataMessage::SubscriptionNotification(data) => {
+          subscribe::Classification::UserMessage(Ok(Ok(Data::Subscription(data))))
+        },
       },
       // JSON errors are directly passed through.
       MessageResult::Ok(Err(err)) => subscribe::Classification::UserMessage(Ok(Err(err))),
@@ -448,7 +770,8 @@ impl<B, Q, T> subscribe::Message for ParsedMessage<B, Q, T> {
     // Both outer `WebSocketError` and inner `JsonError` errors
     // constitute errors in our sense. Note, however, that an API error
     // does not. It's just a regular control message from our
-    // perspective.
+    // perspective (that is also surfaced as regular data, so that
+    // users get to see it).
     user_message
       .as_ref()
       .map(|result| result.is_err())
@@ -566,6 +889,22 @@ impl Symbols {
       Self::All => false,
     }
   }
+
+  /// Retrieve the symbols contained in `self` but not in `other`.
+  fn without(&self, other: &Symbols) -> Symbols {
+    match (self, other) {
+      (_, Self::All) => Self::default(),
+      (Self::All, Self::List(..)) => Self::All,
+      (Self::List(list), Self::List(other)) => {
+        let symbols = list
+          .iter()
+          .filter(|symbol| !other.contains(symbol))
+          .cloned()
+          .collect::<Vec<_>>();
+        Self::List(SymbolList(Cow::from(symbols)))
+      },
+    }
+  }
 }
 
 impl Default for Symbols {
@@ -590,6 +929,22 @@ pub struct MarketData {
 }
 
 impl MarketData {
+  /// Retrieve the market data contained in `self` but not in `other`,
+  /// on a per-channel basis.
+  fn without(&self, other: &MarketData) -> MarketData {
+    MarketData {
+      bars: self.bars.without(&other.bars),
+      quotes: self.quotes.without(&other.quotes),
+      trades: self.trades.without(&other.trades),
+    }
+  }
+
+  /// Check whether the object represents no market data at all.
+  #[inline]
+  fn is_empty(&self) -> bool {
+    self.bars.is_empty() && self.quotes.is_empty() && self.trades.is_empty()
+  }
+
   /// A convenience function for setting the [`bars`][MarketData::bars]
   /// member.
   #[inline]
@@ -655,24 +1010,48 @@ pub enum Request<'d> {
 ///   [`unsubscribe`][Subscription::unsubscribe] operation to resolve,
 ///   the associated [`MessageStream`] stream needs to be polled;
 ///   consider using the [`drive`] function for that purpose
+/// - dropping the `Subscription` along with the associated stream
+///   tears down the connection without leaving anything running in the
+///   background; use [`close`][Subscription::close] to shut it down
+///   gracefully instead
 #[derive(Debug)]
 pub struct Subscription<S, B, Q, T> {
   /// Our internally used subscription object for sending control
   /// messages.
-  subscription: subscribe::Subscription<S, ParsedMessage<B, Q, T>, wrap::Message>,
+  subscription: subscribe::Subscription<Shared<S>, ParsedMessage<B, Q, T>, wrap::Message>,
+  /// The sink used by `subscription`, for closing the connection.
+  sink: Shared<S>,
   /// The currently active individual market data subscriptions.
   subscriptions: MarketData,
+  /// The counters tracking the messages received over the connection.
+  counters: Arc<Counters>,
 }
 
 impl<S, B, Q, T> Subscription<S, B, Q, T> {
   /// Create a `Subscription` object wrapping the `websocket_util` based one.
   #[inline]
-  fn new(subscription: subscribe::Subscription<S, ParsedMessage<B, Q, T>, wrap::Message>) -> Self {
+  fn new(
+    subscription: subscribe::Subscription<Shared<S>, ParsedMessage<B, Q, T>, wrap::Message>,
+    sink: Shared<S>,
+    counters: Arc<Counters>,
+  ) -> Self {
     Self {
       subscription,
+      sink,
       subscriptions: MarketData::default(),
+      counters,
     }
   }
+
+  /// Retrieve statistics about the messages received over the
+  /// connection so far.
+  ///
+  /// The statistics are updated as the associated stream is polled and
+  /// can be retrieved at any time.
+  #[inline]
+  pub fn stats(&self) -> StreamStats {
+    self.counters.snapshot()
+  }
 }
 
 impl<S, B, Q, T> Subscription<S, B, Q, T>
@@ -702,13 +1081,7 @@ where
         Ok(ControlMessage::Subscription(..)) => Ok(Err(Error::Str(
           "server responded with unexpected subscription message".into(),
         ))),
-        Ok(ControlMessage::Error(error)) => Ok(Err(Error::Str(
-          format!(
-            "failed to authenticate with server: {} ({})",
-            error.message, error.code
-          )
-          .into(),
-        ))),
+        Ok(ControlMessage::Error(error)) => Ok(Err(Error::StreamApi(error))),
         Err(()) => Ok(Err(Error::Str("failed to authenticate with server".into()))),
       },
       None => Ok(Err(Error::Str(
@@ -721,7 +1094,7 @@ where
   async fn subscribe_unsubscribe(
     &mut self,
     request: &Request<'_>,
-  ) -> Result<Result<(), Error>, S::Error> {
+  ) -> Result<Result<MarketData, Error>, S::Error> {
     let json = match to_json(request) {
       Ok(json) => json,
       Err(err) => return Ok(Err(Error::Json(err))),
@@ -733,11 +1106,9 @@ where
       Some(response) => match response {
         Ok(ControlMessage::Subscription(data)) => {
           self.subscriptions = data;
-          Ok(Ok(()))
+          Ok(Ok(self.subscriptions.clone()))
         },
-        Ok(ControlMessage::Error(error)) => Ok(Err(Error::Str(
-          format!("failed to subscribe: {error}").into(),
-        ))),
+        Ok(ControlMessage::Error(error)) => Ok(Err(Error::StreamApi(error))),
         Ok(_) => Ok(Err(Error::Str(
           "server responded with unexpected message".into(),
         ))),
@@ -754,21 +1125,38 @@ where
   /// Contained in `subscribe` are the *additional* symbols to subscribe
   /// to. Use the [`unsubscribe`][Self::unsubscribe] method to
   /// unsubscribe from receiving data for certain symbols.
+  ///
+  /// Only symbols not already part of the tracked subscriptions are
+  /// sent to the server. If all of them are subscribed to already, no
+  /// request is made at all.
+  /// On success, the full set of subscriptions as confirmed by the
+  /// server is returned.
   #[inline]
-  pub async fn subscribe(&mut self, subscribe: &MarketData) -> Result<Result<(), Error>, S::Error> {
-    let request = Request::Subscribe(Cow::Borrowed(subscribe));
+  pub async fn subscribe(
+    &mut self,
+    subscribe: &MarketData,
+  ) -> Result<Result<MarketData, Error>, S::Error> {
+    let additional = subscribe.without(&self.subscriptions);
+    if additional.is_empty() && !subscribe.is_empty() {
+      return Ok(Ok(self.subscriptions.clone()))
+    }
+
+    let request = Request::Subscribe(Cow::Owned(additional));
     self.subscribe_unsubscribe(&request).await
   }
 
   /// Unsubscribe from receiving market data for the provided symbols.
   ///
   /// Subscriptions of market data for symbols other than the ones
-  /// provided to this function are left untouched.
+  /// provided to this function are left untouched. On success, the
+  /// full set of remaining subscriptions as confirmed by the server is
+  /// returned. Unsubscribing from a symbol that was never subscribed
+  /// to is not an error: the server merely echoes the current set.
   #[inline]
   pub async fn unsubscribe(
     &mut self,
     unsubscribe: &MarketData,
-  ) -> Result<Result<(), Error>, S::Error> {
+  ) -> Result<Result<MarketData, Error>, S::Error> {
     let request = Request::Unsubscribe(Cow::Borrowed(unsubscribe));
     self.subscribe_unsubscribe(&request).await
   }
@@ -778,6 +1166,84 @@ where
   pub fn subscriptions(&self) -> &MarketData {
     &self.subscriptions
   }
+
+  /// Gracefully shut down the connection.
+  ///
+  /// A WebSocket close frame is sent to the server, after which the
+  /// provided stream, which is assumed to be the one associated with
+  /// this `Subscription`, is drained until the server acknowledges the
+  /// close and the stream ends. Any messages still arriving in the
+  /// meantime are discarded. If the server does not acknowledge within
+  /// `timeout`, an error is reported. Either way, the connection is
+  /// torn down once this method returns.
+  pub async fn close<St>(
+    mut self,
+    mut stream: St,
+    timeout: Duration,
+  ) -> Result<Result<(), Error>, S::Error>
+  where
+    St: FusedStream<Item = UserMessage<B, Q, T>> + Unpin,
+  {
+    let () = self.sink.close().await?;
+
+    let drain = async { while let Some(_message) = stream.next().await {} };
+
+    match tokio::time::timeout(timeout, drain).await {
+      Ok(()) => Ok(Ok(())),
+      Err(..) => Ok(Err(Error::Str(
+        "server did not acknowledge connection close in time".into(),
+      ))),
+    }
+  }
+}
+
+
+/// Duplicate every error and subscription confirmation reported by the
+/// server.
+///
+/// One copy is handled as a control message, conveying the error or
+/// confirmation to a pending control operation, if any. The other one
+/// is surfaced to the user via the message stream. Note that the
+/// control message has to come first, so that a pending operation is
+/// resolved before [`drive`] gets to see the user message.
+fn duplicate_notifications<B, Q, T>(
+  messages: Vec<DataMessage<B, Q, T>>,
+) -> Vec<DataMessage<B, Q, T>> {
+  let mut result = Vec::with_capacity(messages.len());
+  for message in messages {
+    match message {
+      DataMessage::Error(error) => {
+        result.push(DataMessage::Error(error.clone()));
+        result.push(DataMessage::ErrorNotification(error));
+      },
+      DataMessage::Subscription(data) => {
+        result.push(DataMessage::Subscription(data.clone()));
+        result.push(DataMessage::SubscriptionNotification(data));
+      },
+      message => result.push(message),
+    }
+  }
+  result
+}
+
+
+/// Parse a WebSocket message into the data messages it contains.
+#[allow(clippy::result_large_err, clippy::type_complexity)]
+fn parse<B, Q, T>(
+  result: Result<wrap::Message, WebSocketError>,
+) -> Result<Result<Vec<DataMessage<B, Q, T>>, JsonError>, WebSocketError>
+where
+  B: DeserializeOwned,
+  Q: DeserializeOwned,
+  T: DeserializeOwned,
+{
+  result.map(|message| {
+    match message {
+      wrap::Message::Text(string) => json_from_str::<Vec<DataMessage<B, Q, T>>>(&string),
+      wrap::Message::Binary(data) => json_from_slice::<Vec<DataMessage<B, Q, T>>>(&data),
+    }
+    .map(duplicate_notifications)
+  })
 }
 
 
@@ -788,12 +1254,15 @@ type MapFn<B, Q, T> =
   fn(Result<Result<DataMessage<B, Q, T>, JsonError>, WebSocketError>) -> ParsedMessage<B, Q, T>;
 type Stream<B, Q, T> = Map<
   Unfold<
-    Map<Wrapper<WebSocketStream<MaybeTlsStream<TcpStream>>>, ParseFn<B, Q, T>>,
+    Map<Watchdog<Observed<Wrapper<WebSocketStream<MaybeTlsStream<TcpStream>>>>>, ParseFn<B, Q, T>>,
     DataMessage<B, Q, T>,
     JsonError,
   >,
   MapFn<B, Q, T>,
 >;
+type DataStream<B, Q, T> =
+  Fuse<MessageStream<SplitStream<Stream<B, Q, T>>, ParsedMessage<B, Q, T>>>;
+type DataSubscription<B, Q, T> = Subscription<SplitSink<Stream<B, Q, T>, wrap::Message>, B, Q, T>;
 
 
 /// A type used for requesting a subscription to real time market
@@ -818,28 +1287,80 @@ where
   T: Send + Unpin + Debug + DeserializeOwned,
 {
   type Input = ApiInfo;
-  type Subscription = Subscription<SplitSink<Stream<B, Q, T>, wrap::Message>, B, Q, T>;
-  type Stream = Fuse<MessageStream<SplitStream<Stream<B, Q, T>>, ParsedMessage<B, Q, T>>>;
+  type Subscription = DataSubscription<B, Q, T>;
+  type Stream = DataStream<B, Q, T>;
 
+  #[inline]
   async fn connect(api_info: &Self::Input) -> Result<(Self::Stream, Self::Subscription), Error> {
-    fn parse<B, Q, T>(
-      result: Result<wrap::Message, WebSocketError>,
-    ) -> Result<Result<Vec<DataMessage<B, Q, T>>, JsonError>, WebSocketError>
-    where
-      B: DeserializeOwned,
-      Q: DeserializeOwned,
-      T: DeserializeOwned,
-    {
-      result.map(|message| match message {
-        wrap::Message::Text(string) => json_from_str::<Vec<DataMessage<B, Q, T>>>(&string),
-        wrap::Message::Binary(data) => json_from_slice::<Vec<DataMessage<B, Q, T>>>(&data),
-      })
-    }
+    Self::connect_impl(api_info, None, Keepalive::default()).await
+  }
+}
+
+impl<S, B, Q, T> RealtimeData<S, B, Q, T>
+where
+  S: Source,
+  B: Send + Unpin + Debug + DeserializeOwned,
+  Q: Send + Unpin + Debug + DeserializeOwned,
+  T: Send + Unpin + Debug + DeserializeOwned,
+{
+  /// Establish a connection to receive real time market data, just
+  /// like [`Subscribable::connect`], but report every raw frame
+  /// exchanged over the connection to the provided [`Observer`].
+  ///
+  /// The observer sees inbound frames before they are parsed as well
+  /// as all outbound control messages, including the initial
+  /// authentication request. It does not otherwise affect the data
+  /// delivered by the stream.
+  #[inline]
+  pub async fn connect_with_observer(
+    api_info: &ApiInfo,
+    observer: Arc<dyn Observer>,
+  ) -> Result<
+    (
+      <Self as Subscribable>::Stream,
+      <Self as Subscribable>::Subscription,
+    ),
+    Error,
+  > {
+    Self::connect_impl(api_info, Some(observer), Keepalive::default()).await
+  }
+
+  /// Establish a connection to receive real time market data, just
+  /// like [`Subscribable::connect`], but check the health of the
+  /// connection as per the provided [`Keepalive`] configuration.
+  ///
+  /// Once the connection is detected as broken, the stream reports an
+  /// error of kind [`std::io::ErrorKind::TimedOut`] and then ends.
+  #[inline]
+  pub async fn connect_with_keepalive(
+    api_info: &ApiInfo,
+    keepalive: Keepalive,
+  ) -> Result<
+    (
+      <Self as Subscribable>::Stream,
+      <Self as Subscribable>::Subscription,
+    ),
+    Error,
+  > {
+    Self::connect_impl(api_info, None, keepalive).await
+  }
 
+  async fn connect_impl(
+    api_info: &ApiInfo,
+    observer: Option<Arc<dyn Observer>>,
+    keepalive: Keepalive,
+  ) -> Result<
+    (
+      <Self as Subscribable>::Stream,
+      <Self as Subscribable>::Subscription,
+    ),
+    Error,
+  > {
     let ApiInfo {
       data_stream_base_url: url,
       key_id,
       secret,
+      user_agent,
       ..
     } = api_info;
 
@@ -849,19 +1370,35 @@ where
         url.set_path(&format!("v2/{}", component));
         url
       },
+      SourceVariant::Path(path) => {
+        let mut url = url.clone();
+        url.set_path(path);
+        url
+      },
       SourceVariant::Url(url) => Url::parse(&url)?,
     };
 
+    let Keepalive {
+      ping_interval,
+      idle_timeout,
+      ..
+    } = keepalive;
+
+    let wrapper = connect_with_ping_interval(&url, user_agent, ping_interval).await?;
+    let counters = Arc::new(Counters::default());
     let stream = Unfold::new(
-      connect(&url)
-        .await?
-        .map(parse::<B, Q, T> as ParseFn<_, _, _>),
+      Watchdog::new(
+        Observed::new(wrapper, observer, Arc::clone(&counters)),
+        idle_timeout,
+      )
+      .map(parse::<B, Q, T> as ParseFn<_, _, _>),
     )
     .map(MessageResult::from as MapFn<B, Q, T>);
     let (send, recv) = stream.split();
-    let (stream, subscription) = subscribe::subscribe(recv, send);
+    let send = Shared::new(send);
+    let (stream, subscription) = subscribe::subscribe(recv, send.clone());
     let mut stream = stream.fuse();
-    let mut subscription = Subscription::new(subscription);
+    let mut subscription = Subscription::new(subscription, send, counters);
 
     let connect = subscription.subscription.read().boxed();
     let message = drive(connect, &mut stream).await.map_err(|result| {
@@ -873,6 +1410,7 @@ where
 
     match message {
       Some(Ok(ControlMessage::Success)) => (),
+      Some(Ok(ControlMessage::Error(error))) => return Err(Error::StreamApi(error)),
       Some(Ok(_)) => {
         return Err(Error::Str(
           "server responded with unexpected initial message".into(),
@@ -899,17 +1437,144 @@ where
 }
 
 
+/// A type used for requesting a subscription to real time crypto
+/// currency market data.
+pub type CryptoData = RealtimeData<Crypto, Bar, Quote, CryptoTrade>;
+
+/// A type used for requesting a subscription to real time options
+/// market data from the source `S`.
+///
+/// Note that Alpaca only streams quotes and trades for options; the
+/// bar type is merely present for uniformity. Also note that messages
+/// are decoded as JSON, while Alpaca may insist on the MessagePack
+/// encoding for options data, which is not supported currently.
+pub type OptionsData<S> = RealtimeData<S, Bar, OptionQuote, OptionTrade>;
+
+
+impl Client {
+  /// Subscribe to the real time crypto currency market data stream.
+  ///
+  /// This is a shorthand for subscribing to [`CryptoData`].
+  #[inline]
+  pub async fn subscribe_crypto(
+    &self,
+  ) -> Result<
+    (
+      <CryptoData as Subscribable>::Stream,
+      <CryptoData as Subscribable>::Subscription,
+    ),
+    Error,
+  > {
+    self.subscribe::<CryptoData>().await
+  }
+
+  /// Subscribe to the real time options market data stream of source
+  /// `S`.
+  ///
+  /// This is a shorthand for subscribing to [`OptionsData`].
+  #[inline]
+  pub async fn subscribe_options<S>(
+    &self,
+  ) -> Result<
+    (
+      <OptionsData<S> as Subscribable>::Stream,
+      <OptionsData<S> as Subscribable>::Subscription,
+    ),
+    Error,
+  >
+  where
+    S: OptionsSource,
+  {
+    self.subscribe::<OptionsData<S>>().await
+  }
+}
+
+
+/// Replay market data previously recorded by a [`Recorder`].
+///
+/// Recorded frames are passed through the same parsing logic as frames
+/// received over a live connection and the resulting stream yields the
+/// same items. Control messages, such as the confirmation of a
+/// successful authentication, are filtered out. If `pace` is `true`,
+/// each frame is delayed by the time that passed between it and its
+/// predecessor being received originally; otherwise frames are emitted
+/// as quickly as possible.
+///
+/// Note that the provided reader is read from synchronously.
+#[allow(clippy::result_large_err, clippy::type_complexity)]
+pub fn replay<R, B, Q, T>(
+  reader: R,
+  pace: bool,
+) -> impl futures::Stream<Item = Result<Result<Data<B, Q, T>, JsonError>, WebSocketError>> + Unpin
+where
+  R: BufRead,
+  B: Unpin + DeserializeOwned,
+  Q: Unpin + DeserializeOwned,
+  T: Unpin + DeserializeOwned,
+{
+  let frames = unfold(
+    (reader.lines(), None::<DateTime<Utc>>),
+    move |(mut lines, last)| async move {
+      let record = loop {
+        let line = match lines.next()? {
+          Ok(line) => line,
+          Err(err) => return Some((Err(WebSocketError::Io(err)), (lines, last))),
+        };
+
+        if line.trim().is_empty() {
+          continue
+        }
+
+        match json_from_str::<Record>(&line) {
+          Ok(record) => break record,
+          Err(err) => return Some((Ok(Err(err)), (lines, last))),
+        }
+      };
+
+      if let (true, Some(last)) = (pace, last) {
+        if let Ok(delay) = (record.time - last).to_std() {
+          let () = sleep(delay).await;
+        }
+      }
+
+      let message = wrap::Message::from(record.frame);
+      Some((Ok(Ok(message)), (lines, Some(record.time))))
+    },
+  );
+
+  let messages = frames.map(|result| match result {
+    Ok(Ok(message)) => parse::<B, Q, T>(Ok(message)),
+    Ok(Err(err)) => Ok(Err(err)),
+    Err(err) => Err(err),
+  });
+
+  Unfold::new(Box::pin(messages))
+    .map(MessageResult::from)
+    .filter_map(|message: ParsedMessage<B, Q, T>| {
+      let message = match subscribe::Message::classify(message) {
+        subscribe::Classification::UserMessage(message) => Some(message),
+        subscribe::Classification::ControlMessage(..) => None,
+      };
+      ready(message)
+    })
+}
+
+
 #[allow(clippy::to_string_trait_impl)]
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  use std::io;
   use std::str::FromStr;
+  use std::sync::Mutex;
   use std::time::Duration;
 
   use chrono::DateTime;
 
-  use futures::SinkExt as _;
+  use futures::channel::oneshot;
+  use futures::future::join;
+  use futures::future::pending;
   use futures::TryStreamExt as _;
 
   use serial_test::serial;
@@ -926,6 +1591,7 @@ mod tests {
   use websocket_util::tungstenite::Message;
 
   use crate::api::API_BASE_URL;
+  use crate::websocket::test::mock_api_info;
   use crate::websocket::test::mock_stream;
   use crate::Client;
 
@@ -955,6 +1621,45 @@ mod tests {
     assert!(Data::<(), Quote, Trade>::Bar(()).is_bar());
     assert!(Data::<Bar, (), Trade>::Quote(()).is_quote());
     assert!(Data::<Bar, Quote, ()>::Trade(()).is_trade());
+
+    let error = StreamApiError {
+      code: 400,
+      message: "invalid syntax".into(),
+    };
+    assert!(Data::<Bar, Quote, Trade>::Error(error).is_error());
+  }
+
+  /// Check that we classify stream API errors as fatal or non-fatal
+  /// correctly.
+  #[test]
+  fn stream_api_error_fatality() {
+    let error = |code| StreamApiError {
+      code,
+      message: String::new(),
+    };
+
+    assert!(!error(400).is_fatal());
+    assert!(error(401).is_fatal());
+    assert!(error(402).is_fatal());
+    assert!(!error(405).is_fatal());
+    assert!(error(407).is_fatal());
+    assert!(!error(409).is_fatal());
+    assert!(!error(500).is_fatal());
+  }
+
+  /// Check that we classify stream API errors by their code.
+  #[test]
+  fn stream_api_error_kind() {
+    let error = |code| StreamApiError {
+      code,
+      message: String::new(),
+    };
+
+    assert_eq!(error(400).kind(), StreamErrorKind::InvalidSyntax);
+    assert_eq!(error(402).kind(), StreamErrorKind::AuthFailed);
+    assert_eq!(error(405).kind(), StreamErrorKind::SymbolLimitExceeded);
+    assert_eq!(error(410).kind(), StreamErrorKind::InvalidSubscription);
+    assert_eq!(error(499).kind(), StreamErrorKind::Unknown);
   }
 
   /// Test that the [`Symbols::is_empty`] method works as expected.
@@ -1242,20 +1947,84 @@ mod tests {
     );
   }
 
-  /// Check that we can serialize and deserialize the
-  /// [`DataMessage::Success`] variant.
+  /// Check that we can deserialize a crypto currency trade frame.
   #[test]
-  fn serialize_deserialize_success() {
-    let json = r#"{"T":"success","msg":"authenticated"}"#;
-    let message = json_from_str::<DataMessage>(json).unwrap();
-    let () = match message {
-      DataMessage::Success => (),
-      _ => panic!("Decoded unexpected message variant: {message:?}"),
+  fn deserialize_crypto_trade() {
+    let json = r#"[{
+  "T": "t",
+  "S": "BTC/USD",
+  "p": 61734.5,
+  "s": 0.0015,
+  "t": "2024-03-12T10:27:48.858228144Z",
+  "i": 3447222699101865076,
+  "tks": "S"
+}]"#;
+
+    let messages = json_from_str::<Vec<DataMessage<Bar, Quote, CryptoTrade>>>(json).unwrap();
+    let trade = match messages.as_slice() {
+      [DataMessage::Trade(trade)] => trade,
+      _ => panic!("Decoded unexpected messages: {messages:?}"),
     };
-
+    assert_eq!(trade.symbol, "BTC/USD");
+    assert_eq!(trade.trade_id, 3447222699101865076);
+    assert_eq!(trade.trade_price, Num::new(617345, 10));
+    assert_eq!(trade.trade_size, Num::new(15, 10000));
+    assert_eq!(trade.taker_side, TakerSide::Sell);
     assert_eq!(
-      json_from_str::<DataMessage>(&to_json(&message).unwrap()).unwrap(),
-      message
+      trade.timestamp,
+      DateTime::<Utc>::from_str("2024-03-12T10:27:48.858228144Z").unwrap()
+    );
+  }
+
+  /// Check that we can deserialize an options quote frame.
+  #[test]
+  fn deserialize_option_quote() {
+    let json = r#"[{
+  "T": "q",
+  "S": "AAPL240419P00140000",
+  "t": "2024-04-09T15:46:52.280160732Z",
+  "bx": "W",
+  "bp": 0.01,
+  "bs": 30,
+  "ax": "C",
+  "ap": 0.02,
+  "as": 1,
+  "c": "A"
+}]"#;
+
+    let messages = json_from_str::<Vec<DataMessage<Bar, OptionQuote, OptionTrade>>>(json).unwrap();
+    let quote = match messages.as_slice() {
+      [DataMessage::Quote(quote)] => quote,
+      _ => panic!("Decoded unexpected messages: {messages:?}"),
+    };
+    assert_eq!(quote.symbol, "AAPL240419P00140000");
+    assert_eq!(quote.bid_exchange, "W");
+    assert_eq!(quote.bid_price, Num::new(1, 100));
+    assert_eq!(quote.bid_size, Num::from(30));
+    assert_eq!(quote.ask_exchange, "C");
+    assert_eq!(quote.ask_price, Num::new(2, 100));
+    assert_eq!(quote.ask_size, Num::from(1));
+    assert_eq!(quote.condition, "A");
+    assert_eq!(
+      quote.timestamp,
+      DateTime::<Utc>::from_str("2024-04-09T15:46:52.280160732Z").unwrap()
+    );
+  }
+
+  /// Check that we can serialize and deserialize the
+  /// [`DataMessage::Success`] variant.
+  #[test]
+  fn serialize_deserialize_success() {
+    let json = r#"{"T":"success","msg":"authenticated"}"#;
+    let message = json_from_str::<DataMessage>(json).unwrap();
+    let () = match message {
+      DataMessage::Success => (),
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    };
+
+    assert_eq!(
+      json_from_str::<DataMessage>(&to_json(&message).unwrap()).unwrap(),
+      message
     );
   }
 
@@ -1294,6 +2063,50 @@ mod tests {
     );
   }
 
+  /// Check that we can deserialize the various control frames into
+  /// [`ControlMessage`] objects.
+  #[test]
+  fn deserialize_control_messages() {
+    let json = r#"{"T":"success","msg":"authenticated"}"#;
+    let message = json_from_str::<ControlMessage>(json).unwrap();
+    assert_eq!(message, ControlMessage::Success);
+
+    let json = r#"{"T":"error","code":402,"msg":"auth failed"}"#;
+    let message = json_from_str::<ControlMessage>(json).unwrap();
+    match message {
+      ControlMessage::Error(error) => {
+        assert_eq!(error.kind(), StreamErrorKind::AuthFailed);
+        assert_eq!(error.message, "auth failed");
+        assert!(error.is_fatal());
+      },
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    }
+
+    let json = r#"{"T":"error","code":410,"msg":"invalid subscribe action for this feed"}"#;
+    let message = json_from_str::<ControlMessage>(json).unwrap();
+    match message {
+      ControlMessage::Error(error) => {
+        assert_eq!(error.kind(), StreamErrorKind::InvalidSubscription);
+        assert!(!error.is_fatal());
+      },
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    }
+
+    let json = r#"{"T":"subscription","trades":["AAPL"],"quotes":["AMD","CLDR"],"bars":["*"]}"#;
+    let message = json_from_str::<ControlMessage>(json).unwrap();
+    match message {
+      ControlMessage::Subscription(data) => {
+        assert_eq!(data.bars, Symbols::All);
+        assert_eq!(data.trades, Symbols::List(SymbolList::from(["AAPL"])));
+        assert_eq!(
+          data.quotes,
+          Symbols::List(SymbolList::from(["AMD", "CLDR"]))
+        );
+      },
+      _ => panic!("Decoded unexpected message variant: {message:?}"),
+    }
+  }
+
   /// Check that we can serialize and deserialize the
   /// [`Request::Authenticate`] variant properly.
   #[test]
@@ -1406,7 +2219,7 @@ mod tests {
     data.set_bars(["AAPL", "VOO"]);
 
     let subscribe = subscription.subscribe(&data).boxed_local();
-    let () = drive(subscribe, &mut stream)
+    let _ = drive(subscribe, &mut stream)
       .await
       .unwrap()
       .unwrap()
@@ -1419,6 +2232,529 @@ mod tests {
       .unwrap();
   }
 
+  /// Check that the stream statistics account for all received
+  /// messages.
+  #[test(tokio::test)]
+  async fn track_stream_stats() {
+    const BAR: &str = r#"[{"T":"b","S":"SPY","o":388.985,"h":389.13,"l":388.975,"c":389.12,"v":49378,"t":"2021-02-22T19:15:00Z"}]"#;
+
+    async fn test(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      let () = sleep(Duration::from_millis(10)).await;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(BAR)))
+        .await?;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(BAR)))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let (mut stream, subscription) = mock_stream::<RealtimeData<IEX>, _, _>(test).await.unwrap();
+
+    let stats = subscription.stats();
+    assert_eq!(stats.messages_received, 2);
+    assert_eq!(
+      stats.bytes_received,
+      (CONN_RESP.len() + AUTH_RESP.len()) as u64
+    );
+    assert_eq!(stats.reconnects, 0);
+    let connected_at = stats.last_message_at.unwrap();
+
+    for _ in 0..2 {
+      let data = stream.next().await.unwrap().unwrap().unwrap();
+      assert!(data.is_bar());
+    }
+
+    let stats = subscription.stats();
+    assert_eq!(stats.messages_received, 4);
+    assert_eq!(
+      stats.bytes_received,
+      (CONN_RESP.len() + AUTH_RESP.len() + 2 * BAR.len()) as u64
+    );
+    assert!(stats.last_message_at.unwrap() > connected_at);
+  }
+
+  /// Check that we track the set of subscriptions as acknowledged by
+  /// the server when subscribing and unsubscribing incrementally.
+  #[test(tokio::test)]
+  async fn subscribe_unsubscribe_incrementally() {
+    async fn test(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(
+          r#"{"action":"subscribe","bars":["AAPL","MSFT"],"quotes":[],"trades":[]}"#
+        )),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"subscription","bars":["AAPL","MSFT"]}]"#,
+        )))
+        .await?;
+
+      // Only the symbol not yet subscribed to should be sent.
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(
+          r#"{"action":"subscribe","bars":["SPY"],"quotes":[],"trades":[]}"#
+        )),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"subscription","bars":["AAPL","MSFT","SPY"]}]"#,
+        )))
+        .await?;
+
+      // "TSLA" was never subscribed to and the server just echoes the
+      // remaining set.
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(
+          r#"{"action":"unsubscribe","bars":["MSFT","SPY","TSLA"],"quotes":[],"trades":[]}"#
+        )),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"subscription","bars":["AAPL"]}]"#,
+        )))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let (mut stream, mut subscription) =
+      mock_stream::<RealtimeData<IEX>, _, _>(test).await.unwrap();
+
+    let mut data = MarketData::default();
+    data.set_bars(["AAPL", "MSFT"]);
+
+    let subscribe = subscription.subscribe(&data).boxed_local();
+    let confirmed = drive(subscribe, &mut stream)
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+    assert_eq!(confirmed, data);
+    assert_eq!(subscription.subscriptions(), &data);
+
+    // Subscribing to already subscribed symbols does not involve the
+    // server.
+    let confirmed = subscription.subscribe(&data).await.unwrap().unwrap();
+    assert_eq!(confirmed, data);
+
+    let mut data = MarketData::default();
+    data.set_bars(["MSFT", "SPY"]);
+    let subscribe = subscription.subscribe(&data).boxed_local();
+    let confirmed = drive(subscribe, &mut stream)
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+    let mut expected = MarketData::default();
+    expected.set_bars(["AAPL", "MSFT", "SPY"]);
+    assert_eq!(confirmed, expected);
+
+    let mut data = MarketData::default();
+    data.set_bars(["MSFT", "SPY", "TSLA"]);
+    let unsubscribe = subscription.unsubscribe(&data).boxed_local();
+    let confirmed = drive(unsubscribe, &mut stream)
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+    let mut expected = MarketData::default();
+    expected.set_bars(["AAPL"]);
+    assert_eq!(confirmed, expected);
+    assert_eq!(subscription.subscriptions(), &expected);
+
+    stream
+      .map_err(Error::WebSocket)
+      .try_for_each(|result| async { result.map(|_data| ()).map_err(Error::Json) })
+      .await
+      .unwrap();
+  }
+
+  /// Check that an observer sees the raw frames, including the
+  /// authentication and subscription requests, exchanged over the
+  /// stream.
+  #[test(tokio::test)]
+  async fn observe_frames() {
+    async fn test(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(SUB_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(SUB_RESP)))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let observer = {
+      let frames = frames.clone();
+      move |direction: Direction, frame: Frame<'_>| {
+        if let Frame::Text(text) = frame {
+          frames.lock().unwrap().push((direction, text.to_string()))
+        }
+      }
+    };
+
+    let api_info = mock_api_info(test).await;
+    let (mut stream, mut subscription) =
+      RealtimeData::<IEX>::connect_with_observer(&api_info, Arc::new(observer))
+        .await
+        .unwrap();
+
+    let mut data = MarketData::default();
+    data.set_bars(["AAPL", "VOO"]);
+
+    let subscribe = subscription.subscribe(&data).boxed_local();
+    let _ = drive(subscribe, &mut stream)
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+
+    let frames = frames.lock().unwrap().clone();
+    let expected = [
+      (Direction::Inbound, CONN_RESP),
+      (Direction::Outbound, AUTH_REQ),
+      (Direction::Inbound, AUTH_RESP),
+      (Direction::Outbound, SUB_REQ),
+      (Direction::Inbound, SUB_RESP),
+    ]
+    .map(|(direction, text)| (direction, text.to_string()));
+    assert_eq!(frames, expected);
+  }
+
+  /// Check that frames recorded by a [`Recorder`] can be replayed,
+  /// yielding the same data as the original stream.
+  #[test(tokio::test)]
+  async fn record_replay() {
+    async fn test(
+      mut stream: WebSocketStream,
+      connected: oneshot::Receiver<()>,
+    ) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      let () = connected.await.unwrap();
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"b","S":"SPY","o":388.985,"h":389.13,"l":388.975,"c":389.12,"v":49378,"t":"2021-02-22T19:15:00Z"}]"#,
+        )))
+        .await?;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"error","code":405,"msg":"symbol limit exceeded"}]"#,
+        )))
+        .await?;
+      stream
+        .send(Message::Binary(
+          br#"[{"T":"t","S":"AAPL","i":6198,"x":"V","p":123.01,"s":100,"t":"2021-02-22T19:15:01Z","z":"C"}]"#
+            .to_vec()
+            .into(),
+        ))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    let api_info = mock_api_info(|stream| test(stream, receiver)).await;
+    let recorder = Arc::new(Recorder::new(Vec::new()));
+    let (stream, subscription) =
+      RealtimeData::<IEX>::connect_with_observer(&api_info, recorder.clone())
+        .await
+        .unwrap();
+    let () = sender.send(()).unwrap();
+
+    let original = stream
+      .map(|result| format!("{:?}", result.unwrap().unwrap()))
+      .collect::<Vec<_>>()
+      .await;
+    assert_eq!(original.len(), 3);
+
+    drop(subscription);
+    let recording = Arc::try_unwrap(recorder).unwrap().into_inner();
+    // The connection and authentication confirmations are part of
+    // the recording as well.
+    assert_eq!(recording.iter().filter(|b| **b == b'\n').count(), 5);
+
+    let replayed = replay::<_, Bar, Quote, Trade>(recording.as_slice(), true)
+      .map(|result| format!("{:?}", result.unwrap().unwrap()))
+      .collect::<Vec<_>>()
+      .await;
+    assert_eq!(replayed, original);
+  }
+
+  /// Authenticate the client and then stop responding altogether,
+  /// including to pings, while keeping the connection open.
+  async fn unresponsive(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+    stream
+      .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+      .await?;
+    assert_eq!(
+      stream.next().await.unwrap()?,
+      Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+    );
+    stream
+      .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+      .await?;
+
+    let () = pending().await;
+    Ok(())
+  }
+
+  /// Check that the stream reports a disconnect when the server stops
+  /// responding to pings.
+  #[test(tokio::test)]
+  async fn keepalive_unanswered_pings() {
+    let keepalive = Keepalive {
+      ping_interval: Some(Duration::from_millis(100)),
+      ..Default::default()
+    };
+
+    let api_info = mock_api_info(unresponsive).await;
+    let (mut stream, _subscription) =
+      RealtimeData::<IEX>::connect_with_keepalive(&api_info, keepalive)
+        .await
+        .unwrap();
+
+    let result = timeout(Duration::from_secs(2), stream.next())
+      .await
+      .unwrap();
+    assert!(matches!(
+      result,
+      Some(Err(WebSocketError::Io(error))) if error.kind() == io::ErrorKind::TimedOut
+    ));
+    // The broken connection ends the stream.
+    assert!(stream.next().await.is_none());
+  }
+
+  /// Check that the stream reports a disconnect when no data is
+  /// received within the configured idle timeout.
+  #[test(tokio::test)]
+  async fn keepalive_idle_timeout() {
+    let keepalive = Keepalive {
+      ping_interval: None,
+      idle_timeout: Some(Duration::from_millis(100)),
+      ..Default::default()
+    };
+
+    let api_info = mock_api_info(unresponsive).await;
+    let (mut stream, _subscription) =
+      RealtimeData::<IEX>::connect_with_keepalive(&api_info, keepalive)
+        .await
+        .unwrap();
+
+    let result = timeout(Duration::from_secs(2), stream.next())
+      .await
+      .unwrap();
+    assert!(matches!(
+      result,
+      Some(Err(WebSocketError::Io(error))) if error.kind() == io::ErrorKind::TimedOut
+    ));
+    assert!(stream.next().await.is_none());
+  }
+
+  /// Check that closing a subscription sends a close frame and waits
+  /// for the server to acknowledge it.
+  #[test(tokio::test)]
+  async fn close_gracefully() {
+    async fn test(
+      mut stream: WebSocketStream,
+      sender: oneshot::Sender<()>,
+    ) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      assert!(matches!(stream.next().await.unwrap()?, Message::Close(..)));
+      // The close handshake is complete once the server's reply has
+      // been sent, at which point the stream ends.
+      assert!(stream.next().await.is_none());
+      let () = sender.send(()).unwrap();
+      Ok(())
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    let (stream, subscription) =
+      mock_stream::<RealtimeData<IEX>, _, _>(|stream| test(stream, sender))
+        .await
+        .unwrap();
+
+    let () = subscription
+      .close(stream, Duration::from_secs(5))
+      .await
+      .unwrap()
+      .unwrap();
+    let () = timeout(Duration::from_secs(5), receiver)
+      .await
+      .unwrap()
+      .unwrap();
+  }
+
+  /// Make sure that closing a subscription reports an error if the
+  /// server never acknowledges the close.
+  #[test(tokio::test)]
+  async fn close_unacknowledged() {
+    let api_info = mock_api_info(unresponsive).await;
+    let (stream, subscription) = RealtimeData::<IEX>::connect(&api_info).await.unwrap();
+
+    let result = subscription
+      .close(stream, Duration::from_millis(100))
+      .await
+      .unwrap();
+    assert!(matches!(result, Err(Error::Str(..))), "{result:?}");
+  }
+
+  /// Check that we can subscribe to additional symbols on a running
+  /// stream, without reconnecting.
+  #[test(tokio::test)]
+  async fn subscribe_mid_stream() {
+    async fn test(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(
+          r#"{"action":"subscribe","bars":["AAPL"],"quotes":[],"trades":[]}"#
+        )),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"subscription","bars":["AAPL"]}]"#,
+        )))
+        .await?;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"b","S":"AAPL","o":120.5,"h":121.0,"l":120.25,"c":120.75,"v":1000,"t":"2021-02-22T19:15:00Z"}]"#,
+        )))
+        .await?;
+
+      // The additional subscription arrives over the same connection.
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(
+          r#"{"action":"subscribe","bars":["MSFT"],"quotes":[],"trades":[]}"#
+        )),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"subscription","bars":["AAPL","MSFT"]}]"#,
+        )))
+        .await?;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"b","S":"MSFT","o":230.5,"h":231.0,"l":230.25,"c":230.75,"v":500,"t":"2021-02-22T19:15:00Z"}]"#,
+        )))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let (mut stream, mut subscription) =
+      mock_stream::<RealtimeData<IEX>, _, _>(test).await.unwrap();
+
+    let mut data = MarketData::default();
+    data.set_bars(["AAPL"]);
+    let subscribe = subscription.subscribe(&data).boxed_local();
+    let _ = drive(subscribe, &mut stream)
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+
+    match stream.next().await.unwrap().unwrap().unwrap() {
+      Data::Bar(bar) => assert_eq!(bar.symbol, "AAPL"),
+      data => panic!("received unexpected data: {data:?}"),
+    }
+
+    // Subscribe to more data while consuming the stream concurrently.
+    let mut data = MarketData::default();
+    data.set_bars(["MSFT"]);
+    let subscribe = subscription.subscribe(&data);
+    let consume = stream
+      .map_err(Error::WebSocket)
+      .and_then(|result| async { result.map_err(Error::Json) })
+      .try_collect::<Vec<_>>();
+    let (result, data) = join(subscribe, consume).await;
+    let _ = result.unwrap().unwrap();
+    let data = data.unwrap();
+
+    let expected = Symbols::List(["AAPL", "MSFT"].into());
+    assert_eq!(subscription.subscriptions().bars, expected);
+
+    assert_eq!(data.len(), 2);
+    match &data[0] {
+      Data::Subscription(data) => assert_eq!(data.bars, expected),
+      data => panic!("received unexpected data: {data:?}"),
+    }
+    match &data[1] {
+      Data::Bar(bar) => assert_eq!(bar.symbol, "MSFT"),
+      data => panic!("received unexpected data: {data:?}"),
+    }
+  }
+
   /// Check that we correctly handle errors reported as part of
   /// subscription.
   #[test(tokio::test)]
@@ -1461,11 +2797,127 @@ mod tests {
       .unwrap_err();
 
     match error {
-      Error::Str(ref e) if e == "failed to subscribe: invalid syntax (400)" => {},
+      Error::StreamApi(ref e) if e.kind() == StreamErrorKind::InvalidSyntax => {
+        assert_eq!(e.message, "invalid syntax")
+      },
       e => panic!("received unexpected error: {e}"),
     }
   }
 
+  /// Check that a non-fatal error reported by the server is surfaced
+  /// through the stream and that the stream continues to work
+  /// afterwards.
+  #[test(tokio::test)]
+  async fn stream_non_fatal_error() {
+    async fn test(
+      mut stream: WebSocketStream,
+      connected: oneshot::Receiver<()>,
+    ) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      // Authentication.
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      // Only report the error once the client is fully connected, as
+      // otherwise it may get swallowed during connection
+      // establishment.
+      let () = connected.await.unwrap();
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"error","code":405,"msg":"symbol limit exceeded"}]"#,
+        )))
+        .await?;
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"b","S":"SPY","o":388.985,"h":389.13,"l":388.975,"c":389.12,"v":49378,"t":"2021-02-22T19:15:00Z"}]"#,
+        )))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    let (stream, _subscription) =
+      mock_stream::<RealtimeData<IEX>, _, _>(|stream| test(stream, receiver))
+        .await
+        .unwrap();
+    let () = sender.send(()).unwrap();
+
+    let data = stream
+      .map_err(Error::WebSocket)
+      .and_then(|result| async { result.map_err(Error::Json) })
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+
+    assert_eq!(data.len(), 2);
+    match &data[0] {
+      Data::Error(error) => {
+        assert_eq!(error.code, 405);
+        assert_eq!(error.message, "symbol limit exceeded");
+        assert!(!error.is_fatal());
+      },
+      data => panic!("received unexpected data: {data:?}"),
+    }
+    assert!(data[1].is_bar());
+  }
+
+  /// Check that a fatal error reported by the server is surfaced
+  /// through the stream.
+  #[test(tokio::test)]
+  async fn stream_fatal_error() {
+    async fn test(
+      mut stream: WebSocketStream,
+      connected: oneshot::Receiver<()>,
+    ) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      // Authentication.
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(AUTH_RESP)))
+        .await?;
+
+      let () = connected.await.unwrap();
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"error","code":407,"msg":"slow client"}]"#,
+        )))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    let (mut stream, _subscription) =
+      mock_stream::<RealtimeData<IEX>, _, _>(|stream| test(stream, receiver))
+        .await
+        .unwrap();
+    let () = sender.send(()).unwrap();
+
+    let data = stream.next().await.unwrap().unwrap().unwrap();
+    match data {
+      Data::Error(error) => {
+        assert_eq!(error.code, 407);
+        assert_eq!(error.message, "slow client");
+        assert!(error.is_fatal());
+      },
+      data => panic!("received unexpected data: {data:?}"),
+    }
+    assert!(stream.next().await.is_none());
+  }
+
   /// Check that we can adjust the current market data subscription on
   /// the fly.
   #[test(tokio::test)]
@@ -1479,7 +2931,7 @@ mod tests {
     data.set_bars(["AAPL", "SPY"]);
 
     let subscribe = subscription.subscribe(&data).boxed_local();
-    let () = drive(subscribe, &mut stream)
+    let _ = drive(subscribe, &mut stream)
       .await
       .unwrap()
       .unwrap()
@@ -1490,7 +2942,7 @@ mod tests {
     let mut data = MarketData::default();
     data.set_bars(["XLK"]);
     let subscribe = subscription.subscribe(&data).boxed_local();
-    let () = drive(subscribe, &mut stream)
+    let _ = drive(subscribe, &mut stream)
       .await
       .unwrap()
       .unwrap()
@@ -1519,7 +2971,7 @@ mod tests {
     };
 
     let subscribe = subscription.subscribe(&data).boxed_local();
-    let () = drive(subscribe, &mut stream)
+    let _ = drive(subscribe, &mut stream)
       .await
       .unwrap()
       .unwrap()
@@ -1562,7 +3014,7 @@ mod tests {
       data.set_quotes(["SPY"]);
 
       let subscribe = subscription.subscribe(&data).boxed_local();
-      let () = drive(subscribe, &mut stream)
+      let _ = drive(subscribe, &mut stream)
         .await
         .unwrap()
         .unwrap()
@@ -1609,7 +3061,7 @@ mod tests {
     data.set_trades(["SPY"]);
 
     let subscribe = subscription.subscribe(&data).boxed_local();
-    let () = drive(subscribe, &mut stream)
+    let _ = drive(subscribe, &mut stream)
       .await
       .unwrap()
       .unwrap()
@@ -1643,7 +3095,7 @@ mod tests {
     data.set_bars(["AAPL"]);
 
     let unsubscribe = subscription.unsubscribe(&data).boxed_local();
-    let () = drive(unsubscribe, &mut stream)
+    let _ = drive(unsubscribe, &mut stream)
       .await
       .unwrap()
       .unwrap()
@@ -1662,7 +3114,39 @@ mod tests {
     let err = client.subscribe::<RealtimeData<IEX>>().await.unwrap_err();
 
     match err {
-      Error::Str(ref e) if e.starts_with("failed to authenticate with server") => (),
+      Error::StreamApi(ref e) if e.kind() == StreamErrorKind::AuthFailed => (),
+      e => panic!("received unexpected error: {e}"),
+    }
+  }
+
+  /// Make sure that a rejected authentication attempt is reported as a
+  /// typed error.
+  #[test(tokio::test)]
+  async fn authentication_failure() {
+    async fn test(mut stream: WebSocketStream) -> Result<(), WebSocketError> {
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(CONN_RESP)))
+        .await?;
+      assert_eq!(
+        stream.next().await.unwrap()?,
+        Message::Text(Utf8Bytes::from_static(AUTH_REQ)),
+      );
+      stream
+        .send(Message::Text(Utf8Bytes::from_static(
+          r#"[{"T":"error","code":402,"msg":"auth failed"}]"#,
+        )))
+        .await?;
+      stream.send(Message::Close(None)).await?;
+      Ok(())
+    }
+
+    let err = mock_stream::<RealtimeData<IEX>, _, _>(test)
+      .await
+      .unwrap_err();
+    match err {
+      Error::StreamApi(ref e) if e.kind() == StreamErrorKind::AuthFailed => {
+        assert_eq!(e.message, "auth failed")
+      },
       e => panic!("received unexpected error: {e}"),
     }
   }
@@ -2,17 +2,25 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use chrono::DateTime;
+use chrono::Duration;
 use chrono::Utc;
 
 use num_decimal::Num;
 
+use futures::Stream;
+
 use serde::Deserialize;
 use serde::Serialize;
 use serde_urlencoded::to_string as to_query;
 
+use crate::data::v2::range::check_time_range;
+use crate::data::v2::Condition;
 use crate::data::v2::Feed;
-use crate::data::DATA_BASE_URL;
+use crate::data::v2::Tape;
+use crate::raw::RawApi;
 use crate::util::vec_from_str;
+use crate::Client;
+use crate::RequestError;
 use crate::Str;
 
 
@@ -96,12 +104,69 @@ pub struct Trade {
   /// The size of the trade.
   #[serde(rename = "s")]
   pub size: usize,
+  /// The raw condition codes of the trade.
+  ///
+  /// Use [`Trade::decoded_conditions`] to decode them.
+  #[serde(rename = "c", default)]
+  pub conditions: Vec<String>,
+  /// The raw code of the tape the trade was reported on.
+  ///
+  /// Use [`Trade::decoded_tape`] to decode it.
+  #[serde(rename = "z", default)]
+  pub tape: Option<String>,
   /// The type is non-exhaustive and open to extension.
   #[doc(hidden)]
   #[serde(skip)]
   pub _non_exhaustive: (),
 }
 
+impl Trade {
+  /// Retrieve the time that passed between the trade and `now`.
+  ///
+  /// The age is negative if the trade is time stamped after `now`.
+  #[inline]
+  pub fn age(&self, now: DateTime<Utc>) -> Duration {
+    now - self.timestamp
+  }
+
+  /// Check whether the trade happened more than `max_age` ago.
+  ///
+  /// Outside of regular trading hours the latest trade may be hours
+  /// old, making its price a poor indication of the current value.
+  #[inline]
+  pub fn is_stale(&self, max_age: Duration) -> bool {
+    self.age(Utc::now()) > max_age
+  }
+
+  /// Decode the trade's conditions, skipping codes not known to us.
+  #[inline]
+  pub fn decoded_conditions(&self) -> impl Iterator<Item = Condition> + '_ {
+    self
+      .conditions
+      .iter()
+      .filter_map(|code| Condition::from_code(code))
+  }
+
+  /// Decode the tape the trade was reported on, if known.
+  #[inline]
+  pub fn decoded_tape(&self) -> Option<Tape> {
+    self.tape.as_deref().and_then(Tape::from_code)
+  }
+
+  /// Check whether the trade is a regular one, i.e., none of its
+  /// conditions is flagged as irregular by Alpaca.
+  ///
+  /// Irregular trades, such as odd-lot or out-of-sequence ones, are
+  /// excluded by Alpaca when aggregating bars. Condition codes not
+  /// known to us are not considered irregular.
+  #[inline]
+  pub fn is_regular(&self) -> bool {
+    self
+      .decoded_conditions()
+      .all(|condition| condition.is_regular())
+  }
+}
+
 
 /// A collection of trades as returned by the API. This is one page of trades.
 #[derive(Debug, Deserialize, Eq, PartialEq)]
@@ -135,19 +200,36 @@ Endpoint! {
   ]
 
   fn base_url() -> Option<Str> {
-    Some(DATA_BASE_URL.into())
+    RawApi::Data.base_url()
   }
 
   fn path(input: &Self::Input) -> Str {
-    format!("/v2/stocks/{}/trades", input.symbol).into()
+    path!("/v2/stocks/{}/trades", input.symbol).into()
   }
 
   fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+    let () = check_time_range(&input.start, &input.end)?;
     Ok(Some(to_query(input)?.into()))
   }
 }
 
 
+impl Client {
+  /// Create a stream lazily yielding all trades matching `request`,
+  /// transparently paging through the results.
+  ///
+  /// Please refer to [`Client::paginate`] for details on the
+  /// stream's behavior.
+  #[inline]
+  pub fn trades_stream(
+    &self,
+    request: &ListReq,
+  ) -> impl Stream<Item = Result<Trade, RequestError<ListError>>> + '_ {
+    self.paginate::<List>(request)
+  }
+}
+
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -158,13 +240,63 @@ mod tests {
 
   use serde_json::from_str as from_json;
 
+  use futures::StreamExt as _;
+  use futures::TryStreamExt as _;
+
   use test_log::test;
 
   use crate::api_info::ApiInfo;
-  use crate::Client;
-  use crate::RequestError;
 
 
+  /// Check that we compute the age of a trade correctly.
+  #[test]
+  fn trade_age() {
+    let trade = Trade {
+      timestamp: DateTime::from_str("2024-01-02T20:00:00Z").unwrap(),
+      price: Num::from(100),
+      size: 1,
+      conditions: Vec::new(),
+      tape: None,
+      _non_exhaustive: (),
+    };
+
+    let now = DateTime::from_str("2024-01-03T09:30:00Z").unwrap();
+    let age = trade.age(now);
+    assert_eq!(age, Duration::try_minutes(13 * 60 + 30).unwrap());
+    assert_eq!(trade.age(trade.timestamp), Duration::zero());
+    assert!(trade.age(trade.timestamp - Duration::try_seconds(5).unwrap()) < Duration::zero());
+
+    assert!(trade.is_stale(Duration::try_hours(1).unwrap()));
+    let trade = Trade {
+      timestamp: Utc::now(),
+      ..trade
+    };
+    assert!(!trade.is_stale(Duration::try_hours(1).unwrap()));
+  }
+
+  /// Make sure that `Trade::is_regular` filters out odd-lot trades.
+  #[test]
+  fn filter_irregular_trades() {
+    let response = r#"[
+      {"t": "2024-01-02T15:00:00Z", "p": 100, "s": 100, "c": ["@"], "z": "C"},
+      {"t": "2024-01-02T15:00:01Z", "p": 101, "s": 5, "c": ["@", "I"], "z": "C"},
+      {"t": "2024-01-02T15:00:02Z", "p": 102, "s": 200, "c": ["@", "F"], "z": "C"}
+    ]"#;
+
+    let trades = from_json::<Vec<Trade>>(response).unwrap();
+    assert_eq!(
+      trades[1].decoded_conditions().collect::<Vec<_>>(),
+      vec![Condition::Regular, Condition::OddLot]
+    );
+
+    let regular = trades
+      .iter()
+      .filter(|trade| trade.is_regular())
+      .map(|trade| trade.size)
+      .collect::<Vec<_>>();
+    assert_eq!(regular, vec![100, 200]);
+  }
+
   /// Verify that we can properly parse a reference trades response.
   #[test]
   fn parse_reference_trades() {
@@ -207,6 +339,8 @@ mod tests {
     assert!(timestamp.starts_with(expected_time), "{timestamp}");
     assert_eq!(trades[0].price, Num::new(38762, 100));
     assert_eq!(trades[0].size, 100);
+    assert_eq!(trades[0].conditions, vec![" ".to_string(), "T".to_string()]);
+    assert_eq!(trades[0].decoded_tape(), Some(Tape::B));
     assert_eq!(res.symbol, "SPY".to_string());
     assert!(res.next_page_token.is_some())
   }
@@ -339,4 +473,29 @@ mod tests {
       _ => panic!("Received unexpected error: {err:?}"),
     };
   }
+
+  /// Check that we can lazily stream trades across page boundaries.
+  #[test(tokio::test)]
+  async fn stream_trades() {
+    let api_info = ApiInfo::from_env().unwrap();
+    let client = Client::new(api_info);
+    let start = DateTime::from_str("2020-12-03T21:47:00Z").unwrap();
+    let end = DateTime::from_str("2020-12-07T21:47:00Z").unwrap();
+    let request = ListReqInit {
+      limit: Some(2),
+      ..Default::default()
+    }
+    .init("AAPL", start, end);
+
+    let trades = client
+      .trades_stream(&request)
+      .take(5)
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    assert_eq!(trades.len(), 5);
+    assert!(trades
+      .windows(2)
+      .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+  }
 }
@@ -1,12 +1,22 @@
 // Copyright (C) 2019-2023 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::fmt::Write as _;
+
+use chrono::DateTime;
+use chrono::Utc;
+
 use serde::Deserialize;
 use serde_json::Error as JsonError;
 use serde_urlencoded::ser::Error as UrlEncodeError;
 
 use thiserror::Error;
 
+use uuid::Uuid;
+
+#[cfg(feature = "trading")]
+use crate::api::v2::order::OrderValidationError;
+
 
 /// An error type comprising various conversion errors we may encounter.
 #[derive(Debug, Error)]
@@ -17,6 +27,23 @@ pub enum ConversionError {
   /// A variant used when we fail to URL-encode a piece of data.
   #[error("failed to URL-encode data")]
   UrlEncode(#[from] UrlEncodeError),
+  /// A variant used when a request failed validation before being
+  /// issued.
+  #[error("{0}")]
+  Validation(String),
+  /// A variant used when an order request failed client-side
+  /// validation before being submitted.
+  #[cfg(feature = "trading")]
+  #[error("failed to validate order request")]
+  InvalidOrder(#[source] OrderValidationError),
+  /// A variant used when a request's time range ends before it starts.
+  #[error("invalid time range: start {start} lies after end {end}")]
+  InvalidTimeRange {
+    /// The start of the range.
+    start: DateTime<Utc>,
+    /// The end of the range.
+    end: DateTime<Utc>,
+  },
 }
 
 
@@ -27,11 +54,230 @@ pub enum ConversionError {
 #[derive(Clone, Debug, Deserialize, Error, Eq, PartialEq)]
 #[error("{message}")]
 pub struct ApiError {
+  /// The numeric error code as provided by Alpaca, if any.
+  ///
+  /// Error codes are more stable than messages and should be preferred
+  /// for distinguishing between different errors. An insufficient
+  /// buying power error, for example, is reported with code 40310000.
+  #[serde(rename = "code", default)]
+  pub code: Option<u64>,
   /// A message as provided by Alpaca.
   #[serde(rename = "message")]
   pub message: String,
 }
 
+impl ApiError {
+  /// Classify the error based on its code and, if necessary, its
+  /// message.
+  ///
+  /// This classification is mostly useful for errors reported as
+  /// `NotPermitted`, allowing for telling apart invalid credentials from
+  /// a feature not being enabled for the account. It also covers common
+  /// reasons for the rejection of an order submission, which may be
+  /// reported as `NotPermitted` or `InvalidInput`.
+  pub fn kind(&self) -> ApiErrorKind {
+    match self.code {
+      Some(CODE_UNAUTHORIZED) => ApiErrorKind::InvalidCredentials,
+      Some(CODE_PATTERN_DAY_TRADING) => ApiErrorKind::PatternDayTrading,
+      // The remaining codes are shared by a multitude of reasons,
+      // which we can only tell apart by their message. The Data API
+      // may not report a code at all.
+      Some(CODE_FORBIDDEN) | Some(CODE_UNPROCESSABLE) | None
+        if self.message.contains(SIP_NOT_PERMITTED_PHRASE) =>
+      {
+        ApiErrorKind::SipNotPermitted
+      },
+      // Absent a more specific indication in the message, this code
+      // signals insufficient buying power.
+      Some(CODE_FORBIDDEN) => {
+        let message = self.message.to_lowercase();
+        let not_enabled = NOT_ENABLED_PHRASES
+          .iter()
+          .any(|phrase| message.contains(phrase));
+
+        if not_enabled && message.contains("crypto") {
+          ApiErrorKind::CryptoNotEnabled
+        } else if not_enabled && message.contains("option") {
+          ApiErrorKind::OptionsNotEnabled
+        } else if not_enabled {
+          ApiErrorKind::Forbidden
+        } else {
+          classify_order_rejection(&message).unwrap_or(ApiErrorKind::InsufficientBuyingPower)
+        }
+      },
+      Some(CODE_UNPROCESSABLE) | None => {
+        let message = self.message.to_lowercase();
+        classify_order_rejection(&message).unwrap_or(ApiErrorKind::Other)
+      },
+      Some(..) => ApiErrorKind::Other,
+    }
+  }
+}
+
+
+/// Classify the (lower case) message of an error reported with one of
+/// the shared codes for an order submission, if it indicates one of
+/// the well known rejection reasons.
+fn classify_order_rejection(message: &str) -> Option<ApiErrorKind> {
+  if message.contains(INSUFFICIENT_BUYING_POWER_PHRASE) {
+    Some(ApiErrorKind::InsufficientBuyingPower)
+  } else if message.contains(WASH_TRADE_PHRASE) {
+    Some(ApiErrorKind::WashTrade)
+  } else if message.contains(NOT_TRADABLE_PHRASE) {
+    Some(ApiErrorKind::AssetNotTradable)
+  } else if MARKET_CLOSED_PHRASES
+    .iter()
+    .any(|phrase| message.contains(phrase))
+  {
+    Some(ApiErrorKind::MarketClosed)
+  } else {
+    None
+  }
+}
+
+
+/// The code reported for requests that failed authentication.
+const CODE_UNAUTHORIZED: u64 = 40110000;
+/// The code Alpaca reports for orders rejected because of insufficient
+/// buying power. It is also used for other refused requests, e.g.,
+/// when a feature is not enabled for the account.
+const CODE_FORBIDDEN: u64 = 40310000;
+/// The code reported for requests containing invalid data, e.g., an
+/// order for an asset that is not tradable.
+const CODE_UNPROCESSABLE: u64 = 42210000;
+/// The code reported for orders denied by the pattern day trading
+/// protection.
+const CODE_PATTERN_DAY_TRADING: u64 = 40310100;
+// Alpaca does not report dedicated codes for most order rejections,
+// so the following phrases are heuristics derived from the messages
+// observed and may need adjusting should these messages change. Only
+// errors reported with one of the shared codes above, or without a
+// code, are checked against them.
+/// The phrase used in messages of errors indicating that an order got
+/// rejected because of insufficient buying power.
+const INSUFFICIENT_BUYING_POWER_PHRASE: &str = "insufficient buying power";
+/// The phrase used in messages of errors indicating that an order got
+/// rejected because it could result in a wash trade.
+const WASH_TRADE_PHRASE: &str = "wash trade";
+/// The phrase used in messages of errors indicating that an order got
+/// rejected because the asset is not tradable.
+const NOT_TRADABLE_PHRASE: &str = "not tradable";
+/// Phrases used in messages of errors indicating that an order got
+/// rejected because the market is closed for the order type.
+const MARKET_CLOSED_PHRASES: [&str; 3] = [
+  "market is closed",
+  "outside of market hours",
+  "outside of regular trading hours",
+];
+/// Phrases used in messages of errors indicating that a feature is not
+/// enabled for the account.
+const NOT_ENABLED_PHRASES: [&str; 5] = [
+  "not enabled",
+  "not eligible",
+  "not allowed",
+  "not authorized",
+  "not approved",
+];
+/// The phrase used in messages of errors indicating that the account's
+/// market data subscription does not include access to SIP data.
+const SIP_NOT_PERMITTED_PHRASE: &str = "subscription does not permit querying recent SIP data";
+
+
+/// The kind of an [`ApiError`], as determined by [`ApiError::kind`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ApiErrorKind {
+  /// The provided credentials are invalid.
+  InvalidCredentials,
+  /// Crypto currency trading is not enabled for the account.
+  CryptoNotEnabled,
+  /// Options trading is not enabled for the account.
+  OptionsNotEnabled,
+  /// The account's market data subscription does not permit access to
+  /// recent data from the [`SIP`][crate::data::v2::Feed::SIP] feed.
+  ///
+  /// Accounts without a paid subscription are limited to the
+  /// [`IEX`][crate::data::v2::Feed::IEX] feed.
+  SipNotPermitted,
+  /// An order was rejected because of insufficient buying power.
+  ///
+  /// Reducing the order's size may allow for it to be accepted.
+  InsufficientBuyingPower,
+  /// An order was rejected because it could result in a wash trade,
+  /// e.g., as there is an open order on the opposite side.
+  WashTrade,
+  /// An order was rejected because the asset is not tradable.
+  AssetNotTradable,
+  /// An order was rejected because the market is closed for orders of
+  /// its type.
+  ///
+  /// The order may be accepted once the market opens.
+  MarketClosed,
+  /// An order was denied by the pattern day trading protection.
+  PatternDayTrading,
+  /// The request was refused for another reason.
+  Forbidden,
+  /// Any other error.
+  Other,
+}
+
+
+/// A type that can be embedded as a segment in the path of an
+/// endpoint.
+///
+/// Implementations are responsible for percent-encoding all characters
+/// not permitted inside of a single path segment, most notably `/`.
+pub(crate) trait PathSegment {
+  /// Convert the object into a percent-encoded path segment.
+  fn to_segment(&self) -> String;
+}
+
+impl PathSegment for str {
+  fn to_segment(&self) -> String {
+    encode_segment(self)
+  }
+}
+
+impl PathSegment for Uuid {
+  fn to_segment(&self) -> String {
+    self.as_simple().to_string()
+  }
+}
+
+
+/// Percent-encode a string for use as a single path segment.
+///
+/// Only unreserved characters as well as `:` (which is used in
+/// qualified asset symbols) are left untouched.
+pub(crate) fn encode_segment(segment: &str) -> String {
+  let mut encoded = String::with_capacity(segment.len());
+  for byte in segment.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' => {
+        encoded.push(char::from(byte))
+      },
+      _ => {
+        let _result = write!(encoded, "%{byte:02X}");
+      },
+    }
+  }
+  encoded
+}
+
+
+/// A macro for formatting the path of an endpoint, percent-encoding
+/// each of the provided segments.
+///
+/// Every segment has to implement [`PathSegment`], meaning that only
+/// types known to form valid path segments can be embedded.
+macro_rules! path {
+  ($fmt:literal $(, $segment:expr)* $(,)?) => {{
+    #[allow(unused_imports)]
+    use crate::endpoint::PathSegment as _;
+    ::std::format!($fmt $(, ($segment).to_segment())*)
+  }};
+}
+
 
 /// A macro used for defining the properties for a request to a
 /// particular HTTP endpoint, without automated JSON parsing.
@@ -57,6 +303,8 @@ macro_rules! EndpointNoParse {
         /// Order submission/change failure (e.g., due to insufficient
         /// funds or time constraint violations) is also expressed this
         /// way.
+        /// [`ApiError::kind`][crate::ApiError::kind] can help with
+        /// telling apart these cases.
         /* 403 */ FORBIDDEN => NotPermitted,
         /// The rate limit was exceeded, causing the request to be
         /// denied.
@@ -88,3 +336,157 @@ macro_rules! Endpoint {
     }
   };
 }
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(feature = "trading")]
+  use http::StatusCode;
+
+  #[cfg(feature = "trading")]
+  use http_endpoint::Endpoint as _;
+
+  use test_log::test;
+
+  #[cfg(feature = "trading")]
+  use crate::api::v2::account;
+  #[cfg(feature = "trading")]
+  use crate::api::v2::order;
+
+
+  /// Check that errors reported as `NotPermitted` are classified based
+  /// on their code.
+  #[cfg(feature = "trading")]
+  #[test]
+  fn classify_not_permitted_errors() {
+    fn kind(body: &[u8]) -> ApiErrorKind {
+      match order::Create::evaluate(StatusCode::FORBIDDEN, body) {
+        Err(order::CreateError::NotPermitted(Ok(error))) => error.kind(),
+        result => panic!("unexpected result: {result:?}"),
+      }
+    }
+
+    let body = br#"{"code":40310000,"message":"crypto trading is not enabled for this account"}"#;
+    assert_eq!(kind(body), ApiErrorKind::CryptoNotEnabled);
+
+    let body = br#"{"code":40310000,"message":"account not eligible to trade options"}"#;
+    assert_eq!(kind(body), ApiErrorKind::OptionsNotEnabled);
+
+    let body = br#"{"code":40310000,"message":"insufficient options buying power"}"#;
+    assert_eq!(kind(body), ApiErrorKind::InsufficientBuyingPower);
+
+    let body = br#"{"code":40310000,"message":"account is not allowed to short"}"#;
+    assert_eq!(kind(body), ApiErrorKind::Forbidden);
+
+    let body = br#"{"code":40110000,"message":"request is not authorized"}"#;
+    match account::Get::evaluate(StatusCode::FORBIDDEN, body) {
+      Err(account::GetError::NotPermitted(Ok(error))) => {
+        assert_eq!(error.kind(), ApiErrorKind::InvalidCredentials)
+      },
+      result => panic!("unexpected result: {result:?}"),
+    }
+
+    let error = ApiError {
+      code: None,
+      message: "forbidden.".to_string(),
+    };
+    assert_eq!(error.kind(), ApiErrorKind::Other);
+  }
+
+  /// Check that common reasons for order rejections are classified
+  /// correctly.
+  #[cfg(feature = "trading")]
+  #[test]
+  fn classify_order_rejections() {
+    fn kind(status: StatusCode, body: &[u8]) -> ApiErrorKind {
+      match order::Create::evaluate(status, body) {
+        Err(order::CreateError::NotPermitted(Ok(error)))
+        | Err(order::CreateError::InvalidInput(Ok(error))) => error.kind(),
+        result => panic!("unexpected result: {result:?}"),
+      }
+    }
+
+    let body = br#"{"code":40310000,"message":"insufficient buying power"}"#;
+    assert_eq!(
+      kind(StatusCode::FORBIDDEN, body),
+      ApiErrorKind::InsufficientBuyingPower
+    );
+
+    // The code alone indicates insufficient buying power...
+    let body = br#"{"code":40310000,"message":"insufficient day trading buying power"}"#;
+    assert_eq!(
+      kind(StatusCode::FORBIDDEN, body),
+      ApiErrorKind::InsufficientBuyingPower
+    );
+
+    // ... while the message serves as a fallback for other codes.
+    let body = br#"{"code":42210000,"message":"insufficient buying power"}"#;
+    assert_eq!(
+      kind(StatusCode::UNPROCESSABLE_ENTITY, body),
+      ApiErrorKind::InsufficientBuyingPower
+    );
+
+    let body =
+      br#"{"code":40310000,"message":"potential wash trade detected. use complex orders"}"#;
+    assert_eq!(kind(StatusCode::FORBIDDEN, body), ApiErrorKind::WashTrade);
+
+    let body = br#"{"code":42210000,"message":"asset \"XYZ\" is not tradable"}"#;
+    assert_eq!(
+      kind(StatusCode::UNPROCESSABLE_ENTITY, body),
+      ApiErrorKind::AssetNotTradable
+    );
+
+    let body =
+      br#"{"code":42210000,"message":"market orders are not accepted outside of market hours"}"#;
+    assert_eq!(
+      kind(StatusCode::UNPROCESSABLE_ENTITY, body),
+      ApiErrorKind::MarketClosed
+    );
+
+    let body = br#"{"code":42210000,"message":"qty must be > 0"}"#;
+    assert_eq!(
+      kind(StatusCode::UNPROCESSABLE_ENTITY, body),
+      ApiErrorKind::Other
+    );
+
+    // Distinct codes take precedence over any phrases in the message.
+    let body = br#"{"code":40310100,"message":"trade denied due to pattern day trading protection; insufficient buying power"}"#;
+    assert_eq!(
+      kind(StatusCode::FORBIDDEN, body),
+      ApiErrorKind::PatternDayTrading
+    );
+
+    // Phrases are only considered for the generic codes.
+    let body = br#"{"code":40010001,"message":"insufficient buying power"}"#;
+    assert_eq!(
+      kind(StatusCode::UNPROCESSABLE_ENTITY, body),
+      ApiErrorKind::Other
+    );
+  }
+
+
+  /// Check that path segments are percent-encoded as necessary.
+  #[test]
+  fn encode_path_segments() {
+    assert_eq!(path!("/v2/assets/{}", "AAPL"), "/v2/assets/AAPL");
+    assert_eq!(path!("/v2/assets/{}", "BTC/USD"), "/v2/assets/BTC%2FUSD");
+    assert_eq!(
+      path!("/v2/assets/{}", "SPY:NYSE:us_equity"),
+      "/v2/assets/SPY:NYSE:us_equity"
+    );
+    assert_eq!(
+      path!("/v2/assets/{}", "A B?#%"),
+      "/v2/assets/A%20B%3F%23%25"
+    );
+    assert_eq!(path!("/v2/assets/{}", "ÄB"), "/v2/assets/%C3%84B");
+
+    let id = Uuid::parse_str("904837e3-3b76-47ec-b432-046db621571b").unwrap();
+    let symbol = "BTC/USD".to_string();
+    assert_eq!(
+      path!("/v2/watchlists/{}/{}", id, symbol),
+      "/v2/watchlists/904837e33b7647ecb432046db621571b/BTC%2FUSD"
+    );
+  }
+}
@@ -14,8 +14,11 @@ use hyper::Error as HyperError;
 use serde_json::Error as JsonError;
 use thiserror::Error;
 use url::ParseError;
+#[cfg(feature = "streaming")]
 use websocket_util::tungstenite::Error as WebSocketError;
 
+#[cfg(all(feature = "market-data", feature = "streaming"))]
+use crate::data::v2::stream::StreamApiError;
 use crate::Str;
 
 
@@ -46,6 +49,23 @@ pub enum RequestError<E> {
     #[source]
     IoError,
   ),
+  /// The response body exceeded the maximum permitted size (in bytes).
+  #[error("the response body exceeded the maximum size of {0} bytes")]
+  ResponseTooLarge(usize),
+}
+
+impl<E> RequestError<E> {
+  /// Check whether the error is likely transient, i.e., whether
+  /// retrying the request has a chance of succeeding.
+  ///
+  /// Note that a transient error does not imply that the request did
+  /// not reach the server.
+  pub(crate) fn is_transient(&self) -> bool {
+    match self {
+      Self::Hyper(..) | Self::HyperUtil(..) | Self::Io(..) => true,
+      Self::Endpoint(..) | Self::ResponseTooLarge(..) => false,
+    }
+  }
 }
 
 
@@ -87,6 +107,13 @@ pub enum Error {
   /// An error directly originating in this crate.
   #[error("{0}")]
   Str(Str),
+  /// An error reported by the Alpaca Stream API in response to a
+  /// control operation, e.g., a failed authentication attempt.
+  ///
+  /// Check [`StreamApiError::kind`] to determine the cause.
+  #[cfg(all(feature = "market-data", feature = "streaming"))]
+  #[error("the stream API reported an error")]
+  StreamApi(#[source] StreamApiError),
   /// An URL parsing error.
   #[error("failed to parse the URL")]
   Url(
@@ -95,6 +122,7 @@ pub enum Error {
     ParseError,
   ),
   /// A websocket error.
+  #[cfg(feature = "streaming")]
   #[error("encountered a websocket related error")]
   WebSocket(
     #[from]
@@ -0,0 +1,126 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use num_decimal::Num;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+
+/// A [`Num`] wrapper that serializes its value with at least `SCALE`
+/// decimal places, e.g., `"100.00"` instead of `"100"` for a `SCALE`
+/// of two.
+///
+/// The wrapped value itself is never altered: digits beyond `SCALE`
+/// are emitted as well, so that serialization stays lossless.
+/// Deserialization accepts any precision. Fields of the types in this
+/// crate keep serializing in the format Alpaca uses; this type is meant
+/// for forwarding values to systems that require a fixed precision.
+///
+/// Besides wrapping a value, the serializer can be enabled on an
+/// individual `Num` field:
+/// ```
+/// # use apca::FixedScale;
+/// # use num_decimal::Num;
+/// #[derive(serde::Serialize)]
+/// struct Price {
+///   #[serde(serialize_with = "FixedScale::<2>::serialize_num")]
+///   price: Num,
+/// }
+///
+/// let price = Price {
+///   price: Num::from(100),
+/// };
+/// let json = serde_json::to_string(&price).unwrap();
+/// assert_eq!(json, r#"{"price":"100.00"}"#);
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FixedScale<const SCALE: usize>(pub Num);
+
+impl<const SCALE: usize> FixedScale<SCALE> {
+  /// Serialize the provided `Num` with at least `SCALE` decimal places.
+  ///
+  /// This function is meant to be used with serde's `serialize_with`
+  /// attribute.
+  pub fn serialize_num<S>(value: &Num, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    serializer.collect_str(&value.display().min_precision(SCALE))
+  }
+
+  /// Convert the object into the wrapped `Num`.
+  #[inline]
+  pub fn into_inner(self) -> Num {
+    self.0
+  }
+}
+
+impl<const SCALE: usize> From<Num> for FixedScale<SCALE> {
+  #[inline]
+  fn from(value: Num) -> Self {
+    Self(value)
+  }
+}
+
+impl<const SCALE: usize> Display for FixedScale<SCALE> {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+    write!(fmt, "{}", self.0.display().min_precision(SCALE))
+  }
+}
+
+impl<const SCALE: usize> Serialize for FixedScale<SCALE> {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    Self::serialize_num(&self.0, serializer)
+  }
+}
+
+impl<'de, const SCALE: usize> Deserialize<'de> for FixedScale<SCALE> {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    Num::deserialize(deserializer).map(Self)
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use serde_json::from_str as from_json;
+  use serde_json::to_string as to_json;
+
+  use test_log::test;
+
+
+  /// Check that values are serialized with the configured scale and
+  /// deserialize back to the same `Num`.
+  #[test]
+  fn serialize_fixed_scale() {
+    let value = FixedScale::<2>(Num::from(100));
+    let json = to_json(&value).unwrap();
+    assert_eq!(json, r#""100.00""#);
+    assert_eq!(from_json::<FixedScale<2>>(&json).unwrap(), value);
+    assert_eq!(from_json::<Num>(&json).unwrap(), Num::from(100));
+
+    let value = FixedScale::<2>(Num::new(1005, 10));
+    assert_eq!(to_json(&value).unwrap(), r#""100.50""#);
+
+    // Additional precision is never dropped.
+    let value = FixedScale::<2>(Num::new(100125, 1000));
+    let json = to_json(&value).unwrap();
+    assert_eq!(json, r#""100.125""#);
+    assert_eq!(from_json::<FixedScale<2>>(&json).unwrap(), value);
+  }
+}
@@ -99,20 +99,54 @@ pub mod api;
 /// A module for retrieving market data.
 pub mod data;
 
+/// A module providing a blocking facade over the asynchronous client.
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+/// A module providing a mock client for testing code interacting with
+/// the API without contacting Alpaca.
+#[cfg(feature = "testing")]
+pub mod testing;
+
 mod api_info;
 mod client;
 mod error;
+mod fixed_scale;
+#[cfg(feature = "money")]
+mod money;
+mod pagination;
+mod raw;
 mod subscribable;
+#[cfg(test)]
+mod test_util;
 mod util;
+#[cfg(all(
+  feature = "streaming",
+  any(feature = "market-data", feature = "trading")
+))]
 mod websocket;
 
 use std::borrow::Cow;
 
 pub use crate::api_info::ApiInfo;
+pub use crate::api_info::Environment;
+pub use crate::client::ApiClient;
 pub use crate::client::Client;
+pub use crate::client::ResponseMeta;
 pub use crate::endpoint::ApiError;
+pub use crate::endpoint::ApiErrorKind;
 pub use crate::error::Error;
 pub use crate::error::RequestError;
+pub use crate::fixed_scale::FixedScale;
+#[cfg(feature = "money")]
+pub use crate::money::CurrencyMismatch;
+#[cfg(feature = "money")]
+pub use crate::money::Money;
+pub use crate::pagination::Page;
+pub use crate::pagination::PageInfo;
+pub use crate::pagination::Paginated;
+pub use crate::raw::RawApi;
+pub use crate::raw::RawError;
 pub use crate::subscribable::Subscribable;
 
 type Str = Cow<'static, str>;
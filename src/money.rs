@@ -0,0 +1,145 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use num_decimal::Num;
+
+use thiserror::Error;
+
+
+/// An error indicating that an arithmetic operation was attempted on
+/// two [`Money`] values denominated in different currencies.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("currency mismatch: {left} vs. {right}")]
+pub struct CurrencyMismatch {
+  /// The currency of the left hand side operand.
+  pub left: String,
+  /// The currency of the right hand side operand.
+  pub right: String,
+}
+
+
+/// A monetary amount tagged with the ISO 4217 code of the currency it
+/// is denominated in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Money {
+  /// The amount.
+  amount: Num,
+  /// The currency code, e.g., "USD".
+  currency: String,
+}
+
+impl Money {
+  /// Create a new `Money` object from an amount and a currency code.
+  #[inline]
+  pub fn new<C>(amount: Num, currency: C) -> Self
+  where
+    C: Into<String>,
+  {
+    Self {
+      amount,
+      currency: currency.into(),
+    }
+  }
+
+  /// Retrieve the amount.
+  #[inline]
+  pub fn amount(&self) -> &Num {
+    &self.amount
+  }
+
+  /// Retrieve the currency code.
+  #[inline]
+  pub fn currency(&self) -> &str {
+    &self.currency
+  }
+
+  /// Convert the object into the raw amount, dropping the currency.
+  #[inline]
+  pub fn into_amount(self) -> Num {
+    self.amount
+  }
+
+  fn check_currency(&self, other: &Self) -> Result<(), CurrencyMismatch> {
+    if self.currency == other.currency {
+      Ok(())
+    } else {
+      Err(CurrencyMismatch {
+        left: self.currency.clone(),
+        right: other.currency.clone(),
+      })
+    }
+  }
+
+  /// Add `other` to this amount, failing if the two are denominated in
+  /// different currencies.
+  pub fn checked_add(&self, other: &Self) -> Result<Self, CurrencyMismatch> {
+    let () = self.check_currency(other)?;
+    Ok(Self::new(
+      &self.amount + &other.amount,
+      self.currency.clone(),
+    ))
+  }
+
+  /// Subtract `other` from this amount, failing if the two are
+  /// denominated in different currencies.
+  pub fn checked_sub(&self, other: &Self) -> Result<Self, CurrencyMismatch> {
+    let () = self.check_currency(other)?;
+    Ok(Self::new(
+      &self.amount - &other.amount,
+      self.currency.clone(),
+    ))
+  }
+}
+
+impl Display for Money {
+  fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+    write!(fmt, "{} {}", self.amount, self.currency)
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use test_log::test;
+
+
+  /// Check that we can add and subtract amounts of the same currency.
+  #[test]
+  fn same_currency_arithmetic() {
+    let lhs = Money::new(Num::new(1050, 100), "USD");
+    let rhs = Money::new(Num::from(2), "USD");
+
+    let sum = lhs.checked_add(&rhs).unwrap();
+    assert_eq!(sum, Money::new(Num::new(1250, 100), "USD"));
+    assert_eq!(sum.to_string(), "12.5 USD");
+
+    let difference = lhs.checked_sub(&rhs).unwrap();
+    assert_eq!(difference.amount(), &Num::new(850, 100));
+    assert_eq!(difference.currency(), "USD");
+  }
+
+  /// Make sure that arithmetic on amounts of different currencies
+  /// fails.
+  #[test]
+  fn cross_currency_arithmetic() {
+    let usd = Money::new(Num::from(1), "USD");
+    let eur = Money::new(Num::from(1), "EUR");
+
+    let err = usd.checked_add(&eur).unwrap_err();
+    assert_eq!(
+      err,
+      CurrencyMismatch {
+        left: "USD".to_string(),
+        right: "EUR".to_string(),
+      }
+    );
+    assert_eq!(err.to_string(), "currency mismatch: USD vs. EUR");
+    assert!(eur.checked_sub(&usd).is_err());
+  }
+}
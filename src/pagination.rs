@@ -0,0 +1,802 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::vec::IntoIter;
+
+#[cfg(feature = "trading")]
+use chrono::DateTime;
+#[cfg(feature = "trading")]
+use chrono::Duration;
+#[cfg(feature = "trading")]
+use chrono::SecondsFormat;
+#[cfg(feature = "trading")]
+use chrono::Utc;
+
+use futures::stream::unfold;
+use futures::Stream;
+
+use http_endpoint::Endpoint;
+
+#[cfg(feature = "trading")]
+use crate::api::v2::account_activities;
+#[cfg(feature = "trading")]
+use crate::api::v2::order;
+#[cfg(feature = "trading")]
+use crate::api::v2::orders;
+#[cfg(feature = "market-data")]
+use crate::data::v1beta1::forex;
+#[cfg(feature = "market-data")]
+use crate::data::v2::auctions;
+#[cfg(feature = "market-data")]
+use crate::data::v2::bars;
+#[cfg(feature = "market-data")]
+use crate::data::v2::quotes;
+#[cfg(feature = "market-data")]
+use crate::data::v2::trades;
+use crate::Client;
+use crate::RequestError;
+
+
+/// The number of orders reported per page if no limit is provided.
+#[cfg(feature = "trading")]
+const ORDERS_DEFAULT_LIMIT: usize = 50;
+
+
+/// A single page of items as reported by a paginated endpoint.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Page<T> {
+  /// The items contained in the page.
+  pub items: Vec<T>,
+  /// The token for requesting the next page, if any.
+  pub next_page_token: Option<String>,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+impl<T> Page<T> {
+  /// Create a new `Page` from its items and the token for requesting
+  /// the next page, if any.
+  #[inline]
+  pub fn new(items: Vec<T>, next_page_token: Option<String>) -> Self {
+    Self {
+      items,
+      next_page_token,
+      _non_exhaustive: (),
+    }
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl From<auctions::Auctions> for Page<auctions::Auction> {
+  #[inline]
+  fn from(auctions: auctions::Auctions) -> Self {
+    Self::new(auctions.auctions, auctions.next_page_token)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl From<bars::Bars> for Page<bars::Bar> {
+  #[inline]
+  fn from(bars: bars::Bars) -> Self {
+    Self::new(bars.bars, bars.next_page_token)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl From<quotes::Quotes> for Page<quotes::Quote> {
+  #[inline]
+  fn from(quotes: quotes::Quotes) -> Self {
+    Self::new(quotes.quotes, quotes.next_page_token)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl From<trades::Trades> for Page<trades::Trade> {
+  #[inline]
+  fn from(trades: trades::Trades) -> Self {
+    Self::new(trades.trades, trades.next_page_token)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl From<forex::Rates> for Page<(String, forex::Rate)> {
+  fn from(rates: forex::Rates) -> Self {
+    let items = rates
+      .rates
+      .into_iter()
+      .flat_map(|(pair, rates)| rates.into_iter().map(move |rate| (pair.clone(), rate)))
+      .collect();
+    Self::new(items, rates.next_page_token)
+  }
+}
+
+
+/// A trait representing an endpoint whose results are split across
+/// multiple pages, with subsequent pages being requested by means of a
+/// page token.
+pub trait Paginated: Endpoint {
+  /// The type of the items contained in each page.
+  type Item;
+
+  /// Set the page token to use for requesting the next page.
+  fn set_page_token(input: &mut Self::Input, token: Option<String>);
+
+  /// Convert the endpoint's output, as received in response to a
+  /// request with the provided input, into a [`Page`].
+  fn into_page(input: &Self::Input, output: Self::Output) -> Page<Self::Item>;
+}
+
+#[cfg(feature = "market-data")]
+impl Paginated for auctions::Get {
+  type Item = auctions::Auction;
+
+  #[inline]
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    input.page_token = token;
+  }
+
+  #[inline]
+  fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+    Page::from(output)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl Paginated for bars::List {
+  type Item = bars::Bar;
+
+  #[inline]
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    input.page_token = token;
+  }
+
+  #[inline]
+  fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+    Page::from(output)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl Paginated for quotes::List {
+  type Item = quotes::Quote;
+
+  #[inline]
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    input.page_token = token;
+  }
+
+  #[inline]
+  fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+    Page::from(output)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl Paginated for trades::List {
+  type Item = trades::Trade;
+
+  #[inline]
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    input.page_token = token;
+  }
+
+  #[inline]
+  fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+    Page::from(output)
+  }
+}
+
+#[cfg(feature = "market-data")]
+impl Paginated for forex::List {
+  type Item = (String, forex::Rate);
+
+  #[inline]
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    input.page_token = token;
+  }
+
+  #[inline]
+  fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+    Page::from(output)
+  }
+}
+
+#[cfg(feature = "trading")]
+impl Paginated for account_activities::Get {
+  type Item = account_activities::Activity;
+
+  #[inline]
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    input.page_token = token;
+  }
+
+  /// Account activities are paged by the ID of the last activity
+  /// reported. As the response does not indicate whether more
+  /// activities are available, retrieval only concludes with an empty
+  /// page.
+  fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+    let next_page_token = output.last().map(|activity| activity.id().to_string());
+    Page::new(output, next_page_token)
+  }
+}
+
+#[cfg(feature = "trading")]
+impl Paginated for orders::List {
+  type Item = order::Order;
+
+  fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+    let bound = token
+      .and_then(|token| DateTime::parse_from_rfc3339(&token).ok())
+      .map(|bound| bound.with_timezone(&Utc));
+
+    match input.direction.unwrap_or(orders::Direction::Descending) {
+      orders::Direction::Descending => input.until = bound,
+      orders::Direction::Ascending => input.after = bound,
+    }
+  }
+
+  /// Orders are paged by moving the (exclusive) `until` or `after`
+  /// bound, depending on the direction, to the submission time of the
+  /// last order reported. Because other orders may have been
+  /// submitted at the very same time but not fit on the page, trailing
+  /// orders sharing this time are deferred to the next page, which
+  /// includes them by moving the bound past it. Only if a page
+  /// consists exclusively of such orders are all of them reported and
+  /// the bound moved right onto their submission time. Retrieval
+  /// concludes with a page that is not full.
+  fn into_page(input: &Self::Input, mut output: Self::Output) -> Page<Self::Item> {
+    fn submitted_at(order: &order::Order) -> DateTime<Utc> {
+      order.submitted_at.unwrap_or(order.created_at)
+    }
+
+    let limit = input.limit.unwrap_or(ORDERS_DEFAULT_LIMIT);
+    let last = match output.last() {
+      Some(last) if output.len() >= limit => submitted_at(last),
+      _ => return Page::new(output, None),
+    };
+
+    let deferred = output
+      .iter()
+      .rev()
+      .take_while(|order| submitted_at(order) == last)
+      .count();
+    let bound = if deferred < output.len() {
+      let () = output.truncate(output.len() - deferred);
+      // Alpaca reports submission times with microsecond precision,
+      // so moving the bound by as much makes it behave inclusively.
+      match input.direction.unwrap_or(orders::Direction::Descending) {
+        orders::Direction::Descending => last + Duration::microseconds(1),
+        orders::Direction::Ascending => last - Duration::microseconds(1),
+      }
+    } else {
+      last
+    };
+
+    let next_page_token = bound.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+    Page::new(output, Some(next_page_token))
+  }
+}
+
+
+/// Progress information about a paginated retrieval, as reported after
+/// each page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PageInfo {
+  /// The number of the page just retrieved, starting at 1.
+  pub page_number: usize,
+  /// The total number of items retrieved so far, including those of
+  /// the current page.
+  pub items_so_far: usize,
+  /// Whether more pages are available.
+  pub has_more: bool,
+  /// The type is non-exhaustive and open to extension.
+  #[doc(hidden)]
+  pub _non_exhaustive: (),
+}
+
+
+impl Client {
+  /// Issue a request to a paginated endpoint, following page tokens
+  /// until all pages have been retrieved, and return the items of all
+  /// pages combined.
+  ///
+  /// Retrieval starts at the page token set in `input`, if any.
+  #[inline]
+  pub async fn issue_all<R>(&self, input: &R::Input) -> Result<Vec<R::Item>, RequestError<R::Error>>
+  where
+    R: Paginated,
+    R::Input: Clone,
+  {
+    self
+      .issue_all_with_progress::<R, _>(input, |_info| ())
+      .await
+  }
+
+  /// Issue a request to a paginated endpoint just like
+  /// [`Client::issue_all`], but report progress to `progress` after
+  /// each retrieved page.
+  pub async fn issue_all_with_progress<R, F>(
+    &self,
+    input: &R::Input,
+    mut progress: F,
+  ) -> Result<Vec<R::Item>, RequestError<R::Error>>
+  where
+    R: Paginated,
+    R::Input: Clone,
+    F: FnMut(&PageInfo),
+  {
+    let mut input = input.clone();
+    let mut items = Vec::new();
+    let mut page_number = 0;
+
+    loop {
+      let output = self.issue::<R>(&input).await?;
+      let Page {
+        items: page,
+        next_page_token: token,
+        ..
+      } = R::into_page(&input, output);
+      items.extend(page);
+      page_number += 1;
+
+      let info = PageInfo {
+        page_number,
+        items_so_far: items.len(),
+        has_more: token.is_some(),
+        _non_exhaustive: (),
+      };
+      progress(&info);
+
+      match token {
+        Some(token) => R::set_page_token(&mut input, Some(token)),
+        None => break Ok(items),
+      }
+    }
+  }
+}
+
+
+/// The state of a stream created by [`Client::paginate`].
+struct StreamState<R>
+where
+  R: Paginated,
+{
+  /// The request input, containing the page token to use next.
+  input: R::Input,
+  /// The not yet yielded items of the current page.
+  items: IntoIter<R::Item>,
+  /// Whether there are no more pages to retrieve.
+  done: bool,
+}
+
+
+impl Client {
+  /// Create a stream lazily yielding the items of all pages of a
+  /// paginated endpoint, following page tokens as necessary.
+  ///
+  /// Pages are only retrieved once all items of the previous one have
+  /// been consumed and the stream ends after the last page. Errors are
+  /// reported as `Err` items. Transient errors (such as connection
+  /// failures) leave the stream intact and polling it again retries
+  /// retrieval of the page in question, while all other errors
+  /// terminate it.
+  pub fn paginate<'c, R>(
+    &'c self,
+    input: &R::Input,
+  ) -> impl Stream<Item = Result<R::Item, RequestError<R::Error>>> + 'c
+  where
+    R: Paginated + 'c,
+    R::Input: Clone,
+  {
+    let state = StreamState::<R> {
+      input: input.clone(),
+      items: Vec::new().into_iter(),
+      done: false,
+    };
+
+    unfold(state, move |mut state| async move {
+      loop {
+        if let Some(item) = state.items.next() {
+          break Some((Ok(item), state))
+        }
+
+        if state.done {
+          break None
+        }
+
+        match self.issue::<R>(&state.input).await {
+          Ok(output) => {
+            let page = R::into_page(&state.input, output);
+            state.items = page.items.into_iter();
+            match page.next_page_token {
+              Some(token) => R::set_page_token(&mut state.input, Some(token)),
+              None => state.done = true,
+            }
+          },
+          Err(err) => {
+            state.done = !err.is_transient();
+            break Some((Err(err), state))
+          },
+        }
+      }
+    })
+  }
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use futures::StreamExt as _;
+  use futures::TryStreamExt as _;
+
+  use serde::Deserialize;
+  use serde::Serialize;
+  use serde_urlencoded::to_string as to_query;
+
+  use test_log::test;
+
+  use crate::api_info::ApiInfo;
+  use crate::test_util::serve_sequence;
+  use crate::test_util::Reply;
+  use crate::Str;
+
+
+  /// The input to our paginated test endpoint.
+  #[derive(Clone, Debug, Default, Serialize)]
+  struct ListReq {
+    #[serde(rename = "page_token", skip_serializing_if = "Option::is_none")]
+    page_token: Option<String>,
+  }
+
+  /// A page as returned by our paginated test endpoint.
+  #[derive(Debug, Deserialize)]
+  struct ItemPage {
+    items: Vec<u64>,
+    next_page_token: Option<String>,
+  }
+
+  Endpoint! {
+    List(ListReq),
+    Ok => ItemPage, [
+      /* 200 */ OK,
+    ],
+    Err => ListError, []
+
+    fn path(_input: &Self::Input) -> Str {
+      "/v2/items".into()
+    }
+
+    fn query(input: &Self::Input) -> Result<Option<Str>, Self::ConversionError> {
+      Ok(Some(to_query(input)?.into()))
+    }
+  }
+
+  impl Paginated for List {
+    type Item = u64;
+
+    fn set_page_token(input: &mut Self::Input, token: Option<String>) {
+      input.page_token = token;
+    }
+
+    fn into_page(_input: &Self::Input, output: Self::Output) -> Page<Self::Item> {
+      Page::new(output.items, output.next_page_token)
+    }
+  }
+
+
+  /// Check that the progress callback is invoked once per page, with
+  /// the correct cumulative counts.
+  #[test(tokio::test)]
+  async fn report_page_progress() {
+    let (url, _requests) = serve_sequence(vec![
+      Reply::respond("200 OK", r#"{"items": [1, 2], "next_page_token": "2"}"#),
+      Reply::respond("200 OK", r#"{"items": [3, 4, 5], "next_page_token": "3"}"#),
+      Reply::respond("200 OK", r#"{"items": [6], "next_page_token": null}"#),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let mut infos = Vec::new();
+    let items = client
+      .issue_all_with_progress::<List, _>(&ListReq::default(), |info| infos.push(*info))
+      .await
+      .unwrap();
+    assert_eq!(items, vec![1, 2, 3, 4, 5, 6]);
+
+    let info = |page_number, items_so_far, has_more| PageInfo {
+      page_number,
+      items_so_far,
+      has_more,
+      _non_exhaustive: (),
+    };
+    let expected = vec![info(1, 2, true), info(2, 5, true), info(3, 6, false)];
+    assert_eq!(infos, expected);
+  }
+
+  /// Check that we can retrieve all pages without reporting progress.
+  #[test(tokio::test)]
+  async fn retrieve_all_pages() {
+    let (url, _requests) = serve_sequence(vec![
+      Reply::respond("200 OK", r#"{"items": [1], "next_page_token": "2"}"#),
+      Reply::respond("200 OK", r#"{"items": [2], "next_page_token": null}"#),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let items = client.issue_all::<List>(&ListReq::default()).await.unwrap();
+    assert_eq!(items, vec![1, 2]);
+  }
+
+  /// Check that a stream over a paginated endpoint yields the items of
+  /// all pages in order and then terminates.
+  #[test(tokio::test)]
+  async fn stream_all_pages() {
+    let (url, _requests) = serve_sequence(vec![
+      Reply::respond("200 OK", r#"{"items": [1, 2, 3], "next_page_token": "2"}"#),
+      Reply::respond("200 OK", r#"{"items": [4, 5], "next_page_token": null}"#),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let items = client
+      .paginate::<List>(&ListReq::default())
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    assert_eq!(items, vec![1, 2, 3, 4, 5]);
+  }
+
+  /// Check that account activities are paged by the ID of the last
+  /// activity reported, until an empty page is encountered.
+  #[cfg(feature = "trading")]
+  #[test(tokio::test)]
+  async fn stream_activity_pages() {
+    let (url, _requests) = serve_sequence(vec![
+      Reply::respond(
+        "200 OK",
+        r#"[
+        {"activity_type": "DIV", "id": "20190801011955195::1", "date": "2019-08-01", "net_amount": "1.02"},
+        {"activity_type": "DIV", "id": "20190801011955195::2", "date": "2019-08-01", "net_amount": "0.51"}
+      ]"#,
+      ),
+      Reply::respond(
+        "200 OK",
+        r#"[
+        {"activity_type": "DIV", "id": "20190801011955195::3", "date": "2019-08-01", "net_amount": "0.10"}
+      ]"#,
+      ),
+      Reply::respond("200 OK", "[]"),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = account_activities::ActivityReq::default();
+    let activities = client
+      .paginate::<account_activities::Get>(&request)
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    let ids = activities
+      .iter()
+      .map(account_activities::Activity::id)
+      .collect::<Vec<_>>();
+    assert_eq!(
+      ids,
+      [
+        "20190801011955195::1",
+        "20190801011955195::2",
+        "20190801011955195::3"
+      ]
+    );
+  }
+
+  /// Create the JSON representation of an order with the given ID and
+  /// submission time.
+  #[cfg(feature = "trading")]
+  fn order_json(id: &str, submitted_at: &str) -> String {
+    format!(
+      r#"{{
+      "id": "{id}",
+      "client_order_id": "my-order",
+      "created_at": "{submitted_at}",
+      "updated_at": "{submitted_at}",
+      "submitted_at": "{submitted_at}",
+      "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+      "symbol": "AAPL",
+      "asset_class": "us_equity",
+      "qty": "1",
+      "filled_qty": "0",
+      "type": "market",
+      "order_class": "simple",
+      "side": "buy",
+      "time_in_force": "day",
+      "status": "accepted",
+      "extended_hours": false,
+      "legs": null
+    }}"#
+    )
+  }
+
+  /// Check that orders are paged by the submission time of the last
+  /// order reported, without losing orders submitted at the same time,
+  /// until a page that is not full is encountered.
+  #[cfg(feature = "trading")]
+  #[test(tokio::test)]
+  async fn stream_order_pages() {
+    let id1 = "904837e3-3b76-47ec-b432-046db6215701";
+    let id2 = "904837e3-3b76-47ec-b432-046db6215702";
+    let id3 = "904837e3-3b76-47ec-b432-046db6215703";
+    let id4 = "904837e3-3b76-47ec-b432-046db6215704";
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond(
+        "200 OK",
+        format!(
+          "[{},{}]",
+          order_json(id1, "2018-10-05T05:48:59Z"),
+          order_json(id2, "2018-10-05T05:47:00Z"),
+        ),
+      ),
+      Reply::respond(
+        "200 OK",
+        format!(
+          "[{},{}]",
+          order_json(id2, "2018-10-05T05:47:00Z"),
+          order_json(id3, "2018-10-05T05:47:00Z"),
+        ),
+      ),
+      Reply::respond(
+        "200 OK",
+        format!("[{}]", order_json(id4, "2018-10-04T12:00:00Z")),
+      ),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = orders::ListReq {
+      limit: Some(2),
+      ..Default::default()
+    };
+    let orders = client
+      .paginate::<orders::List>(&request)
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    let ids = orders
+      .iter()
+      .map(|order| order.id.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(ids, [id1, id2, id3, id4]);
+
+    let lines = requests.lines();
+    assert_eq!(lines.len(), 3);
+    assert!(!lines[0].contains("until="), "{}", lines[0]);
+    assert!(
+      lines[1].contains("until=2018-10-05T05%3A47%3A00.000001Z"),
+      "{}",
+      lines[1]
+    );
+    assert!(
+      lines[2].contains("until=2018-10-05T05%3A47%3A00Z"),
+      "{}",
+      lines[2]
+    );
+    assert!(lines.iter().all(|line| !line.contains("after=")));
+  }
+
+  /// Check that orders reported in ascending order are paged by moving
+  /// the `after` bound.
+  #[cfg(feature = "trading")]
+  #[test(tokio::test)]
+  async fn stream_ascending_order_pages() {
+    let id1 = "904837e3-3b76-47ec-b432-046db6215701";
+    let id2 = "904837e3-3b76-47ec-b432-046db6215702";
+    let id3 = "904837e3-3b76-47ec-b432-046db6215703";
+    let (url, requests) = serve_sequence(vec![
+      Reply::respond(
+        "200 OK",
+        format!(
+          "[{},{}]",
+          order_json(id1, "2018-10-05T10:00:00Z"),
+          order_json(id2, "2018-10-05T11:00:00Z"),
+        ),
+      ),
+      Reply::respond(
+        "200 OK",
+        format!(
+          "[{},{}]",
+          order_json(id2, "2018-10-05T11:00:00Z"),
+          order_json(id3, "2018-10-05T12:00:00Z"),
+        ),
+      ),
+      Reply::respond(
+        "200 OK",
+        format!("[{}]", order_json(id3, "2018-10-05T12:00:00Z")),
+      ),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let request = orders::ListReq {
+      limit: Some(2),
+      direction: Some(orders::Direction::Ascending),
+      ..Default::default()
+    };
+    let orders = client.issue_all::<orders::List>(&request).await.unwrap();
+    let ids = orders
+      .iter()
+      .map(|order| order.id.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(ids, [id1, id2, id3]);
+
+    let lines = requests.lines();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains("direction=asc"), "{}", lines[0]);
+    assert!(!lines[0].contains("after="), "{}", lines[0]);
+    assert!(
+      lines[1].contains("after=2018-10-05T10%3A59%3A59.999999Z"),
+      "{}",
+      lines[1]
+    );
+    assert!(
+      lines[2].contains("after=2018-10-05T11%3A59%3A59.999999Z"),
+      "{}",
+      lines[2]
+    );
+    assert!(lines.iter().all(|line| !line.contains("until=")));
+  }
+
+  /// Make sure that a stream only retrieves the pages it needs.
+  #[test(tokio::test)]
+  async fn stream_lazily() {
+    // Only a single page is served, so any attempt to retrieve the
+    // second one would fail.
+    let (url, _requests) = serve_sequence(vec![Reply::respond(
+      "200 OK",
+      r#"{"items": [1, 2, 3], "next_page_token": "2"}"#,
+    )])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let items = client
+      .paginate::<List>(&ListReq::default())
+      .take(2)
+      .try_collect::<Vec<_>>()
+      .await
+      .unwrap();
+    assert_eq!(items, vec![1, 2]);
+  }
+
+  /// Check that an endpoint error is reported as part of the stream
+  /// and terminates it.
+  #[test(tokio::test)]
+  async fn stream_error() {
+    let (url, _requests) = serve_sequence(vec![
+      Reply::respond("200 OK", r#"{"items": [1], "next_page_token": "2"}"#),
+      Reply::respond("200 OK", "{}"),
+    ])
+    .await;
+    let api_info = ApiInfo::from_parts(url, "key", "secret").unwrap();
+    let client = Client::new(api_info);
+
+    let results = client
+      .paginate::<List>(&ListReq::default())
+      .collect::<Vec<_>>()
+      .await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(*results[0].as_ref().unwrap(), 1);
+    assert!(matches!(
+      results[1],
+      Err(RequestError::Endpoint(ListError::Conversion(..)))
+    ));
+  }
+}
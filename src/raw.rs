@@ -0,0 +1,86 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde_json::from_slice as from_json;
+use serde_json::Value;
+
+use crate::Str;
+
+
+/// The API a raw request is sent to, as used by
+/// [`Client::issue_raw`][crate::Client::issue_raw].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RawApi {
+  /// The trading API, as configured by
+  /// [`ApiInfo::api_base_url`][crate::ApiInfo::api_base_url].
+  Trading,
+  /// The market data API, as configured by
+  /// [`ApiInfo::data_base_url`][crate::ApiInfo::data_base_url].
+  Data,
+}
+
+impl RawApi {
+  /// The pseudo URL an endpoint reports as its base URL to select the
+  /// API it belongs to.
+  ///
+  /// `Endpoint` offers no other means of conveying the API, so the
+  /// client resolves these markers to the configured base URL instead
+  /// of connecting to them.
+  const fn marker(self) -> &'static str {
+    match self {
+      Self::Trading => "apca-api:trading",
+      Self::Data => "apca-api:data",
+    }
+  }
+
+  /// Retrieve the marker selecting this API, for use as the base URL
+  /// of an endpoint.
+  #[cfg(any(test, feature = "market-data"))]
+  #[inline]
+  pub(crate) fn base_url(self) -> Option<Str> {
+    Some(self.marker().into())
+  }
+
+  /// Determine the API selected by the base URL an endpoint reports,
+  /// if it is one of our markers.
+  pub(crate) fn from_base_url(url: &str) -> Option<Self> {
+    [Self::Trading, Self::Data]
+      .into_iter()
+      .find(|api| api.marker() == url)
+  }
+}
+
+
+EndpointNoParse! {
+  /// The representation of an arbitrary request, as issued by
+  /// [`Client::issue_raw`][crate::Client::issue_raw].
+  ///
+  /// Only the evaluation of responses is used; requests themselves are
+  /// constructed by the client.
+  pub Raw(()),
+  Ok => Value, [
+    /* 200 */ OK,
+    /* 201 */ CREATED,
+    /* 202 */ ACCEPTED,
+    /* 204 */ NO_CONTENT,
+    /* 207 */ MULTI_STATUS,
+  ],
+  Err => RawError, []
+
+  fn path(_input: &Self::Input) -> Str {
+    "/".into()
+  }
+
+  fn parse(body: &[u8]) -> Result<Self::Output, Self::ConversionError> {
+    if body.is_empty() {
+      Ok(Value::Null)
+    } else {
+      from_json::<Self::Output>(body).map_err(Self::ConversionError::from)
+    }
+  }
+
+  fn parse_err(body: &[u8]) -> Result<Self::ApiError, Vec<u8>> {
+    from_json::<Self::ApiError>(body).map_err(|_| body.to_vec())
+  }
+}
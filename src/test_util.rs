@@ -0,0 +1,293 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Helpers for serving canned HTTP responses on a local socket in
+//! tests.
+
+// Not every helper is used with every combination of features.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::future::pending;
+
+use tokio::io::AsyncReadExt as _;
+use tokio::io::AsyncWriteExt as _;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::time::sleep;
+
+
+/// An HTTP response to be served.
+#[derive(Clone, Debug)]
+pub(crate) struct Response {
+  /// The status line, e.g., "200 OK".
+  status: &'static str,
+  /// Additional headers to include.
+  headers: Vec<(&'static str, String)>,
+  /// The response body.
+  body: Vec<u8>,
+  /// The time to wait before responding.
+  delay: Option<Duration>,
+}
+
+impl Response {
+  /// Create a response with the given status line and body.
+  pub(crate) fn new(status: &'static str, body: impl Into<Vec<u8>>) -> Self {
+    Self {
+      status,
+      headers: Vec::new(),
+      body: body.into(),
+      delay: None,
+    }
+  }
+
+  /// Add a header to the response.
+  pub(crate) fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+    let () = self.headers.push((name, value.into()));
+    self
+  }
+
+  /// Only respond after the given delay.
+  pub(crate) fn delay(mut self, delay: Duration) -> Self {
+    self.delay = Some(delay);
+    self
+  }
+
+  /// Serialize the response, as it is sent over the wire.
+  fn to_bytes(&self) -> Vec<u8> {
+    let mut head = format!("HTTP/1.1 {}\r\n", self.status);
+    for (name, value) in &self.headers {
+      head += &format!("{name}: {value}\r\n");
+    }
+    head += &format!(
+      "content-length: {}\r\nconnection: close\r\n\r\n",
+      self.body.len()
+    );
+
+    let mut bytes = head.into_bytes();
+    let () = bytes.extend_from_slice(&self.body);
+    bytes
+  }
+}
+
+
+/// The behavior of a mock server for a single connection.
+#[derive(Clone, Debug)]
+pub(crate) enum Reply {
+  /// Close the connection without responding.
+  Close,
+  /// Keep the connection open without ever responding.
+  Hang,
+  /// Respond with the given response.
+  Respond(Response),
+}
+
+impl Reply {
+  /// Create a reply responding with the given status line and body.
+  pub(crate) fn respond(status: &'static str, body: impl Into<Vec<u8>>) -> Self {
+    Self::Respond(Response::new(status, body))
+  }
+}
+
+impl From<Response> for Reply {
+  fn from(response: Response) -> Self {
+    Self::Respond(response)
+  }
+}
+
+
+/// The requests received by a mock server, in order.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Requests(Arc<Mutex<Vec<String>>>);
+
+impl Requests {
+  /// Retrieve all requests received so far, verbatim.
+  pub(crate) fn all(&self) -> Vec<String> {
+    self.0.lock().unwrap().clone()
+  }
+
+  /// Retrieve the request lines, without the HTTP version, of all
+  /// requests received so far.
+  pub(crate) fn lines(&self) -> Vec<String> {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|request| request_line(request).to_string())
+      .collect()
+  }
+
+  /// Record a request.
+  fn push(&self, request: String) {
+    let () = self.0.lock().unwrap().push(request);
+  }
+}
+
+
+/// Extract the request line, e.g., "GET /v2/account", without the HTTP
+/// version from a request.
+pub(crate) fn request_line(request: &str) -> &str {
+  let line = request.lines().next().unwrap_or_default();
+  line.trim_end_matches(" HTTP/1.1")
+}
+
+/// Determine the total length of the HTTP request starting `data`, if
+/// its header is complete.
+fn request_len(data: &[u8]) -> Option<usize> {
+  let end = data.windows(4).position(|window| window == b"\r\n\r\n")? + 4;
+  let header = String::from_utf8_lossy(&data[..end]);
+  let length = header
+    .lines()
+    .filter_map(|line| line.split_once(':'))
+    .find(|(name, _value)| name.eq_ignore_ascii_case("content-length"))
+    .and_then(|(_name, value)| value.trim().parse::<usize>().ok())
+    .unwrap_or(0);
+  Some(end + length)
+}
+
+/// Read an HTTP request, including its body, from a stream.
+async fn read_request(stream: &mut TcpStream) -> String {
+  let mut request = Vec::new();
+  let mut buffer = [0; 4096];
+
+  while request_len(&request).map_or(true, |len| request.len() < len) {
+    match stream.read(&mut buffer).await {
+      Ok(0) | Err(..) => break,
+      Ok(count) => request.extend_from_slice(&buffer[..count]),
+    }
+  }
+  String::from_utf8_lossy(&request).into_owned()
+}
+
+/// Reply to a request on the given stream, returning the stream if it
+/// is to be kept open.
+async fn reply(mut stream: TcpStream, reply: Reply) -> Option<TcpStream> {
+  match reply {
+    Reply::Close => None,
+    Reply::Hang => Some(stream),
+    Reply::Respond(response) => {
+      if let Some(delay) = response.delay {
+        let () = sleep(delay).await;
+      }
+      // The client may hang up on us early, so ignore any errors.
+      let _result = stream.write_all(&response.to_bytes()).await;
+      None
+    },
+  }
+}
+
+
+/// Serve the provided response body to the first connecting client
+/// on a local socket, returning the URL to connect to.
+pub(crate) async fn serve_once(body: impl Into<Vec<u8>>) -> String {
+  serve_once_with_status("200 OK", body).await
+}
+
+/// Serve the provided response body with the given status line to the
+/// first connecting client on a local socket, returning the URL to
+/// connect to.
+pub(crate) async fn serve_once_with_status(
+  status: &'static str,
+  body: impl Into<Vec<u8>>,
+) -> String {
+  let (url, _requests) = serve_sequence(vec![Reply::respond(status, body)]).await;
+  url
+}
+
+/// Serve the provided replies, one per connection and in order, on a
+/// local socket. Return the URL to connect to along with the requests
+/// received.
+pub(crate) async fn serve_sequence(replies: Vec<Reply>) -> (String, Requests) {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  let requests = Requests::default();
+  let received = requests.clone();
+
+  let _handle = tokio::spawn(async move {
+    let mut hanging = Vec::new();
+    for reply_ in replies {
+      let (mut stream, _addr) = listener.accept().await.unwrap();
+      let request = read_request(&mut stream).await;
+      let () = received.push(request);
+
+      if let Some(stream) = reply(stream, reply_).await {
+        let () = hanging.push(stream);
+      }
+    }
+
+    if !hanging.is_empty() {
+      // Keep hanging connections open for as long as the test runs.
+      let () = pending().await;
+    }
+  });
+
+  (format!("http://{addr}"), requests)
+}
+
+/// Serve connections concurrently and indefinitely on a local socket,
+/// replying to each request as determined by `handler`. Return the
+/// URL to connect to along with the requests received.
+pub(crate) async fn serve_with<F>(handler: F) -> (String, Requests)
+where
+  F: Fn(&str) -> Reply + Send + Sync + 'static,
+{
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  let requests = Requests::default();
+  let received = requests.clone();
+  let handler = Arc::new(handler);
+
+  let _handle = tokio::spawn(async move {
+    loop {
+      let (mut stream, _addr) = listener.accept().await.unwrap();
+      let handler = Arc::clone(&handler);
+      let received = received.clone();
+
+      let _handle = tokio::spawn(async move {
+        let request = read_request(&mut stream).await;
+        let reply_ = handler(&request);
+        let () = received.push(request);
+
+        if let Some(_stream) = reply(stream, reply_).await {
+          let () = pending().await;
+        }
+      });
+    }
+  });
+
+  (format!("http://{addr}"), requests)
+}
+
+/// Serve the provided response body with the given status line to the
+/// first connecting client on a local socket, without an async
+/// runtime, returning the URL to connect to.
+#[cfg(feature = "blocking")]
+pub(crate) fn serve_once_blocking(status: &'static str, body: impl Into<Vec<u8>>) -> String {
+  use std::io::Read as _;
+  use std::io::Write as _;
+  use std::net::TcpListener;
+  use std::thread::spawn;
+
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let addr = listener.local_addr().unwrap();
+  let response = Response::new(status, body);
+
+  let _handle = spawn(move || {
+    let (mut stream, _addr) = listener.accept().unwrap();
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    while request_len(&request).map_or(true, |len| request.len() < len) {
+      match stream.read(&mut buffer) {
+        Ok(0) | Err(..) => break,
+        Ok(count) => request.extend_from_slice(&buffer[..count]),
+      }
+    }
+    let _result = stream.write_all(&response.to_bytes());
+  });
+
+  format!("http://{addr}")
+}
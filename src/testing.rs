@@ -0,0 +1,275 @@
+// Copyright (C) 2024 The apca Developers
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::any::type_name;
+use std::any::Any;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use http_endpoint::Endpoint;
+
+use crate::ApiClient;
+use crate::RequestError;
+
+
+/// The result of a request as scripted for a [`MockClient`].
+type Scripted<R> = Result<<R as Endpoint>::Output, RequestError<<R as Endpoint>::Error>>;
+
+
+/// A client replaying scripted responses instead of contacting Alpaca.
+///
+/// Responses are scripted on a per-endpoint basis and handed out in
+/// the order in which they were provided. The request input is not
+/// inspected. Issuing a request to an endpoint for which no (more)
+/// responses are scripted results in a panic.
+///
+/// ```
+/// use apca::api::v2::clock;
+/// use apca::testing::MockClient;
+/// use apca::ApiClient as _;
+///
+/// # let clock = serde_json::from_str(r#"{
+/// #   "timestamp": "2024-01-02T10:00:00-05:00",
+/// #   "is_open": true,
+/// #   "next_open": "2024-01-03T09:30:00-05:00",
+/// #   "next_close": "2024-01-02T16:00:00-05:00"
+/// # }"#).unwrap();
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let client = MockClient::new();
+/// client.respond::<clock::Get>(clock);
+///
+/// let clock = client.issue::<clock::Get>(&()).await.unwrap();
+/// assert!(clock.open);
+/// # })
+/// ```
+#[derive(Debug, Default)]
+pub struct MockClient {
+  /// The scripted results, keyed by the endpoint they belong to.
+  responses: Mutex<HashMap<TypeId, VecDeque<Box<dyn Any + Send>>>>,
+}
+
+impl MockClient {
+  /// Create a new `MockClient` without any scripted responses.
+  #[inline]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Script the result of the next not yet scripted request to
+  /// endpoint `R`.
+  pub fn script<R>(&self, result: Scripted<R>)
+  where
+    R: Endpoint + 'static,
+    R::Output: Send,
+    R::Error: Send,
+  {
+    let mut responses = self.responses.lock().unwrap();
+    let () = responses
+      .entry(TypeId::of::<R>())
+      .or_default()
+      .push_back(Box::new(result));
+  }
+
+  /// Script a successful response to the next not yet scripted request
+  /// to endpoint `R`.
+  #[inline]
+  pub fn respond<R>(&self, output: R::Output)
+  where
+    R: Endpoint + 'static,
+    R::Output: Send,
+    R::Error: Send,
+  {
+    self.script::<R>(Ok(output))
+  }
+
+  /// Check whether all scripted responses have been consumed.
+  pub fn is_exhausted(&self) -> bool {
+    let responses = self.responses.lock().unwrap();
+    responses.values().all(VecDeque::is_empty)
+  }
+}
+
+#[async_trait]
+impl ApiClient for MockClient {
+  async fn issue<R>(&self, _input: &R::Input) -> Result<R::Output, RequestError<R::Error>>
+  where
+    R: Endpoint + 'static,
+    R::Input: Sync,
+    R::Output: Send,
+    R::Error: Send,
+  {
+    let response = self
+      .responses
+      .lock()
+      .unwrap()
+      .get_mut(&TypeId::of::<R>())
+      .and_then(VecDeque::pop_front)
+      .unwrap_or_else(|| panic!("no response scripted for endpoint {}", type_name::<R>()));
+
+    // SANITY: Responses are keyed by the endpoint's type ID and so the
+    //         result type always matches.
+    *response.downcast::<Scripted<R>>().unwrap()
+  }
+}
+
+
+#[cfg(all(test, feature = "trading"))]
+mod tests {
+  use super::*;
+
+  use num_decimal::Num;
+
+  use serde_json::from_str as from_json;
+
+  use test_log::test;
+
+  use crate::api::v2::account;
+  use crate::api::v2::order;
+  use crate::api::v2::orders;
+
+
+  /// A reference account with $5000 of buying power.
+  const ACCOUNT: &str = r#"{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "admin_configurations": {},
+  "user_configurations": null,
+  "account_number": "PALPACA_123",
+  "status": "ACTIVE",
+  "crypto_status": "ACTIVE",
+  "currency": "USD",
+  "buying_power": "5000.00",
+  "regt_buying_power": "0.0",
+  "daytrading_buying_power": "0.0",
+  "options_buying_power": "0.0",
+  "effective_buying_power": "0.0",
+  "non_marginable_buying_power": "0.0",
+  "bod_dtbp": "0.0",
+  "cash": "5000.00",
+  "accrued_fees": "0.0",
+  "pending_transfer_in": "0.0",
+  "portfolio_value": "5000.00",
+  "pattern_day_trader": false,
+  "trade_suspended_by_user": false,
+  "trading_blocked": false,
+  "transfers_blocked": false,
+  "account_blocked": false,
+  "created_at": "2018-10-01T13:35:25Z",
+  "shorting_enabled": true,
+  "multiplier": "2",
+  "long_market_value": "7000.00",
+  "short_market_value": "-3000.00",
+  "position_market_value": "4000.00",
+  "equity": "5000.00",
+  "last_equity": "5000.00",
+  "initial_margin": "5000.00",
+  "maintenance_margin": "3000.00",
+  "last_maintenance_margin": "3000.00",
+  "sma": "0.0",
+  "daytrade_count": 0,
+  "balance_asof": "2018-10-01",
+  "crypto_tier": 1,
+  "options_trading_level": 2,
+  "intraday_adjustments": "0.0",
+  "pending_reg_taf_fees": "0.0"
+}"#;
+
+  /// A reference order for a single share of AAPL.
+  const ORDER: &str = r#"{
+  "id": "904837e3-3b76-47ec-b432-046db621571b",
+  "client_order_id": "my-order",
+  "created_at": "2018-10-05T05:48:59Z",
+  "updated_at": "2018-10-05T05:48:59Z",
+  "submitted_at": "2018-10-05T05:48:59Z",
+  "filled_at": null,
+  "expired_at": null,
+  "canceled_at": null,
+  "asset_id": "904837e3-3b76-47ec-b432-046db621571b",
+  "symbol": "AAPL",
+  "asset_class": "us_equity",
+  "qty": "1",
+  "filled_qty": "0",
+  "type": "market",
+  "order_class": "simple",
+  "side": "buy",
+  "time_in_force": "day",
+  "limit_price": null,
+  "stop_price": null,
+  "filled_avg_price": null,
+  "status": "accepted",
+  "extended_hours": false,
+  "legs": null
+}"#;
+
+
+  /// A simple strategy buying a share of AAPL if there is sufficient
+  /// buying power and no order is open yet.
+  async fn buy_apple<C>(client: &C) -> Option<order::Order>
+  where
+    C: ApiClient,
+  {
+    let account = client.issue::<account::Get>(&()).await.unwrap();
+    if account.buying_power < Num::from(200) {
+      return None
+    }
+
+    let open = client
+      .issue::<orders::List>(&orders::ListReq::default())
+      .await
+      .unwrap();
+    if !open.is_empty() {
+      return None
+    }
+
+    let request =
+      order::CreateReqInit::default().init("AAPL", order::Side::Buy, order::Amount::quantity(1));
+    let order = client.issue::<order::Create>(&request).await.unwrap();
+    Some(order)
+  }
+
+
+  /// Check that a strategy can be driven entirely by a `MockClient`.
+  #[test(tokio::test)]
+  async fn drive_strategy() {
+    let client = MockClient::new();
+    client.respond::<account::Get>(from_json(ACCOUNT).unwrap());
+    client.respond::<orders::List>(Vec::new());
+    client.respond::<order::Create>(from_json(ORDER).unwrap());
+
+    let order = buy_apple(&client).await.unwrap();
+    assert_eq!(order.symbol, "AAPL");
+    assert_eq!(order.status, order::Status::Accepted);
+    assert!(client.is_exhausted());
+
+    client.respond::<account::Get>(from_json(ACCOUNT).unwrap());
+    client.respond::<orders::List>(vec![order]);
+    assert_eq!(buy_apple(&client).await, None);
+    assert!(client.is_exhausted());
+  }
+
+  /// Make sure that scripted errors are reported as well.
+  #[test(tokio::test)]
+  async fn script_error() {
+    let client = MockClient::new();
+    client.script::<account::Get>(Err(RequestError::Endpoint(
+      account::GetError::NotPermitted(Err(Vec::new())),
+    )));
+
+    let result = client.issue::<account::Get>(&()).await;
+    assert!(matches!(
+      result,
+      Err(RequestError::Endpoint(account::GetError::NotPermitted(..)))
+    ));
+  }
+
+  /// Check that issuing a request without a scripted response panics.
+  #[test(tokio::test)]
+  #[should_panic(expected = "no response scripted for endpoint")]
+  async fn unscripted_request() {
+    let client = MockClient::new();
+    let _result = client.issue::<account::Get>(&()).await;
+  }
+}
@@ -1,19 +1,84 @@
 // Copyright (C) 2020-2022 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::cell::Cell;
+
+#[cfg(any(feature = "market-data", feature = "trading"))]
 use crate::Str;
 
+#[cfg(feature = "trading")]
 use num_decimal::Num;
 
+use serde::de::DeserializeOwned;
+use serde::de::Error as _;
+use serde::de::IntoDeserializer as _;
 use serde::Deserialize;
 use serde::Deserializer;
+#[cfg(any(feature = "market-data", feature = "trading"))]
 use serde::Serialize;
+#[cfg(any(feature = "market-data", feature = "trading"))]
 use serde::Serializer;
+#[cfg(any(feature = "market-data", feature = "trading"))]
 use serde_variant::to_variant_name;
 
 
+thread_local! {
+  /// Whether unknown enum variants are rejected when deserializing on
+  /// the current thread.
+  static STRICT: Cell<bool> = const { Cell::new(false) };
+}
+
+
+/// Run `f` with strict deserialization of enum variants enabled or
+/// disabled, as per `strict`.
+pub(crate) fn with_strict<F, R>(strict: bool, f: F) -> R
+where
+  F: FnOnce() -> R,
+{
+  /// A guard restoring the previous mode when dropped, even if `f`
+  /// panics.
+  struct Restore(bool);
+
+  impl Drop for Restore {
+    fn drop(&mut self) {
+      let () = STRICT.with(|strict| strict.set(self.0));
+    }
+  }
+
+  let _restore = Restore(STRICT.with(|cell| cell.replace(strict)));
+  f()
+}
+
+
+/// A trait for enums with a catch-all variant for values we do not
+/// know about.
+pub(crate) trait UnknownVariant {
+  /// Check whether the value is the catch-all variant.
+  fn is_unknown(&self) -> bool;
+}
+
+
+/// Deserialize an enum with a catch-all variant, rejecting values
+/// mapping to said variant if strict deserialization is enabled (see
+/// [`with_strict`]).
+pub(crate) fn strict_variant<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: DeserializeOwned + UnknownVariant,
+{
+  let name = String::deserialize(deserializer)?;
+  let value = T::deserialize(name.as_str().into_deserializer())?;
+  if value.is_unknown() && STRICT.with(Cell::get) {
+    Err(D::Error::custom(format!("unknown variant `{name}`")))
+  } else {
+    Ok(value)
+  }
+}
+
+
 /// Deserialize a `Num` from a string, parsing the value as signed first
 /// and then dropping the sign.
+#[cfg(feature = "trading")]
 pub(crate) fn abs_num_from_str<'de, D>(deserializer: D) -> Result<Num, D::Error>
 where
   D: Deserializer<'de>,
@@ -35,6 +100,7 @@ where
 
 /// Deserialize a `Vec<String>` from a string (that could be `null`)
 /// with comma separated elements.
+#[cfg(feature = "trading")]
 pub(crate) fn vec_from_comma_separated_str<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
   D: Deserializer<'de>,
@@ -50,6 +116,7 @@ where
 /// Serialize a slice into a string of textual representations of the
 /// elements, retrieved by applying a function to each, and then
 /// separated by comma.
+#[cfg(any(feature = "market-data", feature = "trading"))]
 pub(crate) fn slice_to_str<S, F, T>(
   slice: &[T],
   name_fn: F,
@@ -78,6 +145,7 @@ where
 /// # Notes
 /// - this function should only be used for cases where `T` is an enum
 ///   type
+#[cfg(any(feature = "market-data", feature = "trading"))]
 pub(crate) fn enum_slice_to_str<S, T>(slice: &[T], serializer: S) -> Result<S::Ok, S::Error>
 where
   S: Serializer,
@@ -98,6 +166,7 @@ where
 
 /// Serialize a slice of strings into a comma-separated string combining
 /// the individual strings.
+#[cfg(any(feature = "market-data", feature = "trading"))]
 pub(crate) fn string_slice_to_str<S>(slice: &[String], serializer: S) -> Result<S::Ok, S::Error>
 where
   S: Serializer,
@@ -1,6 +1,9 @@
 // Copyright (C) 2019-2023 The apca Developers
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+#[cfg(feature = "market-data")]
+use std::time::Duration;
+
 use url::Url;
 
 use tokio::net::TcpStream;
@@ -12,6 +15,9 @@ use tracing::Level;
 use tracing_futures::Instrument;
 
 use tungstenite::connect_async;
+use tungstenite::tungstenite::client::IntoClientRequest as _;
+use tungstenite::tungstenite::http::header::USER_AGENT;
+use tungstenite::tungstenite::http::HeaderValue;
 use tungstenite::MaybeTlsStream;
 use tungstenite::WebSocketStream;
 
@@ -42,17 +48,25 @@ impl<T, E> From<Result<T, E>> for MessageResult<T, E> {
 }
 
 
-/// Internal function to connect to websocket server.
-async fn connect_internal(url: &Url) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, Error> {
+/// Internal function to connect to websocket server, identifying as
+/// the provided user agent.
+async fn connect_internal(
+  url: &Url,
+  user_agent: &str,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, Error> {
   let span = span!(Level::DEBUG, "stream");
 
   async move {
     debug!(message = "connecting", url = display(url));
 
+    let mut request = url.as_str().into_client_request()?;
+    let user_agent = HeaderValue::from_str(user_agent).map_err(http::Error::from)?;
+    let _previous = request.headers_mut().insert(USER_AGENT, user_agent);
+
     // We just ignore the response & headers that are sent along after
     // the connection is made. Alpaca does not seem to be using them,
     // really.
-    let (stream, response) = connect_async(url).await?;
+    let (stream, response) = connect_async(request).await?;
     debug!("connection successful");
     trace!(response = debug(&response));
 
@@ -64,15 +78,33 @@ async fn connect_internal(url: &Url) -> Result<WebSocketStream<MaybeTlsStream<Tc
 
 
 /// Connect to a websocket server.
+#[cfg(feature = "trading")]
 pub(crate) async fn connect(
   url: &Url,
+  user_agent: &str,
 ) -> Result<Wrapper<WebSocketStream<MaybeTlsStream<TcpStream>>>, Error> {
-  connect_internal(url)
+  connect_internal(url, user_agent)
     .await
     .map(|stream| Wrapper::builder().build(stream))
 }
 
 
+/// Connect to a websocket server, pinging it at the provided interval
+/// (if any).
+#[cfg(feature = "market-data")]
+pub(crate) async fn connect_with_ping_interval(
+  url: &Url,
+  user_agent: &str,
+  ping_interval: Option<Duration>,
+) -> Result<Wrapper<WebSocketStream<MaybeTlsStream<TcpStream>>>, Error> {
+  connect_internal(url, user_agent).await.map(|stream| {
+    Wrapper::builder()
+      .set_ping_interval(ping_interval)
+      .build(stream)
+  })
+}
+
+
 #[cfg(test)]
 pub(crate) mod test {
   use super::*;
@@ -83,8 +115,10 @@ pub(crate) mod test {
   use websocket_util::test::WebSocketStream;
   use websocket_util::tungstenite::Error as WebSocketError;
 
+  use crate::api::DEFAULT_USER_AGENT;
   use crate::subscribable::Subscribable;
   use crate::ApiInfo;
+  use crate::Environment;
 
 
   /// The fake key-id we use.
@@ -94,11 +128,10 @@ pub(crate) mod test {
 
 
   /// Instantiate a dummy websocket server serving messages as per the
-  /// provided function `f` and attempt to connect to it to stream
-  /// messages.
-  pub(crate) async fn mock_stream<S, F, R>(f: F) -> Result<(S::Stream, S::Subscription), Error>
+  /// provided function `f` and return an `ApiInfo` object referencing
+  /// it.
+  pub(crate) async fn mock_api_info<F, R>(f: F) -> ApiInfo
   where
-    S: Subscribable<Input = ApiInfo>,
     F: FnOnce(WebSocketStream) -> R + Send + Sync + 'static,
     R: Future<Output = Result<(), WebSocketError>> + Send + Sync + 'static,
   {
@@ -108,15 +141,28 @@ pub(crate) mod test {
     // We just set both the API stream URL and the data stream URL to
     // our websocket server. We don't know which one clients are trying
     // to mock, but currently it's only one or the other.
-    let api_info = ApiInfo {
+    ApiInfo {
       api_base_url: Url::parse("http://example.com").unwrap(),
       api_stream_url: stream_url.clone(),
       data_base_url: Url::parse("http://example.com").unwrap(),
       data_stream_base_url: stream_url.clone(),
       key_id: KEY_ID.to_string(),
       secret: SECRET.to_string(),
-    };
+      environment: Environment::Custom,
+      user_agent: DEFAULT_USER_AGENT.to_string(),
+    }
+  }
 
+  /// Instantiate a dummy websocket server serving messages as per the
+  /// provided function `f` and attempt to connect to it to stream
+  /// messages.
+  pub(crate) async fn mock_stream<S, F, R>(f: F) -> Result<(S::Stream, S::Subscription), Error>
+  where
+    S: Subscribable<Input = ApiInfo>,
+    F: FnOnce(WebSocketStream) -> R + Send + Sync + 'static,
+    R: Future<Output = Result<(), WebSocketError>> + Send + Sync + 'static,
+  {
+    let api_info = mock_api_info(f).await;
     S::connect(&api_info).await
   }
 }